@@ -57,6 +57,13 @@ pub enum Statement {
         span: Span,
     },
 
+    /// Destructuring declaration: let [a, b] = expr; let { x, y } = expr;
+    LetPattern {
+        pattern: Pattern,
+        value: Expression,
+        span: Span,
+    },
+
     /// Constant declaration: const x = expr;
     Const {
         name: String,
@@ -65,6 +72,13 @@ pub enum Statement {
         span: Span,
     },
 
+    /// Assignment to an existing binding or container element: x = expr; arr[i] = expr; obj.field = expr;
+    Assign {
+        target: Expression,
+        value: Expression,
+        span: Span,
+    },
+
     /// WOW #2: Reactive variable declaration: reactive x <- expr;
     Reactive {
         name: String,
@@ -92,6 +106,18 @@ pub enum Statement {
     /// Expression statement
     Expression { expr: Expression, span: Span },
 
+    /// `assert cond[, message];`, skipped entirely when assertions are off
+    Assert {
+        condition: Expression,
+        message: Option<Expression>,
+        /// Source text of `condition`, quoted in the failure
+        source: String,
+        span: Span,
+    },
+
+    /// Bare `{ ... }` block with its own scope
+    Block { body: Vec<Statement>, span: Span },
+
     /// If statement
     If {
         condition: Expression,
@@ -100,10 +126,11 @@ pub enum Statement {
         span: Span,
     },
 
-    /// While loop
+    /// While loop, optionally labeled: outer: while (cond) { }
     While {
         condition: Expression,
         body: Vec<Statement>,
+        label: Option<String>,
         span: Span,
     },
 
@@ -112,14 +139,26 @@ pub enum Statement {
         variable: String,
         iterable: Expression,
         body: Vec<Statement>,
+        label: Option<String>,
         span: Span,
     },
 
-    /// Break statement
-    Break { span: Span },
+    /// Classic C-style for loop: for (init; condition; update) { }
+    ///
+    /// The parser wraps a loop with an init in a `Block`, which scopes it.
+    ForClassic {
+        condition: Option<Expression>,
+        update: Option<Box<Statement>>,
+        body: Vec<Statement>,
+        label: Option<String>,
+        span: Span,
+    },
+
+    /// Break statement: `break` or `break label`
+    Break { label: Option<String>, span: Span },
 
-    /// Continue statement
-    Continue { span: Span },
+    /// Continue statement: `continue` or `continue label`
+    Continue { label: Option<String>, span: Span },
 
     /// Match expression (pattern matching)
     Match {
@@ -128,6 +167,14 @@ pub enum Statement {
         span: Span,
     },
 
+    /// C-style switch: compares the value with `==` against each case in
+    /// order and falls through until a `break`
+    Switch {
+        value: Expression,
+        cases: Vec<SwitchCase>,
+        span: Span,
+    },
+
     /// Struct definition
     Struct {
         name: String,
@@ -146,11 +193,17 @@ pub enum Statement {
     Import {
         path: Vec<String>,
         items: Vec<String>,
+        /// Name bound by `import name from "path"`
+        alias: Option<String>,
         span: Span,
     },
 
-    /// Export statement
-    Export { item: String, span: Span },
+    /// `export name`, or `export` in front of the declaration of `item`
+    Export {
+        item: String,
+        declaration: Option<Box<Statement>>,
+        span: Span,
+    },
 
     /// Class definition
     Class {
@@ -172,8 +225,15 @@ pub enum Statement {
     /// Throw statement
     Throw { value: Expression, span: Span },
 
-    /// WOW #1: Time-Travel Debugging - Snapshot
-    Snapshot { label: Option<String>, span: Span },
+    /// Defer statement: defer expr; runs expr when the enclosing function exits
+    Defer { expression: Expression, span: Span },
+
+    /// WOW #1: Time-Travel Debugging - Snapshot, with optional `#tag`s
+    Snapshot {
+        label: Option<String>,
+        tags: Vec<String>,
+        span: Span,
+    },
 
     /// WOW #1: Time-Travel Debugging - Rewind
     Rewind {
@@ -185,6 +245,9 @@ pub enum Statement {
     /// WOW #1: Time-Travel Debugging - Checkpoint
     Checkpoint { label: String, span: Span },
 
+    /// Time-Travel Debugging - start replaying from a checkpoint
+    Replay { label: String, span: Span },
+
     /// WOW #2: Watch expression for reactive updates
     Watch {
         expression: Expression,
@@ -263,22 +326,29 @@ pub enum Expression {
         span: Span,
     },
 
+    /// Template string: the display forms of `parts`, concatenated
+    Template { parts: Vec<Expression>, span: Span },
+
     /// Array literal
     Array {
         elements: Vec<Expression>,
         span: Span,
     },
 
+    /// Spread: `...expr` inside an array literal or argument list
+    Spread { expr: Box<Expression>, span: Span },
+
     /// Object literal (hash map)
     Object {
         fields: Vec<(String, Expression)>,
         span: Span,
     },
 
-    /// Property access: obj.property
+    /// Property access: obj.property, or obj?.property when `optional`
     PropertyAccess {
         object: Box<Expression>,
         property: String,
+        optional: bool,
         span: Span,
     },
 
@@ -365,15 +435,34 @@ pub enum Expression {
         span: Span,
     },
 
-    /// New expression: new ClassName(args)
+    /// New expression: new ClassName(args) or new ClassName { field: value }
     New {
         class_name: String,
         arguments: Vec<Expression>,
+        fields: Vec<(String, Expression)>,
         span: Span,
     },
 
     /// This keyword
     This { span: Span },
+
+    /// `if` used as a value: yields the last value of the taken branch
+    If {
+        condition: Box<Expression>,
+        then_branch: Vec<Statement>,
+        else_branch: Option<Vec<Statement>>,
+        span: Span,
+    },
+
+    /// `match` used as a value: yields the value of the matching arm
+    Match {
+        value: Box<Expression>,
+        arms: Vec<MatchArm>,
+        span: Span,
+    },
+
+    /// `{ ... }` used as a value: a scoped block yielding its last value
+    Block { body: Vec<Statement>, span: Span },
 }
 
 /// Literal values
@@ -408,6 +497,8 @@ pub enum BinaryOp {
     // Logical
     And,
     Or,
+    /// `a ?? b`: `b` only when `a` is nil
+    NullCoalesce,
 
     // Bitwise
     BitwiseAnd,
@@ -434,6 +525,7 @@ impl fmt::Display for BinaryOp {
             BinaryOp::GreaterEqual => write!(f, ">="),
             BinaryOp::And => write!(f, "&&"),
             BinaryOp::Or => write!(f, "||"),
+            BinaryOp::NullCoalesce => write!(f, "??"),
             BinaryOp::BitwiseAnd => write!(f, "&"),
             BinaryOp::BitwiseOr => write!(f, "|"),
             BinaryOp::BitwiseXor => write!(f, "^"),
@@ -467,6 +559,8 @@ pub struct Parameter {
     pub name: String,
     pub type_annotation: Option<TypeAnnotation>,
     pub default_value: Option<Expression>,
+    /// `...name`: collects the remaining arguments into an array
+    pub is_rest: bool,
     pub span: Span,
 }
 
@@ -479,6 +573,14 @@ pub struct MatchArm {
     pub span: Span,
 }
 
+/// One `case value:` of a switch; `value` is None for `default:`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SwitchCase {
+    pub value: Option<Expression>,
+    pub body: Vec<Statement>,
+    pub span: Span,
+}
+
 /// Pattern for pattern matching
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Pattern {
@@ -488,7 +590,17 @@ pub enum Pattern {
     Tuple(Vec<Pattern>),
     Array(Vec<Pattern>),
     Object(Vec<(String, Pattern)>),
-    Variant { name: String, fields: Vec<Pattern> },
+    /// `Rgb(r, g, b)` or the qualified `Color.Rgb(r, g, b)`
+    Variant {
+        enum_name: Option<String>,
+        name: String,
+        fields: Vec<Pattern>,
+    },
+    /// `Integer n` in `match x is { ... }`: matches by runtime type
+    Type {
+        type_name: String,
+        binding: Option<String>,
+    },
 }
 
 /// Class method definition
@@ -599,6 +711,8 @@ impl Expression {
             Expression::Unary { span, .. } => span,
             Expression::Call { span, .. } => span,
             Expression::Array { span, .. } => span,
+            Expression::Template { span, .. } => span,
+            Expression::Spread { span, .. } => span,
             Expression::Object { span, .. } => span,
             Expression::PropertyAccess { span, .. } => span,
             Expression::IndexAccess { span, .. } => span,
@@ -615,6 +729,9 @@ impl Expression {
             Expression::Ternary { span, .. } => span,
             Expression::New { span, .. } => span,
             Expression::This { span } => span,
+            Expression::If { span, .. } => span,
+            Expression::Match { span, .. } => span,
+            Expression::Block { span, .. } => span,
         }
     }
 }
@@ -623,7 +740,9 @@ impl Statement {
     pub fn span(&self) -> &Span {
         match self {
             Statement::Let { span, .. } => span,
+            Statement::LetPattern { span, .. } => span,
             Statement::Const { span, .. } => span,
+            Statement::Assign { span, .. } => span,
             Statement::Reactive { span, .. } => span,
             Statement::Function { span, .. } => span,
             Statement::Return { span, .. } => span,
@@ -631,9 +750,13 @@ impl Statement {
             Statement::If { span, .. } => span,
             Statement::While { span, .. } => span,
             Statement::For { span, .. } => span,
-            Statement::Break { span } => span,
-            Statement::Continue { span } => span,
+            Statement::ForClassic { span, .. } => span,
+            Statement::Block { span, .. } => span,
+            Statement::Assert { span, .. } => span,
+            Statement::Break { span, .. } => span,
+            Statement::Continue { span, .. } => span,
             Statement::Match { span, .. } => span,
+            Statement::Switch { span, .. } => span,
             Statement::Struct { span, .. } => span,
             Statement::Enum { span, .. } => span,
             Statement::Import { span, .. } => span,
@@ -641,9 +764,11 @@ impl Statement {
             Statement::Class { span, .. } => span,
             Statement::Try { span, .. } => span,
             Statement::Throw { span, .. } => span,
+            Statement::Defer { span, .. } => span,
             Statement::Snapshot { span, .. } => span,
             Statement::Rewind { span, .. } => span,
             Statement::Checkpoint { span, .. } => span,
+            Statement::Replay { span, .. } => span,
             Statement::Watch { span, .. } => span,
             Statement::Effect { span, .. } => span,
             Statement::Computed { span, .. } => span,
@@ -656,6 +781,413 @@ impl Statement {
     }
 }
 
+impl Expression {
+    /// Call `f` with the name of every free identifier this expression reads,
+    /// including reads inside nested lambda bodies. Names bound within the
+    /// expression (lambda parameters, local `let`s, loop variables, ...) are
+    /// skipped wherever they shadow an outer binding, as are the arguments
+    /// of an `untrack(...)` call.
+    pub fn visit_identifiers(&self, f: &mut dyn FnMut(&str)) {
+        self.visit_free(&mut Vec::new(), f);
+    }
+
+    fn visit_free(&self, bound: &mut Vec<String>, f: &mut dyn FnMut(&str)) {
+        match self {
+            Expression::Identifier { name, .. } => {
+                if !bound.contains(name) {
+                    f(name);
+                }
+            }
+            Expression::Binary { left, right, .. } => {
+                left.visit_free(bound, f);
+                right.visit_free(bound, f);
+            }
+            Expression::Unary { operand, .. } => operand.visit_free(bound, f),
+            Expression::Call {
+                callee, arguments, ..
+            } => {
+                callee.visit_free(bound, f);
+                // Reads inside `untrack(...)` are deliberately not dependencies
+                let untracked = matches!(callee.as_ref(), Expression::Identifier { name, .. }
+                    if name == "untrack" && !bound.iter().any(|b| b == name));
+                if !untracked {
+                    for arg in arguments {
+                        arg.visit_free(bound, f);
+                    }
+                }
+            }
+            Expression::Array { elements, .. }
+            | Expression::Template {
+                parts: elements, ..
+            } => {
+                for element in elements {
+                    element.visit_free(bound, f);
+                }
+            }
+            Expression::Object { fields, .. } => {
+                for (_, value) in fields {
+                    value.visit_free(bound, f);
+                }
+            }
+            Expression::PropertyAccess { object, .. } | Expression::Spread { expr: object, .. } => {
+                object.visit_free(bound, f)
+            }
+            Expression::IndexAccess { object, index, .. } => {
+                object.visit_free(bound, f);
+                index.visit_free(bound, f);
+            }
+            Expression::Lambda {
+                parameters, body, ..
+            } => visit_function_free(parameters, body, bound, f),
+            Expression::Await { expression, .. }
+            | Expression::Signal {
+                initial_value: expression,
+                ..
+            }
+            | Expression::Unquote { expression, .. } => expression.visit_free(bound, f),
+            Expression::ParallelMap {
+                collection: left,
+                function: right,
+                ..
+            }
+            | Expression::ParallelFilter {
+                collection: left,
+                predicate: right,
+                ..
+            }
+            | Expression::Pipeline {
+                value: left,
+                function: right,
+                ..
+            }
+            | Expression::StreamPipe {
+                source: left,
+                handler: right,
+                ..
+            }
+            | Expression::Range {
+                start: left,
+                end: right,
+                ..
+            } => {
+                left.visit_free(bound, f);
+                right.visit_free(bound, f);
+            }
+            Expression::Ternary {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                condition.visit_free(bound, f);
+                then_expr.visit_free(bound, f);
+                else_expr.visit_free(bound, f);
+            }
+            Expression::New {
+                arguments, fields, ..
+            } => {
+                for arg in arguments {
+                    arg.visit_free(bound, f);
+                }
+                for (_, value) in fields {
+                    value.visit_free(bound, f);
+                }
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                condition.visit_free(bound, f);
+                visit_block_free(then_branch, bound, f);
+                if let Some(else_branch) = else_branch {
+                    visit_block_free(else_branch, bound, f);
+                }
+            }
+            Expression::Match { value, arms, .. } => visit_match_free(value, arms, bound, f),
+            Expression::Block { body, .. } => visit_block_free(body, bound, f),
+            // Quoted code is data until unquoted, so it reads nothing
+            Expression::Literal { .. } | Expression::Quote { .. } | Expression::This { .. } => {}
+        }
+    }
+}
+
+impl Statement {
+    /// Call `f` with the name of every free identifier this statement reads.
+    /// A plain `name = ...` target is a write, not a read, and is skipped.
+    pub fn visit_identifiers(&self, f: &mut dyn FnMut(&str)) {
+        self.visit_free(&mut Vec::new(), f);
+    }
+
+    /// Walk this statement, adding any names it declares to `bound` so later
+    /// statements in the same block see them as local
+    fn visit_free(&self, bound: &mut Vec<String>, f: &mut dyn FnMut(&str)) {
+        match self {
+            Statement::Let { name, value, .. }
+            | Statement::Const { name, value, .. }
+            | Statement::Reactive {
+                name,
+                initial_value: value,
+                ..
+            }
+            | Statement::Computed {
+                name,
+                expression: value,
+                ..
+            } => {
+                value.visit_free(bound, f);
+                bound.push(name.clone());
+            }
+            Statement::LetPattern { pattern, value, .. } => {
+                value.visit_free(bound, f);
+                pattern.bind_names(bound);
+            }
+            Statement::Expression { expr: value, .. }
+            | Statement::Throw { value, .. }
+            | Statement::Defer {
+                expression: value, ..
+            }
+            | Statement::SyntaxExtension {
+                transformer: value, ..
+            } => value.visit_free(bound, f),
+            Statement::Assign { target, value, .. } => {
+                if !matches!(target, Expression::Identifier { .. }) {
+                    target.visit_free(bound, f);
+                }
+                value.visit_free(bound, f);
+            }
+            Statement::Function {
+                name,
+                parameters,
+                body,
+                ..
+            } => {
+                bound.push(name.clone());
+                visit_function_free(parameters, body, bound, f);
+            }
+            Statement::Effect { body, .. }
+            | Statement::Parallel { body, .. }
+            | Statement::Atomic { body, .. } => visit_block_free(body, bound, f),
+            Statement::Return { value, .. } => {
+                if let Some(value) = value {
+                    value.visit_free(bound, f);
+                }
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                condition.visit_free(bound, f);
+                visit_block_free(then_branch, bound, f);
+                if let Some(else_branch) = else_branch {
+                    visit_block_free(else_branch, bound, f);
+                }
+            }
+            Statement::While {
+                condition: head,
+                body,
+                ..
+            }
+            | Statement::Watch {
+                expression: head,
+                handler: body,
+                ..
+            } => {
+                head.visit_free(bound, f);
+                visit_block_free(body, bound, f);
+            }
+            Statement::For {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                iterable.visit_free(bound, f);
+                let mark = bound.len();
+                bound.push(variable.clone());
+                visit_block_free(body, bound, f);
+                bound.truncate(mark);
+            }
+            Statement::ForClassic {
+                condition,
+                update,
+                body,
+                ..
+            } => {
+                if let Some(condition) = condition {
+                    condition.visit_free(bound, f);
+                }
+                let mark = bound.len();
+                visit_block_free(body, bound, f);
+                if let Some(update) = update {
+                    update.visit_free(bound, f);
+                }
+                bound.truncate(mark);
+            }
+            Statement::Block { body, .. } => visit_block_free(body, bound, f),
+            Statement::Assert {
+                condition, message, ..
+            } => {
+                condition.visit_free(bound, f);
+                if let Some(message) = message {
+                    message.visit_free(bound, f);
+                }
+            }
+            Statement::Match { value, arms, .. } => visit_match_free(value, arms, bound, f),
+            Statement::Switch { value, cases, .. } => {
+                value.visit_free(bound, f);
+                // Cases fall through into each other, so they share one scope
+                let mark = bound.len();
+                for case in cases {
+                    if let Some(value) = &case.value {
+                        value.visit_free(bound, f);
+                    }
+                    for statement in &case.body {
+                        statement.visit_free(bound, f);
+                    }
+                }
+                bound.truncate(mark);
+            }
+            Statement::Try {
+                try_block,
+                catch_clause,
+                finally_block,
+                ..
+            } => {
+                visit_block_free(try_block, bound, f);
+                if let Some(catch) = catch_clause {
+                    let mark = bound.len();
+                    bound.extend(catch.parameter.iter().cloned());
+                    visit_block_free(&catch.body, bound, f);
+                    bound.truncate(mark);
+                }
+                if let Some(finally_block) = finally_block {
+                    visit_block_free(finally_block, bound, f);
+                }
+            }
+            Statement::Rewind { steps, .. } => {
+                if let Some(steps) = steps {
+                    steps.visit_free(bound, f);
+                }
+            }
+            Statement::Concurrent { tasks, .. } => {
+                for task in tasks {
+                    task.visit_free(bound, f);
+                }
+            }
+            Statement::Class {
+                constructor,
+                methods,
+                ..
+            } => {
+                if let Some(constructor) = constructor {
+                    visit_function_free(&constructor.parameters, &constructor.body, bound, f);
+                }
+                for method in methods {
+                    visit_function_free(&method.parameters, &method.body, bound, f);
+                }
+            }
+            Statement::Export {
+                declaration: Some(declaration),
+                ..
+            } => declaration.visit_free(bound, f),
+            Statement::Break { .. }
+            | Statement::Continue { .. }
+            | Statement::Struct { .. }
+            | Statement::Enum { .. }
+            | Statement::Import { .. }
+            | Statement::Export {
+                declaration: None, ..
+            }
+            | Statement::Snapshot { .. }
+            | Statement::Checkpoint { .. }
+            | Statement::Replay { .. }
+            | Statement::Context { .. } => {}
+        }
+    }
+}
+
+impl Pattern {
+    /// Push every name this pattern binds onto `names`
+    pub fn bind_names(&self, names: &mut Vec<String>) {
+        match self {
+            Pattern::Identifier(name) => names.push(name.clone()),
+            Pattern::Tuple(patterns) | Pattern::Array(patterns) => {
+                for pattern in patterns {
+                    pattern.bind_names(names);
+                }
+            }
+            Pattern::Object(fields) => {
+                for (_, pattern) in fields {
+                    pattern.bind_names(names);
+                }
+            }
+            Pattern::Variant { fields, .. } => {
+                for pattern in fields {
+                    pattern.bind_names(names);
+                }
+            }
+            Pattern::Type {
+                binding: Some(name),
+                ..
+            } => names.push(name.clone()),
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Type { binding: None, .. } => {}
+        }
+    }
+}
+
+/// Call `f` with every free identifier read anywhere in `statements`
+pub fn visit_block_identifiers(statements: &[Statement], f: &mut dyn FnMut(&str)) {
+    visit_block_free(statements, &mut Vec::new(), f);
+}
+
+/// Walk a block; names it declares go out of scope when it ends
+fn visit_block_free(statements: &[Statement], bound: &mut Vec<String>, f: &mut dyn FnMut(&str)) {
+    let mark = bound.len();
+    for statement in statements {
+        statement.visit_free(bound, f);
+    }
+    bound.truncate(mark);
+}
+
+/// Walk a match subject and its arms; pattern names are local to their arm
+fn visit_match_free(
+    value: &Expression,
+    arms: &[MatchArm],
+    bound: &mut Vec<String>,
+    f: &mut dyn FnMut(&str),
+) {
+    value.visit_free(bound, f);
+    for arm in arms {
+        let mark = bound.len();
+        arm.pattern.bind_names(bound);
+        if let Some(guard) = &arm.guard {
+            guard.visit_free(bound, f);
+        }
+        visit_block_free(&arm.body, bound, f);
+        bound.truncate(mark);
+    }
+}
+
+/// Walk a function body with its parameters bound; defaults are walked first
+fn visit_function_free(
+    parameters: &[Parameter],
+    body: &[Statement],
+    bound: &mut Vec<String>,
+    f: &mut dyn FnMut(&str),
+) {
+    for default in parameters.iter().filter_map(|p| p.default_value.as_ref()) {
+        default.visit_free(bound, f);
+    }
+    let mark = bound.len();
+    bound.extend(parameters.iter().map(|p| p.name.clone()));
+    visit_block_free(body, bound, f);
+    bound.truncate(mark);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -682,4 +1214,110 @@ mod tests {
         assert_eq!(format!("{}", BinaryOp::Equal), "==");
         assert_eq!(format!("{}", BinaryOp::Power), "**");
     }
+
+    #[test]
+    fn test_visit_identifiers_skips_assignment_targets() {
+        let ident = |name: &str| Expression::Identifier {
+            name: name.to_string(),
+            span: Span::dummy(),
+        };
+        let body = vec![Statement::Assign {
+            target: ident("total"),
+            value: Expression::Binary {
+                left: Box::new(ident("a")),
+                operator: BinaryOp::Add,
+                right: Box::new(Expression::Call {
+                    callee: Box::new(ident("f")),
+                    arguments: vec![ident("b")],
+                    span: Span::dummy(),
+                }),
+                span: Span::dummy(),
+            },
+            span: Span::dummy(),
+        }];
+
+        let mut names = Vec::new();
+        visit_block_identifiers(&body, &mut |name| names.push(name.to_string()));
+        assert_eq!(names, vec!["a", "f", "b"]);
+    }
+
+    #[test]
+    fn test_visit_identifiers_ignores_shadowed_names() {
+        let ident = |name: &str| Expression::Identifier {
+            name: name.to_string(),
+            span: Span::dummy(),
+        };
+        let param = |name: &str| Parameter {
+            name: name.to_string(),
+            type_annotation: None,
+            default_value: None,
+            is_rest: false,
+            span: Span::dummy(),
+        };
+        // let count = 1; map(items, count => count + step); count + total
+        let body = vec![
+            Statement::Let {
+                name: "count".to_string(),
+                value: ident("seed"),
+                type_annotation: None,
+                span: Span::dummy(),
+            },
+            Statement::Expression {
+                expr: Expression::Call {
+                    callee: Box::new(ident("map")),
+                    arguments: vec![
+                        ident("items"),
+                        Expression::Lambda {
+                            parameters: vec![param("count")],
+                            body: vec![Statement::Return {
+                                value: Some(Expression::Binary {
+                                    left: Box::new(ident("count")),
+                                    operator: BinaryOp::Add,
+                                    right: Box::new(ident("step")),
+                                    span: Span::dummy(),
+                                }),
+                                span: Span::dummy(),
+                            }],
+                            return_type: None,
+                            span: Span::dummy(),
+                        },
+                    ],
+                    span: Span::dummy(),
+                },
+                span: Span::dummy(),
+            },
+            Statement::Expression {
+                expr: Expression::Binary {
+                    left: Box::new(ident("count")),
+                    operator: BinaryOp::Add,
+                    right: Box::new(ident("total")),
+                    span: Span::dummy(),
+                },
+                span: Span::dummy(),
+            },
+        ];
+
+        let mut names = Vec::new();
+        visit_block_identifiers(&body, &mut |name| names.push(name.to_string()));
+        assert_eq!(names, vec!["seed", "map", "items", "step", "total"]);
+
+        // A lambda parameter only shadows inside the lambda
+        let lambda = Expression::Lambda {
+            parameters: vec![param("x")],
+            body: vec![Statement::Return {
+                value: Some(ident("x")),
+                span: Span::dummy(),
+            }],
+            return_type: None,
+            span: Span::dummy(),
+        };
+        let call = Expression::Call {
+            callee: Box::new(lambda),
+            arguments: vec![ident("x")],
+            span: Span::dummy(),
+        };
+        let mut names = Vec::new();
+        call.visit_identifiers(&mut |name| names.push(name.to_string()));
+        assert_eq!(names, vec!["x"]);
+    }
 }
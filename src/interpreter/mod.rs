@@ -5,13 +5,20 @@
 pub mod value;
 
 use crate::ast::*;
+use crate::parser::TemplateSegment;
 use crate::reactive::ReactiveContext;
 use crate::stdlib::ffi::FFIContext;
-use crate::time_travel::{TimeTravelConfig, TimeTravelDebugger};
+use crate::stdlib::network::{HttpClient, HttpMethod, HttpRequest};
+use crate::stdlib::random::Rng;
+use crate::time_travel::{StackFrame, TimeTravelConfig, TimeTravelDebugger, UndoHistory, UndoStep};
+use crate::types::{Type, TypeChecker, TypeContext, TypeError};
 use im::HashMap as PersistentHashMap;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
-use value::{FunctionValue, Value};
+use value::{FunctionValue, NativeError, RenderStyle, Value};
 
 /// Interpreter error types
 #[derive(Debug, Clone)]
@@ -20,8 +27,10 @@ pub enum InterpreterError {
     TypeError(String),
     RuntimeError(String),
     ReturnValue(Value),
-    BreakStatement,
-    ContinueStatement,
+    /// `break`, carrying its label if it names a loop
+    BreakStatement(Option<String>),
+    /// `continue`, carrying its label if it names a loop
+    ContinueStatement(Option<String>),
     DivisionByZero,
     IndexOutOfBounds,
     InvalidOperation(String),
@@ -37,8 +46,24 @@ impl std::fmt::Display for InterpreterError {
             InterpreterError::TypeError(msg) => write!(f, "Type error: {}", msg),
             InterpreterError::RuntimeError(msg) => write!(f, "Runtime error: {}", msg),
             InterpreterError::ReturnValue(_) => write!(f, "Return statement outside function"),
-            InterpreterError::BreakStatement => write!(f, "Break statement outside loop"),
-            InterpreterError::ContinueStatement => write!(f, "Continue statement outside loop"),
+            InterpreterError::BreakStatement(None) => write!(f, "Break statement outside loop"),
+            InterpreterError::BreakStatement(Some(label)) => {
+                write!(
+                    f,
+                    "Break to label '{}' outside a loop with that label",
+                    label
+                )
+            }
+            InterpreterError::ContinueStatement(None) => {
+                write!(f, "Continue statement outside loop")
+            }
+            InterpreterError::ContinueStatement(Some(label)) => {
+                write!(
+                    f,
+                    "Continue to label '{}' outside a loop with that label",
+                    label
+                )
+            }
             InterpreterError::DivisionByZero => write!(f, "Division by zero"),
             InterpreterError::IndexOutOfBounds => write!(f, "Index out of bounds"),
             InterpreterError::InvalidOperation(msg) => write!(f, "Invalid operation: {}", msg),
@@ -49,6 +74,50 @@ impl std::fmt::Display for InterpreterError {
 
 impl std::error::Error for InterpreterError {}
 
+impl InterpreterError {
+    /// The value a `catch` clause binds for this error: thrown values as they
+    /// are, runtime failures as `{message, kind}`. Control flow, and errors
+    /// that point at a mistake in the program itself, are not catchable.
+    fn into_exception(self) -> Result<Value, InterpreterError> {
+        let (kind, message) = match self {
+            InterpreterError::Throw(value) => return Ok(value),
+            InterpreterError::RuntimeError(message) => ("RuntimeError", message),
+            InterpreterError::TypeError(message) => ("TypeError", message),
+            InterpreterError::DivisionByZero => ("DivisionByZero", self.to_string()),
+            InterpreterError::IndexOutOfBounds => ("IndexOutOfBounds", self.to_string()),
+            other => return Err(other),
+        };
+        Ok(Value::Object(HashMap::from([
+            ("message".to_string(), Value::String(message)),
+            ("kind".to_string(), Value::String(kind.to_string())),
+        ])))
+    }
+}
+
+/// The integers from `start` toward `end` in steps of `step`, which must not
+/// be zero. A step pointing away from `end` gives nothing, and `end` itself is
+/// only included when `inclusive` and a step lands on it.
+fn stepped_range(start: i64, end: i64, step: i64, inclusive: bool) -> Vec<Value> {
+    // Widened so stepping past either end of i64 can't overflow
+    let (mut n, end, step) = (i128::from(start), i128::from(end), i128::from(step));
+    let mut items = Vec::new();
+    while (step > 0 && n < end) || (step < 0 && n > end) || (inclusive && n == end) {
+        items.push(Value::Integer(n as i64));
+        if n == end {
+            break;
+        }
+        n += step;
+    }
+    items
+}
+
+/// Whether a `break`/`continue` aimed at `target` stops at a loop labeled
+/// `label`: unlabeled ones stop at the innermost loop, labeled ones only at
+/// the loop with that label
+fn targets(target: &Option<String>, label: &Option<String>) -> bool {
+    target.is_none() || target == label
+}
+
 /// Environment for variable storage with scoping
 #[derive(Debug, Clone)]
 pub struct Environment {
@@ -97,9 +166,37 @@ impl Environment {
         Err(InterpreterError::UndefinedVariable(name.to_string()))
     }
 
+    /// Whether definitions land in the outermost (global) scope
+    pub fn is_global_scope(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    /// Number of scopes, the innermost being `depth() - 1`
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Remove the innermost binding of `name`, returning its value
+    pub fn remove(&mut self, name: &str) -> Option<Value> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.remove(name))
+    }
+
+    /// Iterate over every binding in every scope, innermost last
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.scopes.iter().flat_map(|scope| scope.values())
+    }
+
     pub fn to_persistent(&self) -> PersistentHashMap<String, Value> {
+        self.bindings_in(0..self.scopes.len())
+    }
+
+    /// The bindings of the scopes in `range`, inner ones shadowing outer ones
+    pub fn bindings_in(&self, range: std::ops::Range<usize>) -> PersistentHashMap<String, Value> {
         let mut result = PersistentHashMap::new();
-        for scope in &self.scopes {
+        for scope in self.scopes.get(range).unwrap_or_default() {
             for (k, v) in scope {
                 result.insert(k.clone(), v.clone());
             }
@@ -114,6 +211,53 @@ impl Default for Environment {
     }
 }
 
+/// A host-supplied rewrite applied to every program before it runs
+pub type ProgramTransform = Box<dyn Fn(Program) -> Program>;
+
+/// Host access a sandboxed interpreter withholds until the embedder allows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Reading and writing files, including `import`
+    Filesystem,
+    /// Running shell commands
+    Process,
+    /// Reading and changing environment variables
+    Environment,
+    /// Loading and calling native libraries
+    Ffi,
+    /// HTTP requests and servers
+    Network,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 5] = [
+        Capability::Filesystem,
+        Capability::Process,
+        Capability::Environment,
+        Capability::Ffi,
+        Capability::Network,
+    ];
+
+    /// The builtins this capability guards
+    fn builtins(self) -> &'static [&'static str] {
+        match self {
+            Capability::Filesystem => &[
+                "readFile",
+                "writeFile",
+                "readLines",
+                "readFileChunks",
+                "appendFile",
+                "fileExists",
+                "listDir",
+            ],
+            Capability::Process => &["exec"],
+            Capability::Environment => &["getEnv", "setEnv"],
+            Capability::Ffi => &["ffiLoadLibrary", "ffiRegisterFunction", "ffiCall"],
+            Capability::Network => &["httpGet", "httpPost", "createServer", "cors"],
+        }
+    }
+}
+
 /// The A-lang interpreter with all WOW features
 pub struct Interpreter {
     env: Environment,
@@ -123,6 +267,71 @@ pub struct Interpreter {
     current_file: String,
     current_line: usize,
     auto_snapshot_counter: usize,
+    transforms: Vec<ProgramTransform>,
+    reactive_gc_threshold: usize,
+    /// Pending `defer` expressions, one frame per running function (plus the program)
+    defer_stack: Vec<Vec<Expression>>,
+    undo_history: UndoHistory,
+    /// When false, `assert` statements are skipped without evaluating them
+    assertions_enabled: bool,
+    denied: HashSet<Capability>,
+    /// Builtins removed by `deny`, kept so `allow` can restore them
+    withheld_builtins: HashMap<String, Value>,
+    /// Directory that file builtins and imports are confined to, if any
+    fs_root: Arc<RwLock<Option<PathBuf>>>,
+    /// Field lists of `struct` definitions, by name
+    structs: HashMap<String, Arc<[StructField]>>,
+    /// Newest top-level `fn` declaration of each name, shared with child
+    /// interpreters so reloads reach running effects too
+    functions: Arc<RwLock<HashMap<String, Arc<FunctionValue>>>>,
+    /// Source files being imported, outermost first, to catch import cycles
+    importing: Vec<PathBuf>,
+    /// Generator behind the random builtins, reseeded by `seed(n)`
+    rng: Arc<Mutex<Rng>>,
+    /// Functions currently running, outermost first
+    call_stack: Vec<CallFrame>,
+    /// Where an uncaught runtime error was raised, innermost call first,
+    /// recorded as the error leaves the innermost function
+    error_trace: Vec<String>,
+}
+
+/// Reactive node count below which unreachable nodes are not swept
+const REACTIVE_GC_MIN_THRESHOLD: usize = 64;
+
+/// Elements each parallelReduce worker folds before results are combined
+const PARALLEL_REDUCE_CHUNK: usize = 256;
+
+/// Most tasks spawnAll and raceAll run at the same time
+const TASK_GROUP_WORKERS: usize = 16;
+
+/// Largest string, in bytes, that `repeat` builds
+const MAX_REPEAT_BYTES: usize = 1 << 30;
+
+/// Builtins outside the capability lists that a parallel worker may not
+/// call: console IO, reactive control and shared mutable state
+const IMPURE_BUILTINS: &[&str] = &[
+    "print",
+    "input",
+    "deferEffects",
+    "flush",
+    "tick",
+    "observable",
+    "untrack",
+    "pushUndo",
+    "undo",
+    "redo",
+    "setRef",
+];
+
+/// A running function call. Its locals stay in the environment and are only
+/// copied out when a snapshot records the call stack.
+struct CallFrame {
+    function_name: String,
+    /// Line of the call that entered this function
+    line: usize,
+    /// Index of the scope the call pushed; the function's locals live there
+    /// and in the scopes above it, up to the next frame's
+    scope: usize,
 }
 
 impl Interpreter {
@@ -135,10 +344,37 @@ impl Interpreter {
             current_file: "main.al".to_string(),
             current_line: 0,
             auto_snapshot_counter: 0,
+            transforms: Vec::new(),
+            reactive_gc_threshold: REACTIVE_GC_MIN_THRESHOLD,
+            defer_stack: Vec::new(),
+            undo_history: UndoHistory::new(),
+            assertions_enabled: true,
+            denied: HashSet::new(),
+            withheld_builtins: HashMap::new(),
+            fs_root: Arc::new(RwLock::new(None)),
+            structs: HashMap::new(),
+            functions: Arc::new(RwLock::new(HashMap::new())),
+            importing: Vec::new(),
+            rng: Arc::new(Mutex::new(Rng::from_entropy())),
+            call_stack: Vec::new(),
+            error_trace: Vec::new(),
         };
 
         interpreter.register_builtins();
         interpreter.register_ffi_builtins();
+        interpreter.register_reactive_builtins();
+        interpreter.register_time_travel_builtins();
+        interpreter
+    }
+
+    /// An interpreter for untrusted scripts: every `Capability` is denied, so
+    /// only builtins for pure computation are defined and `import` is refused.
+    /// Use `allow` to hand individual capabilities back.
+    pub fn new_sandboxed() -> Self {
+        let mut interpreter = Self::new();
+        for capability in Capability::ALL {
+            interpreter.deny(capability);
+        }
         interpreter
     }
 
@@ -151,31 +387,197 @@ impl Interpreter {
             current_file: "main.al".to_string(),
             current_line: 0,
             auto_snapshot_counter: 0,
+            transforms: Vec::new(),
+            reactive_gc_threshold: REACTIVE_GC_MIN_THRESHOLD,
+            defer_stack: Vec::new(),
+            undo_history: UndoHistory::new(),
+            assertions_enabled: true,
+            denied: HashSet::new(),
+            withheld_builtins: HashMap::new(),
+            fs_root: Arc::new(RwLock::new(None)),
+            structs: HashMap::new(),
+            functions: Arc::new(RwLock::new(HashMap::new())),
+            importing: Vec::new(),
+            rng: Arc::new(Mutex::new(Rng::from_entropy())),
+            call_stack: Vec::new(),
+            error_trace: Vec::new(),
         };
 
         interpreter.register_builtins();
         interpreter.register_ffi_builtins();
+        interpreter.register_reactive_builtins();
+        interpreter.register_time_travel_builtins();
         interpreter
     }
 
+    /// An interpreter sharing this one's runtime state but running in its own
+    /// scope over `env`, used to re-run reactive bodies outside the main loop
+    fn child(
+        mut env: Environment,
+        reactive_ctx: Arc<ReactiveContext>,
+        time_travel: Arc<RwLock<TimeTravelDebugger>>,
+        ffi_context: Arc<Mutex<FFIContext>>,
+        current_file: String,
+    ) -> Self {
+        env.push_scope();
+        Self {
+            env,
+            reactive_ctx,
+            time_travel,
+            ffi_context,
+            current_file,
+            current_line: 0,
+            auto_snapshot_counter: 0,
+            transforms: Vec::new(),
+            // The snapshot env doesn't see every live binding, so never sweep from here
+            reactive_gc_threshold: usize::MAX,
+            defer_stack: Vec::new(),
+            undo_history: UndoHistory::new(),
+            assertions_enabled: true,
+            denied: HashSet::new(),
+            withheld_builtins: HashMap::new(),
+            fs_root: Arc::new(RwLock::new(None)),
+            structs: HashMap::new(),
+            functions: Arc::new(RwLock::new(HashMap::new())),
+            importing: Vec::new(),
+            rng: Arc::new(Mutex::new(Rng::from_entropy())),
+            call_stack: Vec::new(),
+            error_trace: Vec::new(),
+        }
+    }
+
+    /// Build child interpreters over a snapshot of the current environment.
+    /// The context owns the reactive nodes holding this, so it's held weakly;
+    /// the spawner yields None once the interpreter is gone.
+    fn child_spawner(&self) -> impl Fn() -> Option<Interpreter> + Send + Sync + 'static {
+        let reactive_ctx = Arc::downgrade(&self.reactive_ctx);
+        let time_travel = Arc::clone(&self.time_travel);
+        let ffi_context = Arc::clone(&self.ffi_context);
+        let env = self.env.clone();
+        let current_file = self.current_file.clone();
+        let assertions_enabled = self.assertions_enabled;
+        let denied = self.denied.clone();
+        let fs_root = Arc::clone(&self.fs_root);
+        let structs = self.structs.clone();
+        let functions = Arc::clone(&self.functions);
+        let rng = Arc::clone(&self.rng);
+
+        move || {
+            let reactive_ctx = reactive_ctx.upgrade()?;
+            let mut child = Interpreter::child(
+                env.clone(),
+                reactive_ctx,
+                Arc::clone(&time_travel),
+                Arc::clone(&ffi_context),
+                current_file.clone(),
+            );
+            child.assertions_enabled = assertions_enabled;
+            child.denied = denied.clone();
+            child.fs_root = Arc::clone(&fs_root);
+            child.structs = structs.clone();
+            child.functions = Arc::clone(&functions);
+            child.rng = Arc::clone(&rng);
+            Some(child)
+        }
+    }
+
+    /// Declared dependencies plus every identifier `visit` reports that is
+    /// currently bound to a reactive node
+    fn reactive_dependencies(
+        &self,
+        declared: &[String],
+        visit: impl FnOnce(&mut dyn FnMut(&str)),
+    ) -> Vec<String> {
+        let mut deps = declared.to_vec();
+        visit(&mut |name| {
+            if matches!(self.env.get(name), Some(Value::ReactiveRef(_)))
+                && !deps.iter().any(|dep| dep == name)
+            {
+                deps.push(name.to_string());
+            }
+        });
+        deps
+    }
+
+    /// Declare `name` as a computed value of `expression`, recomputed when one
+    /// of `deps` changes
+    fn declare_computed(
+        &mut self,
+        name: &str,
+        deps: Vec<String>,
+        expression: &Expression,
+    ) -> Result<Value, InterpreterError> {
+        let spawn_child = self.child_spawner();
+        let expression = expression.clone();
+        // Errors can't leave the compute closure: the first run's error is
+        // reported by this statement, later ones go to stderr like effects
+        let initial_error = Arc::new(Mutex::new(None));
+        let error_slot = Arc::clone(&initial_error);
+        let declared = Arc::new(AtomicBool::new(false));
+        let declared_in_closure = Arc::clone(&declared);
+
+        if self.reactive_ctx.contains(name) {
+            self.collect_reactive_garbage();
+        }
+        // Declaring a computed again replaces the earlier definition
+        if self.reactive_ctx.is_computed(name) {
+            self.reactive_ctx
+                .remove(name)
+                .map_err(|e| InterpreterError::RuntimeError(e.to_string()))?;
+        }
+        let node_id = self
+            .reactive_ctx
+            .register_computed(name.to_string(), deps, move |_| {
+                let Some(mut child) = spawn_child() else {
+                    return Value::Nil;
+                };
+                // `computed x = () => expr` and `computed x = expr` are both accepted
+                let result = match child.evaluate_expression(&expression) {
+                    Ok(func @ Value::Function(_)) => child.call_function(func, Vec::new()),
+                    other => other,
+                };
+                match result {
+                    Ok(value) => value,
+                    Err(e) if declared_in_closure.load(Ordering::SeqCst) => {
+                        eprintln!("Error in computed: {}", e);
+                        Value::Nil
+                    }
+                    Err(e) => {
+                        *error_slot.lock().unwrap() = Some(e);
+                        Value::Nil
+                    }
+                }
+            })
+            .map_err(|e| InterpreterError::RuntimeError(e.to_string()))?;
+        self.env
+            .define(name.to_string(), Value::ReactiveRef(node_id));
+        declared.store(true, Ordering::SeqCst);
+
+        let initial_error = initial_error.lock().unwrap().take();
+        match initial_error {
+            Some(e) => Err(e),
+            None => Ok(Value::Nil),
+        }
+    }
+
     /// Register built-in functions
     fn register_builtins(&mut self) {
         // print function
         self.env.define(
             "print".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 for arg in args {
-                    print!("{} ", arg);
+                    print!("{} ", arg.render(RenderStyle::Display)?);
                 }
                 println!();
                 Ok(Value::Nil)
-            })),
+            }),
         );
 
         // input function (Python-style)
         self.env.define(
             "input".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 use std::io::{self, Write};
 
                 // Show prompt if provided
@@ -195,40 +597,57 @@ impl Interpreter {
                     Ok(_) => Ok(Value::String(buffer.trim_end().to_string())),
                     Err(e) => Err(format!("Failed to read input: {}", e)),
                 }
-            })),
+            }),
         );
 
         // len function
         self.env.define(
             "len".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("len expects exactly 1 argument".to_string());
                 }
                 match &args[0] {
-                    Value::String(s) => Ok(Value::Integer(s.len() as i64)),
+                    Value::String(s) => Ok(Value::Integer(s.chars().count() as i64)),
                     Value::Array(arr) => Ok(Value::Integer(arr.len() as i64)),
+                    Value::Bytes(bytes) => Ok(Value::Integer(bytes.len() as i64)),
                     Value::Object(obj) => Ok(Value::Integer(obj.len() as i64)),
                     _ => Err(format!("len not supported for {}", args[0].type_name())),
                 }
-            })),
+            }),
         );
 
         // type_of function
         self.env.define(
             "type_of".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("type_of expects exactly 1 argument".to_string());
                 }
                 Ok(Value::String(args[0].type_name().to_string()))
-            })),
+            }),
+        );
+
+        // typeName function: typeName(value, deep?) renders structural types when deep
+        self.env.define(
+            "typeName".to_string(),
+            Value::native(|args| {
+                if args.is_empty() || args.len() > 2 {
+                    return Err("typeName expects 1 or 2 arguments (value, deep?)".to_string());
+                }
+                let deep = args.get(1).map(|v| v.is_truthy()).unwrap_or(false);
+                if deep {
+                    Ok(Value::String(Type::of_value(&args[0]).name()))
+                } else {
+                    Ok(Value::String(args[0].type_name().to_string()))
+                }
+            }),
         );
 
         // push function (for arrays)
         self.env.define(
             "push".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("push expects exactly 2 arguments".to_string());
                 }
@@ -240,13 +659,13 @@ impl Interpreter {
                     }
                     _ => Err("push expects an array as first argument".to_string()),
                 }
-            })),
+            }),
         );
 
         // pop function
         self.env.define(
             "pop".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("pop expects exactly 1 argument".to_string());
                 }
@@ -258,30 +677,42 @@ impl Interpreter {
                     }
                     _ => Err("pop expects an array".to_string()),
                 }
-            })),
+            }),
         );
 
-        // join function
+        // join function: elements are stringified like str(), separator defaults to ","
         self.env.define(
             "join".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.len() != 2 {
-                    return Err("join expects 2 arguments (array, separator)".to_string());
-                }
-                match (&args[0], &args[1]) {
-                    (Value::Array(arr), Value::String(sep)) => {
+            Value::native(|args| {
+                if args.is_empty() || args.len() > 2 {
+                    return Err("join expects 1 or 2 arguments (array, separator?)".to_string());
+                }
+                let sep = match args.get(1) {
+                    None => ",".to_string(),
+                    Some(sep @ (Value::String(_) | Value::Integer(_) | Value::Float(_))) => {
+                        sep.as_string()
+                    }
+                    Some(other) => {
+                        return Err(format!(
+                            "join separator must be a string or number, got {}",
+                            other.type_name()
+                        ))
+                    }
+                };
+                match &args[0] {
+                    Value::Array(arr) => {
                         let strings: Vec<String> = arr.iter().map(|v| v.as_string()).collect();
-                        Ok(Value::String(strings.join(sep)))
+                        Ok(Value::String(strings.join(&sep)))
                     }
-                    _ => Err("join expects (array, string)".to_string()),
+                    _ => Err("join expects an array as first argument".to_string()),
                 }
-            })),
+            }),
         );
 
         // split function
         self.env.define(
             "split".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("split expects 2 arguments (string, separator)".to_string());
                 }
@@ -295,24 +726,24 @@ impl Interpreter {
                     }
                     _ => Err("split expects (string, string)".to_string()),
                 }
-            })),
+            }),
         );
 
         // str function (convert to string)
         self.env.define(
             "str".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("str expects exactly 1 argument".to_string());
                 }
-                Ok(Value::String(args[0].as_string()))
-            })),
+                args[0].render(RenderStyle::Display).map(Value::String)
+            }),
         );
 
         // int function (convert to integer)
         self.env.define(
             "int".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("int expects exactly 1 argument".to_string());
                 }
@@ -320,13 +751,13 @@ impl Interpreter {
                     Some(n) => Ok(Value::Integer(n)),
                     None => Err(format!("Cannot convert {} to integer", args[0].type_name())),
                 }
-            })),
+            }),
         );
 
         // float function (convert to float)
         self.env.define(
             "float".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("float expects exactly 1 argument".to_string());
                 }
@@ -334,13 +765,48 @@ impl Interpreter {
                     Some(f) => Ok(Value::Float(f)),
                     None => Err(format!("Cannot convert {} to float", args[0].type_name())),
                 }
-            })),
+            }),
+        );
+
+        // parseInt function - Lenient integer parsing, nil when nothing parses
+        self.env.define(
+            "parseInt".to_string(),
+            Value::native(|args| {
+                let radix = match args.get(1) {
+                    None => None,
+                    Some(Value::Integer(r)) if (2..=36).contains(r) => Some(*r as u32),
+                    Some(_) => {
+                        return Err("parseInt radix must be an integer from 2 to 36".to_string())
+                    }
+                };
+                match args.as_slice() {
+                    [Value::String(s)] | [Value::String(s), _] => {
+                        Ok(Self::parse_int_prefix(s, radix)
+                            .map(Value::Integer)
+                            .unwrap_or(Value::Nil))
+                    }
+                    [_] | [_, _] => Err("parseInt expects a string".to_string()),
+                    _ => Err("parseInt expects 1 or 2 arguments (string, radix?)".to_string()),
+                }
+            }),
+        );
+
+        // parseFloat function - Leading float of a string, nil when there is none
+        self.env.define(
+            "parseFloat".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::String(s)] => Ok(Self::parse_float_prefix(s)
+                    .map(Value::Float)
+                    .unwrap_or(Value::Nil)),
+                [_] => Err("parseFloat expects a string".to_string()),
+                _ => Err("parseFloat expects exactly 1 argument".to_string()),
+            }),
         );
 
         // abs function
         self.env.define(
             "abs".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("abs expects exactly 1 argument".to_string());
                 }
@@ -349,13 +815,13 @@ impl Interpreter {
                     Value::Float(f) => Ok(Value::Float(f.abs())),
                     _ => Err("abs expects a number".to_string()),
                 }
-            })),
+            }),
         );
 
         // min function
         self.env.define(
             "min".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.is_empty() {
                     return Err("min expects at least 1 argument".to_string());
                 }
@@ -368,13 +834,13 @@ impl Interpreter {
                     }
                 }
                 Ok(min_val)
-            })),
+            }),
         );
 
         // max function
         self.env.define(
             "max".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.is_empty() {
                     return Err("max expects at least 1 argument".to_string());
                 }
@@ -387,13 +853,13 @@ impl Interpreter {
                     }
                 }
                 Ok(max_val)
-            })),
+            }),
         );
 
         // floor function
         self.env.define(
             "floor".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("floor expects exactly 1 argument".to_string());
                 }
@@ -402,13 +868,13 @@ impl Interpreter {
                     Value::Integer(n) => Ok(Value::Integer(*n)),
                     _ => Err("floor expects a number".to_string()),
                 }
-            })),
+            }),
         );
 
         // ceil function
         self.env.define(
             "ceil".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("ceil expects exactly 1 argument".to_string());
                 }
@@ -417,13 +883,13 @@ impl Interpreter {
                     Value::Integer(n) => Ok(Value::Integer(*n)),
                     _ => Err("ceil expects a number".to_string()),
                 }
-            })),
+            }),
         );
 
         // round function
         self.env.define(
             "round".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("round expects exactly 1 argument".to_string());
                 }
@@ -432,13 +898,13 @@ impl Interpreter {
                     Value::Integer(n) => Ok(Value::Integer(*n)),
                     _ => Err("round expects a number".to_string()),
                 }
-            })),
+            }),
         );
 
         // keys function (for objects)
         self.env.define(
             "keys".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("keys expects exactly 1 argument".to_string());
                 }
@@ -450,13 +916,13 @@ impl Interpreter {
                     }
                     _ => Err("keys expects an object".to_string()),
                 }
-            })),
+            }),
         );
 
         // values function (for objects)
         self.env.define(
             "values".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("values expects exactly 1 argument".to_string());
                 }
@@ -467,15 +933,85 @@ impl Interpreter {
                     }
                     _ => Err("values expects an object".to_string()),
                 }
-            })),
+            }),
+        );
+
+        // entries function: [key, value] pairs of an object, sorted by key
+        self.env.define(
+            "entries".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::Object(obj)] => {
+                    let mut pairs: Vec<(&String, &Value)> = obj.iter().collect();
+                    pairs.sort_by_key(|(key, _)| *key);
+                    Ok(Value::Array(
+                        pairs
+                            .into_iter()
+                            .map(|(k, v)| Value::Array(vec![Value::String(k.clone()), v.clone()]))
+                            .collect(),
+                    ))
+                }
+                [_] => Err("entries expects an object".to_string()),
+                _ => Err("entries expects exactly 1 argument".to_string()),
+            }),
+        );
+
+        // deleteKey / merge functions: copies without a key, or of several
+        // objects merged left to right
+        self.env.define(
+            "deleteKey".to_string(),
+            Value::Native(Arc::new(Self::builtin_delete_key)),
+        );
+        self.env.define(
+            "merge".to_string(),
+            Value::Native(Arc::new(Self::builtin_merge)),
+        );
+
+        // pick function: copy of an object with only the listed keys. Objects
+        // are hash maps, so neither pick nor omit preserves key order
+        self.env.define(
+            "pick".to_string(),
+            Value::native(|args| {
+                if args.len() != 2 {
+                    return Err("pick expects 2 arguments (object, keys)".to_string());
+                }
+                let keys = Self::key_list("pick", &args[1])?;
+                match &args[0] {
+                    Value::Object(obj) => Ok(Value::Object(
+                        keys.into_iter()
+                            .filter_map(|k| obj.get(&k).map(|v| (k, v.clone())))
+                            .collect(),
+                    )),
+                    other => Err(format!("pick expects an object, got {}", other.type_name())),
+                }
+            }),
+        );
+
+        // omit function: copy of an object without the listed keys
+        self.env.define(
+            "omit".to_string(),
+            Value::native(|args| {
+                if args.len() != 2 {
+                    return Err("omit expects 2 arguments (object, keys)".to_string());
+                }
+                let keys = Self::key_list("omit", &args[1])?;
+                match &args[0] {
+                    Value::Object(obj) => Ok(Value::Object(
+                        obj.iter()
+                            .filter(|(k, _)| !keys.contains(k))
+                            .map(|(k, v)| (k.clone(), v.clone()))
+                            .collect(),
+                    )),
+                    other => Err(format!("omit expects an object, got {}", other.type_name())),
+                }
+            }),
         );
 
         // range function
         self.env.define(
             "range".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.len() < 1 || args.len() > 2 {
-                    return Err("range expects 1 or 2 arguments".to_string());
+            Value::native(|args| {
+                if args.is_empty() || args.len() > 3 {
+                    return Err("range expects 1 to 3 arguments (start?, end, step?)".to_string());
                 }
                 let (start, end) = if args.len() == 1 {
                     (0, args[0].as_integer().unwrap_or(0))
@@ -485,9 +1021,14 @@ impl Interpreter {
                         args[1].as_integer().unwrap_or(0),
                     )
                 };
-                let arr: Vec<Value> = (start..end).map(Value::Integer).collect();
-                Ok(Value::Array(arr))
-            })),
+                let step = match args.get(2) {
+                    Some(Value::Integer(0)) => return Err("range step cannot be 0".to_string()),
+                    Some(Value::Integer(step)) => *step,
+                    Some(_) => return Err("range step must be an integer".to_string()),
+                    None => 1,
+                };
+                Ok(Value::Array(stepped_range(start, end, step, false)))
+            }),
         );
 
         // map, filter, reduce are handled specially in call_function
@@ -496,7 +1037,7 @@ impl Interpreter {
         // reverse function
         self.env.define(
             "reverse".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("reverse expects exactly 1 argument".to_string());
                 }
@@ -512,13 +1053,31 @@ impl Interpreter {
                     }
                     _ => Err("reverse expects an array or string".to_string()),
                 }
-            })),
+            }),
+        );
+
+        // sort function
+        self.env.define(
+            "sort".to_string(),
+            Value::native(|args| {
+                if args.len() != 1 {
+                    return Err("sort expects exactly 1 argument".to_string());
+                }
+                match &args[0] {
+                    Value::Array(arr) => {
+                        let mut new_arr = arr.clone();
+                        Self::sort_values(&mut new_arr)?;
+                        Ok(Value::Array(new_arr))
+                    }
+                    _ => Err("sort expects an array".to_string()),
+                }
+            }),
         );
 
         // slice function
         self.env.define(
             "slice".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() < 2 || args.len() > 3 {
                     return Err("slice expects 2 or 3 arguments (array, start, end?)".to_string());
                 }
@@ -533,15 +1092,161 @@ impl Interpreter {
                         let sliced = arr[start.min(arr.len())..end.min(arr.len())].to_vec();
                         Ok(Value::Array(sliced))
                     }
-                    _ => Err("slice expects an array".to_string()),
+                    Value::Bytes(bytes) => {
+                        let start = args[1].as_integer().unwrap_or(0) as usize;
+                        let end = if args.len() == 3 {
+                            args[2].as_integer().unwrap_or(bytes.len() as i64) as usize
+                        } else {
+                            bytes.len()
+                        };
+                        let end = end.min(bytes.len());
+                        Ok(Value::Bytes(bytes[start.min(end)..end].to_vec()))
+                    }
+                    _ => Err("slice expects an array or bytes".to_string()),
+                }
+            }),
+        );
+
+        // take function - First n elements
+        self.env.define(
+            "take".to_string(),
+            Value::native(|args| {
+                if args.len() != 2 {
+                    return Err("take expects 2 arguments (array, n)".to_string());
+                }
+                match (&args[0], &args[1]) {
+                    (Value::Array(arr), Value::Integer(n)) => {
+                        let n = (*n).clamp(0, arr.len() as i64) as usize;
+                        Ok(Value::Array(arr[..n].to_vec()))
+                    }
+                    _ => Err("take expects (array, integer)".to_string()),
+                }
+            }),
+        );
+
+        // drop function - All but the first n elements
+        self.env.define(
+            "drop".to_string(),
+            Value::native(|args| {
+                if args.len() != 2 {
+                    return Err("drop expects 2 arguments (array, n)".to_string());
+                }
+                match (&args[0], &args[1]) {
+                    (Value::Array(arr), Value::Integer(n)) => {
+                        let n = (*n).clamp(0, arr.len() as i64) as usize;
+                        Ok(Value::Array(arr[n..].to_vec()))
+                    }
+                    _ => Err("drop expects (array, integer)".to_string()),
+                }
+            }),
+        );
+
+        // rotate function - Cyclic shift right by n (left when negative)
+        self.env.define(
+            "rotate".to_string(),
+            Value::native(|args| {
+                if args.len() != 2 {
+                    return Err("rotate expects 2 arguments (array, n)".to_string());
+                }
+                match (&args[0], &args[1]) {
+                    (Value::Array(arr), Value::Integer(n)) => {
+                        let mut rotated = arr.clone();
+                        if !rotated.is_empty() {
+                            let shift = n.rem_euclid(rotated.len() as i64) as usize;
+                            rotated.rotate_right(shift);
+                        }
+                        Ok(Value::Array(rotated))
+                    }
+                    _ => Err("rotate expects (array, integer)".to_string()),
+                }
+            }),
+        );
+
+        // flatten function - Splice nested arrays in, one level deep
+        self.env.define(
+            "flatten".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::Array(arr)] => {
+                    let mut flat = Vec::with_capacity(arr.len());
+                    for item in arr {
+                        match item {
+                            Value::Array(inner) => flat.extend(inner.iter().cloned()),
+                            other => flat.push(other.clone()),
+                        }
+                    }
+                    Ok(Value::Array(flat))
+                }
+                [_] => Err("flatten expects an array".to_string()),
+                _ => Err("flatten expects exactly 1 argument".to_string()),
+            }),
+        );
+
+        // unique function - Drop repeated elements, keeping the first of each
+        self.env.define(
+            "unique".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::Array(arr)] => {
+                    let mut seen: Vec<Value> = Vec::new();
+                    for item in arr {
+                        if !seen.contains(item) {
+                            seen.push(item.clone());
+                        }
+                    }
+                    Ok(Value::Array(seen))
                 }
-            })),
+                [_] => Err("unique expects an array".to_string()),
+                _ => Err("unique expects exactly 1 argument".to_string()),
+            }),
+        );
+
+        // sum / product functions - Fold an array of numbers
+        for name in ["sum", "product"] {
+            self.env.define(
+                name.to_string(),
+                Value::Native(Arc::new(move |args| match <[Value; 1]>::try_from(args) {
+                    Ok([array]) => Self::builtin_sum_product(name, array),
+                    Err(_) => Err(NativeError::Runtime(format!(
+                        "{} expects exactly 1 argument",
+                        name
+                    ))),
+                })),
+            );
+        }
+
+        // enumerate function - [index, value] pairs
+        self.env.define(
+            "enumerate".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::Array(arr)] => Ok(Value::Array(
+                    arr.iter()
+                        .enumerate()
+                        .map(|(i, v)| Value::Array(vec![Value::Integer(i as i64), v.clone()]))
+                        .collect(),
+                )),
+                [_] => Err("enumerate expects an array".to_string()),
+                _ => Err("enumerate expects exactly 1 argument".to_string()),
+            }),
+        );
+
+        // zip function - [a, b] pairs, as many as the shorter array has
+        self.env.define(
+            "zip".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::Array(a), Value::Array(b)] => Ok(Value::Array(
+                    a.iter()
+                        .zip(b.iter())
+                        .map(|(x, y)| Value::Array(vec![x.clone(), y.clone()]))
+                        .collect(),
+                )),
+                [_, _] => Err("zip expects (array, array)".to_string()),
+                _ => Err("zip expects 2 arguments (array, array)".to_string()),
+            }),
         );
 
         // indexOf function
         self.env.define(
             "indexOf".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("indexOf expects 2 arguments (array/string, value)".to_string());
                 }
@@ -560,13 +1265,13 @@ impl Interpreter {
                     },
                     _ => Err("indexOf expects (array, value) or (string, string)".to_string()),
                 }
-            })),
+            }),
         );
 
         // includes function
         self.env.define(
             "includes".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("includes expects 2 arguments (array/string, value)".to_string());
                 }
@@ -577,13 +1282,13 @@ impl Interpreter {
                     }
                     _ => Err("includes expects (array, value) or (string, string)".to_string()),
                 }
-            })),
+            }),
         );
 
         // toUpperCase function
         self.env.define(
             "toUpperCase".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("toUpperCase expects exactly 1 argument".to_string());
                 }
@@ -591,13 +1296,13 @@ impl Interpreter {
                     Value::String(s) => Ok(Value::String(s.to_uppercase())),
                     _ => Err("toUpperCase expects a string".to_string()),
                 }
-            })),
+            }),
         );
 
         // toLowerCase function
         self.env.define(
             "toLowerCase".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("toLowerCase expects exactly 1 argument".to_string());
                 }
@@ -605,13 +1310,13 @@ impl Interpreter {
                     Value::String(s) => Ok(Value::String(s.to_lowercase())),
                     _ => Err("toLowerCase expects a string".to_string()),
                 }
-            })),
+            }),
         );
 
         // trim function
         self.env.define(
             "trim".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("trim expects exactly 1 argument".to_string());
                 }
@@ -619,13 +1324,13 @@ impl Interpreter {
                     Value::String(s) => Ok(Value::String(s.trim().to_string())),
                     _ => Err("trim expects a string".to_string()),
                 }
-            })),
+            }),
         );
 
         // replace function
         self.env.define(
             "replace".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 3 {
                     return Err(
                         "replace expects 3 arguments (string, search, replacement)".to_string()
@@ -637,74 +1342,215 @@ impl Interpreter {
                     ),
                     _ => Err("replace expects (string, string, string)".to_string()),
                 }
-            })),
+            }),
         );
 
-        // ===== FILE I/O =====
-
-        // readFile function
+        // startsWith function
         self.env.define(
-            "readFile".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.len() != 1 {
-                    return Err("readFile expects exactly 1 argument (path)".to_string());
-                }
-                match &args[0] {
-                    Value::String(path) => match std::fs::read_to_string(path) {
-                        Ok(content) => Ok(Value::String(content)),
-                        Err(e) => Err(format!("Failed to read file: {}", e)),
-                    },
-                    _ => Err("readFile expects a string path".to_string()),
+            "startsWith".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::String(s), Value::String(prefix)] => {
+                    Ok(Value::Boolean(s.starts_with(prefix.as_str())))
                 }
-            })),
+                _ => Err("startsWith expects 2 arguments (string, prefix)".to_string()),
+            }),
         );
 
-        // writeFile function
+        // endsWith function
         self.env.define(
-            "writeFile".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.len() != 2 {
-                    return Err("writeFile expects 2 arguments (path, content)".to_string());
-                }
-                match (&args[0], &args[1]) {
-                    (Value::String(path), Value::String(content)) => {
-                        match std::fs::write(path, content) {
-                            Ok(_) => Ok(Value::Nil),
-                            Err(e) => Err(format!("Failed to write file: {}", e)),
-                        }
-                    }
-                    _ => Err("writeFile expects (string, string)".to_string()),
+            "endsWith".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::String(s), Value::String(suffix)] => {
+                    Ok(Value::Boolean(s.ends_with(suffix.as_str())))
                 }
-            })),
+                _ => Err("endsWith expects 2 arguments (string, suffix)".to_string()),
+            }),
         );
 
-        // readLines function
+        // repeat function: repeat(string, count)
         self.env.define(
-            "readLines".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.len() != 1 {
-                    return Err("readLines expects exactly 1 argument (path)".to_string());
-                }
-                match &args[0] {
-                    Value::String(path) => match std::fs::read_to_string(path) {
-                        Ok(content) => {
-                            let lines: Vec<Value> = content
-                                .lines()
-                                .map(|l| Value::String(l.to_string()))
-                                .collect();
-                            Ok(Value::Array(lines))
+            "repeat".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::String(s), Value::Integer(n)] if *n >= 0 => {
+                    let size = usize::try_from(*n)
+                        .ok()
+                        .and_then(|count| s.len().checked_mul(count));
+                    match size {
+                        Some(size) if size <= MAX_REPEAT_BYTES => {
+                            Ok(Value::String(s.repeat(*n as usize)))
                         }
-                        Err(e) => Err(format!("Failed to read file: {}", e)),
-                    },
-                    _ => Err("readLines expects a string path".to_string()),
+                        _ => Err(format!("repeat result would be too large ({} copies)", n)),
+                    }
                 }
-            })),
+                [Value::String(_), Value::Integer(n)] => {
+                    Err(format!("repeat count must not be negative, got {}", n))
+                }
+                _ => Err("repeat expects 2 arguments (string, count)".to_string()),
+            }),
+        );
+
+        // padStart / padEnd functions: pad(string, length, pad?) to `length`
+        // characters with repeats of `pad` (default " "), cut to fit
+        for (name, at_start) in [("padStart", true), ("padEnd", false)] {
+            self.env.define(
+                name.to_string(),
+                Value::native(move |args| {
+                    let (s, width, pad) = match args.as_slice() {
+                        [Value::String(s), Value::Integer(width)] => (s, *width, " "),
+                        [Value::String(s), Value::Integer(width), Value::String(pad)] => {
+                            (s, *width, pad.as_str())
+                        }
+                        _ => {
+                            return Err(format!(
+                                "{} expects 2 or 3 arguments (string, length, pad?)",
+                                name
+                            ))
+                        }
+                    };
+                    let missing = (width.max(0) as usize).saturating_sub(s.chars().count());
+                    if missing == 0 || pad.is_empty() {
+                        return Ok(Value::String(s.clone()));
+                    }
+                    let fill: String = pad.chars().cycle().take(missing).collect();
+                    Ok(Value::String(if at_start {
+                        fill + s
+                    } else {
+                        s.clone() + &fill
+                    }))
+                }),
+            );
+        }
+
+        // substring function: substring(string, start, end?) by character
+        // index; indices are clamped to the string and start >= end gives ""
+        self.env.define(
+            "substring".to_string(),
+            Value::native(|args| {
+                let (s, start, end) = match args.as_slice() {
+                    [Value::String(s), Value::Integer(start)] => (s, *start, i64::MAX),
+                    [Value::String(s), Value::Integer(start), Value::Integer(end)] => {
+                        (s, *start, *end)
+                    }
+                    _ => {
+                        return Err(
+                            "substring expects 2 or 3 arguments (string, start, end?)".to_string()
+                        )
+                    }
+                };
+                let start = start.max(0) as usize;
+                let end = end.max(0) as usize;
+                Ok(Value::String(
+                    s.chars()
+                        .skip(start)
+                        .take(end.saturating_sub(start))
+                        .collect(),
+                ))
+            }),
+        );
+
+        // render function: render(template, context, strict?)
+        self.env.define(
+            "render".to_string(),
+            Value::native(|args| {
+                if args.len() != 2 && args.len() != 3 {
+                    return Err(
+                        "render expects 2 or 3 arguments (template, context, strict?)".to_string(),
+                    );
+                }
+                let strict = args.get(2).map(|v| v.is_truthy()).unwrap_or(false);
+                match (&args[0], &args[1]) {
+                    (Value::String(template), Value::Object(_)) => {
+                        Self::render_template(template, &args[1], strict).map(Value::String)
+                    }
+                    _ => Err("render expects (string, object)".to_string()),
+                }
+            }),
+        );
+
+        // format function: format(template, ...args) with {} and {0} placeholders
+        self.env.define(
+            "format".to_string(),
+            Value::native(|args| match args.split_first() {
+                Some((Value::String(template), rest)) => {
+                    Self::format_string(template, rest).map(Value::String)
+                }
+                _ => Err("format expects a template string and its arguments".to_string()),
+            }),
+        );
+
+        // ===== FILE I/O =====
+
+        // readFile function
+        let fs_root = Arc::clone(&self.fs_root);
+        self.env.define(
+            "readFile".to_string(),
+            Value::native(move |args| {
+                if args.len() != 1 {
+                    return Err("readFile expects exactly 1 argument (path)".to_string());
+                }
+                match &args[0] {
+                    Value::String(path) => {
+                        match std::fs::read_to_string(Self::resolve_fs_path(&fs_root, path)?) {
+                            Ok(content) => Ok(Value::String(content)),
+                            Err(e) => Err(format!("Failed to read file: {}", e)),
+                        }
+                    }
+                    _ => Err("readFile expects a string path".to_string()),
+                }
+            }),
+        );
+
+        // writeFile function
+        let fs_root = Arc::clone(&self.fs_root);
+        self.env.define(
+            "writeFile".to_string(),
+            Value::native(move |args| {
+                if args.len() != 2 {
+                    return Err("writeFile expects 2 arguments (path, content)".to_string());
+                }
+                match (&args[0], &args[1]) {
+                    (Value::String(path), Value::String(content)) => {
+                        match std::fs::write(Self::resolve_fs_path(&fs_root, path)?, content) {
+                            Ok(_) => Ok(Value::Nil),
+                            Err(e) => Err(format!("Failed to write file: {}", e)),
+                        }
+                    }
+                    _ => Err("writeFile expects (string, string)".to_string()),
+                }
+            }),
+        );
+
+        // readLines function
+        let fs_root = Arc::clone(&self.fs_root);
+        self.env.define(
+            "readLines".to_string(),
+            Value::native(move |args| {
+                if args.len() != 1 {
+                    return Err("readLines expects exactly 1 argument (path)".to_string());
+                }
+                match &args[0] {
+                    Value::String(path) => {
+                        match std::fs::read_to_string(Self::resolve_fs_path(&fs_root, path)?) {
+                            Ok(content) => {
+                                let lines: Vec<Value> = content
+                                    .lines()
+                                    .map(|l| Value::String(l.to_string()))
+                                    .collect();
+                                Ok(Value::Array(lines))
+                            }
+                            Err(e) => Err(format!("Failed to read file: {}", e)),
+                        }
+                    }
+                    _ => Err("readLines expects a string path".to_string()),
+                }
+            }),
         );
 
         // appendFile function
+        let fs_root = Arc::clone(&self.fs_root);
         self.env.define(
             "appendFile".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(move |args| {
                 if args.len() != 2 {
                     return Err("appendFile expects 2 arguments (path, content)".to_string());
                 }
@@ -712,6 +1558,7 @@ impl Interpreter {
                     (Value::String(path), Value::String(content)) => {
                         use std::fs::OpenOptions;
                         use std::io::Write;
+                        let path = Self::resolve_fs_path(&fs_root, path)?;
                         match OpenOptions::new().create(true).append(true).open(path) {
                             Ok(mut file) => match writeln!(file, "{}", content) {
                                 Ok(_) => Ok(Value::Nil),
@@ -722,21 +1569,165 @@ impl Interpreter {
                     }
                     _ => Err("appendFile expects (string, string)".to_string()),
                 }
-            })),
+            }),
         );
 
         // fileExists function
+        let fs_root = Arc::clone(&self.fs_root);
         self.env.define(
             "fileExists".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(move |args| {
                 if args.len() != 1 {
                     return Err("fileExists expects exactly 1 argument (path)".to_string());
                 }
                 match &args[0] {
-                    Value::String(path) => Ok(Value::Boolean(std::path::Path::new(path).exists())),
+                    Value::String(path) => Ok(Value::Boolean(
+                        Self::resolve_fs_path(&fs_root, path)?.exists(),
+                    )),
                     _ => Err("fileExists expects a string path".to_string()),
                 }
-            })),
+            }),
+        );
+
+        // listDir function - sorted names of a directory's entries
+        let fs_root = Arc::clone(&self.fs_root);
+        self.env.define(
+            "listDir".to_string(),
+            Value::native(move |args| {
+                if args.len() != 1 {
+                    return Err("listDir expects exactly 1 argument (path)".to_string());
+                }
+                let Value::String(path) = &args[0] else {
+                    return Err("listDir expects a string path".to_string());
+                };
+                let entries = std::fs::read_dir(Self::resolve_fs_path(&fs_root, path)?)
+                    .map_err(|e| format!("Failed to list directory: {}", e))?;
+                let mut names = entries
+                    .map(|entry| {
+                        entry
+                            .map(|e| e.file_name().to_string_lossy().into_owned())
+                            .map_err(|e| format!("Failed to list directory: {}", e))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                names.sort();
+                Ok(Value::Array(names.into_iter().map(Value::String).collect()))
+            }),
+        );
+
+        // ===== FUNCTIONS =====
+
+        // arity function: parameter count, or -1 for variadic functions and natives
+        self.env.define(
+            "arity".to_string(),
+            Value::native(|args| {
+                if args.len() != 1 {
+                    return Err("arity expects exactly 1 argument".to_string());
+                }
+                match &args[0] {
+                    Value::Function(func) if func.variadic => Ok(Value::Integer(-1)),
+                    Value::Function(func) => Ok(Value::Integer(func.parameters.len() as i64)),
+                    Value::Native(_) => Ok(Value::Integer(-1)),
+                    other => Err(format!(
+                        "arity expects a function, got {}",
+                        other.type_name()
+                    )),
+                }
+            }),
+        );
+
+        // functionName function: declared name, or nil for lambdas and natives
+        self.env.define(
+            "functionName".to_string(),
+            Value::native(|args| {
+                if args.len() != 1 {
+                    return Err("functionName expects exactly 1 argument".to_string());
+                }
+                match &args[0] {
+                    Value::Function(func) => {
+                        Ok(func.name.clone().map(Value::String).unwrap_or(Value::Nil))
+                    }
+                    Value::Native(_) => Ok(Value::Nil),
+                    other => Err(format!(
+                        "functionName expects a function, got {}",
+                        other.type_name()
+                    )),
+                }
+            }),
+        );
+
+        // bind function: copy of a function with `this` pre-bound
+        self.env.define(
+            "bind".to_string(),
+            Value::native(|args| {
+                if args.len() != 2 {
+                    return Err("bind expects 2 arguments (function, this)".to_string());
+                }
+                match &args[0] {
+                    Value::Function(func) => {
+                        let mut bound = FunctionValue::clone(func);
+                        bound.closure.insert("this".to_string(), args[1].clone());
+                        Ok(Value::Function(Arc::new(bound)))
+                    }
+                    other => Err(format!(
+                        "bind expects a user-defined function, got {}",
+                        other.type_name()
+                    )),
+                }
+            }),
+        );
+
+        // ===== REFERENCES =====
+
+        // ref function - shared mutable cell holding a value
+        self.env.define(
+            "ref".to_string(),
+            Value::native(|args| {
+                if args.len() != 1 {
+                    return Err("ref expects exactly 1 argument".to_string());
+                }
+                Ok(Value::Ref(Arc::new(RwLock::new(args[0].clone()))))
+            }),
+        );
+
+        // deref function - current value of a ref
+        self.env.define(
+            "deref".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::Ref(cell)] => Ok(cell.read().unwrap().clone()),
+                [other] => Err(format!("deref expects a ref, got {}", other.type_name())),
+                _ => Err("deref expects exactly 1 argument".to_string()),
+            }),
+        );
+
+        // setRef function - replace a ref's value, visible to every holder
+        self.env.define(
+            "setRef".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::Ref(cell), value] => {
+                    *cell.write().unwrap() = value.clone();
+                    Ok(Value::Nil)
+                }
+                [other, _] => Err(format!("setRef expects a ref, got {}", other.type_name())),
+                _ => Err("setRef expects 2 arguments (ref, value)".to_string()),
+            }),
+        );
+
+        // deepEqual function - structural equality that compares refs by contents
+        self.env.define(
+            "deepEqual".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [a, b] => Ok(Value::Boolean(a.deep_equal(b))),
+                _ => Err("deepEqual expects exactly 2 arguments".to_string()),
+            }),
+        );
+
+        // clone function - an independent deep copy, refs included
+        self.env.define(
+            "clone".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [value] => Ok(value.deep_clone()),
+                _ => Err("clone expects exactly 1 argument".to_string()),
+            }),
         );
 
         // ===== JSON =====
@@ -744,7 +1735,7 @@ impl Interpreter {
         // parseJSON function
         self.env.define(
             "parseJSON".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("parseJSON expects exactly 1 argument (json string)".to_string());
                 }
@@ -757,24 +1748,106 @@ impl Interpreter {
                     }
                     _ => Err("parseJSON expects a string".to_string()),
                 }
-            })),
+            }),
         );
 
-        // stringifyJSON function
+        // stringifyJSON function: stringifyJSON(value, indent?) or
+        // stringifyJSON(value, {indent, skipFunctions})
         self.env.define(
             "stringifyJSON".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
+                let (indent, skip_functions) = match args.as_slice() {
+                    [_] => (Value::Nil, false),
+                    [_, Value::Object(options)] => (
+                        options.get("indent").cloned().unwrap_or(Value::Nil),
+                        options.get("skipFunctions").is_some_and(Value::is_truthy),
+                    ),
+                    [_, indent] => (indent.clone(), false),
+                    _ => {
+                        return Err(
+                            "stringifyJSON expects 1 or 2 arguments (value, indent or options)"
+                                .to_string(),
+                        )
+                    }
+                };
+                let indent = match indent {
+                    Value::Nil => 0,
+                    Value::Integer(n) if n >= 0 => n as usize,
+                    other => {
+                        return Err(format!(
+                            "stringifyJSON indent must be a non-negative integer, got {}",
+                            other
+                        ))
+                    }
+                };
+
+                let json = args[0]
+                    .to_json_with(skip_functions)?
+                    .unwrap_or(serde_json::Value::Null);
+                let text = if indent == 0 {
+                    serde_json::to_string(&json)
+                } else {
+                    let indent = " ".repeat(indent);
+                    let formatter =
+                        serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+                    let mut out = Vec::new();
+                    let mut serializer =
+                        serde_json::Serializer::with_formatter(&mut out, formatter);
+                    serde::Serialize::serialize(&json, &mut serializer)
+                        .map(|()| String::from_utf8_lossy(&out).into_owned())
+                };
+                text.map(Value::String)
+                    .map_err(|e| format!("Failed to stringify JSON: {}", e))
+            }),
+        );
+
+        // ===== TOML =====
+
+        // parseTOML function
+        self.env.define(
+            "parseTOML".to_string(),
+            Value::native(|args| {
                 if args.len() != 1 {
-                    return Err("stringifyJSON expects exactly 1 argument".to_string());
+                    return Err("parseTOML expects exactly 1 argument (toml string)".to_string());
                 }
-                match Self::value_to_json(&args[0]) {
-                    Ok(json) => match serde_json::to_string(&json) {
-                        Ok(s) => Ok(Value::String(s)),
-                        Err(e) => Err(format!("Failed to stringify JSON: {}", e)),
-                    },
-                    Err(e) => Err(e),
+                match &args[0] {
+                    Value::String(source) => crate::stdlib::toml::parse(source)
+                        .map_err(|e| format!("Failed to parse TOML: {}", e)),
+                    _ => Err("parseTOML expects a string".to_string()),
+                }
+            }),
+        );
+
+        // ===== SERIALIZATION =====
+
+        // serialize function - Encode a value as "json" (default), "msgpack" or "cbor"
+        self.env.define(
+            "serialize".to_string(),
+            Value::native(|args| {
+                if args.is_empty() || args.len() > 2 {
+                    return Err("serialize expects 1 or 2 arguments (value, format)".to_string());
+                }
+                match args.get(1) {
+                    None => Self::serialize_value(&args[0], "json"),
+                    Some(Value::String(format)) => Self::serialize_value(&args[0], format),
+                    Some(_) => Err("serialize expects the format to be a string".to_string()),
+                }
+            }),
+        );
+
+        // deserialize function - Decode the output of serialize
+        self.env.define(
+            "deserialize".to_string(),
+            Value::native(|args| {
+                if args.is_empty() || args.len() > 2 {
+                    return Err("deserialize expects 1 or 2 arguments (data, format)".to_string());
+                }
+                match args.get(1) {
+                    None => Self::deserialize_value(&args[0], "json"),
+                    Some(Value::String(format)) => Self::deserialize_value(&args[0], format),
+                    Some(_) => Err("deserialize expects the format to be a string".to_string()),
                 }
-            })),
+            }),
         );
 
         // ===== ADVANCED MATH =====
@@ -782,7 +1855,7 @@ impl Interpreter {
         // sqrt function
         self.env.define(
             "sqrt".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("sqrt expects exactly 1 argument".to_string());
                 }
@@ -790,13 +1863,13 @@ impl Interpreter {
                     Some(n) => Ok(Value::Float(n.sqrt())),
                     None => Err("sqrt expects a number".to_string()),
                 }
-            })),
+            }),
         );
 
         // pow function
         self.env.define(
             "pow".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("pow expects 2 arguments (base, exponent)".to_string());
                 }
@@ -804,52 +1877,94 @@ impl Interpreter {
                     (Some(base), Some(exp)) => Ok(Value::Float(base.powf(exp))),
                     _ => Err("pow expects numbers".to_string()),
                 }
-            })),
+            }),
         );
 
         // random function (0.0 to 1.0)
+        let rng = Arc::clone(&self.rng);
         self.env.define(
             "random".to_string(),
-            Value::Native(Arc::new(|_args| {
-                use std::collections::hash_map::RandomState;
-                use std::hash::{BuildHasher, Hash, Hasher};
-                let state = RandomState::new();
-                let mut hasher = state.build_hasher();
-                std::time::SystemTime::now().hash(&mut hasher);
-                let hash = hasher.finish();
-                let random = (hash as f64) / (u64::MAX as f64);
-                Ok(Value::Float(random))
-            })),
+            Value::native(move |_args| Ok(Value::Float(rng.lock().unwrap().next_f64()))),
         );
 
-        // randomInt function
+        // randomInt function - randomInt(min, max), both ends included
+        let rng = Arc::clone(&self.rng);
         self.env.define(
             "randomInt".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(move |args| {
                 if args.len() != 2 {
                     return Err("randomInt expects 2 arguments (min, max)".to_string());
                 }
                 match (args[0].as_integer(), args[1].as_integer()) {
+                    (Some(min), Some(max)) if min > max => {
+                        Err(format!("randomInt min {} is above max {}", min, max))
+                    }
                     (Some(min), Some(max)) => {
-                        use std::collections::hash_map::RandomState;
-                        use std::hash::{BuildHasher, Hash, Hasher};
-                        let state = RandomState::new();
-                        let mut hasher = state.build_hasher();
-                        std::time::SystemTime::now().hash(&mut hasher);
-                        let hash = hasher.finish();
-                        let range = (max - min) as u64;
-                        let result = min + (hash % range) as i64;
-                        Ok(Value::Integer(result))
+                        Ok(Value::Integer(rng.lock().unwrap().between(min, max)))
                     }
                     _ => Err("randomInt expects integers".to_string()),
                 }
-            })),
+            }),
+        );
+
+        // seed function - make random, randomInt, shuffle and sample reproducible
+        let rng = Arc::clone(&self.rng);
+        self.env.define(
+            "seed".to_string(),
+            Value::native(move |args| match args.as_slice() {
+                [Value::Integer(n)] => {
+                    *rng.lock().unwrap() = Rng::seeded(*n as u64);
+                    Ok(Value::Nil)
+                }
+                _ => Err("seed expects exactly 1 integer argument".to_string()),
+            }),
+        );
+
+        // shuffle function - a new array with the elements in random order
+        let rng = Arc::clone(&self.rng);
+        self.env.define(
+            "shuffle".to_string(),
+            Value::native(move |args| match args.as_slice() {
+                [Value::Array(items)] => {
+                    let mut items = items.clone();
+                    rng.lock().unwrap().shuffle(&mut items);
+                    Ok(Value::Array(items))
+                }
+                [other] => Err(format!(
+                    "shuffle expects an array, got {}",
+                    other.type_name()
+                )),
+                _ => Err("shuffle expects exactly 1 argument (array)".to_string()),
+            }),
+        );
+
+        // sample function - n elements drawn without replacement
+        let rng = Arc::clone(&self.rng);
+        self.env.define(
+            "sample".to_string(),
+            Value::native(move |args| match args.as_slice() {
+                [Value::Array(items), Value::Integer(n)] => {
+                    if *n < 0 || *n as usize > items.len() {
+                        return Err(format!(
+                            "sample size {} is out of range for an array of {}",
+                            n,
+                            items.len()
+                        ));
+                    }
+                    let count = *n as usize;
+                    let mut items = items.clone();
+                    rng.lock().unwrap().partial_shuffle(&mut items, count);
+                    items.truncate(count);
+                    Ok(Value::Array(items))
+                }
+                _ => Err("sample expects 2 arguments (array, count)".to_string()),
+            }),
         );
 
         // sin function
         self.env.define(
             "sin".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("sin expects exactly 1 argument".to_string());
                 }
@@ -857,13 +1972,13 @@ impl Interpreter {
                     Some(n) => Ok(Value::Float(n.sin())),
                     None => Err("sin expects a number".to_string()),
                 }
-            })),
+            }),
         );
 
         // cos function
         self.env.define(
             "cos".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("cos expects exactly 1 argument".to_string());
                 }
@@ -871,13 +1986,13 @@ impl Interpreter {
                     Some(n) => Ok(Value::Float(n.cos())),
                     None => Err("cos expects a number".to_string()),
                 }
-            })),
+            }),
         );
 
         // tan function
         self.env.define(
             "tan".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("tan expects exactly 1 argument".to_string());
                 }
@@ -885,87 +2000,229 @@ impl Interpreter {
                     Some(n) => Ok(Value::Float(n.tan())),
                     None => Err("tan expects a number".to_string()),
                 }
-            })),
+            }),
         );
 
-        // PI constant
-        self.env
-            .define("PI".to_string(), Value::Float(std::f64::consts::PI));
-
-        // E constant
-        self.env
-            .define("E".to_string(), Value::Float(std::f64::consts::E));
-
-        // ===== SYSTEM UTILITIES (IoT/Embedded) =====
+        // asin, acos, atan, exp, log10 and log2 functions
+        for (name, op) in [
+            ("asin", f64::asin as fn(f64) -> f64),
+            ("acos", f64::acos),
+            ("atan", f64::atan),
+            ("exp", f64::exp),
+            ("log10", f64::log10),
+            ("log2", f64::log2),
+        ] {
+            self.env.define(
+                name.to_string(),
+                Value::native(move |args| match args.as_slice() {
+                    [x] => match x.as_float() {
+                        Some(n) => Ok(Value::Float(op(n))),
+                        None => Err(format!("{} expects a number", name)),
+                    },
+                    _ => Err(format!("{} expects exactly 1 argument", name)),
+                }),
+            );
+        }
 
-        // exec function - Execute system commands
+        // atan2 function: atan2(y, x), the angle of the point (x, y)
         self.env.define(
-            "exec".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.is_empty() {
-                    return Err("exec expects at least 1 argument (command)".to_string());
+            "atan2".to_string(),
+            Value::native(|args| {
+                if args.len() != 2 {
+                    return Err("atan2 expects 2 arguments (y, x)".to_string());
                 }
-                match &args[0] {
-                    Value::String(cmd) => {
-                        use std::process::Command;
-                        let parts: Vec<&str> = cmd.split_whitespace().collect();
-                        if parts.is_empty() {
-                            return Err("Empty command".to_string());
-                        }
-
-                        let output = Command::new(parts[0]).args(&parts[1..]).output();
-
-                        match output {
-                            Ok(out) => {
-                                let stdout = String::from_utf8_lossy(&out.stdout).to_string();
-                                Ok(Value::String(stdout))
-                            }
-                            Err(e) => Err(format!("Failed to execute: {}", e)),
-                        }
-                    }
-                    _ => Err("exec expects a string command".to_string()),
+                match (args[0].as_float(), args[1].as_float()) {
+                    (Some(y), Some(x)) => Ok(Value::Float(y.atan2(x))),
+                    _ => Err("atan2 expects numbers".to_string()),
                 }
-            })),
+            }),
         );
 
-        // getEnv function - Get environment variable
+        // log function: log(x) is the natural log, log(x, base) any other base
         self.env.define(
-            "getEnv".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.len() != 1 {
-                    return Err("getEnv expects exactly 1 argument (var name)".to_string());
-                }
-                match &args[0] {
-                    Value::String(name) => match std::env::var(name) {
-                        Ok(val) => Ok(Value::String(val)),
-                        Err(_) => Ok(Value::Nil),
+            "log".to_string(),
+            Value::native(|args| {
+                if args.is_empty() || args.len() > 2 {
+                    return Err("log expects 1 or 2 arguments (x, base?)".to_string());
+                }
+                let x = args[0].as_float().ok_or("log expects a number")?;
+                match args.get(1) {
+                    None => Ok(Value::Float(x.ln())),
+                    Some(base) => match base.as_float() {
+                        Some(base) => Ok(Value::Float(x.log(base))),
+                        None => Err("log base must be a number".to_string()),
                     },
-                    _ => Err("getEnv expects a string".to_string()),
                 }
-            })),
+            }),
         );
 
-        // setEnv function - Set environment variable
+        // approxEq function: approxEq(a, b, epsilon?) with an absolute tolerance, default 1e-9
         self.env.define(
-            "setEnv".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.len() != 2 {
-                    return Err("setEnv expects 2 arguments (name, value)".to_string());
+            "approxEq".to_string(),
+            Value::native(|args| {
+                if args.len() < 2 || args.len() > 3 {
+                    return Err("approxEq expects 2 or 3 arguments (a, b, epsilon?)".to_string());
                 }
-                match (&args[0], &args[1]) {
+                let epsilon = match args.get(2) {
+                    Some(eps) => match eps.as_float() {
+                        Some(eps) if eps >= 0.0 => eps,
+                        _ => {
+                            return Err("approxEq epsilon must be a non-negative number".to_string())
+                        }
+                    },
+                    None => 1e-9,
+                };
+                match (args[0].as_float(), args[1].as_float()) {
+                    // NaN compares false both ways; equal infinities pass the `==` check
+                    (Some(a), Some(b)) => Ok(Value::Boolean(a == b || (a - b).abs() <= epsilon)),
+                    _ => Err("approxEq expects numbers".to_string()),
+                }
+            }),
+        );
+
+        // clamp function: clamp(x, lo, hi); integers stay integers, lo > hi is an error
+        self.env.define(
+            "clamp".to_string(),
+            Value::native(|args| {
+                if args.len() != 3 {
+                    return Err("clamp expects 3 arguments (x, lo, hi)".to_string());
+                }
+                if let [Value::Integer(x), Value::Integer(lo), Value::Integer(hi)] = args.as_slice()
+                {
+                    if lo > hi {
+                        return Err(format!(
+                            "clamp lower bound {} is above upper bound {}",
+                            lo, hi
+                        ));
+                    }
+                    return Ok(Value::Integer(*x.clamp(lo, hi)));
+                }
+                match (args[0].as_float(), args[1].as_float(), args[2].as_float()) {
+                    (Some(x), Some(lo), Some(hi)) if lo <= hi => Ok(Value::Float(x.clamp(lo, hi))),
+                    (Some(_), Some(lo), Some(hi)) if lo > hi => Err(format!(
+                        "clamp lower bound {} is above upper bound {}",
+                        args[1], args[2]
+                    )),
+                    _ => Err("clamp expects numbers".to_string()),
+                }
+            }),
+        );
+
+        // lerp function: lerp(a, b, t) = a + (b - a) * t, not limited to 0 <= t <= 1
+        self.env.define(
+            "lerp".to_string(),
+            Value::native(|args| {
+                if args.len() != 3 {
+                    return Err("lerp expects 3 arguments (a, b, t)".to_string());
+                }
+                match (args[0].as_float(), args[1].as_float(), args[2].as_float()) {
+                    (Some(a), Some(b), Some(t)) => Ok(Value::Float(a + (b - a) * t)),
+                    _ => Err("lerp expects numbers".to_string()),
+                }
+            }),
+        );
+
+        // mapRange function: rescale x from [inLo, inHi] to [outLo, outHi]; either
+        // range may run backwards, and x outside the input range is extrapolated
+        self.env.define(
+            "mapRange".to_string(),
+            Value::native(|args| {
+                if args.len() != 5 {
+                    return Err(
+                        "mapRange expects 5 arguments (x, inLo, inHi, outLo, outHi)".to_string()
+                    );
+                }
+                let numbers: Option<Vec<f64>> = args.iter().map(Value::as_float).collect();
+                match numbers.as_deref() {
+                    Some([_, in_lo, in_hi, _, _]) if in_lo == in_hi => {
+                        Err("mapRange input range must not be empty".to_string())
+                    }
+                    Some(&[x, in_lo, in_hi, out_lo, out_hi]) => Ok(Value::Float(
+                        out_lo + (x - in_lo) / (in_hi - in_lo) * (out_hi - out_lo),
+                    )),
+                    _ => Err("mapRange expects numbers".to_string()),
+                }
+            }),
+        );
+
+        // PI constant
+        self.env
+            .define("PI".to_string(), Value::Float(std::f64::consts::PI));
+
+        // E constant
+        self.env
+            .define("E".to_string(), Value::Float(std::f64::consts::E));
+
+        // ===== SYSTEM UTILITIES (IoT/Embedded) =====
+
+        // exec function - Execute system commands
+        self.env.define(
+            "exec".to_string(),
+            Value::native(|args| {
+                if args.is_empty() {
+                    return Err("exec expects at least 1 argument (command)".to_string());
+                }
+                match &args[0] {
+                    Value::String(cmd) => {
+                        use std::process::Command;
+                        let parts: Vec<&str> = cmd.split_whitespace().collect();
+                        if parts.is_empty() {
+                            return Err("Empty command".to_string());
+                        }
+
+                        let output = Command::new(parts[0]).args(&parts[1..]).output();
+
+                        match output {
+                            Ok(out) => {
+                                let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+                                Ok(Value::String(stdout))
+                            }
+                            Err(e) => Err(format!("Failed to execute: {}", e)),
+                        }
+                    }
+                    _ => Err("exec expects a string command".to_string()),
+                }
+            }),
+        );
+
+        // getEnv function - Get environment variable
+        self.env.define(
+            "getEnv".to_string(),
+            Value::native(|args| {
+                if args.len() != 1 {
+                    return Err("getEnv expects exactly 1 argument (var name)".to_string());
+                }
+                match &args[0] {
+                    Value::String(name) => match std::env::var(name) {
+                        Ok(val) => Ok(Value::String(val)),
+                        Err(_) => Ok(Value::Nil),
+                    },
+                    _ => Err("getEnv expects a string".to_string()),
+                }
+            }),
+        );
+
+        // setEnv function - Set environment variable
+        self.env.define(
+            "setEnv".to_string(),
+            Value::native(|args| {
+                if args.len() != 2 {
+                    return Err("setEnv expects 2 arguments (name, value)".to_string());
+                }
+                match (&args[0], &args[1]) {
                     (Value::String(name), Value::String(val)) => {
                         std::env::set_var(name, val);
                         Ok(Value::Nil)
                     }
                     _ => Err("setEnv expects (string, string)".to_string()),
                 }
-            })),
+            }),
         );
 
         // sleep function - Sleep for milliseconds
         self.env.define(
             "sleep".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("sleep expects exactly 1 argument (milliseconds)".to_string());
                 }
@@ -976,146 +2233,160 @@ impl Interpreter {
                     }
                     None => Err("sleep expects an integer (milliseconds)".to_string()),
                 }
-            })),
+            }),
         );
 
         // timestamp function - Get current Unix timestamp
         self.env.define(
             "timestamp".to_string(),
-            Value::Native(Arc::new(|_args| {
+            Value::native(|_args| {
                 match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
                     Ok(duration) => Ok(Value::Integer(duration.as_secs() as i64)),
                     Err(_) => Err("Failed to get timestamp".to_string()),
                 }
-            })),
+            }),
+        );
+
+        // nowMillis function - Wall-clock milliseconds since the Unix epoch
+        self.env.define(
+            "nowMillis".to_string(),
+            Value::native(|_args| {
+                match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+                    Ok(duration) => Ok(Value::Integer(duration.as_millis() as i64)),
+                    Err(_) => Err("Failed to get timestamp".to_string()),
+                }
+            }),
+        );
+
+        // monotonic function - Fractional milliseconds on a clock that only
+        // moves forward, for measuring durations
+        let started = std::time::Instant::now();
+        self.env.define(
+            "monotonic".to_string(),
+            Value::native(move |_args| Ok(Value::Float(started.elapsed().as_secs_f64() * 1000.0))),
         );
 
         // ===== BINARY/BYTES HANDLING =====
 
-        // bytes function - Convert string to byte array
+        // bytes function - A string's UTF-8, or an array of byte values, as bytes
         self.env.define(
             "bytes".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.len() != 1 {
-                    return Err("bytes expects exactly 1 argument".to_string());
-                }
-                match &args[0] {
-                    Value::String(s) => {
-                        let bytes: Vec<Value> =
-                            s.bytes().map(|b| Value::Integer(b as i64)).collect();
-                        Ok(Value::Array(bytes))
-                    }
-                    _ => Err("bytes expects a string".to_string()),
-                }
-            })),
+            Value::native(|args| match args.as_slice() {
+                [Value::String(s)] => Ok(Value::Bytes(s.as_bytes().to_vec())),
+                [data] => Self::byte_values(data, "bytes").map(|b| Value::Bytes(b.into_owned())),
+                _ => Err("bytes expects exactly 1 argument".to_string()),
+            }),
+        );
+
+        // byteArray function - Bytes as an array of integers 0-255
+        self.env.define(
+            "byteArray".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [data] => Self::byte_values(data, "byteArray").map(|bytes| {
+                    Value::Array(bytes.iter().map(|&b| Value::Integer(b as i64)).collect())
+                }),
+                _ => Err("byteArray expects exactly 1 argument".to_string()),
+            }),
         );
 
-        // fromBytes function - Convert byte array to string
+        // fromBytes function - Convert bytes to a UTF-8 string
         self.env.define(
             "fromBytes".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
-                    return Err("fromBytes expects exactly 1 argument (byte array)".to_string());
+                    return Err("fromBytes expects exactly 1 argument (bytes)".to_string());
                 }
-                match &args[0] {
-                    Value::Array(arr) => {
-                        let mut bytes = Vec::new();
-                        for val in arr {
-                            match val.as_integer() {
-                                Some(b) if b >= 0 && b <= 255 => bytes.push(b as u8),
-                                _ => return Err("Invalid byte value".to_string()),
-                            }
-                        }
-                        match String::from_utf8(bytes) {
-                            Ok(s) => Ok(Value::String(s)),
-                            Err(_) => Err("Invalid UTF-8 sequence".to_string()),
-                        }
+                let bytes = Self::byte_values(&args[0], "fromBytes")?;
+                match String::from_utf8(bytes.into_owned()) {
+                    Ok(s) => Ok(Value::String(s)),
+                    Err(_) => Err("Invalid UTF-8 sequence".to_string()),
+                }
+            }),
+        );
+
+        // ord function - Unicode scalar value of a one-character string
+        self.env.define(
+            "ord".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::String(s)] => {
+                    let mut chars = s.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Ok(Value::Integer(c as i64)),
+                        _ => Err(format!(
+                            "ord expects a single character, got {} characters",
+                            s.chars().count()
+                        )),
                     }
-                    _ => Err("fromBytes expects an array".to_string()),
                 }
-            })),
+                [_] => Err("ord expects a string".to_string()),
+                _ => Err("ord expects exactly 1 argument".to_string()),
+            }),
+        );
+
+        // char function - One-character string for a Unicode code point
+        self.env.define(
+            "char".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::Integer(n)] => u32::try_from(*n)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(|c| Value::String(c.to_string()))
+                    .ok_or_else(|| format!("{:#X} is not a valid Unicode scalar value", n)),
+                [_] => Err("char expects an integer code point".to_string()),
+                _ => Err("char expects exactly 1 argument".to_string()),
+            }),
         );
 
         // packInt function - Pack integer to bytes (little-endian)
         self.env.define(
             "packInt".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("packInt expects exactly 1 argument".to_string());
                 }
                 match args[0].as_integer() {
-                    Some(n) => {
-                        let bytes: Vec<Value> = n
-                            .to_le_bytes()
-                            .iter()
-                            .map(|&b| Value::Integer(b as i64))
-                            .collect();
-                        Ok(Value::Array(bytes))
-                    }
+                    Some(n) => Ok(Value::Bytes(n.to_le_bytes().to_vec())),
                     None => Err("packInt expects an integer".to_string()),
                 }
-            })),
+            }),
         );
 
         // unpackInt function - Unpack bytes to integer (little-endian)
         self.env.define(
             "unpackInt".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
-                    return Err("unpackInt expects exactly 1 argument (byte array)".to_string());
+                    return Err("unpackInt expects exactly 1 argument (bytes)".to_string());
                 }
-                match &args[0] {
-                    Value::Array(arr) => {
-                        if arr.len() != 8 {
-                            return Err("unpackInt expects 8 bytes".to_string());
-                        }
-                        let mut bytes = [0u8; 8];
-                        for (i, val) in arr.iter().enumerate() {
-                            match val.as_integer() {
-                                Some(b) if b >= 0 && b <= 255 => bytes[i] = b as u8,
-                                _ => return Err("Invalid byte value".to_string()),
-                            }
-                        }
-                        Ok(Value::Integer(i64::from_le_bytes(bytes)))
-                    }
-                    _ => Err("unpackInt expects an array".to_string()),
+                let bytes = Self::byte_values(&args[0], "unpackInt")?;
+                match <[u8; 8]>::try_from(bytes.as_ref()) {
+                    Ok(bytes) => Ok(Value::Integer(i64::from_le_bytes(bytes))),
+                    Err(_) => Err("unpackInt expects 8 bytes".to_string()),
                 }
-            })),
+            }),
         );
 
         // hexEncode function - Encode bytes to hex string
         self.env.define(
             "hexEncode".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("hexEncode expects exactly 1 argument".to_string());
                 }
-                match &args[0] {
-                    Value::Array(arr) => {
-                        let mut hex = String::new();
-                        for val in arr {
-                            match val.as_integer() {
-                                Some(b) if b >= 0 && b <= 255 => {
-                                    hex.push_str(&format!("{:02x}", b as u8));
-                                }
-                                _ => return Err("Invalid byte value".to_string()),
-                            }
-                        }
-                        Ok(Value::String(hex))
-                    }
-                    Value::String(s) => {
-                        let hex: String = s.bytes().map(|b| format!("{:02x}", b)).collect();
-                        Ok(Value::String(hex))
-                    }
-                    _ => Err("hexEncode expects an array or string".to_string()),
-                }
-            })),
+                let bytes = match &args[0] {
+                    Value::String(s) => s.as_bytes().into(),
+                    data => Self::byte_values(data, "hexEncode")?,
+                };
+                Ok(Value::String(
+                    bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+                ))
+            }),
         );
 
         // hexDecode function - Decode hex string to bytes
         self.env.define(
             "hexDecode".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("hexDecode expects exactly 1 argument (hex string)".to_string());
                 }
@@ -1128,32 +2399,90 @@ impl Interpreter {
                         let mut bytes = Vec::new();
                         for i in (0..hex_clean.len()).step_by(2) {
                             match u8::from_str_radix(&hex_clean[i..i + 2], 16) {
-                                Ok(b) => bytes.push(Value::Integer(b as i64)),
+                                Ok(b) => bytes.push(b),
                                 Err(_) => return Err("Invalid hex string".to_string()),
                             }
                         }
-                        Ok(Value::Array(bytes))
+                        Ok(Value::Bytes(bytes))
                     }
                     _ => Err("hexDecode expects a string".to_string()),
                 }
-            })),
+            }),
+        );
+
+        // base64Encode function - Encode bytes (or a string's UTF-8) to base64
+        self.env.define(
+            "base64Encode".to_string(),
+            Value::native(|args| {
+                if args.len() != 1 {
+                    return Err("base64Encode expects exactly 1 argument".to_string());
+                }
+                let bytes = match &args[0] {
+                    Value::String(s) => s.as_bytes().into(),
+                    data => Self::byte_values(data, "base64Encode")?,
+                };
+                Ok(Value::String(crate::stdlib::BinaryEncoder::to_base64(
+                    &bytes,
+                )))
+            }),
+        );
+
+        // base64Decode function - Decode base64 to bytes
+        self.env.define(
+            "base64Decode".to_string(),
+            Value::native(|args| match args.as_slice() {
+                [Value::String(s)] => {
+                    crate::stdlib::BinaryEncoder::from_base64(s).map(Value::Bytes)
+                }
+                [_] => Err("base64Decode expects a string".to_string()),
+                _ => Err("base64Decode expects exactly 1 argument (base64 string)".to_string()),
+            }),
         );
 
         // ===== NETWORK UTILITIES (Basic) =====
 
-        // httpGet function - Simple HTTP GET (using std only, no external deps)
+        // httpGet function - httpGet(url), the body of a 2xx response
         self.env.define(
             "httpGet".to_string(),
-            Value::Native(Arc::new(|args| {
-                if args.len() != 1 {
-                    return Err("httpGet expects exactly 1 argument (URL)".to_string());
+            Value::native(|args| match args.as_slice() {
+                [Value::String(url)] => {
+                    Self::http_send(HttpRequest::new(HttpMethod::Get, url.clone()))
                 }
-                // Note: This is a placeholder. In production, use reqwest or similar
-                Err(
-                    "httpGet not yet implemented - add reqwest dependency for HTTP support"
-                        .to_string(),
-                )
-            })),
+                _ => Err("httpGet expects exactly 1 argument (URL)".to_string()),
+            }),
+        );
+
+        // httpPost function - httpPost(url, body, headers?), the body of a 2xx
+        // response; Content-Type defaults to application/json
+        self.env.define(
+            "httpPost".to_string(),
+            Value::native(|args| {
+                let (url, body, headers) = match args.as_slice() {
+                    [Value::String(url), Value::String(body)] => (url, body, None),
+                    [Value::String(url), Value::String(body), Value::Object(headers)] => {
+                        (url, body, Some(headers))
+                    }
+                    _ => {
+                        return Err(
+                            "httpPost expects 2 or 3 arguments (url, body, headers?)".to_string()
+                        )
+                    }
+                };
+                let mut request = HttpRequest::new(HttpMethod::Post, url.clone())
+                    .with_body(body.as_bytes().to_vec());
+                for (name, value) in headers.into_iter().flatten() {
+                    request = request.with_header(name.clone(), value.as_string());
+                }
+                if !request
+                    .headers
+                    .keys()
+                    .any(|name| name.eq_ignore_ascii_case("Content-Type"))
+                {
+                    request = request
+                        .with_header("Content-Type".to_string(), "application/json".to_string());
+                }
+                Self::http_send(request)
+            }),
         );
 
         // ===== HARDWARE/IoT SIMULATION =====
@@ -1161,58 +2490,53 @@ impl Interpreter {
         // GPIO simulation (for IoT/embedded)
         self.env.define(
             "gpioSetup".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("gpioSetup expects 2 arguments (pin, mode)".to_string());
                 }
                 // Simulated GPIO - in real implementation, would interface with hardware
                 Ok(Value::Boolean(true))
-            })),
+            }),
         );
 
         self.env.define(
             "gpioWrite".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("gpioWrite expects 2 arguments (pin, value)".to_string());
                 }
                 // Simulated GPIO write
                 Ok(Value::Nil)
-            })),
+            }),
         );
 
+        let rng = Arc::clone(&self.rng);
         self.env.define(
             "gpioRead".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(move |args| {
                 if args.len() != 1 {
                     return Err("gpioRead expects 1 argument (pin)".to_string());
                 }
                 // Simulated GPIO read - returns random 0 or 1
-                use std::collections::hash_map::RandomState;
-                use std::hash::{BuildHasher, Hash, Hasher};
-                let state = RandomState::new();
-                let mut hasher = state.build_hasher();
-                std::time::SystemTime::now().hash(&mut hasher);
-                let val = (hasher.finish() % 2) as i64;
-                Ok(Value::Integer(val))
-            })),
+                Ok(Value::Integer(rng.lock().unwrap().below(2) as i64))
+            }),
         );
 
         // I2C simulation
         self.env.define(
             "i2cWrite".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("i2cWrite expects 2 arguments (address, data)".to_string());
                 }
                 // Simulated I2C write
                 Ok(Value::Nil)
-            })),
+            }),
         );
 
         self.env.define(
             "i2cRead".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("i2cRead expects 2 arguments (address, length)".to_string());
                 }
@@ -1224,13 +2548,13 @@ impl Interpreter {
                     }
                     None => Err("i2cRead expects length as integer".to_string()),
                 }
-            })),
+            }),
         );
 
         // SPI simulation
         self.env.define(
             "spiTransfer".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("spiTransfer expects 1 argument (data array)".to_string());
                 }
@@ -1239,27 +2563,27 @@ impl Interpreter {
                     Value::Array(arr) => Ok(Value::Array(arr.clone())),
                     _ => Err("spiTransfer expects an array".to_string()),
                 }
-            })),
+            }),
         );
 
         // UART simulation
         self.env.define(
             "uartWrite".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("uartWrite expects 1 argument (data)".to_string());
                 }
                 // Simulated UART write
                 Ok(Value::Nil)
-            })),
+            }),
         );
 
         self.env.define(
             "uartRead".to_string(),
-            Value::Native(Arc::new(|_args| {
+            Value::native(|_args| {
                 // Simulated UART read - returns empty string
                 Ok(Value::String(String::new()))
-            })),
+            }),
         );
 
         // ===== BITWISE UTILITIES =====
@@ -1267,7 +2591,7 @@ impl Interpreter {
         // bitAnd function
         self.env.define(
             "bitAnd".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("bitAnd expects 2 arguments".to_string());
                 }
@@ -1275,13 +2599,13 @@ impl Interpreter {
                     (Some(a), Some(b)) => Ok(Value::Integer(a & b)),
                     _ => Err("bitAnd expects integers".to_string()),
                 }
-            })),
+            }),
         );
 
         // bitOr function
         self.env.define(
             "bitOr".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("bitOr expects 2 arguments".to_string());
                 }
@@ -1289,13 +2613,13 @@ impl Interpreter {
                     (Some(a), Some(b)) => Ok(Value::Integer(a | b)),
                     _ => Err("bitOr expects integers".to_string()),
                 }
-            })),
+            }),
         );
 
         // bitXor function
         self.env.define(
             "bitXor".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("bitXor expects 2 arguments".to_string());
                 }
@@ -1303,13 +2627,13 @@ impl Interpreter {
                     (Some(a), Some(b)) => Ok(Value::Integer(a ^ b)),
                     _ => Err("bitXor expects integers".to_string()),
                 }
-            })),
+            }),
         );
 
         // bitNot function
         self.env.define(
             "bitNot".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("bitNot expects 1 argument".to_string());
                 }
@@ -1317,13 +2641,13 @@ impl Interpreter {
                     Some(a) => Ok(Value::Integer(!a)),
                     _ => Err("bitNot expects an integer".to_string()),
                 }
-            })),
+            }),
         );
 
         // bitShiftLeft function
         self.env.define(
             "bitShiftLeft".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("bitShiftLeft expects 2 arguments".to_string());
                 }
@@ -1331,13 +2655,13 @@ impl Interpreter {
                     (Some(a), Some(b)) => Ok(Value::Integer(a << b)),
                     _ => Err("bitShiftLeft expects integers".to_string()),
                 }
-            })),
+            }),
         );
 
         // bitShiftRight function
         self.env.define(
             "bitShiftRight".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("bitShiftRight expects 2 arguments".to_string());
                 }
@@ -1345,40 +2669,40 @@ impl Interpreter {
                     (Some(a), Some(b)) => Ok(Value::Integer(a >> b)),
                     _ => Err("bitShiftRight expects integers".to_string()),
                 }
-            })),
+            }),
         );
 
         // Hardware object with GPIO methods
         let mut gpio_map = HashMap::new();
         gpio_map.insert(
             "write".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("gpio.write expects 2 arguments (pin, value)".to_string());
                 }
                 // Simulated GPIO write
                 Ok(Value::Nil)
-            })),
+            }),
         );
         gpio_map.insert(
             "read".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("gpio.read expects 1 argument (pin)".to_string());
                 }
                 // Simulated GPIO read - returns 0
                 Ok(Value::Integer(0))
-            })),
+            }),
         );
         gpio_map.insert(
             "init".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("gpio.init expects 2 arguments (pin, mode)".to_string());
                 }
                 // Simulated GPIO init
                 Ok(Value::Nil)
-            })),
+            }),
         );
 
         let gpio_obj = Value::Object(gpio_map);
@@ -1391,7 +2715,7 @@ impl Interpreter {
 
         self.env.define(
             "createServer".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if !args.is_empty() {
                     return Err("createServer expects no arguments".to_string());
                 }
@@ -1402,7 +2726,7 @@ impl Interpreter {
                 let routes_clone = Arc::clone(&routes);
                 server_methods.insert(
                     "get".to_string(),
-                    Value::Native(Arc::new(move |args| {
+                    Value::native(move |args| {
                         if args.len() != 2 {
                             return Err("server.get expects 2 arguments (path, handler)".to_string());
                         }
@@ -1417,13 +2741,13 @@ impl Interpreter {
                             }
                             _ => Err("Path must be a string".to_string()),
                         }
-                    })),
+                    }),
                 );
 
                 let routes_clone = Arc::clone(&routes);
                 server_methods.insert(
                     "post".to_string(),
-                    Value::Native(Arc::new(move |args| {
+                    Value::native(move |args| {
                         if args.len() != 2 {
                             return Err("server.post expects 2 arguments (path, handler)".to_string());
                         }
@@ -1438,13 +2762,13 @@ impl Interpreter {
                             }
                             _ => Err("Path must be a string".to_string()),
                         }
-                    })),
+                    }),
                 );
 
                 let routes_clone = Arc::clone(&routes);
                 server_methods.insert(
                     "put".to_string(),
-                    Value::Native(Arc::new(move |args| {
+                    Value::native(move |args| {
                         if args.len() != 2 {
                             return Err("server.put expects 2 arguments (path, handler)".to_string());
                         }
@@ -1459,13 +2783,13 @@ impl Interpreter {
                             }
                             _ => Err("Path must be a string".to_string()),
                         }
-                    })),
+                    }),
                 );
 
                 let routes_clone = Arc::clone(&routes);
                 server_methods.insert(
                     "delete".to_string(),
-                    Value::Native(Arc::new(move |args| {
+                    Value::native(move |args| {
                         if args.len() != 2 {
                             return Err("server.delete expects 2 arguments (path, handler)".to_string());
                         }
@@ -1480,18 +2804,18 @@ impl Interpreter {
                             }
                             _ => Err("Path must be a string".to_string()),
                         }
-                    })),
+                    }),
                 );
 
                 server_methods.insert(
                     "use".to_string(),
-                    Value::Native(Arc::new(|_args| Ok(Value::Nil))),
+                    Value::native(|_args| Ok(Value::Nil)),
                 );
 
                 let routes_clone = Arc::clone(&routes);
                 server_methods.insert(
                     "listen".to_string(),
-                    Value::Native(Arc::new(move |args| {
+                    Value::native(move |args| {
                         if args.is_empty() {
                             return Err("server.listen expects a port number".to_string());
                         }
@@ -1563,38 +2887,38 @@ impl Interpreter {
                                                                     let rd = Arc::clone(&response_data);
                                                                     res_methods.insert(
                                                                         "json".to_string(),
-                                                                        Value::Native(Arc::new(move |args| {
+                                                                        Value::native(move |args| {
                                                                             if !args.is_empty() {
                                                                                 let json_str = args[0].as_string();
                                                                                 let mut data = rd.lock().unwrap();
                                                                                 data.0 = json_str;
                                                                             }
                                                                             Ok(Value::Nil)
-                                                                        })),
+                                                                        }),
                                                                     );
 
                                                                     let rd = Arc::clone(&response_data);
                                                                     res_methods.insert(
                                                                         "status".to_string(),
-                                                                        Value::Native(Arc::new(move |args| {
+                                                                        Value::native(move |args| {
                                                                             if let Some(code) = args.first().and_then(|v| v.as_integer()) {
                                                                                 let mut data = rd.lock().unwrap();
                                                                                 data.1 = code as u16;
                                                                             }
                                                                             Ok(Value::Object(HashMap::new()))
-                                                                        })),
+                                                                        }),
                                                                     );
 
                                                                     let rd = Arc::clone(&response_data);
                                                                     res_methods.insert(
                                                                         "text".to_string(),
-                                                                        Value::Native(Arc::new(move |args| {
+                                                                        Value::native(move |args| {
                                                                             if let Some(Value::String(text)) = args.first() {
                                                                                 let mut data = rd.lock().unwrap();
                                                                                 data.0 = text.clone();
                                                                             }
                                                                             Ok(Value::Nil)
-                                                                        })),
+                                                                        }),
                                                                     );
 
                                                                     let req_val = Value::Object(req_obj);
@@ -1650,18 +2974,16 @@ impl Interpreter {
                             }
                             None => Err("Port must be a number".to_string()),
                         }
-                    })),
+                    }),
                 );
 
                 Ok(Value::Object(server_methods))
-            })),
+            }),
         );
 
         self.env.define(
             "cors".to_string(),
-            Value::Native(Arc::new(|_args| {
-                Ok(Value::String("cors-middleware".to_string()))
-            })),
+            Value::native(|_args| Ok(Value::String("cors-middleware".to_string()))),
         );
 
         // db object - Database operations
@@ -1670,7 +2992,7 @@ impl Interpreter {
         // db.query(sql)
         db_methods.insert(
             "query".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.is_empty() {
                     return Err("db.query expects a SQL query string".to_string());
                 }
@@ -1689,13 +3011,13 @@ impl Interpreter {
                     }
                     _ => Err("db.query expects a string".to_string()),
                 }
-            })),
+            }),
         );
 
         // db.insert(table, data)
         db_methods.insert(
             "insert".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("db.insert expects 2 arguments (table, data)".to_string());
                 }
@@ -1707,13 +3029,13 @@ impl Interpreter {
                     }
                     _ => Err("db.insert expects (string, object)".to_string()),
                 }
-            })),
+            }),
         );
 
         // db.update(table, id, data)
         db_methods.insert(
             "update".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 3 {
                     return Err("db.update expects 3 arguments (table, id, data)".to_string());
                 }
@@ -1724,13 +3046,13 @@ impl Interpreter {
                     }
                     _ => Err("db.update expects (string, integer, object)".to_string()),
                 }
-            })),
+            }),
         );
 
         // db.delete(table, id)
         db_methods.insert(
             "delete".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("db.delete expects 2 arguments (table, id)".to_string());
                 }
@@ -1741,13 +3063,74 @@ impl Interpreter {
                     }
                     _ => Err("db.delete expects (string, integer)".to_string()),
                 }
-            })),
+            }),
         );
 
         let db_obj = Value::Object(db_methods);
         self.env.define("db".to_string(), db_obj);
     }
 
+    /// Register builtins that control the reactive system
+    fn register_reactive_builtins(&mut self) {
+        // deferEffects(enabled: boolean) -> nil
+        let reactive_ctx = Arc::clone(&self.reactive_ctx);
+        self.env.define(
+            "deferEffects".to_string(),
+            Value::native(move |args| {
+                if args.len() != 1 {
+                    return Err("deferEffects expects exactly 1 argument (enabled)".to_string());
+                }
+                reactive_ctx.set_deferred(args[0].is_truthy());
+                Ok(Value::Nil)
+            }),
+        );
+
+        // flush() / tick() -> nil: run pending recomputations and effects once each
+        let reactive_ctx = Arc::clone(&self.reactive_ctx);
+        let flush = Value::native(move |args| {
+            if !args.is_empty() {
+                return Err("flush expects no arguments".to_string());
+            }
+            reactive_ctx.flush().map_err(|e| e.to_string())?;
+            Ok(Value::Nil)
+        });
+        self.env.define("flush".to_string(), flush.clone());
+        self.env.define("tick".to_string(), flush);
+
+        // reactiveGraph() -> string: the dependency graph in Graphviz DOT format
+        let reactive_ctx = Arc::clone(&self.reactive_ctx);
+        self.env.define(
+            "reactiveGraph".to_string(),
+            Value::native(move |args| {
+                if !args.is_empty() {
+                    return Err("reactiveGraph expects no arguments".to_string());
+                }
+                Ok(Value::String(reactive_ctx.to_dot()))
+            }),
+        );
+    }
+
+    /// Register builtins that query the time-travel history
+    fn register_time_travel_builtins(&mut self) {
+        // snapshotsByTag(tag: string) -> array of labels, nil for unlabeled snapshots
+        let time_travel = Arc::clone(&self.time_travel);
+        self.env.define(
+            "snapshotsByTag".to_string(),
+            Value::native(move |args| match args.as_slice() {
+                [Value::String(tag)] => {
+                    let debugger = time_travel.read().unwrap();
+                    let labels = debugger
+                        .snapshots_with_tag(tag)
+                        .into_iter()
+                        .map(|s| s.label.clone().map_or(Value::Nil, Value::String))
+                        .collect();
+                    Ok(Value::Array(labels))
+                }
+                _ => Err("snapshotsByTag expects exactly 1 string argument".to_string()),
+            }),
+        );
+    }
+
     /// Register FFI (Foreign Function Interface) built-in functions
     fn register_ffi_builtins(&mut self) {
         use crate::stdlib::ffi::FFIType;
@@ -1756,7 +3139,7 @@ impl Interpreter {
         let ffi_ctx = Arc::clone(&self.ffi_context);
         self.env.define(
             "ffiLoadLibrary".to_string(),
-            Value::Native(Arc::new(move |args| {
+            Value::native(move |args| {
                 if args.len() != 2 {
                     return Err("ffiLoadLibrary expects 2 arguments (name, path)".to_string());
                 }
@@ -1771,14 +3154,14 @@ impl Interpreter {
                     }
                     _ => Err("ffiLoadLibrary expects (string, string)".to_string()),
                 }
-            })),
+            }),
         );
 
         // ffiRegisterFunction(name: string, return_type: string, param_types: array) -> boolean
         let ffi_ctx = Arc::clone(&self.ffi_context);
         self.env.define(
             "ffiRegisterFunction".to_string(),
-            Value::Native(Arc::new(move |args| {
+            Value::native(move |args| {
                 if args.len() != 3 {
                     return Err(
                         "ffiRegisterFunction expects 3 arguments (name, return_type, param_types)"
@@ -1810,14 +3193,14 @@ impl Interpreter {
                     }
                     _ => Err("ffiRegisterFunction expects (string, string, array)".to_string()),
                 }
-            })),
+            }),
         );
 
         // ffiCall(lib_name: string, func_name: string, args: array) -> any
         let ffi_ctx = Arc::clone(&self.ffi_context);
         self.env.define(
             "ffiCall".to_string(),
-            Value::Native(Arc::new(move |args| {
+            Value::native(move |args| {
                 if args.len() != 3 {
                     return Err(
                         "ffiCall expects 3 arguments (lib_name, func_name, args)".to_string()
@@ -1835,7 +3218,7 @@ impl Interpreter {
                     }
                     _ => Err("ffiCall expects (string, string, array)".to_string()),
                 }
-            })),
+            }),
         );
     }
 
@@ -1868,54 +3251,149 @@ impl Interpreter {
         }
     }
 
-    // Helper to convert our Value to serde_json::Value
-    fn value_to_json(value: &Value) -> Result<serde_json::Value, String> {
-        match value {
-            Value::Nil => Ok(serde_json::Value::Null),
-            Value::Boolean(b) => Ok(serde_json::Value::Bool(*b)),
-            Value::Integer(i) => Ok(serde_json::Value::Number((*i).into())),
-            Value::Float(f) => {
-                if let Some(n) = serde_json::Number::from_f64(*f) {
-                    Ok(serde_json::Value::Number(n))
-                } else {
-                    Err("Invalid float value for JSON".to_string())
-                }
+    // Helper to encode a value in one of the serialize() formats. Binary
+    // formats produce bytes, like hexDecode.
+    fn serialize_value(value: &Value, format: &str) -> Result<Value, String> {
+        let json = value.to_json()?;
+        let bytes: Result<Vec<u8>, String> = match format {
+            "json" => {
+                return serde_json::to_string(&json)
+                    .map(Value::String)
+                    .map_err(|e| format!("Failed to serialize JSON: {}", e))
             }
-            Value::String(s) => Ok(serde_json::Value::String(s.clone())),
-            Value::Array(arr) => {
-                let mut json_arr = Vec::new();
-                for item in arr {
-                    json_arr.push(Self::value_to_json(item)?);
-                }
-                Ok(serde_json::Value::Array(json_arr))
+            #[cfg(feature = "msgpack")]
+            "msgpack" => rmp_serde::to_vec(&json)
+                .map_err(|e| format!("Failed to serialize MessagePack: {}", e)),
+            #[cfg(feature = "cbor")]
+            "cbor" => {
+                let mut out = Vec::new();
+                ciborium::into_writer(&json, &mut out)
+                    .map(|_| out)
+                    .map_err(|e| format!("Failed to serialize CBOR: {}", e))
             }
-            Value::Object(obj) => {
-                let mut json_obj = serde_json::Map::new();
-                for (k, v) in obj {
-                    json_obj.insert(k.clone(), Self::value_to_json(v)?);
+            #[cfg(not(feature = "msgpack"))]
+            "msgpack" => Err("format 'msgpack' is not enabled in this build".to_string()),
+            #[cfg(not(feature = "cbor"))]
+            "cbor" => Err("format 'cbor' is not enabled in this build".to_string()),
+            _ => Err(format!("Unknown serialization format '{}'", format)),
+        };
+        bytes.map(Value::Bytes)
+    }
+
+    // Helper to decode the output of serialize_value
+    fn deserialize_value(data: &Value, format: &str) -> Result<Value, String> {
+        let json: Result<serde_json::Value, String> = match format {
+            "json" => match data {
+                Value::String(s) => {
+                    serde_json::from_str(s).map_err(|e| format!("Failed to parse JSON: {}", e))
                 }
-                Ok(serde_json::Value::Object(json_obj))
-            }
-            _ => Err(format!("Cannot convert {} to JSON", value.type_name())),
+                _ => Err("deserialize expects a string for format 'json'".to_string()),
+            },
+            #[cfg(feature = "msgpack")]
+            "msgpack" => rmp_serde::from_slice(&Self::byte_values(data, "deserialize")?)
+                .map_err(|e| format!("Failed to parse MessagePack: {}", e)),
+            #[cfg(feature = "cbor")]
+            "cbor" => ciborium::from_reader(Self::byte_values(data, "deserialize")?.as_ref())
+                .map_err(|e| format!("Failed to parse CBOR: {}", e)),
+            #[cfg(not(feature = "msgpack"))]
+            "msgpack" => Err("format 'msgpack' is not enabled in this build".to_string()),
+            #[cfg(not(feature = "cbor"))]
+            "cbor" => Err("format 'cbor' is not enabled in this build".to_string()),
+            _ => Err(format!("Unknown serialization format '{}'", format)),
+        };
+        json.map(|json| Self::json_to_value(&json))
+    }
+
+    // Helper to read bytes, or an array of byte values, as raw bytes; `name`
+    // is the builtin to blame when the value is neither
+    fn byte_values<'a>(data: &'a Value, name: &str) -> Result<Cow<'a, [u8]>, String> {
+        match data {
+            Value::Bytes(bytes) => Ok(Cow::Borrowed(bytes)),
+            Value::Array(arr) => arr
+                .iter()
+                .map(|val| match val.as_integer() {
+                    Some(b) if (0..=255).contains(&b) => Ok(b as u8),
+                    _ => Err("Invalid byte value".to_string()),
+                })
+                .collect::<Result<Vec<u8>, String>>()
+                .map(Cow::Owned),
+            _ => Err(format!(
+                "{} expects bytes or an array of byte values, got {}",
+                name,
+                data.type_name()
+            )),
         }
     }
 
+    /// Register an AST transform that runs between parsing and execution.
+    ///
+    /// Transforms are applied in registration order to every program passed to
+    /// `execute`, including files pulled in by `import`.
+    pub fn add_transform(&mut self, transform: ProgramTransform) {
+        self.transforms.push(transform);
+    }
+
     /// Execute a program
     pub fn execute(&mut self, program: &Program) -> Result<Value, InterpreterError> {
-        let mut last_value = Value::Nil;
+        let transformed;
+        let program = if self.transforms.is_empty() {
+            program
+        } else {
+            transformed = self
+                .transforms
+                .iter()
+                .fold(program.clone(), |program, transform| transform(program));
+            &transformed
+        };
 
-        for statement in &program.statements {
-            last_value = self.execute_statement(statement)?;
+        self.defer_stack.push(Vec::new());
+        self.error_trace.clear();
+        let result = self.execute_block(&program.statements);
+        match self.run_deferred(result) {
+            Err(InterpreterError::RuntimeError(message)) if !self.error_trace.is_empty() => {
+                let trace = std::mem::take(&mut self.error_trace);
+                Err(InterpreterError::RuntimeError(format!(
+                    "{}\n    {}",
+                    message,
+                    trace.join("\n    ")
+                )))
+            }
+            result => result,
         }
+    }
 
-        Ok(last_value)
+    /// Pop the innermost defer frame and run its expressions in reverse order.
+    /// An error from a deferred expression only replaces a successful result.
+    fn run_deferred(
+        &mut self,
+        mut result: Result<Value, InterpreterError>,
+    ) -> Result<Value, InterpreterError> {
+        let deferred = self.defer_stack.pop().unwrap_or_default();
+        for expression in deferred.iter().rev() {
+            if let Err(e) = self.evaluate_expression(expression) {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
     }
 
     /// Execute a single statement
     fn execute_statement(&mut self, statement: &Statement) -> Result<Value, InterpreterError> {
+        let line = statement.span().line;
+        if line > 0 {
+            self.current_line = line;
+        }
+
         // Auto-snapshot for time-travel debugging
         self.auto_snapshot_counter += 1;
-        if self.auto_snapshot_counter % 100 == 0 {
+        let due = self
+            .time_travel
+            .read()
+            .unwrap()
+            .auto_snapshot_due(self.auto_snapshot_counter);
+        if due {
             let _ = self.take_snapshot(None);
         }
 
@@ -1926,19 +3404,29 @@ impl Interpreter {
                 type_annotation: _,
                 span: _,
             } => {
+                // `let` always declares in the current scope; plain `x = v` is an Assign
                 let val = self.evaluate_expression(value)?;
-                // Check if it's a reactive variable
-                if let Some(Value::ReactiveRef(_)) = self.env.get(name) {
-                    self.reactive_ctx
-                        .set(name, val)
-                        .map_err(|e| InterpreterError::RuntimeError(e.to_string()))?;
-                } else if self.env.get(name).is_some() {
-                    // Try to set existing variable
-                    self.env.set(name, val)?;
-                } else {
-                    // Define new variable
-                    self.env.define(name.clone(), val);
-                }
+                self.env.define(name.clone(), val);
+                Ok(Value::Nil)
+            }
+
+            Statement::LetPattern {
+                pattern,
+                value,
+                span: _,
+            } => {
+                let val = self.evaluate_expression(value)?;
+                self.destructure(pattern, val)?;
+                Ok(Value::Nil)
+            }
+
+            Statement::Assign {
+                target,
+                value,
+                span: _,
+            } => {
+                let val = self.evaluate_expression(value)?;
+                self.assign_to_target(target, val)?;
                 Ok(Value::Nil)
             }
 
@@ -1959,7 +3447,20 @@ impl Interpreter {
                 type_annotation: _,
                 span: _,
             } => {
+                // `reactive s = `...${x}...`` keeps the interpolation live when it
+                // reads a reactive: it becomes a computed string that re-renders
+                if let Expression::Template { .. } = initial_value {
+                    let deps =
+                        self.reactive_dependencies(&[], |f| initial_value.visit_identifiers(f));
+                    if !deps.is_empty() {
+                        return self.declare_computed(name, deps, initial_value);
+                    }
+                }
                 let val = self.evaluate_expression(initial_value)?;
+                if self.reactive_ctx.contains(name) {
+                    // The name may belong to a node from a scope that has since ended
+                    self.collect_reactive_garbage();
+                }
                 let node_id = self
                     .reactive_ctx
                     .register_signal(name.clone(), val)
@@ -1978,15 +3479,25 @@ impl Interpreter {
             } => {
                 let param_names: Vec<String> = parameters.iter().map(|p| p.name.clone()).collect();
 
-                let func = Value::Function(Arc::new(FunctionValue {
+                let reloadable = self.env.is_global_scope();
+                let func = Arc::new(FunctionValue {
                     name: Some(name.clone()),
                     parameters: param_names,
+                    defaults: parameters.iter().map(|p| p.default_value.clone()).collect(),
+                    variadic: parameters.last().is_some_and(|p| p.is_rest),
                     body: body.clone(),
                     closure: HashMap::new(),
                     is_async: *is_async,
-                }));
+                    reloadable,
+                });
+                if reloadable {
+                    self.functions
+                        .write()
+                        .unwrap()
+                        .insert(name.clone(), Arc::clone(&func));
+                }
 
-                self.env.define(name.clone(), func);
+                self.env.define(name.clone(), Value::Function(func));
                 Ok(Value::Nil)
             }
 
@@ -2006,26 +3517,26 @@ impl Interpreter {
                 then_branch,
                 else_branch,
                 span: _,
-            } => {
-                let cond_val = self.evaluate_expression(condition)?;
-                if cond_val.is_truthy() {
-                    self.execute_block(then_branch)
-                } else if let Some(else_stmts) = else_branch {
-                    self.execute_block(else_stmts)
-                } else {
-                    Ok(Value::Nil)
-                }
-            }
+            } => self.execute_if(condition, then_branch, else_branch),
 
             Statement::While {
                 condition,
                 body,
+                label,
                 span: _,
             } => {
                 while self.evaluate_expression(condition)?.is_truthy() {
                     match self.execute_block(body) {
-                        Err(InterpreterError::BreakStatement) => break,
-                        Err(InterpreterError::ContinueStatement) => continue,
+                        Err(InterpreterError::BreakStatement(target))
+                            if targets(&target, label) =>
+                        {
+                            break
+                        }
+                        Err(InterpreterError::ContinueStatement(target))
+                            if targets(&target, label) =>
+                        {
+                            continue
+                        }
                         Err(e) => return Err(e),
                         Ok(_) => {}
                     }
@@ -2037,24 +3548,30 @@ impl Interpreter {
                 variable,
                 iterable,
                 body,
+                label,
                 span: _,
             } => {
                 let iter_val = self.evaluate_expression(iterable)?;
 
                 let items = match iter_val {
                     Value::Array(arr) => arr,
+                    Value::Bytes(bytes) => bytes
+                        .into_iter()
+                        .map(|b| Value::Integer(b as i64))
+                        .collect(),
+                    // `5..0` counts down
                     Value::Range {
                         start,
                         end,
                         inclusive,
-                    } => {
-                        let range = if inclusive {
-                            start..=end
-                        } else {
-                            start..=end - 1
-                        };
-                        range.map(Value::Integer).collect()
+                    } => stepped_range(start, end, if start <= end { 1 } else { -1 }, inclusive),
+                    // Keys in sorted order, since the map itself has none
+                    Value::Object(map) => {
+                        let mut keys: Vec<String> = map.into_keys().collect();
+                        keys.sort();
+                        keys.into_iter().map(Value::String).collect()
                     }
+                    Value::String(s) => s.chars().map(|c| Value::String(c.to_string())).collect(),
                     _ => {
                         return Err(InterpreterError::TypeError(format!(
                             "Cannot iterate over {}",
@@ -2067,8 +3584,16 @@ impl Interpreter {
                 for item in items {
                     self.env.define(variable.clone(), item);
                     match self.execute_block(body) {
-                        Err(InterpreterError::BreakStatement) => break,
-                        Err(InterpreterError::ContinueStatement) => continue,
+                        Err(InterpreterError::BreakStatement(target))
+                            if targets(&target, label) =>
+                        {
+                            break
+                        }
+                        Err(InterpreterError::ContinueStatement(target))
+                            if targets(&target, label) =>
+                        {
+                            continue
+                        }
                         Err(e) => {
                             self.env.pop_scope();
                             return Err(e);
@@ -2081,12 +3606,89 @@ impl Interpreter {
                 Ok(Value::Nil)
             }
 
-            Statement::Break { span: _ } => Err(InterpreterError::BreakStatement),
+            Statement::ForClassic {
+                condition,
+                update,
+                body,
+                label,
+                span: _,
+            } => self.execute_classic_for(condition, update, body, label),
+
+            Statement::Block { body, span: _ } => self.execute_scoped_block(body),
+
+            Statement::Parallel { body, span: _ } => self.execute_parallel(body),
+
+            Statement::Struct {
+                name,
+                fields,
+                span: _,
+            } => {
+                self.structs.insert(name.clone(), fields.as_slice().into());
+                Ok(Value::Nil)
+            }
+
+            Statement::Enum {
+                name,
+                variants,
+                span: _,
+            } => {
+                let namespace = self.enum_namespace(name, variants);
+                self.env.define(name.clone(), namespace);
+                Ok(Value::Nil)
+            }
+
+            Statement::Assert {
+                condition,
+                message,
+                source,
+                span: _,
+            } => {
+                if !self.assertions_enabled || self.evaluate_expression(condition)?.is_truthy() {
+                    return Ok(Value::Nil);
+                }
+                let mut text = format!("Assertion failed: {}", source);
+                if let Some(message) = message {
+                    text.push_str(&format!(
+                        " ({})",
+                        self.evaluate_expression(message)?.as_string()
+                    ));
+                }
+                Err(InterpreterError::Throw(Value::String(text)))
+            }
+
+            Statement::Break { label, span: _ } => {
+                Err(InterpreterError::BreakStatement(label.clone()))
+            }
+
+            Statement::Continue { label, span: _ } => {
+                Err(InterpreterError::ContinueStatement(label.clone()))
+            }
+
+            Statement::Match {
+                value,
+                arms,
+                span: _,
+            } => self.execute_match(value, arms),
 
-            Statement::Continue { span: _ } => Err(InterpreterError::ContinueStatement),
+            Statement::Switch {
+                value,
+                cases,
+                span: _,
+            } => self.execute_switch(value, cases),
 
-            Statement::Snapshot { label, span: _ } => {
-                self.take_snapshot(label.clone())?;
+            Statement::Snapshot {
+                label,
+                tags,
+                span: _,
+            } => {
+                let id = self.take_snapshot(label.clone())?;
+                if !tags.is_empty() {
+                    self.time_travel
+                        .write()
+                        .unwrap()
+                        .tag_snapshot(id, tags.iter().cloned())
+                        .map_err(|e| InterpreterError::RuntimeError(e.to_string()))?;
+                }
                 Ok(Value::Nil)
             }
 
@@ -2117,6 +3719,15 @@ impl Interpreter {
                 Ok(Value::Nil)
             }
 
+            Statement::Replay { label, span: _ } => {
+                self.time_travel
+                    .write()
+                    .unwrap()
+                    .start_replay(label)
+                    .map_err(|e| InterpreterError::RuntimeError(e.to_string()))?;
+                Ok(Value::Nil)
+            }
+
             Statement::Watch {
                 expression: _,
                 handler: _,
@@ -2127,41 +3738,46 @@ impl Interpreter {
             }
 
             Statement::Effect {
-                dependencies: _,
+                dependencies,
                 body,
                 span: _,
             } => {
-                self.execute_block(body)?;
+                let deps =
+                    self.reactive_dependencies(dependencies, |f| visit_block_identifiers(body, f));
+                let spawn_child = self.child_spawner();
+                let body = body.clone();
+                // An effect that writes a signal it reads would otherwise re-trigger itself forever
+                let running = Arc::new(AtomicBool::new(false));
+
+                let name = format!("effect@{}", self.reactive_ctx.len());
+                self.reactive_ctx
+                    .register_effect(name, deps, move |_| {
+                        let Some(mut child) = spawn_child() else {
+                            return;
+                        };
+                        if running.swap(true, Ordering::SeqCst) {
+                            return;
+                        }
+                        child.defer_stack.push(Vec::new());
+                        let result = child.execute_block(&body);
+                        if let Err(e) = child.run_deferred(result) {
+                            eprintln!("Error in effect: {}", e);
+                        }
+                        running.store(false, Ordering::SeqCst);
+                    })
+                    .map_err(|e| InterpreterError::RuntimeError(e.to_string()))?;
                 Ok(Value::Nil)
             }
 
             Statement::Computed {
                 name,
-                dependencies: _,
+                dependencies,
                 expression,
                 span: _,
             } => {
-                let func = self.evaluate_expression(expression)?;
-
-                let result = match func {
-                    Value::Function(func_val) => {
-                        self.env.push_scope();
-                        let result = self.execute_block(&func_val.body);
-                        self.env.pop_scope();
-
-                        match result {
-                            Ok(Value::Nil) => Ok(Value::Nil),
-                            Ok(v) => Ok(v),
-                            Err(InterpreterError::ReturnValue(v)) => Ok(v),
-                            Err(e) => Err(e),
-                        }
-                    }
-                    _ => Ok(func),
-                };
-
-                let computed_value = result?;
-                self.env.define(name.clone(), computed_value);
-                Ok(Value::Nil)
+                let deps =
+                    self.reactive_dependencies(dependencies, |f| expression.visit_identifiers(f));
+                self.declare_computed(name, deps, expression)
             }
 
             Statement::Try {
@@ -2174,10 +3790,10 @@ impl Interpreter {
                 let try_result = self.execute_block(try_block);
 
                 // Handle result
-                let result = match try_result {
-                    Err(InterpreterError::Throw(exception)) => {
-                        // Exception was thrown, execute catch if present
-                        if let Some(catch) = catch_clause {
+                let result = match (try_result, catch_clause) {
+                    (Err(error), Some(catch)) => match error.into_exception() {
+                        Ok(exception) => {
+                            self.error_trace.clear();
                             self.env.push_scope();
                             if let Some(param) = &catch.parameter {
                                 self.env.define(param.clone(), exception);
@@ -2185,198 +3801,890 @@ impl Interpreter {
                             let catch_result = self.execute_block(&catch.body);
                             self.env.pop_scope();
                             catch_result
-                        } else {
-                            // No catch clause, re-throw
-                            Err(InterpreterError::Throw(exception))
                         }
-                    }
-                    other => other,
+                        Err(error) => Err(error),
+                    },
+                    // No catch clause: errors propagate once finally has run
+                    (result, _) => result,
                 };
 
-                // Execute finally block if present
-                if let Some(finally) = finally_block {
-                    let _ = self.execute_block(finally);
-                }
-
-                result
-            }
-
-            Statement::Throw { value, span: _ } => {
-                let val = self.evaluate_expression(value)?;
-                Err(InterpreterError::Throw(val))
-            }
-
-            _ => Ok(Value::Nil),
-        }
-    }
-
-    /// Execute a block of statements
-    fn execute_block(&mut self, statements: &[Statement]) -> Result<Value, InterpreterError> {
-        let mut last_value = Value::Nil;
-        for statement in statements {
-            last_value = self.execute_statement(statement)?;
-        }
-        Ok(last_value)
-    }
-
-    /// Evaluate an expression
-    fn evaluate_expression(&mut self, expr: &Expression) -> Result<Value, InterpreterError> {
-        match expr {
-            Expression::Literal { value, span: _ } => Ok(self.literal_to_value(value)),
-
-            Expression::Identifier { name, span: _ } => {
-                // Check if it's a reactive reference
-                if let Some(Value::ReactiveRef(_node_id)) = self.env.get(name) {
-                    self.reactive_ctx
-                        .get(name)
-                        .map_err(|e| InterpreterError::RuntimeError(e.to_string()))
-                } else {
-                    self.env
-                        .get(name)
-                        .ok_or_else(|| InterpreterError::UndefinedVariable(name.clone()))
+                // Finally always runs; an error (or return) from it replaces
+                // whatever the try/catch produced
+                match finally_block {
+                    Some(finally) => self.execute_block(finally).and(result),
+                    None => result,
                 }
             }
 
-            Expression::Binary {
-                left,
-                operator,
-                right,
+            Statement::Defer {
+                expression,
                 span: _,
             } => {
-                let left_val = self.evaluate_expression(left)?;
-                let right_val = self.evaluate_expression(right)?;
-                self.apply_binary_op(&left_val, operator, &right_val)
+                match self.defer_stack.last_mut() {
+                    Some(frame) => frame.push(expression.clone()),
+                    None => {
+                        return Err(InterpreterError::RuntimeError(
+                            "defer used outside of a function or program".to_string(),
+                        ))
+                    }
+                }
+                Ok(Value::Nil)
             }
 
-            Expression::Unary {
-                operator,
-                operand,
-                span: _,
-            } => {
-                let val = self.evaluate_expression(operand)?;
-                self.apply_unary_op(operator, &val)
+            Statement::Throw { value, span: _ } => {
+                let val = self.evaluate_expression(value)?;
+                Err(InterpreterError::Throw(val))
             }
 
-            Expression::Call {
-                callee,
-                arguments,
+            Statement::Import {
+                path,
+                items,
+                alias,
                 span: _,
             } => {
-                // Check if this is a special builtin (map, filter, reduce)
-                if let Expression::Identifier { name, .. } = callee.as_ref() {
-                    if name == "map" && arguments.len() == 2 {
-                        let array = self.evaluate_expression(&arguments[0])?;
-                        let func = self.evaluate_expression(&arguments[1])?;
-                        return self.builtin_map(array, func);
-                    } else if name == "filter" && arguments.len() == 2 {
-                        let array = self.evaluate_expression(&arguments[0])?;
-                        let func = self.evaluate_expression(&arguments[1])?;
-                        return self.builtin_filter(array, func);
-                    } else if name == "reduce" && (arguments.len() == 2 || arguments.len() == 3) {
-                        let array = self.evaluate_expression(&arguments[0])?;
-                        let func = self.evaluate_expression(&arguments[1])?;
-                        let initial = if arguments.len() == 3 {
-                            Some(self.evaluate_expression(&arguments[2])?)
-                        } else {
-                            None
-                        };
-                        return self.builtin_reduce(array, func, initial);
+                if !self.is_allowed(Capability::Filesystem) {
+                    return Err(InterpreterError::RuntimeError(format!(
+                        "Cannot import '{}': filesystem access is not allowed",
+                        path.join("/")
+                    )));
+                }
+                let module_path = self.resolve_import_path(&path.join("/"));
+                let module_path =
+                    Self::resolve_fs_path(&self.fs_root, &module_path.to_string_lossy())
+                        .map_err(InterpreterError::RuntimeError)?;
+                let is_data = matches!(
+                    module_path.extension().and_then(|ext| ext.to_str()),
+                    Some("json" | "toml" | "yaml" | "yml")
+                );
+                if !items.is_empty() {
+                    if is_data {
+                        return Err(InterpreterError::RuntimeError(format!(
+                            "Cannot import names from data file '{}'; use import config from \"{}\"",
+                            module_path.display(),
+                            path.join("/")
+                        )));
                     }
+                    return self.import_module_items(&module_path, items);
                 }
-
-                let func = self.evaluate_expression(callee)?;
-                let args: Result<Vec<_>, _> = arguments
-                    .iter()
-                    .map(|arg| self.evaluate_expression(arg))
-                    .collect();
-                let args = args?;
-
-                self.call_function(func, args)
-            }
-
-            Expression::Array { elements, span: _ } => {
-                let values: Result<Vec<_>, _> = elements
-                    .iter()
-                    .map(|elem| self.evaluate_expression(elem))
-                    .collect();
-                Ok(Value::Array(values?))
-            }
-
-            Expression::Object { fields, span: _ } => {
-                let mut map = HashMap::new();
-                for (key, value_expr) in fields {
-                    let value = self.evaluate_expression(value_expr)?;
-                    map.insert(key.clone(), value);
+                match (alias, is_data) {
+                    (Some(name), true) => {
+                        let data = self.load_data_module(&module_path)?;
+                        self.env.define(name.clone(), data);
+                        Ok(Value::Nil)
+                    }
+                    (None, true) => Err(InterpreterError::RuntimeError(format!(
+                        "Data file '{}' must be imported with a name: import config from \"{}\"",
+                        module_path.display(),
+                        path.join("/")
+                    ))),
+                    (Some(name), false) => Err(InterpreterError::RuntimeError(format!(
+                        "Cannot bind source module '{}' to '{}'; use import \"{}\"",
+                        module_path.display(),
+                        name,
+                        path.join("/")
+                    ))),
+                    (None, false) => self.execute_file_in_place(&module_path),
                 }
-                Ok(Value::Object(map))
             }
 
-            Expression::PropertyAccess {
-                object,
-                property,
+            Statement::Export {
+                item,
+                declaration,
                 span: _,
-            } => {
-                let obj = self.evaluate_expression(object)?;
-                match obj {
-                    Value::Object(map) => map.get(property).cloned().ok_or_else(|| {
-                        InterpreterError::RuntimeError(format!("Property '{}' not found", property))
-                    }),
-                    _ => Err(InterpreterError::TypeError(format!(
-                        "Cannot access property on {}",
-                        obj.type_name()
-                    ))),
+            } => match declaration {
+                Some(declaration) => self.execute_statement(declaration),
+                None if self.env.get(item).is_some() || self.structs.contains_key(item) => {
+                    Ok(Value::Nil)
                 }
-            }
+                None => Err(InterpreterError::UndefinedVariable(item.clone())),
+            },
 
-            Expression::IndexAccess {
-                object,
-                index,
-                span: _,
-            } => {
-                let obj = self.evaluate_expression(object)?;
+            _ => Ok(Value::Nil),
+        }
+    }
+
+    /// Store `value` into an assignable expression, writing modified containers
+    /// back through their parents up to the root variable
+    fn assign_to_target(
+        &mut self,
+        target: &Expression,
+        value: Value,
+    ) -> Result<(), InterpreterError> {
+        match target {
+            Expression::Identifier { name, .. } => match self.env.get(name) {
+                Some(Value::ReactiveRef(_)) => self
+                    .reactive_ctx
+                    .set(name, value)
+                    .map_err(|e| InterpreterError::RuntimeError(e.to_string())),
+                // Update the nearest existing binding, walking outwards through scopes
+                Some(_) => self.env.set(name, value),
+                // Assigning an unknown name declares it (like `x = 5` at top level)
+                None => {
+                    self.env.define(name.clone(), value);
+                    Ok(())
+                }
+            },
+            Expression::IndexAccess { object, index, .. } => {
+                let container = self.evaluate_expression(object)?;
                 let idx = self.evaluate_expression(index)?;
 
-                match (obj, idx) {
-                    (Value::Array(arr), Value::Integer(i)) => {
+                match (container, idx) {
+                    (Value::Array(mut arr), Value::Integer(i)) => {
                         if i < 0 || i >= arr.len() as i64 {
                             return Err(InterpreterError::IndexOutOfBounds);
                         }
-                        Ok(arr[i as usize].clone())
+                        arr[i as usize] = value;
+                        self.assign_to_target(object, Value::Array(arr))
                     }
-                    (Value::String(s), Value::Integer(i)) => {
-                        if i < 0 || i >= s.len() as i64 {
+                    (Value::Bytes(mut bytes), Value::Integer(i)) => {
+                        if i < 0 || i >= bytes.len() as i64 {
                             return Err(InterpreterError::IndexOutOfBounds);
                         }
-                        Ok(Value::String(
-                            s.chars().nth(i as usize).unwrap().to_string(),
-                        ))
+                        bytes[i as usize] = match value {
+                            Value::Integer(b) if (0..=255).contains(&b) => b as u8,
+                            other => {
+                                return Err(InterpreterError::TypeError(format!(
+                                    "Bytes hold integers 0-255, got {}",
+                                    other
+                                )))
+                            }
+                        };
+                        self.assign_to_target(object, Value::Bytes(bytes))
                     }
-                    _ => Err(InterpreterError::TypeError(
-                        "Invalid index operation".to_string(),
-                    )),
+                    (Value::Object(mut map), Value::String(key)) => {
+                        map.insert(key, value);
+                        self.assign_to_target(object, Value::Object(map))
+                    }
+                    (Value::Observable(obs), Value::String(key)) => obs
+                        .set(&key, value, &self.reactive_ctx)
+                        .map_err(|e| InterpreterError::RuntimeError(e.to_string())),
+                    (Value::Array(_), idx) => Err(InterpreterError::TypeError(format!(
+                        "Array index must be an integer, got {}",
+                        idx.type_name()
+                    ))),
+                    (other, _) => Err(InterpreterError::TypeError(format!(
+                        "Cannot assign to an index of {}",
+                        other.type_name()
+                    ))),
                 }
             }
+            Expression::PropertyAccess {
+                object, property, ..
+            } => match self.evaluate_expression(object)? {
+                Value::Object(mut map) => {
+                    map.insert(property.clone(), value);
+                    self.assign_to_target(object, Value::Object(map))
+                }
+                // Fields are shared signals, so there is no container to write back
+                Value::Observable(obs) => obs
+                    .set(property, value, &self.reactive_ctx)
+                    .map_err(|e| InterpreterError::RuntimeError(e.to_string())),
+                other => Err(InterpreterError::TypeError(format!(
+                    "Cannot set property '{}' on {}",
+                    property,
+                    other.type_name()
+                ))),
+            },
+            _ => Err(InterpreterError::InvalidOperation(
+                "Invalid assignment target".to_string(),
+            )),
+        }
+    }
 
-            Expression::Lambda {
-                parameters,
-                body,
-                return_type: _,
-                span: _,
-            } => {
-                let param_names: Vec<String> = parameters.iter().map(|p| p.name.clone()).collect();
+    /// Run a match arm if its pattern and guard accept `subject`.
+    /// Bindings go into the current scope; returns None when the arm doesn't apply.
+    fn execute_match_arm(
+        &mut self,
+        arm: &MatchArm,
+        subject: &Value,
+    ) -> Result<Option<Value>, InterpreterError> {
+        if !self.bind_pattern(&arm.pattern, subject) {
+            return Ok(None);
+        }
 
-                Ok(Value::Function(Arc::new(FunctionValue {
-                    name: None,
-                    parameters: param_names,
-                    body: body.clone(),
-                    closure: HashMap::new(),
-                    is_async: false,
-                })))
+        if let Some(guard) = &arm.guard {
+            if !self.evaluate_expression(guard)?.is_truthy() {
+                return Ok(None);
             }
+        }
 
-            Expression::Range {
-                start,
-                end,
+        self.execute_block(&arm.body).map(Some)
+    }
+
+    /// Build the object an `enum` name is bound to: unit variants are stored
+    /// as values, payload variants as constructors checking their arity
+    fn enum_namespace(&self, name: &str, variants: &[EnumVariant]) -> Value {
+        let mut namespace = HashMap::new();
+        for variant in variants {
+            let type_name = name.to_string();
+            let variant_name = variant.name.clone();
+            let value = if variant.fields.is_empty() {
+                Value::Enum {
+                    type_name,
+                    variant: variant_name,
+                    values: Vec::new(),
+                }
+            } else {
+                let arity = variant.fields.len();
+                Value::native(move |args| {
+                    if args.len() != arity {
+                        return Err(format!(
+                            "{}.{} expects {} values but got {}",
+                            type_name,
+                            variant_name,
+                            arity,
+                            args.len()
+                        ));
+                    }
+                    Ok(Value::Enum {
+                        type_name: type_name.clone(),
+                        variant: variant_name.clone(),
+                        values: args.to_vec(),
+                    })
+                })
+            };
+            namespace.insert(variant.name.clone(), value);
+        }
+        Value::Object(namespace)
+    }
+
+    /// Whether `value` is of the type a `match ... is` arm names: a runtime
+    /// type such as `Integer`, `Number` for either kind of number, or the name
+    /// of an enum
+    fn has_type(value: &Value, type_name: &str) -> bool {
+        match (type_name, value) {
+            ("Number", _) => value.is_numeric(),
+            ("Function", Value::Function(_) | Value::Native(_)) => true,
+            (
+                _,
+                Value::Enum {
+                    type_name: name, ..
+                },
+            ) if name == type_name => true,
+            _ => type_name.eq_ignore_ascii_case(value.type_name()),
+        }
+    }
+
+    /// Evaluate array elements or call arguments in order, splicing in the
+    /// items of any `...expr` spread
+    fn evaluate_elements(
+        &mut self,
+        elements: &[Expression],
+    ) -> Result<Vec<Value>, InterpreterError> {
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            match element {
+                Expression::Spread { expr, .. } => match self.evaluate_expression(expr)? {
+                    Value::Array(items) => values.extend(items),
+                    other => {
+                        return Err(InterpreterError::TypeError(format!(
+                            "Cannot spread {}, expected an array",
+                            other.type_name()
+                        )))
+                    }
+                },
+                _ => values.push(self.evaluate_expression(element)?),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Define the names in a `let` pattern from `value`. Elements or keys the
+    /// value lacks bind `nil`; only a shape mismatch is an error.
+    fn destructure(&mut self, pattern: &Pattern, value: Value) -> Result<(), InterpreterError> {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => Ok(()),
+            (Pattern::Identifier(name), value) => {
+                self.env.define(name.clone(), value);
+                Ok(())
+            }
+            (Pattern::Array(patterns), Value::Array(items) | Value::Tuple(items)) => {
+                let mut items = items.into_iter();
+                for pattern in patterns {
+                    self.destructure(pattern, items.next().unwrap_or(Value::Nil))?;
+                }
+                Ok(())
+            }
+            (
+                Pattern::Object(fields),
+                Value::Object(mut map)
+                | Value::Struct {
+                    fields: mut map, ..
+                },
+            ) => {
+                for (key, pattern) in fields {
+                    self.destructure(pattern, map.remove(key).unwrap_or(Value::Nil))?;
+                }
+                Ok(())
+            }
+            (Pattern::Array(_), value) => Err(InterpreterError::TypeError(format!(
+                "Cannot destructure {} as an array",
+                value.type_name()
+            ))),
+            (Pattern::Object(_), value) => Err(InterpreterError::TypeError(format!(
+                "Cannot destructure {} as an object",
+                value.type_name()
+            ))),
+            _ => Err(InterpreterError::RuntimeError(
+                "Unsupported pattern in 'let'".to_string(),
+            )),
+        }
+    }
+
+    /// Test `value` against `pattern`, defining any identifiers it binds
+    fn bind_pattern(&mut self, pattern: &Pattern, value: &Value) -> bool {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => true,
+            (Pattern::Identifier(name), _) => {
+                self.env.define(name.clone(), value.clone());
+                true
+            }
+            (Pattern::Literal(literal), _) => self.literal_to_value(literal) == *value,
+            (Pattern::Type { type_name, binding }, _) => {
+                if !Self::has_type(value, type_name) {
+                    return false;
+                }
+                if let Some(name) = binding {
+                    self.env.define(name.clone(), value.clone());
+                }
+                true
+            }
+            (Pattern::Array(patterns), Value::Array(items))
+            | (Pattern::Tuple(patterns), Value::Tuple(items)) => {
+                patterns.len() == items.len()
+                    && patterns
+                        .iter()
+                        .zip(items.iter())
+                        .all(|(p, v)| self.bind_pattern(p, v))
+            }
+            (Pattern::Object(fields), Value::Object(map)) => fields
+                .iter()
+                .all(|(key, p)| map.get(key).is_some_and(|v| self.bind_pattern(p, v))),
+            (
+                Pattern::Variant {
+                    enum_name,
+                    name,
+                    fields,
+                },
+                Value::Enum {
+                    type_name,
+                    variant,
+                    values,
+                },
+            ) => {
+                enum_name.as_ref().is_none_or(|e| e == type_name)
+                    && name == variant
+                    && fields.len() == values.len()
+                    && fields
+                        .iter()
+                        .zip(values.iter())
+                        .all(|(p, v)| self.bind_pattern(p, v))
+            }
+            _ => false,
+        }
+    }
+
+    /// Run the taken branch of an `if`, yielding its last value
+    fn execute_if(
+        &mut self,
+        condition: &Expression,
+        then_branch: &[Statement],
+        else_branch: &Option<Vec<Statement>>,
+    ) -> Result<Value, InterpreterError> {
+        if self.evaluate_expression(condition)?.is_truthy() {
+            self.execute_block(then_branch)
+        } else if let Some(else_stmts) = else_branch {
+            self.execute_block(else_stmts)
+        } else {
+            Ok(Value::Nil)
+        }
+    }
+
+    /// Run the first matching arm, yielding its value (nil if none match)
+    fn execute_match(
+        &mut self,
+        value: &Expression,
+        arms: &[MatchArm],
+    ) -> Result<Value, InterpreterError> {
+        let subject = self.evaluate_expression(value)?;
+
+        for arm in arms {
+            self.env.push_scope();
+            let result = self.execute_match_arm(arm, &subject);
+            self.env.pop_scope();
+
+            if let Some(value) = result? {
+                return Ok(value);
+            }
+        }
+
+        Ok(Value::Nil)
+    }
+
+    /// Start at the first case equal (by `==`) to the value, or at `default`,
+    /// and run every body from there on until a `break`
+    fn execute_switch(
+        &mut self,
+        value: &Expression,
+        cases: &[SwitchCase],
+    ) -> Result<Value, InterpreterError> {
+        let subject = self.evaluate_expression(value)?;
+
+        let mut start = None;
+        for (i, case) in cases.iter().enumerate() {
+            if let Some(case_value) = &case.value {
+                let case_value = self.evaluate_expression(case_value)?;
+                if self
+                    .apply_binary_op(&subject, &BinaryOp::Equal, &case_value)?
+                    .is_truthy()
+                {
+                    start = Some(i);
+                    break;
+                }
+            }
+        }
+        let Some(start) = start.or_else(|| cases.iter().position(|c| c.value.is_none())) else {
+            return Ok(Value::Nil);
+        };
+
+        self.env.push_scope();
+        let mut result = Ok(Value::Nil);
+        for case in &cases[start..] {
+            result = self.execute_block(&case.body);
+            if result.is_err() {
+                break;
+            }
+        }
+        self.env.pop_scope();
+
+        match result {
+            Err(InterpreterError::BreakStatement(None)) => Ok(Value::Nil),
+            other => other,
+        }
+    }
+
+    /// Run a block in its own scope, yielding its last value
+    fn execute_scoped_block(&mut self, body: &[Statement]) -> Result<Value, InterpreterError> {
+        self.env.push_scope();
+        let result = self.execute_block(body);
+        self.env.pop_scope();
+        result
+    }
+
+    /// Run a `parallel { ... }` block in the current scope.
+    ///
+    /// Consecutive `let`/`const` statements form a batch whose values are
+    /// evaluated concurrently. A binding joins the batch only if its name is
+    /// new to the batch and its value reads none of the names the batch
+    /// declares; otherwise the batch is cut and a new one starts with it. Each
+    /// value runs in a child interpreter over a snapshot of the environment, so
+    /// only the bindings themselves are merged back, in source order;
+    /// assignments a value makes to existing variables are not. Every other
+    /// statement runs on its own, after the batch before it has finished.
+    fn execute_parallel(&mut self, body: &[Statement]) -> Result<Value, InterpreterError> {
+        let mut last_value = Value::Nil;
+        let mut batch: Vec<(&String, &Expression)> = Vec::new();
+        for statement in body {
+            let binding = match statement {
+                Statement::Let { name, value, .. } | Statement::Const { name, value, .. } => {
+                    Some((name, value))
+                }
+                _ => None,
+            };
+            match binding {
+                Some((name, value)) => {
+                    let mut independent = !batch.iter().any(|(bound, _)| *bound == name);
+                    value.visit_identifiers(&mut |read| {
+                        independent &= !batch.iter().any(|(bound, _)| *bound == read);
+                    });
+                    if !independent {
+                        self.run_parallel_batch(std::mem::take(&mut batch))?;
+                    }
+                    batch.push((name, value));
+                    last_value = Value::Nil;
+                }
+                None => {
+                    self.run_parallel_batch(std::mem::take(&mut batch))?;
+                    last_value = self.execute_statement(statement)?;
+                }
+            }
+        }
+        self.run_parallel_batch(batch)?;
+        Ok(last_value)
+    }
+
+    /// Evaluate a batch of independent bindings on their own threads, then
+    /// define them in order. The first error, in source order, is returned.
+    fn run_parallel_batch(
+        &mut self,
+        batch: Vec<(&String, &Expression)>,
+    ) -> Result<(), InterpreterError> {
+        match batch.as_slice() {
+            [] => return Ok(()),
+            [(name, value)] => {
+                let val = self.evaluate_expression(value)?;
+                self.env.define((*name).clone(), val);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let spawn_child = self.child_spawner();
+        let spawn_child = &spawn_child;
+        let tasks: Vec<_> = batch
+            .iter()
+            .map(|&(_, value)| {
+                move || match spawn_child() {
+                    Some(mut child) => child.evaluate_expression(value),
+                    None => Err(InterpreterError::RuntimeError(
+                        "Interpreter dropped during parallel block".to_string(),
+                    )),
+                }
+            })
+            .collect();
+        let results =
+            crate::parallel::run_concurrently(tasks).map_err(InterpreterError::RuntimeError)?;
+
+        for ((name, _), result) in batch.into_iter().zip(results) {
+            self.env.define(name.clone(), result?);
+        }
+        Ok(())
+    }
+
+    /// Run a C-style for loop. `update` runs after every iteration, including
+    /// ones ended early by `continue`.
+    fn execute_classic_for(
+        &mut self,
+        condition: &Option<Expression>,
+        update: &Option<Box<Statement>>,
+        body: &[Statement],
+        label: &Option<String>,
+    ) -> Result<Value, InterpreterError> {
+        loop {
+            if let Some(condition) = condition {
+                if !self.evaluate_expression(condition)?.is_truthy() {
+                    break;
+                }
+            }
+            match self.execute_block(body) {
+                Err(InterpreterError::BreakStatement(target)) if targets(&target, label) => break,
+                Err(InterpreterError::ContinueStatement(target)) if targets(&target, label) => {}
+                Ok(_) => {}
+                Err(e) => return Err(e),
+            }
+            if let Some(update) = update {
+                self.execute_statement(update)?;
+            }
+        }
+        Ok(Value::Nil)
+    }
+
+    /// Execute a block of statements
+    fn execute_block(&mut self, statements: &[Statement]) -> Result<Value, InterpreterError> {
+        let mut last_value = Value::Nil;
+        for statement in statements {
+            last_value = self.execute_statement(statement)?;
+        }
+        Ok(last_value)
+    }
+
+    /// Evaluate a property access, index or call, or the chain of them that
+    /// `expr` ends. `None` means a `?.` found nil, which skips the rest of the
+    /// chain: `a?.b.c` and `a?.m()` are nil when `a` is.
+    fn evaluate_chain(&mut self, expr: &Expression) -> Result<Option<Value>, InterpreterError> {
+        match expr {
+            Expression::PropertyAccess {
+                object,
+                property,
+                optional,
+                span: _,
+            } => {
+                let Some(obj) = self.evaluate_chain(object)? else {
+                    return Ok(None);
+                };
+                if *optional && matches!(obj, Value::Nil) {
+                    return Ok(None);
+                }
+                self.property_value(obj, property).map(Some)
+            }
+            Expression::IndexAccess {
+                object,
+                index,
+                span: _,
+            } => {
+                let Some(obj) = self.evaluate_chain(object)? else {
+                    return Ok(None);
+                };
+                let idx = self.evaluate_expression(index)?;
+                self.index_value(obj, idx).map(Some)
+            }
+            // Calls by name may be special-cased builtins, so only method-style
+            // calls are part of a chain
+            Expression::Call {
+                callee, arguments, ..
+            } if !matches!(callee.as_ref(), Expression::Identifier { .. }) => {
+                let Some(func) = self.evaluate_chain(callee)? else {
+                    return Ok(None);
+                };
+                let args = self.evaluate_elements(arguments)?;
+                self.call_function(func, args).map(Some)
+            }
+            _ => self.evaluate_expression(expr).map(Some),
+        }
+    }
+
+    fn property_value(&self, obj: Value, property: &str) -> Result<Value, InterpreterError> {
+        match obj {
+            Value::Object(map) => map.get(property).cloned().ok_or_else(|| {
+                InterpreterError::RuntimeError(format!("Property '{}' not found", property))
+            }),
+            Value::Observable(obs) => obs.get(property, &self.reactive_ctx).ok_or_else(|| {
+                InterpreterError::RuntimeError(format!("Property '{}' not found", property))
+            }),
+            _ => Err(InterpreterError::TypeError(format!(
+                "Cannot access property on {}",
+                obj.type_name()
+            ))),
+        }
+    }
+
+    fn index_value(&self, obj: Value, idx: Value) -> Result<Value, InterpreterError> {
+        match (obj, idx) {
+            (Value::Array(arr), Value::Integer(i)) => {
+                if i < 0 || i >= arr.len() as i64 {
+                    return Err(InterpreterError::IndexOutOfBounds);
+                }
+                Ok(arr[i as usize].clone())
+            }
+            (Value::Bytes(bytes), Value::Integer(i)) => usize::try_from(i)
+                .ok()
+                .and_then(|i| bytes.get(i))
+                .map(|&b| Value::Integer(b as i64))
+                .ok_or(InterpreterError::IndexOutOfBounds),
+            // Indexes count characters, matching len()
+            (Value::String(s), Value::Integer(i)) => usize::try_from(i)
+                .ok()
+                .and_then(|i| s.chars().nth(i))
+                .map(|c| Value::String(c.to_string()))
+                .ok_or(InterpreterError::IndexOutOfBounds),
+            (Value::Object(map), Value::String(key)) => map.get(&key).cloned().ok_or_else(|| {
+                InterpreterError::RuntimeError(format!("Property '{}' not found", key))
+            }),
+            (Value::Observable(obs), Value::String(key)) => {
+                obs.get(&key, &self.reactive_ctx).ok_or_else(|| {
+                    InterpreterError::RuntimeError(format!("Property '{}' not found", key))
+                })
+            }
+            _ => Err(InterpreterError::TypeError(
+                "Invalid index operation".to_string(),
+            )),
+        }
+    }
+
+    /// Evaluate an expression
+    fn evaluate_expression(&mut self, expr: &Expression) -> Result<Value, InterpreterError> {
+        match expr {
+            Expression::Literal { value, span: _ } => Ok(self.literal_to_value(value)),
+
+            Expression::Identifier { name, span: _ } => {
+                // Check if it's a reactive reference
+                if let Some(Value::ReactiveRef(_node_id)) = self.env.get(name) {
+                    self.reactive_ctx
+                        .get(name)
+                        .map_err(|e| InterpreterError::RuntimeError(e.to_string()))
+                } else {
+                    self.env
+                        .get(name)
+                        .ok_or_else(|| InterpreterError::UndefinedVariable(name.clone()))
+                }
+            }
+
+            Expression::This { span: _ } => self.env.get("this").ok_or_else(|| {
+                InterpreterError::RuntimeError(
+                    "'this' is not bound here; use bind(fn, value)".to_string(),
+                )
+            }),
+
+            Expression::New {
+                class_name,
+                arguments,
+                fields,
+                span: _,
+            } => self.instantiate_struct(class_name, arguments, fields),
+
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                span: _,
+            } => self.execute_if(condition, then_branch, else_branch),
+
+            Expression::Match {
+                value,
+                arms,
+                span: _,
+            } => self.execute_match(value, arms),
+
+            Expression::Block { body, span: _ } => self.execute_scoped_block(body),
+
+            Expression::ParallelMap {
+                collection,
+                function,
+                span: _,
+            } => {
+                let array = self.evaluate_expression(collection)?;
+                let func = self.evaluate_expression(function)?;
+                self.builtin_pmap(array, func)
+            }
+
+            Expression::Pipeline {
+                value,
+                function,
+                span: _,
+            } => {
+                let arg = self.evaluate_expression(value)?;
+                let func = self.evaluate_expression(function)?;
+                self.call_function(func, vec![arg])
+            }
+
+            Expression::StreamPipe {
+                source,
+                handler,
+                span: _,
+            } => {
+                let handler = self.evaluate_expression(handler)?;
+                if let Expression::Identifier { name, .. } = source.as_ref() {
+                    if let Some(Value::ReactiveRef(_)) = self.env.get(name) {
+                        return self.subscribe_stream(name, handler);
+                    }
+                }
+                match self.evaluate_expression(source)? {
+                    Value::Array(items) => {
+                        for item in &items {
+                            self.call_function(handler.clone(), vec![item.clone()])?;
+                        }
+                        Ok(Value::Array(items))
+                    }
+                    other => Err(InterpreterError::TypeError(format!(
+                        "Cannot stream from {}; expected an array or reactive variable",
+                        other.type_name()
+                    ))),
+                }
+            }
+
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                span: _,
+            } => {
+                let left_val = self.evaluate_expression(left)?;
+                if *operator == BinaryOp::NullCoalesce {
+                    // The right side only runs when it is needed
+                    return match left_val {
+                        Value::Nil => self.evaluate_expression(right),
+                        other => Ok(other),
+                    };
+                }
+                let right_val = self.evaluate_expression(right)?;
+                self.apply_binary_op(&left_val, operator, &right_val)
+            }
+
+            Expression::Unary {
+                operator,
+                operand,
+                span: _,
+            } => {
+                let val = self.evaluate_expression(operand)?;
+                self.apply_unary_op(operator, &val)
+            }
+
+            Expression::Call {
+                callee,
+                arguments,
+                span: _,
+            } => {
+                // Check if this is a special builtin (map, filter, reduce)
+                let mut evaluated = None;
+                if let Expression::Identifier { name, .. } = callee.as_ref() {
+                    if self.is_named_builtin(name) {
+                        // Disabled assertions don't evaluate their arguments
+                        if matches!(name.as_str(), "assert" | "assertEq")
+                            && !self.assertions_enabled
+                        {
+                            return Ok(Value::Nil);
+                        }
+                        let args = self.evaluate_elements(arguments)?;
+                        match self.call_named_builtin(name, args) {
+                            Ok(result) => return result,
+                            Err(args) => evaluated = Some(args),
+                        }
+                    }
+                }
+
+                let Some(func) = self.evaluate_chain(callee)? else {
+                    return Ok(Value::Nil);
+                };
+                let args = match evaluated {
+                    Some(args) => args,
+                    None => self.evaluate_elements(arguments)?,
+                };
+
+                self.call_function(func, args)
+            }
+
+            Expression::Array { elements, span: _ } => {
+                Ok(Value::Array(self.evaluate_elements(elements)?))
+            }
+
+            Expression::Template { parts, span: _ } => {
+                let mut text = String::new();
+                for part in parts {
+                    text.push_str(&self.evaluate_expression(part)?.to_string());
+                }
+                Ok(Value::String(text))
+            }
+
+            Expression::Spread { .. } => Err(InterpreterError::RuntimeError(
+                "'...' can only spread into an array literal or argument list".to_string(),
+            )),
+
+            Expression::Object { fields, span: _ } => {
+                let mut map = HashMap::new();
+                for (key, value_expr) in fields {
+                    let value = self.evaluate_expression(value_expr)?;
+                    map.insert(key.clone(), value);
+                }
+                Ok(Value::Object(map))
+            }
+
+            Expression::PropertyAccess { .. } | Expression::IndexAccess { .. } => {
+                Ok(self.evaluate_chain(expr)?.unwrap_or(Value::Nil))
+            }
+
+            Expression::Lambda {
+                parameters,
+                body,
+                return_type: _,
+                span: _,
+            } => {
+                let param_names: Vec<String> = parameters.iter().map(|p| p.name.clone()).collect();
+
+                Ok(Value::Function(Arc::new(FunctionValue {
+                    name: None,
+                    parameters: param_names,
+                    defaults: parameters.iter().map(|p| p.default_value.clone()).collect(),
+                    variadic: parameters.last().is_some_and(|p| p.is_rest),
+                    body: body.clone(),
+                    closure: HashMap::new(),
+                    is_async: false,
+                    reloadable: false,
+                })))
+            }
+
+            Expression::Range {
+                start,
+                end,
                 inclusive,
                 span: _,
             } => {
@@ -2437,6 +4745,11 @@ impl Interpreter {
             BinaryOp::Add => left.add(right).map_err(InterpreterError::RuntimeError),
             BinaryOp::Subtract => left.subtract(right).map_err(InterpreterError::RuntimeError),
             BinaryOp::Multiply => left.multiply(right).map_err(InterpreterError::RuntimeError),
+            BinaryOp::Divide | BinaryOp::Modulo
+                if matches!((left, right), (Value::Integer(_), Value::Integer(0))) =>
+            {
+                Err(InterpreterError::DivisionByZero)
+            }
             BinaryOp::Divide => left.divide(right).map_err(InterpreterError::RuntimeError),
             BinaryOp::Modulo => left.modulo(right).map_err(InterpreterError::RuntimeError),
             BinaryOp::Power => left.power(right).map_err(InterpreterError::RuntimeError),
@@ -2464,10 +4777,42 @@ impl Interpreter {
             )),
             BinaryOp::And => Ok(Value::Boolean(left.is_truthy() && right.is_truthy())),
             BinaryOp::Or => Ok(Value::Boolean(left.is_truthy() || right.is_truthy())),
-            _ => Err(InterpreterError::InvalidOperation(format!(
-                "Binary operation {} not implemented",
-                op
-            ))),
+            BinaryOp::NullCoalesce => Ok(match left {
+                Value::Nil => right.clone(),
+                other => other.clone(),
+            }),
+            BinaryOp::BitwiseAnd
+            | BinaryOp::BitwiseOr
+            | BinaryOp::BitwiseXor
+            | BinaryOp::LeftShift
+            | BinaryOp::RightShift => {
+                let (a, b) = match (left, right) {
+                    (Value::Integer(a), Value::Integer(b)) => (*a, *b),
+                    _ => {
+                        return Err(InterpreterError::TypeError(format!(
+                            "Operator {} expects integers, got {} and {}",
+                            op,
+                            left.type_name(),
+                            right.type_name()
+                        )))
+                    }
+                };
+                let shift = |b: i64| {
+                    u32::try_from(b).ok().filter(|b| *b < 64).ok_or_else(|| {
+                        InterpreterError::RuntimeError(format!(
+                            "Shift amount {} out of range 0..64",
+                            b
+                        ))
+                    })
+                };
+                Ok(Value::Integer(match op {
+                    BinaryOp::BitwiseAnd => a & b,
+                    BinaryOp::BitwiseOr => a | b,
+                    BinaryOp::BitwiseXor => a ^ b,
+                    BinaryOp::LeftShift => a << shift(b)?,
+                    _ => a >> shift(b)?,
+                }))
+            }
         }
     }
 
@@ -2494,76 +4839,689 @@ impl Interpreter {
     }
 
     /// Call a function
+    /// Define the parameters after the first `given` from their defaults,
+    /// left to right in the function's scope, so a default can use the
+    /// parameters before it
+    fn bind_defaults(
+        &mut self,
+        func: &FunctionValue,
+        given: usize,
+    ) -> Result<(), InterpreterError> {
+        for (param, default) in func.parameters.iter().zip(&func.defaults).skip(given) {
+            if let Some(expr) = default {
+                let value = self.evaluate_expression(expr)?;
+                self.env.define(param.clone(), value);
+            }
+        }
+        Ok(())
+    }
+
     fn call_function(&mut self, func: Value, args: Vec<Value>) -> Result<Value, InterpreterError> {
         match func {
             Value::Function(func_val) => {
-                if args.len() != func_val.parameters.len() {
+                let func_val = self.current_definition(func_val);
+                // Only trailing parameters with defaults may be left out
+                let fixed = func_val.parameters.len() - usize::from(func_val.variadic);
+                let required = (0..fixed)
+                    .rev()
+                    .find(|&i| !matches!(func_val.defaults.get(i), Some(Some(_))))
+                    .map_or(0, |last| last + 1);
+                if args.len() < required || (args.len() > fixed && !func_val.variadic) {
+                    let expected = if func_val.variadic {
+                        format!("at least {}", required)
+                    } else if required == fixed {
+                        fixed.to_string()
+                    } else {
+                        format!("{} to {}", required, fixed)
+                    };
                     return Err(InterpreterError::RuntimeError(format!(
                         "Expected {} arguments, got {}",
-                        func_val.parameters.len(),
+                        expected,
                         args.len()
                     )));
                 }
 
+                // A rest parameter takes every argument after the fixed ones;
+                // fixed slots left empty are filled from defaults further on
+                let given = args.len();
+                let mut args = args;
+                if func_val.variadic {
+                    let rest = args.split_off(fixed.min(args.len()));
+                    args.resize(fixed, Value::Nil);
+                    args.push(Value::Array(rest));
+                }
+
                 self.env.push_scope();
+                self.defer_stack.push(Vec::new());
+                self.call_stack.push(CallFrame {
+                    function_name: func_val
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| "<anonymous>".to_string()),
+                    line: self.current_line,
+                    scope: self.env.depth() - 1,
+                });
+
+                // Captured bindings, such as `this` from `bind`
+                for (name, value) in &func_val.closure {
+                    self.env.define(name.clone(), value.clone());
+                }
 
                 // Bind parameters
                 for (param, arg) in func_val.parameters.iter().zip(args.iter()) {
                     self.env.define(param.clone(), arg.clone());
                 }
-
-                // Execute function body
-                let result = match self.execute_block(&func_val.body) {
-                    Err(InterpreterError::ReturnValue(val)) => Ok(val),
-                    Err(e) => Err(e),
-                    Ok(_) => Ok(Value::Nil),
-                };
-
-                self.env.pop_scope();
-                result
+
+                // Execute function body, after filling in omitted arguments
+                let result = self.bind_defaults(&func_val, given).and_then(|()| {
+                    match self.execute_block(&func_val.body) {
+                        Err(InterpreterError::ReturnValue(val)) => Ok(val),
+                        Err(e) => Err(e),
+                        Ok(_) => Ok(Value::Nil),
+                    }
+                });
+
+                // Deferred expressions still see the function's locals
+                let result = self.run_deferred(result);
+                if matches!(result, Err(InterpreterError::RuntimeError(_)))
+                    && self.error_trace.is_empty()
+                {
+                    self.error_trace = self.stack_trace();
+                }
+                if let Some(frame) = self.call_stack.pop() {
+                    self.current_line = frame.line;
+                }
+                self.env.pop_scope();
+                self.maybe_collect_reactive_garbage();
+                result
+            }
+            Value::Native(native_fn) => native_fn(args).map_err(|err| match err {
+                NativeError::Runtime(message) => InterpreterError::RuntimeError(message),
+                NativeError::Type(message) => InterpreterError::TypeError(message),
+            }),
+            _ => Err(InterpreterError::TypeError(format!(
+                "Cannot call {}",
+                func.type_name()
+            ))),
+        }
+    }
+
+    /// One `at name (line n)` entry per running function, innermost first,
+    /// naming the line each one has reached
+    fn stack_trace(&self) -> Vec<String> {
+        let reached = self
+            .call_stack
+            .iter()
+            .skip(1)
+            .map(|frame| frame.line)
+            .chain([self.current_line]);
+        let mut trace: Vec<String> = self
+            .call_stack
+            .iter()
+            .zip(reached)
+            .map(|(frame, line)| format!("at {} (line {})", frame.function_name, line))
+            .collect();
+        trace.reverse();
+        trace
+    }
+
+    /// The body a call to `func` should run: for a top-level declaration,
+    /// the newest declaration of its name. Bindings added by `bind` are kept.
+    fn current_definition(&self, func: Arc<FunctionValue>) -> Arc<FunctionValue> {
+        let (true, Some(name)) = (func.reloadable, &func.name) else {
+            return func;
+        };
+        let latest = match self.functions.read().unwrap().get(name) {
+            Some(latest) if !Arc::ptr_eq(latest, &func) => Arc::clone(latest),
+            _ => return func,
+        };
+        if func.closure.is_empty() {
+            return latest;
+        }
+        let mut merged = FunctionValue::clone(&latest);
+        merged.closure.extend(func.closure.clone());
+        Arc::new(merged)
+    }
+
+    /// Whether a call to `name` may be one of the builtins below. Apart from
+    /// map, filter and reduce, a user function of the same name wins.
+    fn is_named_builtin(&self, name: &str) -> bool {
+        match name {
+            "map" | "filter" | "reduce" => true,
+            "pmap" | "flatMap" | "takeWhile" | "dropWhile" | "find" | "every" | "some"
+            | "forEach" | "mapValues" | "mapKeys" | "sortBy" | "pushUndo" | "undo" | "redo"
+            | "assert" | "assertEq" | "setAssertions" | "observable" | "untrack"
+            | "parallelReduce" | "spawnAll" | "raceAll" => self.env.get(name).is_none(),
+            "readFileChunks" => {
+                self.is_allowed(Capability::Filesystem) && self.env.get(name).is_none()
+            }
+            _ => false,
+        }
+    }
+
+    /// Builtins that call back into the interpreter, matched by name and by
+    /// the number of (spread-expanded) arguments. The arguments are handed
+    /// back when they don't fit, for an ordinary call.
+    fn call_named_builtin(
+        &mut self,
+        name: &str,
+        args: Vec<Value>,
+    ) -> Result<Result<Value, InterpreterError>, Vec<Value>> {
+        let fits = match name {
+            "map" | "pmap" | "flatMap" | "filter" | "takeWhile" | "dropWhile" | "find"
+            | "every" | "some" | "forEach" | "mapValues" | "mapKeys" | "sortBy" => args.len() == 2,
+            "pushUndo" => args.len() <= 1,
+            "undo" | "redo" => args.is_empty(),
+            "assert" => matches!(args.len(), 1 | 2),
+            "assertEq" | "reduce" => matches!(args.len(), 2 | 3),
+            "setAssertions" | "observable" | "untrack" | "spawnAll" | "raceAll" => args.len() == 1,
+            "parallelReduce" | "readFileChunks" => args.len() == 3,
+            _ => false,
+        };
+        if !fits {
+            return Err(args);
+        }
+
+        let mut args = args.into_iter();
+        let mut arg = || args.next().unwrap_or(Value::Nil);
+        Ok(match name {
+            "map" => self.builtin_map(arg(), arg()),
+            "pmap" => self.builtin_pmap(arg(), arg()),
+            "flatMap" => self.builtin_flat_map(arg(), arg()),
+            "filter" => self.builtin_filter(arg(), arg()),
+            "takeWhile" | "dropWhile" => self.builtin_take_while(name, arg(), arg()),
+            "find" => self.builtin_find(arg(), arg()),
+            "every" | "some" => self.builtin_every_some(name, arg(), arg()),
+            "forEach" => self.builtin_for_each(arg(), arg()),
+            "mapValues" | "mapKeys" => self.builtin_map_object(name, arg(), arg()),
+            "sortBy" => self.builtin_sort_by(arg(), arg()),
+            "pushUndo" => match args.next() {
+                Some(Value::String(label)) => {
+                    self.push_undo(label);
+                    Ok(Value::Nil)
+                }
+                None => {
+                    self.push_undo(String::new());
+                    Ok(Value::Nil)
+                }
+                Some(other) => Err(InterpreterError::TypeError(format!(
+                    "pushUndo expects a string label, got {}",
+                    other.type_name()
+                ))),
+            },
+            "undo" => self.undo().map(Value::String),
+            "redo" => self.redo().map(Value::String),
+            "assert" => {
+                let condition = arg();
+                Self::builtin_assert(condition, args.next())
+            }
+            "assertEq" => {
+                let (actual, expected) = (arg(), arg());
+                Self::builtin_assert_eq(actual, expected, args.next())
+            }
+            "setAssertions" => {
+                self.assertions_enabled = arg().is_truthy();
+                Ok(Value::Nil)
+            }
+            "observable" => match arg() {
+                Value::Object(fields) => self
+                    .reactive_ctx
+                    .observable(fields)
+                    .map(Value::Observable)
+                    .map_err(|e| InterpreterError::RuntimeError(e.to_string())),
+                other => Err(InterpreterError::TypeError(format!(
+                    "observable expects an object, got {}",
+                    other.type_name()
+                ))),
+            },
+            "untrack" => {
+                let func = arg();
+                let reactive_ctx = Arc::clone(&self.reactive_ctx);
+                reactive_ctx.untracked(|| self.call_function(func, Vec::new()))
+            }
+            "reduce" => {
+                let (array, func) = (arg(), arg());
+                self.builtin_reduce(array, func, args.next())
+            }
+            "parallelReduce" => self.builtin_parallel_reduce(arg(), arg(), arg()),
+            "spawnAll" | "raceAll" => self.builtin_task_group(name, arg()),
+            "readFileChunks" => self.builtin_read_file_chunks(arg(), arg(), arg()),
+            _ => unreachable!("{} is not a named builtin", name),
+        })
+    }
+
+    /// Helper for map operation
+    fn builtin_map(&mut self, array: Value, func: Value) -> Result<Value, InterpreterError> {
+        match array {
+            Value::Array(arr) => {
+                let mut result = Vec::new();
+                for item in arr {
+                    let mapped = self.call_function(func.clone(), vec![item])?;
+                    result.push(mapped);
+                }
+                Ok(Value::Array(result))
+            }
+            _ => Err(InterpreterError::TypeError(
+                "map expects an array".to_string(),
+            )),
+        }
+    }
+
+    /// Build the object for `new Name(args)` or `new Name { field: value }`.
+    /// Positional arguments fill fields in declaration order; fields given
+    /// neither way take their default, and a field without one is an error.
+    fn instantiate_struct(
+        &mut self,
+        name: &str,
+        arguments: &[Expression],
+        initializers: &[(String, Expression)],
+    ) -> Result<Value, InterpreterError> {
+        let Some(fields) = self.structs.get(name).cloned() else {
+            return Err(InterpreterError::RuntimeError(format!(
+                "Unknown struct '{}'",
+                name
+            )));
+        };
+        if arguments.len() > fields.len() {
+            return Err(InterpreterError::RuntimeError(format!(
+                "Struct '{}' has {} fields but {} arguments were given",
+                name,
+                fields.len(),
+                arguments.len()
+            )));
+        }
+
+        let mut values = HashMap::new();
+        for (field, arg) in fields.iter().zip(arguments) {
+            values.insert(field.name.clone(), self.evaluate_expression(arg)?);
+        }
+        for (key, expr) in initializers {
+            if !fields.iter().any(|field| &field.name == key) {
+                return Err(InterpreterError::RuntimeError(format!(
+                    "Unknown field '{}' for struct '{}'",
+                    key, name
+                )));
+            }
+            values.insert(key.clone(), self.evaluate_expression(expr)?);
+        }
+        for field in fields.iter() {
+            if values.contains_key(&field.name) {
+                continue;
+            }
+            let Some(default) = &field.default_value else {
+                return Err(InterpreterError::RuntimeError(format!(
+                    "Missing field '{}' for struct '{}'",
+                    field.name, name
+                )));
+            };
+            let value = self.evaluate_expression(default)?;
+            values.insert(field.name.clone(), value);
+        }
+
+        Ok(Value::Object(values))
+    }
+
+    /// `name ~> handler` for a reactive variable: an effect that calls the
+    /// handler with the current value now and after every change. Returns the
+    /// current value.
+    fn subscribe_stream(&mut self, name: &str, handler: Value) -> Result<Value, InterpreterError> {
+        let spawn_child = self.child_spawner();
+        let source = name.to_string();
+        // A handler that writes its own source would otherwise re-trigger itself forever
+        let running = Arc::new(AtomicBool::new(false));
+
+        let effect_name = format!("stream@{}", self.reactive_ctx.len());
+        self.reactive_ctx
+            .register_effect(effect_name, vec![name.to_string()], move |ctx| {
+                let (Some(mut child), Ok(value)) = (spawn_child(), ctx.peek(&source)) else {
+                    return;
+                };
+                if running.swap(true, Ordering::SeqCst) {
+                    return;
+                }
+                if let Err(e) = child.call_function(handler.clone(), vec![value]) {
+                    eprintln!("Error in stream handler: {}", e);
+                }
+                running.store(false, Ordering::SeqCst);
+            })
+            .map_err(|e| InterpreterError::RuntimeError(e.to_string()))?;
+
+        self.reactive_ctx
+            .peek(name)
+            .map_err(|e| InterpreterError::RuntimeError(e.to_string()))
+    }
+
+    /// Helper for pmap: map on rayon's pool, keeping the element order.
+    /// Each worker calls the function in its own child interpreter over a
+    /// snapshot of the environment, so the function should be pure; any
+    /// variables it assigns are discarded.
+    fn builtin_pmap(&mut self, array: Value, func: Value) -> Result<Value, InterpreterError> {
+        use rayon::prelude::*;
+
+        match array {
+            Value::Array(arr) => {
+                let spawn_child = self.child_spawner();
+                arr.into_par_iter()
+                    .map_init(&spawn_child, |child, item| match child {
+                        Some(child) => child.call_function(func.clone(), vec![item]),
+                        None => Err(InterpreterError::RuntimeError(
+                            "Interpreter dropped during pmap".to_string(),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Value::Array)
+            }
+            _ => Err(InterpreterError::TypeError(
+                "pmap expects an array".to_string(),
+            )),
+        }
+    }
+
+    /// Read an array of key names, as taken by `pick` and `omit`
+    fn key_list(name: &str, keys: &Value) -> Result<Vec<String>, String> {
+        match keys {
+            Value::Array(items) => items
+                .iter()
+                .map(|item| match item {
+                    Value::String(key) => Ok(key.clone()),
+                    other => Err(format!(
+                        "{} keys must be strings, got {}",
+                        name,
+                        other.type_name()
+                    )),
+                })
+                .collect(),
+            other => Err(format!(
+                "{} expects an array of keys, got {}",
+                name,
+                other.type_name()
+            )),
+        }
+    }
+
+    /// Helper for flatMap operation: array results are spliced in, anything
+    /// else is appended as a single element
+    fn builtin_flat_map(&mut self, array: Value, func: Value) -> Result<Value, InterpreterError> {
+        match array {
+            Value::Array(arr) => {
+                let mut result = Vec::new();
+                for item in arr {
+                    match self.call_function(func.clone(), vec![item])? {
+                        Value::Array(items) => result.extend(items),
+                        other => result.push(other),
+                    }
+                }
+                Ok(Value::Array(result))
+            }
+            _ => Err(InterpreterError::TypeError(
+                "flatMap expects an array".to_string(),
+            )),
+        }
+    }
+
+    /// Helper for filter operation
+    fn builtin_filter(&mut self, array: Value, func: Value) -> Result<Value, InterpreterError> {
+        match array {
+            Value::Array(arr) => {
+                let mut result = Vec::new();
+                for item in arr {
+                    let keep = self.call_function(func.clone(), vec![item.clone()])?;
+                    if keep.is_truthy() {
+                        result.push(item);
+                    }
+                }
+                Ok(Value::Array(result))
+            }
+            _ => Err(InterpreterError::TypeError(
+                "filter expects an array".to_string(),
+            )),
+        }
+    }
+
+    /// Helper for takeWhile/dropWhile: split the array at the first element
+    /// the predicate rejects, keeping the front or the rest
+    fn builtin_take_while(
+        &mut self,
+        name: &str,
+        array: Value,
+        func: Value,
+    ) -> Result<Value, InterpreterError> {
+        match array {
+            Value::Array(arr) => {
+                let mut split = arr.len();
+                for (i, item) in arr.iter().enumerate() {
+                    if !self
+                        .call_function(func.clone(), vec![item.clone()])?
+                        .is_truthy()
+                    {
+                        split = i;
+                        break;
+                    }
+                }
+                let mut front = arr;
+                let rest = front.split_off(split);
+                Ok(Value::Array(if name == "takeWhile" { front } else { rest }))
+            }
+            _ => Err(InterpreterError::TypeError(format!(
+                "{} expects an array",
+                name
+            ))),
+        }
+    }
+
+    /// Helper for sum/product: fold the numbers of an array, staying an
+    /// integer unless a float shows up
+    fn builtin_sum_product(name: &str, array: Value) -> Result<Value, NativeError> {
+        let Value::Array(items) = array else {
+            return Err(NativeError::Type(format!(
+                "{} expects an array, got {}",
+                name,
+                array.type_name()
+            )));
+        };
+        let identity = Value::Integer(if name == "sum" { 0 } else { 1 });
+        items
+            .iter()
+            .enumerate()
+            .try_fold(identity, |acc, (i, item)| {
+                if !matches!(item, Value::Integer(_) | Value::Float(_)) {
+                    return Err(NativeError::Type(format!(
+                        "{} expects numbers, got {} at index {}",
+                        name,
+                        item.type_name(),
+                        i
+                    )));
+                }
+                let result = if name == "sum" {
+                    acc.add(item)
+                } else {
+                    acc.multiply(item)
+                };
+                result.map_err(NativeError::Runtime)
+            })
+    }
+
+    /// Helper for deleteKey: a copy of the object without `key`, which need
+    /// not be present
+    fn builtin_delete_key(args: Vec<Value>) -> Result<Value, NativeError> {
+        match <[Value; 2]>::try_from(args) {
+            Ok([Value::Object(mut map), Value::String(key)]) => {
+                map.remove(&key);
+                Ok(Value::Object(map))
+            }
+            Ok([Value::Object(_), key]) => Err(NativeError::Type(format!(
+                "deleteKey expects a string key, got {}",
+                key.type_name()
+            ))),
+            Ok([other, _]) => Err(NativeError::Type(format!(
+                "deleteKey expects an object, got {}",
+                other.type_name()
+            ))),
+            Err(args) => Err(NativeError::Runtime(format!(
+                "deleteKey expects 2 arguments (object, key), got {}",
+                args.len()
+            ))),
+        }
+    }
+
+    /// Helper for merge: shallow-merges objects left to right, so a key in a
+    /// later object replaces the same key from an earlier one
+    fn builtin_merge(args: Vec<Value>) -> Result<Value, NativeError> {
+        let mut merged = HashMap::new();
+        for (i, arg) in args.into_iter().enumerate() {
+            match arg {
+                Value::Object(map) => merged.extend(map),
+                other => {
+                    return Err(NativeError::Type(format!(
+                        "merge expects objects, got {} at argument {}",
+                        other.type_name(),
+                        i
+                    )))
+                }
+            }
+        }
+        Ok(Value::Object(merged))
+    }
+
+    /// Helper for find operation: the first element the predicate accepts, or nil
+    fn builtin_find(&mut self, array: Value, func: Value) -> Result<Value, InterpreterError> {
+        match array {
+            Value::Array(arr) => {
+                for item in arr {
+                    if self
+                        .call_function(func.clone(), vec![item.clone()])?
+                        .is_truthy()
+                    {
+                        return Ok(item);
+                    }
+                }
+                Ok(Value::Nil)
+            }
+            _ => Err(InterpreterError::TypeError(
+                "find expects an array".to_string(),
+            )),
+        }
+    }
+
+    /// Helper for every/some: stops calling the predicate as soon as the
+    /// answer is known
+    fn builtin_every_some(
+        &mut self,
+        name: &str,
+        array: Value,
+        func: Value,
+    ) -> Result<Value, InterpreterError> {
+        match array {
+            Value::Array(arr) => {
+                // every looks for a rejected element, some for an accepted one
+                let decisive = name == "some";
+                for item in arr {
+                    if self.call_function(func.clone(), vec![item])?.is_truthy() == decisive {
+                        return Ok(Value::Boolean(decisive));
+                    }
+                }
+                Ok(Value::Boolean(!decisive))
             }
-            Value::Native(native_fn) => native_fn(args).map_err(InterpreterError::RuntimeError),
             _ => Err(InterpreterError::TypeError(format!(
-                "Cannot call {}",
-                func.type_name()
+                "{} expects an array",
+                name
             ))),
         }
     }
 
-    /// Helper for map operation
-    fn builtin_map(&mut self, array: Value, func: Value) -> Result<Value, InterpreterError> {
+    /// Helper for forEach operation: calls the function for its side effects
+    fn builtin_for_each(&mut self, array: Value, func: Value) -> Result<Value, InterpreterError> {
         match array {
             Value::Array(arr) => {
-                let mut result = Vec::new();
                 for item in arr {
-                    let mapped = self.call_function(func.clone(), vec![item])?;
-                    result.push(mapped);
+                    self.call_function(func.clone(), vec![item])?;
                 }
-                Ok(Value::Array(result))
+                Ok(Value::Nil)
             }
             _ => Err(InterpreterError::TypeError(
-                "map expects an array".to_string(),
+                "forEach expects an array".to_string(),
             )),
         }
     }
 
-    /// Helper for filter operation
-    fn builtin_filter(&mut self, array: Value, func: Value) -> Result<Value, InterpreterError> {
-        match array {
-            Value::Array(arr) => {
-                let mut result = Vec::new();
-                for item in arr {
-                    let keep = self.call_function(func.clone(), vec![item.clone()])?;
-                    if keep.is_truthy() {
-                        result.push(item);
+    /// Helper for mapValues/mapKeys: builds a new object with `func` applied to
+    /// each value or key. Keys are visited in sorted order, so when `mapKeys`
+    /// maps two keys to the same name the one sorting last wins.
+    fn builtin_map_object(
+        &mut self,
+        name: &str,
+        object: Value,
+        func: Value,
+    ) -> Result<Value, InterpreterError> {
+        let Value::Object(map) = object else {
+            return Err(InterpreterError::TypeError(format!(
+                "{} expects an object, got {}",
+                name,
+                object.type_name()
+            )));
+        };
+        let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut result = HashMap::with_capacity(entries.len());
+        for (key, value) in entries {
+            if name == "mapValues" {
+                let mapped = self.call_function(func.clone(), vec![value])?;
+                result.insert(key, mapped);
+            } else {
+                match self.call_function(func.clone(), vec![Value::String(key)])? {
+                    Value::String(mapped) => {
+                        result.insert(mapped, value);
+                    }
+                    other => {
+                        return Err(InterpreterError::TypeError(format!(
+                            "mapKeys expects the function to return a string, got {}",
+                            other.type_name()
+                        )))
                     }
                 }
-                Ok(Value::Array(result))
             }
-            _ => Err(InterpreterError::TypeError(
-                "filter expects an array".to_string(),
-            )),
         }
+        Ok(Value::Object(result))
+    }
+
+    /// `assert(cond, message?)` used as an expression, e.g. inside a lambda.
+    /// Statements starting with `assert` are parsed as the assert statement,
+    /// which can also quote the condition's source.
+    fn builtin_assert(condition: Value, message: Option<Value>) -> Result<Value, InterpreterError> {
+        if condition.is_truthy() {
+            return Ok(Value::Nil);
+        }
+        let mut text = "Assertion failed".to_string();
+        if let Some(message) = message {
+            text.push_str(&format!(" ({})", message.as_string()));
+        }
+        Err(InterpreterError::Throw(Value::String(text)))
+    }
+
+    /// `assertEq(actual, expected, message?)`: throws, catchably, when the two
+    /// differ. Like the `assert` statement it evaluates nothing when
+    /// assertions are off, which the caller checks.
+    fn builtin_assert_eq(
+        actual: Value,
+        expected: Value,
+        message: Option<Value>,
+    ) -> Result<Value, InterpreterError> {
+        if actual == expected {
+            return Ok(Value::Nil);
+        }
+        // Types alongside, so 1 and "1" read differently
+        let mut text = format!(
+            "assertEq failed: {} ({}) != {} ({})",
+            actual,
+            actual.type_name(),
+            expected,
+            expected.type_name()
+        );
+        if let Some(message) = message {
+            text.push_str(&format!(" ({})", message.as_string()));
+        }
+        Err(InterpreterError::Throw(Value::String(text)))
     }
 
     /// Helper for reduce operation
@@ -2605,10 +5563,520 @@ impl Interpreter {
         }
     }
 
+    /// Helper for parallelReduce: fold fixed-size chunks on rayon's pool, each
+    /// starting from `identity`, then combine the partial results in order.
+    /// That is only equal to a sequential reduce when `func` is associative
+    /// and `identity` is its identity. Chunk boundaries depend only on the
+    /// array length, so the result doesn't vary with scheduling.
+    fn builtin_parallel_reduce(
+        &mut self,
+        array: Value,
+        func: Value,
+        identity: Value,
+    ) -> Result<Value, InterpreterError> {
+        use rayon::prelude::*;
+
+        let Value::Array(arr) = array else {
+            return Err(InterpreterError::TypeError(
+                "parallelReduce expects an array".to_string(),
+            ));
+        };
+        self.check_pure("parallelReduce", &func)?;
+
+        let spawn_child = self.child_spawner();
+        let partials = arr
+            .par_chunks(PARALLEL_REDUCE_CHUNK)
+            .map_init(&spawn_child, |child, chunk| {
+                let Some(child) = child else {
+                    return Err(InterpreterError::RuntimeError(
+                        "Interpreter dropped during parallelReduce".to_string(),
+                    ));
+                };
+                chunk.iter().try_fold(identity.clone(), |acc, item| {
+                    child.call_function(func.clone(), vec![acc, item.clone()])
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        partials.into_iter().try_fold(identity, |acc, partial| {
+            self.call_function(func.clone(), vec![acc, partial])
+        })
+    }
+
+    /// Helper for spawnAll and raceAll: call each zero-argument function in
+    /// its own child interpreter, at most `TASK_GROUP_WORKERS` at a time.
+    /// spawnAll waits for every task and returns the results in input order,
+    /// with an error object in place of each task that failed. raceAll
+    /// returns the first task to finish, raising its error if it failed.
+    fn builtin_task_group(&mut self, name: &str, tasks: Value) -> Result<Value, InterpreterError> {
+        let funcs = match tasks {
+            Value::Array(funcs) => funcs,
+            other => {
+                return Err(InterpreterError::TypeError(format!(
+                    "{} expects an array of functions, got {}",
+                    name,
+                    other.type_name()
+                )))
+            }
+        };
+        for func in &funcs {
+            if !matches!(func, Value::Function(_) | Value::Native(_)) {
+                return Err(InterpreterError::TypeError(format!(
+                    "{} expects an array of functions, found {}",
+                    name,
+                    func.type_name()
+                )));
+            }
+            self.check_pure(name, func)?;
+        }
+
+        let spawn_child = Arc::new(self.child_spawner());
+        let tasks: Vec<_> = funcs
+            .into_iter()
+            .map(|func| {
+                let spawn_child = Arc::clone(&spawn_child);
+                let name = name.to_string();
+                move || match spawn_child() {
+                    Some(mut child) => child.call_function(func, Vec::new()),
+                    None => Err(InterpreterError::RuntimeError(format!(
+                        "Interpreter dropped during {}",
+                        name
+                    ))),
+                }
+            })
+            .collect();
+
+        if name == "raceAll" {
+            if tasks.is_empty() {
+                return Err(InterpreterError::RuntimeError(
+                    "raceAll expects at least one function".to_string(),
+                ));
+            }
+            return crate::parallel::race(tasks, TASK_GROUP_WORKERS)
+                .map_err(InterpreterError::RuntimeError)?;
+        }
+
+        crate::parallel::run_pooled(tasks, TASK_GROUP_WORKERS)
+            .map_err(InterpreterError::RuntimeError)?
+            .into_iter()
+            .map(|result| result.or_else(InterpreterError::into_exception))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array)
+    }
+
+    /// Reject a function handed to a parallel builtin if it, or a function it
+    /// calls, uses IO, host capabilities or reactive state
+    fn check_pure(&self, builtin: &str, func: &Value) -> Result<(), InterpreterError> {
+        let impure_native = |value: &Value| {
+            let Value::Native(native) = value else {
+                return None;
+            };
+            Capability::ALL
+                .iter()
+                .flat_map(|capability| capability.builtins())
+                .chain(IMPURE_BUILTINS)
+                .find(|name| {
+                    matches!(self.env.get(name), Some(Value::Native(other)) if Arc::ptr_eq(native, &other))
+                })
+                .map(|name| name.to_string())
+        };
+        let impure = |name: &str| {
+            InterpreterError::RuntimeError(format!(
+                "{} needs a pure function, but it uses '{}'",
+                builtin, name
+            ))
+        };
+
+        let mut pending = vec![func.clone()];
+        let mut seen = HashSet::new();
+        while let Some(value) = pending.pop() {
+            if let Some(name) = impure_native(&value) {
+                return Err(impure(&name));
+            }
+            let Value::Function(func) = value else {
+                continue;
+            };
+            let func = self.current_definition(func);
+            if !seen.insert(Arc::as_ptr(&func)) {
+                continue;
+            }
+
+            let mut names = Vec::new();
+            visit_block_identifiers(&func.body, &mut |name| names.push(name.to_string()));
+            for name in names {
+                if func.parameters.contains(&name) {
+                    continue;
+                }
+                let value = func
+                    .closure
+                    .get(&name)
+                    .cloned()
+                    .or_else(|| self.env.get(&name));
+                match value {
+                    Some(Value::ReactiveRef(_)) | Some(Value::Observable(_)) => {
+                        return Err(impure(&name))
+                    }
+                    Some(value @ (Value::Function(_) | Value::Native(_))) => pending.push(value),
+                    // Builtins the evaluator handles by name have no binding
+                    None if IMPURE_BUILTINS.contains(&name.as_str())
+                        || Capability::ALL
+                            .iter()
+                            .any(|capability| capability.builtins().contains(&name.as_str())) =>
+                    {
+                        return Err(impure(&name))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Substitute `${path}` placeholders in a template with values looked up in
+    /// `context` by dotted path. Unknown paths render as empty text unless
+    /// `strict` is set, in which case they are an error.
+    fn render_template(template: &str, context: &Value, strict: bool) -> Result<String, String> {
+        let mut output = String::new();
+        for segment in crate::parser::split_template(template) {
+            match segment {
+                TemplateSegment::Text(text) => output.push_str(&text),
+                TemplateSegment::Placeholder(path) => {
+                    let path = path.trim();
+                    let mut current = Some(context);
+                    for key in path.split('.') {
+                        current = match current {
+                            Some(Value::Object(map)) => map.get(key.trim()),
+                            _ => None,
+                        };
+                    }
+                    match current {
+                        Some(value) => output.push_str(&value.to_string()),
+                        None if strict => {
+                            return Err(format!("render: undefined reference '{}'", path))
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Send a request for httpGet/httpPost. Connection failures and non-2xx
+    /// statuses are errors that name the request and the status.
+    fn http_send(request: HttpRequest) -> Result<Value, String> {
+        let target = format!("{} {}", request.method.as_str(), request.url);
+        let response = HttpClient::new()
+            .execute(request)
+            .map_err(|e| format!("{} failed: {}", target, e))?;
+        if !(200..300).contains(&response.status_code) {
+            return Err(format!(
+                "{} failed: HTTP {} {}",
+                target, response.status_code, response.status_text
+            )
+            .trim_end()
+            .to_string());
+        }
+        response.body_string().map(Value::String)
+    }
+
+    /// Fill `{}` placeholders in order and `{0}`, `{1}`, ... by index, with
+    /// `{{` and `}}` standing for literal braces. Every argument must be used
+    /// and every placeholder must have an argument.
+    fn format_string(template: &str, args: &[Value]) -> Result<String, String> {
+        let mut output = String::new();
+        let mut used = vec![false; args.len()];
+        let mut next = 0;
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    output.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    output.push('}');
+                }
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => return Err("format: unclosed '{' in template".to_string()),
+                        }
+                    }
+                    let index = if spec.is_empty() {
+                        next += 1;
+                        next - 1
+                    } else {
+                        spec.trim()
+                            .parse::<usize>()
+                            .map_err(|_| format!("format: invalid placeholder '{{{}}}'", spec))?
+                    };
+                    let arg = args.get(index).ok_or_else(|| {
+                        format!(
+                            "format: the template needs argument {} but {} given",
+                            index,
+                            args.len()
+                        )
+                    })?;
+                    used[index] = true;
+                    output.push_str(&arg.as_string());
+                }
+                '}' => return Err("format: unmatched '}' in template (use '}}')".to_string()),
+                c => output.push(c),
+            }
+        }
+        if let Some(unused) = used.iter().position(|used| !used) {
+            return Err(format!(
+                "format: argument {} is not used by the template ({} given)",
+                unused,
+                args.len()
+            ));
+        }
+        Ok(output)
+    }
+
+    /// Helper for sortBy operation: derives one key per element, then stable-sorts by key
+    fn builtin_sort_by(&mut self, array: Value, func: Value) -> Result<Value, InterpreterError> {
+        match array {
+            Value::Array(arr) => {
+                let mut keys = Vec::with_capacity(arr.len());
+                for item in &arr {
+                    keys.push(self.call_function(func.clone(), vec![item.clone()])?);
+                }
+                Self::check_sortable(&keys).map_err(InterpreterError::RuntimeError)?;
+
+                let mut keyed: Vec<(Value, Value)> = keys.into_iter().zip(arr).collect();
+                keyed.sort_by(|(a, _), (b, _)| a.compare(b).unwrap_or(std::cmp::Ordering::Equal));
+                Ok(Value::Array(
+                    keyed.into_iter().map(|(_, item)| item).collect(),
+                ))
+            }
+            _ => Err(InterpreterError::TypeError(
+                "sortBy expects an array".to_string(),
+            )),
+        }
+    }
+
+    /// Stream a file to `func` in chunks of at most `chunk_size` bytes, so only
+    /// one chunk is held in memory. A character split by a chunk boundary is
+    /// carried into the next chunk. Stops early when `func` returns false and
+    /// gives back the number of chunks delivered.
+    fn builtin_read_file_chunks(
+        &mut self,
+        path: Value,
+        chunk_size: Value,
+        func: Value,
+    ) -> Result<Value, InterpreterError> {
+        use std::io::Read;
+
+        let (Value::String(path), Value::Integer(chunk_size)) = (path, chunk_size) else {
+            return Err(InterpreterError::TypeError(
+                "readFileChunks expects (path, chunkSize, fn)".to_string(),
+            ));
+        };
+        if chunk_size <= 0 {
+            return Err(InterpreterError::RuntimeError(format!(
+                "readFileChunks chunk size must be positive, got {}",
+                chunk_size
+            )));
+        }
+        let not_utf8 = || InterpreterError::RuntimeError(format!("'{}' is not valid UTF-8", path));
+
+        let resolved =
+            Self::resolve_fs_path(&self.fs_root, &path).map_err(InterpreterError::RuntimeError)?;
+        let mut file = std::fs::File::open(resolved)
+            .map_err(|e| InterpreterError::RuntimeError(format!("Failed to read file: {}", e)))?;
+
+        let mut chunk = Vec::new();
+        let mut delivered = 0;
+        loop {
+            let wanted = (chunk_size as usize).saturating_sub(chunk.len()).max(1);
+            let read = (&mut file)
+                .take(wanted as u64)
+                .read_to_end(&mut chunk)
+                .map_err(|e| {
+                    InterpreterError::RuntimeError(format!("Failed to read file: {}", e))
+                })?;
+            if read == 0 {
+                if chunk.is_empty() {
+                    break;
+                }
+                // The file ends partway through a character
+                return Err(not_utf8());
+            }
+
+            let complete = match std::str::from_utf8(&chunk) {
+                Ok(_) => chunk.len(),
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => return Err(not_utf8()),
+            };
+            if complete == 0 {
+                continue;
+            }
+            let rest = chunk.split_off(complete);
+            let text =
+                String::from_utf8(std::mem::replace(&mut chunk, rest)).map_err(|_| not_utf8())?;
+
+            delivered += 1;
+            let verdict = self.call_function(func.clone(), vec![Value::String(text)])?;
+            if matches!(verdict, Value::Boolean(false)) {
+                break;
+            }
+        }
+        Ok(Value::Integer(delivered))
+    }
+
+    /// Ensure every pair of values can be ordered with `Value::compare`, so a
+    /// sort never silently mixes numbers with strings or hits a NaN
+    fn check_sortable(values: &[Value]) -> Result<(), String> {
+        if let Some(first) = values.first() {
+            for value in values {
+                value.compare(first)?;
+                value.compare(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The integer spelled by the leading digits of `s`, JS `parseInt` style:
+    /// surrounding whitespace and trailing junk are ignored, a sign is allowed,
+    /// and `0x` selects base 16 when no other radix is given
+    fn parse_int_prefix(s: &str, radix: Option<u32>) -> Option<i64> {
+        let s = s.trim_start();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let hex = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"));
+        let (radix, s) = match (radix, hex) {
+            (None | Some(16), Some(rest)) => (16, rest),
+            (radix, _) => (radix.unwrap_or(10), s),
+        };
+        let end = s.find(|c: char| !c.is_digit(radix)).unwrap_or(s.len());
+        let digits = &s[..end];
+        if digits.is_empty() {
+            return None;
+        }
+        let sign = if negative { "-" } else { "" };
+        i64::from_str_radix(&format!("{}{}", sign, digits), radix).ok()
+    }
+
+    /// The float spelled by the longest numeric prefix of `s`, so
+    /// `"3.14abc"` gives 3.14; leading whitespace is skipped
+    fn parse_float_prefix(s: &str) -> Option<f64> {
+        let s = s.trim_start();
+        let bytes = s.as_bytes();
+        let digits_from = |mut i: usize| {
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            i
+        };
+
+        let mut end = if matches!(bytes.first(), Some(b'+' | b'-')) {
+            1
+        } else {
+            0
+        };
+        let int_end = digits_from(end);
+        let mut has_digits = int_end > end;
+        end = int_end;
+        if bytes.get(end) == Some(&b'.') {
+            let frac_end = digits_from(end + 1);
+            has_digits |= frac_end > end + 1;
+            end = frac_end;
+        }
+        if !has_digits {
+            return None;
+        }
+        // An exponent only counts when digits follow it
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            let mut exp = end + 1;
+            if matches!(bytes.get(exp), Some(b'+' | b'-')) {
+                exp += 1;
+            }
+            let exp_end = digits_from(exp);
+            if exp_end > exp {
+                end = exp_end;
+            }
+        }
+        s[..end].parse().ok()
+    }
+
+    /// Stable-sort values in ascending order using `Value::compare`
+    fn sort_values(values: &mut [Value]) -> Result<(), String> {
+        Self::check_sortable(values)?;
+        values.sort_by(|a, b| a.compare(b).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(())
+    }
+
+    /// Sweep reactive nodes once the graph has grown past the current threshold,
+    /// then move the threshold to twice the surviving size
+    fn maybe_collect_reactive_garbage(&mut self) {
+        if self.reactive_ctx.len() >= self.reactive_gc_threshold {
+            self.collect_reactive_garbage();
+            self.reactive_gc_threshold =
+                (self.reactive_ctx.len() * 2).max(REACTIVE_GC_MIN_THRESHOLD);
+        }
+    }
+
+    /// Remove reactive nodes that no binding in any live scope refers to
+    fn collect_reactive_garbage(&mut self) -> usize {
+        // `seen` holds the refs already visited, since a ref may contain itself
+        fn mark(
+            value: &Value,
+            roots: &mut HashSet<usize>,
+            seen: &mut HashSet<*const RwLock<Value>>,
+        ) {
+            match value {
+                Value::ReactiveRef(id) => {
+                    roots.insert(*id);
+                }
+                Value::Array(items) | Value::Tuple(items) => {
+                    items.iter().for_each(|item| mark(item, roots, seen));
+                }
+                Value::Object(fields) | Value::Struct { fields, .. } => {
+                    fields.values().for_each(|field| mark(field, roots, seen));
+                }
+                Value::Observable(obs) => roots.extend(obs.field_ids()),
+                Value::Ref(cell) if seen.insert(Arc::as_ptr(cell)) => {
+                    mark(&cell.read().unwrap(), roots, seen);
+                }
+                _ => {}
+            }
+        }
+
+        let mut roots = HashSet::new();
+        let mut seen = HashSet::new();
+        for value in self.env.values() {
+            mark(value, &mut roots, &mut seen);
+        }
+        self.reactive_ctx.collect_unreachable(&roots)
+    }
+
     /// Take a snapshot for time-travel debugging
     fn take_snapshot(&mut self, label: Option<String>) -> Result<usize, InterpreterError> {
         let state = self.env.to_persistent();
-        let call_stack = vec![]; // TODO: Track actual call stack
+        let scope_ends = self
+            .call_stack
+            .iter()
+            .skip(1)
+            .map(|frame| frame.scope)
+            .chain([self.env.depth()]);
+        let call_stack = self
+            .call_stack
+            .iter()
+            .zip(scope_ends)
+            .map(|(frame, end)| StackFrame {
+                function_name: frame.function_name.clone(),
+                line: frame.line,
+                locals: self.env.bindings_in(frame.scope..end),
+            })
+            .collect();
 
         self.time_travel
             .write()
@@ -2651,6 +6119,366 @@ impl Interpreter {
         Ok(())
     }
 
+    /// Record the current variables as an undo restore point
+    pub fn push_undo(&mut self, label: impl Into<String>) {
+        let state = self.undoable_state();
+        self.undo_history.push(label.into(), state);
+    }
+
+    /// Restore the previous undo point, returning its label
+    pub fn undo(&mut self) -> Result<String, InterpreterError> {
+        let step = self
+            .undo_history
+            .undo(self.undoable_state())
+            .map_err(|e| InterpreterError::RuntimeError(e.to_string()))?;
+        Ok(self.apply_undo_step(step))
+    }
+
+    /// Re-apply the most recently undone restore point, returning its label
+    pub fn redo(&mut self) -> Result<String, InterpreterError> {
+        let step = self
+            .undo_history
+            .redo(self.undoable_state())
+            .map_err(|e| InterpreterError::RuntimeError(e.to_string()))?;
+        Ok(self.apply_undo_step(step))
+    }
+
+    /// Variables tracked by undo. Natives (including builtin namespaces like
+    /// `db`) never compare equal, so any binding holding one is left out.
+    fn undoable_state(&self) -> PersistentHashMap<String, Value> {
+        fn holds_native(value: &Value) -> bool {
+            match value {
+                Value::Native(_) => true,
+                Value::Array(items) | Value::Tuple(items) => items.iter().any(holds_native),
+                Value::Object(fields) | Value::Struct { fields, .. } => {
+                    fields.values().any(holds_native)
+                }
+                _ => false,
+            }
+        }
+
+        self.env
+            .to_persistent()
+            .into_iter()
+            .filter(|(_, value)| !holds_native(value))
+            .collect()
+    }
+
+    /// Touch only the bindings the step's diff says changed
+    fn apply_undo_step(&mut self, step: UndoStep) -> String {
+        for (name, _, value) in step.diff.modified {
+            let _ = self.env.set(&name, value);
+        }
+        for name in step.diff.added {
+            if let Some(value) = step.state.get(&name) {
+                self.env.define(name, value.clone());
+            }
+        }
+        for name in step.diff.removed {
+            self.env.remove(&name);
+        }
+        step.label
+    }
+
+    /// Resolve an import path relative to the directory of the current file
+    fn resolve_import_path(&self, path: &str) -> PathBuf {
+        let target = Path::new(path);
+        if target.is_absolute() {
+            return target.to_path_buf();
+        }
+        match Path::new(&self.current_file).parent() {
+            Some(dir) => dir.join(target),
+            None => target.to_path_buf(),
+        }
+    }
+
+    /// Read a data file and parse it according to its extension
+    fn load_data_module(&self, path: &Path) -> Result<Value, InterpreterError> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            InterpreterError::RuntimeError(format!("Failed to import '{}': {}", path.display(), e))
+        })?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str::<serde_json::Value>(&source)
+                .map(|json| Self::json_to_value(&json))
+                .map_err(|e| {
+                    InterpreterError::RuntimeError(format!(
+                        "Failed to parse '{}': {}",
+                        path.display(),
+                        e
+                    ))
+                }),
+            Some("toml") => crate::stdlib::toml::parse(&source).map_err(|e| {
+                InterpreterError::RuntimeError(format!(
+                    "Failed to parse '{}': {}",
+                    path.display(),
+                    e
+                ))
+            }),
+            _ => serde_yaml::from_str::<serde_json::Value>(&source)
+                .map(|json| Self::json_to_value(&json))
+                .map_err(|e| {
+                    InterpreterError::RuntimeError(format!(
+                        "Failed to parse '{}': {}",
+                        path.display(),
+                        e
+                    ))
+                }),
+        }
+    }
+
+    /// Read and parse a source file, naming it in any error
+    fn parse_file(path: &Path) -> Result<Program, InterpreterError> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            InterpreterError::RuntimeError(format!("Failed to import '{}': {}", path.display(), e))
+        })?;
+        let tokens = crate::lexer::tokenize(&source).map_err(|e| {
+            InterpreterError::RuntimeError(format!("In '{}': {}", path.display(), e))
+        })?;
+        crate::parser::parse_with_source(tokens, &source)
+            .map_err(|e| InterpreterError::RuntimeError(format!("In '{}': {}", path.display(), e)))
+    }
+
+    /// Record that `path` is being imported, failing if it already is
+    fn enter_import(&mut self, path: &Path) -> Result<(), InterpreterError> {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if let Some(start) = self.importing.iter().position(|p| *p == path) {
+            let chain: Vec<String> = self.importing[start..]
+                .iter()
+                .chain([&path])
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(InterpreterError::RuntimeError(format!(
+                "Import cycle: {}",
+                chain.join(" -> ")
+            )));
+        }
+        self.importing.push(path);
+        Ok(())
+    }
+
+    /// Execute another source file in this interpreter, with `current_file`
+    /// pointing at it for the duration so nested imports and snapshots see it
+    fn execute_file_in_place(&mut self, path: &Path) -> Result<Value, InterpreterError> {
+        let program = Self::parse_file(path)?;
+
+        self.enter_import(path)?;
+        let previous_file =
+            std::mem::replace(&mut self.current_file, path.to_string_lossy().into_owned());
+        let result = self.execute(&program);
+        self.current_file = previous_file;
+        self.importing.pop();
+        result.map(|_| Value::Nil)
+    }
+
+    /// `import { a, b } from "path"`: run the module in a fresh interpreter
+    /// with this one's restrictions and bind the exported names asked for.
+    /// Imported functions carry the module's top-level bindings with them,
+    /// so helpers they call need not be imported too.
+    fn import_module_items(
+        &mut self,
+        path: &Path,
+        items: &[String],
+    ) -> Result<Value, InterpreterError> {
+        let program = Self::parse_file(path)?;
+        let mut exported = Vec::new();
+        let mut globals = Vec::new();
+        for statement in &program.statements {
+            match statement {
+                Statement::Export { item, .. } => {
+                    exported.push(item.as_str());
+                    globals.push(item.as_str());
+                }
+                Statement::Function { name, .. }
+                | Statement::Let { name, .. }
+                | Statement::Const { name, .. } => globals.push(name.as_str()),
+                _ => {}
+            }
+        }
+        if let Some(missing) = items.iter().find(|item| !exported.contains(&item.as_str())) {
+            return Err(InterpreterError::RuntimeError(format!(
+                "Module '{}' does not export '{}'",
+                path.display(),
+                missing
+            )));
+        }
+
+        let mut module = Interpreter::new();
+        for &capability in &self.denied {
+            module.deny(capability);
+        }
+        module.fs_root = Arc::clone(&self.fs_root);
+        module.assertions_enabled = self.assertions_enabled;
+        module.current_file = path.to_string_lossy().into_owned();
+        module.importing = self.importing.clone();
+        module.enter_import(path)?;
+        module.execute(&program)?;
+
+        // Module functions resolve against the module, never against the
+        // importer's same-named top-level declarations
+        let pin = |value: Value| match value {
+            Value::Function(func) if func.reloadable => {
+                let mut func = FunctionValue::clone(&func);
+                func.reloadable = false;
+                Value::Function(Arc::new(func))
+            }
+            other => other,
+        };
+        let globals: HashMap<String, Value> = globals
+            .into_iter()
+            .filter_map(|name| Some((name.to_string(), pin(module.env.get(name)?))))
+            .collect();
+        for item in items {
+            if let Some(fields) = module.structs.get(item) {
+                self.structs.insert(item.clone(), Arc::clone(fields));
+                continue;
+            }
+            let value = match module.env.get(item) {
+                Some(Value::Function(func)) => {
+                    let mut func = FunctionValue::clone(&func);
+                    func.reloadable = false;
+                    for (name, value) in &globals {
+                        func.closure
+                            .entry(name.clone())
+                            .or_insert_with(|| value.clone());
+                    }
+                    Value::Function(Arc::new(func))
+                }
+                Some(value) => value,
+                None => return Err(InterpreterError::UndefinedVariable(item.clone())),
+            };
+            self.env.define(item.clone(), value);
+        }
+        Ok(Value::Nil)
+    }
+
+    /// Remove the builtins guarded by `capability` from the global scope
+    pub fn deny(&mut self, capability: Capability) {
+        if !self.denied.insert(capability) {
+            return;
+        }
+        for &name in capability.builtins() {
+            if let Some(builtin) = self.env.remove(name) {
+                self.withheld_builtins.insert(name.to_string(), builtin);
+            }
+        }
+    }
+
+    /// Restore the builtins of a denied `capability`
+    pub fn allow(&mut self, capability: Capability) {
+        if !self.denied.remove(&capability) {
+            return;
+        }
+        for &name in capability.builtins() {
+            if let Some(builtin) = self.withheld_builtins.remove(name) {
+                self.env.define(name.to_string(), builtin);
+            }
+        }
+    }
+
+    /// Whether scripts may use `capability`
+    pub fn is_allowed(&self, capability: Capability) -> bool {
+        !self.denied.contains(&capability)
+    }
+
+    /// Confine file builtins and imports to `root`. Relative paths resolve
+    /// against it, and paths that lead outside it are rejected.
+    pub fn set_fs_root(&mut self, root: impl AsRef<Path>) -> Result<(), InterpreterError> {
+        let root = root.as_ref().canonicalize().map_err(|e| {
+            InterpreterError::RuntimeError(format!(
+                "Invalid filesystem root '{}': {}",
+                root.as_ref().display(),
+                e
+            ))
+        })?;
+        *self.fs_root.write().unwrap() = Some(root);
+        Ok(())
+    }
+
+    /// Lift the filesystem root set by `set_fs_root`
+    pub fn clear_fs_root(&mut self) {
+        *self.fs_root.write().unwrap() = None;
+    }
+
+    /// Map a script-supplied path into the filesystem root, if one is set.
+    /// `..` may not climb above the root and absolute paths must already lie
+    /// inside it. Symlinks are then followed: the nearest part of the path
+    /// that exists (the whole path, or for a new file its parent) must
+    /// canonicalize to somewhere inside the root.
+    fn resolve_fs_path(root: &RwLock<Option<PathBuf>>, path: &str) -> Result<PathBuf, String> {
+        let Some(root) = root.read().unwrap().clone() else {
+            return Ok(PathBuf::from(path));
+        };
+        let outside = || format!("Path '{}' is outside the filesystem root", path);
+
+        let requested = Path::new(path);
+        let relative = if requested.is_absolute() {
+            requested.strip_prefix(&root).map_err(|_| outside())?
+        } else {
+            requested
+        };
+
+        let mut resolved = root.clone();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::CurDir => {}
+                Component::ParentDir if resolved != root => {
+                    resolved.pop();
+                }
+                _ => return Err(outside()),
+            }
+        }
+
+        // A dangling symlink exists but can't be canonicalized, so it's rejected
+        // rather than letting a write create its target
+        let existing = resolved
+            .ancestors()
+            .find(|ancestor| ancestor.symlink_metadata().is_ok())
+            .ok_or_else(outside)?;
+        let rest = resolved.strip_prefix(existing).map_err(|_| outside())?;
+        let canonical = existing.canonicalize().map_err(|_| outside())?;
+        if !canonical.starts_with(&root) {
+            return Err(outside());
+        }
+        Ok(if rest.as_os_str().is_empty() {
+            canonical
+        } else {
+            canonical.join(rest)
+        })
+    }
+
+    /// Turn `assert` statements on or off. Disabled asserts don't evaluate
+    /// their condition at all.
+    pub fn set_assertions(&mut self, enabled: bool) {
+        self.assertions_enabled = enabled;
+    }
+
+    /// Set the file path used to resolve relative imports and label snapshots
+    pub fn set_current_file(&mut self, path: impl Into<String>) {
+        self.current_file = path.into();
+    }
+
+    /// Get the file path currently being executed
+    pub fn current_file(&self) -> &str {
+        &self.current_file
+    }
+
+    /// Build a type context seeded with the types of all currently bound values
+    pub fn type_context(&self) -> TypeContext {
+        TypeContext::from_values(&self.env.to_persistent())
+    }
+
+    /// Statically check `program` against the current bindings without
+    /// running it. Gradual: only operations that can't succeed are reported.
+    pub fn type_check(&self, program: &Program) -> Result<(), Vec<TypeError>> {
+        let errors = TypeChecker::new(self.type_context()).check(program);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     /// Get the reactive context
     pub fn reactive_context(&self) -> &Arc<ReactiveContext> {
         &self.reactive_ctx
@@ -2722,12 +6550,14 @@ mod tests {
                     name: "a".to_string(),
                     type_annotation: None,
                     default_value: None,
+                    is_rest: false,
                     span: Span::dummy(),
                 },
                 Parameter {
                     name: "b".to_string(),
                     type_annotation: None,
                     default_value: None,
+                    is_rest: false,
                     span: Span::dummy(),
                 },
             ],
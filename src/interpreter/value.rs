@@ -4,9 +4,10 @@
 //! reactive values, functions, and complex data structures.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 /// Runtime value representation
 #[derive(Clone, Serialize, Deserialize)]
@@ -29,6 +30,9 @@ pub enum Value {
     /// Array/List
     Array(Vec<Value>),
 
+    /// Raw bytes, one `u8` each instead of a whole `Value` per element
+    Bytes(Vec<u8>),
+
     /// Object/Hash Map
     Object(HashMap<String, Value>),
 
@@ -63,6 +67,14 @@ pub enum Value {
     #[serde(skip)]
     ReactiveRef(usize), // NodeId
 
+    /// Object whose fields are individually tracked signals
+    #[serde(skip)]
+    Observable(Arc<crate::reactive::Observable>),
+
+    /// Shared mutable cell created by `ref`; clones alias the same value
+    #[serde(skip)]
+    Ref(Arc<RwLock<Value>>),
+
     /// Quoted code (WOW #3)
     Quote(Box<Value>),
 
@@ -80,9 +92,16 @@ pub enum Value {
 pub struct FunctionValue {
     pub name: Option<String>,
     pub parameters: Vec<String>,
+    /// Default for each parameter, evaluated when a caller omits it
+    pub defaults: Vec<Option<crate::ast::Expression>>,
+    /// The last parameter is `...name` and collects any further arguments
+    pub variadic: bool,
     pub body: Vec<crate::ast::Statement>,
     pub closure: HashMap<String, Value>,
     pub is_async: bool,
+    /// Declared with `fn` at the top level: calls run the newest top-level
+    /// declaration of the same name, so redefining it updates every copy
+    pub reloadable: bool,
 }
 
 impl fmt::Debug for FunctionValue {
@@ -109,9 +128,10 @@ impl fmt::Debug for Value {
             Value::Nil => write!(f, "Nil"),
             Value::Boolean(b) => write!(f, "Boolean({})", b),
             Value::Integer(n) => write!(f, "Integer({})", n),
-            Value::Float(fl) => write!(f, "Float({})", fl),
+            Value::Float(fl) => write!(f, "Float({:?})", fl),
             Value::String(s) => write!(f, "String({:?})", s),
             Value::Array(arr) => write!(f, "Array({:?})", arr),
+            Value::Bytes(bytes) => write!(f, "Bytes({:?})", bytes),
             Value::Object(obj) => write!(f, "Object({:?})", obj),
             Value::Function(func) => write!(f, "{:?}", func),
             Value::Range {
@@ -133,6 +153,8 @@ impl fmt::Debug for Value {
                 values,
             } => write!(f, "Enum({}::{}, {:?})", type_name, variant, values),
             Value::ReactiveRef(id) => write!(f, "ReactiveRef({})", id),
+            Value::Observable(obs) => write!(f, "Observable({:?})", obs.snapshot()),
+            Value::Ref(cell) => format_ref(cell, f, "Ref", true),
             Value::Quote(val) => write!(f, "Quote({:?})", val),
             Value::Native(_) => write!(f, "Native(<function>)"),
             Value::Future(_) => write!(f, "Future(<pending>)"),
@@ -155,6 +177,7 @@ impl PartialEq for Value {
             }
             (Value::String(a), Value::String(b)) => a == b,
             (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
             (Value::Object(a), Value::Object(b)) => a == b,
             (Value::Function(a), Value::Function(b)) => a == b,
             (
@@ -193,18 +216,92 @@ impl PartialEq for Value {
                 },
             ) => t1 == t2 && v1 == v2 && vals1 == vals2,
             (Value::ReactiveRef(a), Value::ReactiveRef(b)) => a == b,
+            (Value::Observable(a), Value::Observable(b)) => Arc::ptr_eq(a, b),
+            (Value::Ref(a), Value::Ref(b)) => Arc::ptr_eq(a, b),
             (Value::Quote(a), Value::Quote(b)) => a == b,
-            (Value::Native(_), Value::Native(_)) => false, // Functions can't be compared
+            (Value::Native(a), Value::Native(b)) => Arc::ptr_eq(a, b),
             (Value::Future(_), Value::Future(_)) => false, // Futures can't be compared
             _ => false,
         }
     }
 }
 
+/// How [`Value::render`] spells a value out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// Rust-style with the variant visible, e.g. `String("hi")`
+    Debug,
+    /// What `print` and `str` show, e.g. `hi`
+    Display,
+    /// Compact JSON text; fails for values JSON can't hold
+    Json,
+}
+
+thread_local! {
+    /// Refs whose contents are being formatted on this thread, outermost first
+    static FORMATTING_REFS: RefCell<Vec<*const RwLock<Value>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Write `name(contents)` for a ref, or `name(<cycle>)` when the ref is
+/// already being written further out, as in a ref that holds itself
+fn format_ref(
+    cell: &Arc<RwLock<Value>>,
+    f: &mut fmt::Formatter,
+    name: &str,
+    debug: bool,
+) -> fmt::Result {
+    let ptr = Arc::as_ptr(cell);
+    let entered = FORMATTING_REFS.with(|refs| {
+        let mut refs = refs.borrow_mut();
+        if refs.contains(&ptr) {
+            return false;
+        }
+        refs.push(ptr);
+        true
+    });
+    if !entered {
+        return write!(f, "{}(<cycle>)", name);
+    }
+    let inner = cell.read().unwrap();
+    let result = if debug {
+        write!(f, "{}({:?})", name, *inner)
+    } else {
+        write!(f, "{}({})", name, *inner)
+    };
+    FORMATTING_REFS.with(|refs| refs.borrow_mut().pop());
+    result
+}
+
+/// Two refs being compared by `deep_equal`, by cell address
+type RefPair = (*const RwLock<Value>, *const RwLock<Value>);
+
+/// Error raised by a native function
+#[derive(Debug, Clone, PartialEq)]
+pub enum NativeError {
+    /// Surfaced as a runtime error
+    Runtime(String),
+    /// Surfaced as a type error, for arguments of the wrong kind
+    Type(String),
+}
+
+impl From<String> for NativeError {
+    fn from(message: String) -> Self {
+        NativeError::Runtime(message)
+    }
+}
+
 /// Native function type
-pub type NativeFn = dyn Fn(Vec<Value>) -> Result<Value, String> + Send + Sync;
+pub type NativeFn = dyn Fn(Vec<Value>) -> Result<Value, NativeError> + Send + Sync;
 
 impl Value {
+    /// Wrap a native function whose errors are runtime errors
+    pub fn native<F>(f: F) -> Value
+    where
+        F: Fn(Vec<Value>) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        Value::Native(Arc::new(move |args| f(args).map_err(NativeError::Runtime)))
+    }
+
     /// Check if value is truthy
     pub fn is_truthy(&self) -> bool {
         match self {
@@ -214,12 +311,15 @@ impl Value {
             Value::Float(f) => *f != 0.0,
             Value::String(s) => !s.is_empty(),
             Value::Array(arr) => !arr.is_empty(),
+            Value::Bytes(bytes) => !bytes.is_empty(),
             Value::Object(obj) => !obj.is_empty(),
             _ => true,
         }
     }
 
-    /// Get type name as string
+    /// The name `type_of` reports. Every variant has a distinct, stable
+    /// lowercase name that scripts may branch on, except that user-defined
+    /// and native functions are both `"function"`.
     pub fn type_name(&self) -> &'static str {
         match self {
             Value::Nil => "nil",
@@ -228,6 +328,7 @@ impl Value {
             Value::Float(_) => "float",
             Value::String(_) => "string",
             Value::Array(_) => "array",
+            Value::Bytes(_) => "bytes",
             Value::Object(_) => "object",
             Value::Function(_) => "function",
             Value::Range { .. } => "range",
@@ -235,8 +336,10 @@ impl Value {
             Value::Struct { .. } => "struct",
             Value::Enum { .. } => "enum",
             Value::ReactiveRef(_) => "reactive",
+            Value::Observable(_) => "observable",
+            Value::Ref(_) => "ref",
             Value::Quote(_) => "quote",
-            Value::Native(_) => "native_function",
+            Value::Native(_) => "function",
             Value::Future(_) => "future",
         }
     }
@@ -271,6 +374,248 @@ impl Value {
         }
     }
 
+    /// Render the value in the given style. Only [`RenderStyle::Json`] can
+    /// fail, for functions, NaN and other values JSON has no form for.
+    pub fn render(&self, style: RenderStyle) -> Result<String, String> {
+        match style {
+            RenderStyle::Debug => Ok(format!("{:?}", self)),
+            RenderStyle::Display => Ok(format!("{}", self)),
+            RenderStyle::Json => serde_json::to_string(&self.to_json()?)
+                .map_err(|e| format!("Failed to stringify JSON: {}", e)),
+        }
+    }
+
+    /// Convert to a `serde_json::Value`
+    pub fn to_json(&self) -> Result<serde_json::Value, String> {
+        self.to_json_with(false)
+            .map(|json| json.unwrap_or(serde_json::Value::Null))
+    }
+
+    /// Convert to a `serde_json::Value`. With `skip_functions`, functions and
+    /// reactive references are dropped the way `JSON.stringify` drops them:
+    /// an object field holding one is left out, an array slot becomes null,
+    /// and the value on its own gives `None`.
+    pub fn to_json_with(&self, skip_functions: bool) -> Result<Option<serde_json::Value>, String> {
+        let json = match self {
+            Value::Nil => serde_json::Value::Null,
+            Value::Boolean(b) => serde_json::Value::Bool(*b),
+            Value::Integer(i) => serde_json::Value::Number((*i).into()),
+            Value::Float(f) => serde_json::Number::from_f64(*f)
+                .map(serde_json::Value::Number)
+                .ok_or_else(|| "Invalid float value for JSON".to_string())?,
+            Value::String(s) => serde_json::Value::String(s.clone()),
+            Value::Array(arr) => serde_json::Value::Array(
+                arr.iter()
+                    .map(|item| {
+                        item.to_json_with(skip_functions)
+                            .map(|json| json.unwrap_or(serde_json::Value::Null))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            // JSON has no binary type, so bytes go out as an array of numbers
+            Value::Bytes(bytes) => serde_json::Value::Array(
+                bytes
+                    .iter()
+                    .map(|&byte| serde_json::Value::Number(byte.into()))
+                    .collect(),
+            ),
+            Value::Object(obj) => {
+                let mut json_obj = serde_json::Map::new();
+                for (k, v) in obj {
+                    if let Some(json) = v.to_json_with(skip_functions)? {
+                        json_obj.insert(k.clone(), json);
+                    }
+                }
+                serde_json::Value::Object(json_obj)
+            }
+            Value::Function(_) | Value::Native(_) | Value::ReactiveRef(_) if skip_functions => {
+                return Ok(None)
+            }
+            _ => return Err(format!("Cannot convert {} to JSON", self.type_name())),
+        };
+        Ok(Some(json))
+    }
+
+    /// Whether serde can write this value and read it back unchanged: plain
+    /// data all the way down, with no functions, reactive handles or refs,
+    /// and no NaN or infinite floats, which JSON cannot hold
+    pub fn is_serializable(&self) -> bool {
+        match self {
+            Value::Nil
+            | Value::Boolean(_)
+            | Value::Integer(_)
+            | Value::String(_)
+            | Value::Bytes(_)
+            | Value::Range { .. } => true,
+            Value::Float(f) => f.is_finite(),
+            Value::Array(items) | Value::Tuple(items) => items.iter().all(Value::is_serializable),
+            Value::Enum { values, .. } => values.iter().all(Value::is_serializable),
+            Value::Object(fields) | Value::Struct { fields, .. } => {
+                fields.values().all(Value::is_serializable)
+            }
+            Value::Quote(inner) => inner.is_serializable(),
+            Value::Function(_)
+            | Value::ReactiveRef(_)
+            | Value::Observable(_)
+            | Value::Ref(_)
+            | Value::Native(_)
+            | Value::Future(_) => false,
+        }
+    }
+
+    /// Structural equality that also looks inside shared cells: refs and
+    /// observables compare by their current contents, where `==` asks
+    /// whether they are the same cell
+    pub fn deep_equal(&self, other: &Value) -> bool {
+        self.deep_equal_with(other, &mut HashSet::new())
+    }
+
+    /// `deep_equal` remembering the pairs of refs under comparison: meeting a
+    /// pair again means a cycle, which counts as equal so far
+    fn deep_equal_with(&self, other: &Value, comparing: &mut HashSet<RefPair>) -> bool {
+        let mut fields_equal = |a: &HashMap<String, Value>, b: &HashMap<String, Value>| {
+            a.len() == b.len()
+                && a.iter().all(|(key, value)| {
+                    b.get(key)
+                        .is_some_and(|other| value.deep_equal_with(other, comparing))
+                })
+        };
+
+        match (self, other) {
+            (Value::Array(a), Value::Array(b)) | (Value::Tuple(a), Value::Tuple(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b)
+                        .all(|(x, y)| x.deep_equal_with(y, comparing))
+            }
+            (Value::Object(a), Value::Object(b)) => fields_equal(a, b),
+            (
+                Value::Struct {
+                    name: n1,
+                    fields: f1,
+                },
+                Value::Struct {
+                    name: n2,
+                    fields: f2,
+                },
+            ) => n1 == n2 && fields_equal(f1, f2),
+            (
+                Value::Enum {
+                    type_name: t1,
+                    variant: v1,
+                    values: vals1,
+                },
+                Value::Enum {
+                    type_name: t2,
+                    variant: v2,
+                    values: vals2,
+                },
+            ) => {
+                t1 == t2
+                    && v1 == v2
+                    && vals1.len() == vals2.len()
+                    && vals1
+                        .iter()
+                        .zip(vals2)
+                        .all(|(x, y)| x.deep_equal_with(y, comparing))
+            }
+            (Value::Ref(a), Value::Ref(b)) => {
+                Arc::ptr_eq(a, b)
+                    || !comparing.insert((Arc::as_ptr(a), Arc::as_ptr(b)))
+                    || a.read()
+                        .unwrap()
+                        .deep_equal_with(&b.read().unwrap(), comparing)
+            }
+            (Value::Observable(a), Value::Observable(b)) => {
+                Arc::ptr_eq(a, b) || fields_equal(&a.snapshot(), &b.snapshot())
+            }
+            _ => self == other,
+        }
+    }
+
+    /// A copy sharing no mutable state with this value: refs inside it are
+    /// copied into new cells. Functions are immutable and stay shared, and
+    /// observables stay shared since their fields live in the reactive graph.
+    pub fn deep_clone(&self) -> Value {
+        self.deep_clone_with(&mut HashMap::new())
+    }
+
+    /// `deep_clone` mapping each ref already copied to its new cell, so a ref
+    /// reached twice, or from inside itself, is copied once and cycles survive
+    fn deep_clone_with(
+        &self,
+        copies: &mut HashMap<*const RwLock<Value>, Arc<RwLock<Value>>>,
+    ) -> Value {
+        let mut fields = |fields: &HashMap<String, Value>| {
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value.deep_clone_with(copies)))
+                .collect()
+        };
+
+        match self {
+            Value::Array(a) => Value::Array(a.iter().map(|v| v.deep_clone_with(copies)).collect()),
+            Value::Tuple(t) => Value::Tuple(t.iter().map(|v| v.deep_clone_with(copies)).collect()),
+            Value::Object(o) => Value::Object(fields(o)),
+            Value::Struct { name, fields: f } => Value::Struct {
+                name: name.clone(),
+                fields: fields(f),
+            },
+            Value::Enum {
+                type_name,
+                variant,
+                values,
+            } => Value::Enum {
+                type_name: type_name.clone(),
+                variant: variant.clone(),
+                values: values.iter().map(|v| v.deep_clone_with(copies)).collect(),
+            },
+            Value::Ref(cell) => {
+                if let Some(copy) = copies.get(&Arc::as_ptr(cell)) {
+                    return Value::Ref(Arc::clone(copy));
+                }
+                let copy = Arc::new(RwLock::new(Value::Nil));
+                copies.insert(Arc::as_ptr(cell), Arc::clone(&copy));
+                let inner = cell.read().unwrap().deep_clone_with(copies);
+                *copy.write().unwrap() = inner;
+                Value::Ref(copy)
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Approximate bytes this value takes up: its own size plus the heap
+    /// data of its strings and containers. Functions, refs and other shared
+    /// handles count only their own size, since no one value owns their data.
+    pub fn deep_size(&self) -> usize {
+        let owned = match self {
+            Value::String(s) => s.capacity(),
+            Value::Bytes(bytes) => bytes.capacity(),
+            Value::Array(items) | Value::Tuple(items) => items.iter().map(Value::deep_size).sum(),
+            Value::Object(fields) => Self::fields_size(fields),
+            Value::Struct { name, fields } => name.capacity() + Self::fields_size(fields),
+            Value::Enum {
+                type_name,
+                variant,
+                values,
+            } => {
+                type_name.capacity()
+                    + variant.capacity()
+                    + values.iter().map(Value::deep_size).sum::<usize>()
+            }
+            Value::Quote(inner) => inner.deep_size(),
+            _ => 0,
+        };
+        std::mem::size_of::<Value>() + owned
+    }
+
+    fn fields_size(fields: &HashMap<String, Value>) -> usize {
+        fields
+            .iter()
+            .map(|(key, value)| std::mem::size_of::<String>() + key.capacity() + value.deep_size())
+            .sum()
+    }
+
     /// Try to convert to boolean
     pub fn as_boolean(&self) -> bool {
         self.is_truthy()
@@ -281,11 +626,6 @@ impl Value {
         matches!(self, Value::Integer(_) | Value::Float(_))
     }
 
-    /// Deep clone with shared references for functions
-    pub fn deep_clone(&self) -> Self {
-        self.clone()
-    }
-
     /// Compare values for equality (handles NaN properly)
     pub fn equals(&self, other: &Value) -> bool {
         match (self, other) {
@@ -316,6 +656,7 @@ impl Value {
                 result.extend(b.clone());
                 Ok(Value::Array(result))
             }
+            (Value::Bytes(a), Value::Bytes(b)) => Ok(Value::Bytes([a.as_slice(), b].concat())),
             _ => Err(format!(
                 "Cannot add {} and {}",
                 self.type_name(),
@@ -369,24 +710,10 @@ impl Value {
                 }
                 Ok(Value::Integer(a / b))
             }
-            (Value::Float(a), Value::Float(b)) => {
-                if *b == 0.0 {
-                    return Err("Division by zero".to_string());
-                }
-                Ok(Value::Float(a / b))
-            }
-            (Value::Integer(a), Value::Float(b)) => {
-                if *b == 0.0 {
-                    return Err("Division by zero".to_string());
-                }
-                Ok(Value::Float(*a as f64 / b))
-            }
-            (Value::Float(a), Value::Integer(b)) => {
-                if *b == 0 {
-                    return Err("Division by zero".to_string());
-                }
-                Ok(Value::Float(a / *b as f64))
-            }
+            // Float division follows IEEE 754: x / 0.0 is inf, -inf or NaN
+            (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a / b)),
+            (Value::Integer(a), Value::Float(b)) => Ok(Value::Float(*a as f64 / b)),
+            (Value::Float(a), Value::Integer(b)) => Ok(Value::Float(a / *b as f64)),
             _ => Err(format!(
                 "Cannot divide {} by {}",
                 self.type_name(),
@@ -404,12 +731,7 @@ impl Value {
                 }
                 Ok(Value::Integer(a % b))
             }
-            (Value::Float(a), Value::Float(b)) => {
-                if *b == 0.0 {
-                    return Err("Modulo by zero".to_string());
-                }
-                Ok(Value::Float(a % b))
-            }
+            (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a % b)),
             _ => Err(format!(
                 "Cannot compute modulo of {} and {}",
                 self.type_name(),
@@ -469,7 +791,8 @@ impl fmt::Display for Value {
             Value::Nil => write!(f, "nil"),
             Value::Boolean(b) => write!(f, "{}", b),
             Value::Integer(n) => write!(f, "{}", n),
-            Value::Float(fl) => write!(f, "{}", fl),
+            // Same spelling as JSON: always a decimal point, exponent when huge
+            Value::Float(fl) => write!(f, "{:?}", fl),
             Value::String(s) => write!(f, "{}", s),
             Value::Array(arr) => {
                 write!(f, "[")?;
@@ -481,6 +804,13 @@ impl fmt::Display for Value {
                 }
                 write!(f, "]")
             }
+            Value::Bytes(bytes) => {
+                write!(f, "<bytes")?;
+                for byte in bytes {
+                    write!(f, " {:02x}", byte)?;
+                }
+                write!(f, ">")
+            }
             Value::Object(obj) => {
                 write!(f, "{{")?;
                 for (i, (key, val)) in obj.iter().enumerate() {
@@ -542,6 +872,8 @@ impl fmt::Display for Value {
                 Ok(())
             }
             Value::ReactiveRef(id) => write!(f, "<reactive:{}>", id),
+            Value::Observable(obs) => write!(f, "{}", Value::Object(obs.snapshot())),
+            Value::Ref(cell) => format_ref(cell, f, "ref", false),
             Value::Quote(val) => write!(f, "quote({})", val),
             Value::Native(_) => write!(f, "<native_function>"),
             Value::Future(_) => write!(f, "<future>"),
@@ -606,4 +938,179 @@ mod tests {
         assert_eq!(format!("{}", Value::Boolean(true)), "true");
         assert_eq!(format!("{}", Value::Nil), "nil");
     }
+
+    #[test]
+    fn test_value_render_styles() {
+        let float = Value::Float(2.0);
+        let string = Value::String("hi".to_string());
+        let array = Value::Array(vec![Value::Integer(1), Value::Float(0.5), string.clone()]);
+
+        let render = |value: &Value, style| value.render(style).unwrap();
+        assert_eq!(render(&float, RenderStyle::Debug), "Float(2.0)");
+        assert_eq!(render(&float, RenderStyle::Display), "2.0");
+        assert_eq!(render(&float, RenderStyle::Json), "2.0");
+
+        assert_eq!(render(&string, RenderStyle::Debug), "String(\"hi\")");
+        assert_eq!(render(&string, RenderStyle::Display), "hi");
+        assert_eq!(render(&string, RenderStyle::Json), "\"hi\"");
+
+        assert_eq!(
+            render(&array, RenderStyle::Debug),
+            "Array([Integer(1), Float(0.5), String(\"hi\")])"
+        );
+        assert_eq!(render(&array, RenderStyle::Display), "[1, 0.5, hi]");
+        assert_eq!(render(&array, RenderStyle::Json), "[1,0.5,\"hi\"]");
+
+        assert!(Value::Float(f64::NAN).render(RenderStyle::Json).is_err());
+    }
+
+    #[test]
+    fn test_deep_size_counts_contents() {
+        let base = std::mem::size_of::<Value>();
+        assert_eq!(Value::Integer(7).deep_size(), base);
+        assert_eq!(Value::String("abcd".to_string()).deep_size(), base + 4);
+
+        let words = Value::Array(vec![Value::String("x".repeat(100)); 10]);
+        assert_eq!(words.deep_size(), base + 10 * (base + 100));
+        let nested = Value::Object(HashMap::from([("words".to_string(), words.clone())]));
+        assert!(nested.deep_size() > words.deep_size());
+    }
+
+    #[test]
+    fn test_bytes_smaller_than_integer_array() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let bytes = Value::Bytes(payload.clone());
+        let array = Value::Array(payload.iter().map(|&b| Value::Integer(b as i64)).collect());
+        assert_eq!(bytes.deep_size(), std::mem::size_of::<Value>() + 256);
+        assert!(bytes.deep_size() * 8 < array.deep_size());
+
+        assert_eq!(bytes.to_json().unwrap(), array.to_json().unwrap());
+        assert_ne!(bytes, array);
+        let hi = Value::Bytes(b"hi".to_vec());
+        assert_eq!(format!("{}", hi), "<bytes 68 69>");
+        assert_eq!(
+            hi.add(&Value::Bytes(vec![0])).unwrap(),
+            Value::Bytes(vec![0x68, 0x69, 0])
+        );
+        assert!(!Value::Bytes(Vec::new()).is_truthy());
+    }
+
+    #[test]
+    fn test_type_names_per_variant() {
+        let function = Value::Function(Arc::new(FunctionValue {
+            name: None,
+            parameters: Vec::new(),
+            defaults: Vec::new(),
+            variadic: false,
+            body: Vec::new(),
+            closure: HashMap::new(),
+            is_async: false,
+            reloadable: false,
+        }));
+        let observable = crate::reactive::ReactiveContext::new()
+            .observable(HashMap::new())
+            .unwrap();
+        let table = [
+            (Value::Nil, "nil"),
+            (Value::Boolean(true), "boolean"),
+            (Value::Integer(1), "integer"),
+            (Value::Float(1.0), "float"),
+            (Value::String(String::new()), "string"),
+            (Value::Array(Vec::new()), "array"),
+            (Value::Bytes(Vec::new()), "bytes"),
+            (Value::Object(HashMap::new()), "object"),
+            (function, "function"),
+            (Value::native(|_| Ok(Value::Nil)), "function"),
+            (
+                Value::Range {
+                    start: 0,
+                    end: 1,
+                    inclusive: false,
+                },
+                "range",
+            ),
+            (Value::Tuple(Vec::new()), "tuple"),
+            (
+                Value::Struct {
+                    name: "Point".to_string(),
+                    fields: HashMap::new(),
+                },
+                "struct",
+            ),
+            (
+                Value::Enum {
+                    type_name: "Color".to_string(),
+                    variant: "Red".to_string(),
+                    values: Vec::new(),
+                },
+                "enum",
+            ),
+            (Value::ReactiveRef(0), "reactive"),
+            (Value::Observable(observable), "observable"),
+            (Value::Ref(Arc::new(RwLock::new(Value::Nil))), "ref"),
+            (Value::Quote(Box::new(Value::Nil)), "quote"),
+            (Value::Future(Arc::new(())), "future"),
+        ];
+        for (value, name) in table {
+            assert_eq!(value.type_name(), name, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_cyclic_ref_formats_with_marker() {
+        let cell = Arc::new(RwLock::new(Value::Nil));
+        let r = Value::Ref(Arc::clone(&cell));
+        *cell.write().unwrap() = Value::Array(vec![r.clone(), Value::Integer(1)]);
+        assert_eq!(format!("{}", r), "ref([ref(<cycle>), 1])");
+        assert_eq!(format!("{:?}", r), "Ref(Array([Ref(<cycle>), Integer(1)]))");
+
+        // Two different refs to the same value are not a cycle
+        let shared = Value::Ref(Arc::new(RwLock::new(Value::Integer(2))));
+        let pair = Value::Array(vec![shared.clone(), shared]);
+        assert_eq!(format!("{}", pair), "[ref(2), ref(2)]");
+    }
+
+    #[test]
+    fn test_deep_equal_and_clone_handle_cyclic_refs() {
+        let cyclic = |n| {
+            let cell = Arc::new(RwLock::new(Value::Nil));
+            *cell.write().unwrap() =
+                Value::Array(vec![Value::Ref(Arc::clone(&cell)), Value::Integer(n)]);
+            Value::Ref(cell)
+        };
+        let (a, b) = (cyclic(1), cyclic(1));
+        assert!(a.deep_equal(&b));
+        assert!(!a.deep_equal(&cyclic(2)));
+
+        let copy = a.deep_clone();
+        assert_ne!(copy, a);
+        assert!(copy.deep_equal(&a));
+        // The copy points back at itself, not at the original
+        let Value::Ref(cell) = &copy else {
+            panic!("expected a ref");
+        };
+        let inner = cell.read().unwrap().clone();
+        let Value::Array(items) = inner else {
+            panic!("expected an array");
+        };
+        assert_eq!(items[0], copy);
+    }
+
+    #[test]
+    fn test_deep_equal_and_clone_see_through_refs() {
+        let cell = |v| Value::Ref(Arc::new(RwLock::new(v)));
+        let a = Value::Array(vec![cell(Value::Integer(1))]);
+        let b = Value::Array(vec![cell(Value::Integer(1))]);
+        assert_ne!(a, b);
+        assert!(a.deep_equal(&b));
+
+        let copy = a.deep_clone();
+        assert!(copy.deep_equal(&a));
+        if let Value::Array(items) = &copy {
+            if let Value::Ref(inner) = &items[0] {
+                *inner.write().unwrap() = Value::Integer(2);
+            }
+        }
+        assert!(!copy.deep_equal(&a));
+    }
 }
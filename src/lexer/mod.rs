@@ -62,6 +62,15 @@ pub enum Token {
     #[token("match")]
     Match,
 
+    #[token("switch")]
+    Switch,
+
+    #[token("case")]
+    Case,
+
+    #[token("default")]
+    Default,
+
     #[token("struct")]
     Struct,
 
@@ -101,6 +110,9 @@ pub enum Token {
     #[token("throw")]
     Throw,
 
+    #[token("defer")]
+    Defer,
+
     #[token("async")]
     Async,
 
@@ -176,37 +188,18 @@ pub enum Token {
     Interface,
 
     // Literals
-    #[regex(r"-?[0-9]+", |lex| lex.slice().parse().ok())]
+    #[regex(r"[0-9][0-9_]*", |lex| strip_separators(lex.slice())?.parse().ok())]
+    #[regex(r"0x[0-9a-fA-F_]+", |lex| parse_radix(lex.slice(), 16))]
+    #[regex(r"0b[01_]+", |lex| parse_radix(lex.slice(), 2))]
+    #[regex(r"0o[0-7_]+", |lex| parse_radix(lex.slice(), 8))]
     Integer(i64),
 
-    #[regex(r"-?[0-9]+\.[0-9]+", |lex| lex.slice().parse().ok())]
+    #[regex(r"[0-9][0-9_]*\.[0-9_]+", |lex| strip_separators(lex.slice())?.parse().ok())]
     Float(f64),
 
     #[regex(r#""([^"\\]|\\.)*""#, |lex| {
         let s = lex.slice();
-        let inner = &s[1..s.len()-1];
-        // Process escape sequences
-        let mut result = String::new();
-        let mut chars = inner.chars().peekable();
-        while let Some(c) = chars.next() {
-            if c == '\\' {
-                match chars.next() {
-                    Some('n') => result.push('\n'),
-                    Some('t') => result.push('\t'),
-                    Some('r') => result.push('\r'),
-                    Some('\\') => result.push('\\'),
-                    Some('"') => result.push('"'),
-                    Some(other) => {
-                        result.push('\\');
-                        result.push(other);
-                    }
-                    None => result.push('\\'),
-                }
-            } else {
-                result.push(c);
-            }
-        }
-        result
+        unescape(&s[1..s.len()-1]).ok()
     })]
     String(String),
 
@@ -373,6 +366,12 @@ pub enum Token {
     #[token("?")]
     Question,
 
+    #[token("?.")]
+    QuestionDot,
+
+    #[token("??")]
+    NullCoalesce,
+
     #[token("@")]
     At,
 
@@ -383,6 +382,75 @@ pub enum Token {
     Dollar,
 }
 
+impl Token {
+    /// The text of an identifier or word keyword. Keywords are accepted
+    /// where only a name can appear, as in `cfg.default` or `{case: 1}`.
+    pub fn name(&self) -> Option<&str> {
+        Some(match self {
+            Token::Identifier(name) => name,
+            Token::Let => "let",
+            Token::Const => "const",
+            Token::Reactive => "reactive",
+            Token::Var => "var",
+            Token::Fn => "fn",
+            Token::Return => "return",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::Elif => "elif",
+            Token::For => "for",
+            Token::While => "while",
+            Token::In => "in",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Match => "match",
+            Token::Switch => "switch",
+            Token::Case => "case",
+            Token::Default => "default",
+            Token::Struct => "struct",
+            Token::Enum => "enum",
+            Token::Class => "class",
+            Token::New => "new",
+            Token::This => "this",
+            Token::Super => "super",
+            Token::Extends => "extends",
+            Token::Import => "import",
+            Token::Export => "export",
+            Token::Try => "try",
+            Token::Catch => "catch",
+            Token::Finally => "finally",
+            Token::Throw => "throw",
+            Token::Defer => "defer",
+            Token::Async => "async",
+            Token::Await => "await",
+            Token::Snapshot => "snapshot",
+            Token::Rewind => "rewind",
+            Token::Replay => "replay",
+            Token::Checkpoint => "checkpoint",
+            Token::Watch => "watch",
+            Token::Effect => "effect",
+            Token::Computed => "computed",
+            Token::Signal => "signal",
+            Token::Syntax => "syntax",
+            Token::Macro => "macro",
+            Token::Quote => "quote",
+            Token::Unquote => "unquote",
+            Token::Parallel => "parallel",
+            Token::Concurrent => "concurrent",
+            Token::Atomic => "atomic",
+            Token::Sync => "sync",
+            Token::Context => "context",
+            Token::Infer => "infer",
+            Token::Adapt => "adapt",
+            Token::Type => "type",
+            Token::Interface => "interface",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Nil => "nil",
+            _ => return None,
+        })
+    }
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -405,6 +473,67 @@ impl fmt::Display for Token {
     }
 }
 
+/// Remove `_` digit separators from a numeric literal. A separator must sit
+/// between two digits, so `_1`, `1_`, `1__0`, `1_.5` and `0x_f` are rejected.
+fn strip_separators(literal: &str) -> Option<String> {
+    let chars: Vec<char> = literal.chars().collect();
+    let misplaced = chars.iter().enumerate().any(|(i, &c)| {
+        c == '_'
+            && !(i > 0
+                && chars[i - 1].is_ascii_hexdigit()
+                && chars
+                    .get(i + 1)
+                    .is_some_and(|next| next.is_ascii_hexdigit()))
+    });
+    (!misplaced).then(|| literal.replace('_', ""))
+}
+
+/// Parse a `0x`/`0b`/`0o` literal; out-of-range values are lexer errors
+fn parse_radix(slice: &str, radix: u32) -> Option<i64> {
+    i64::from_str_radix(&strip_separators(slice)?[2..], radix).ok()
+}
+
+/// Process the escape sequences in a string literal body. Unknown escapes
+/// and `\u{...}` values that aren't Unicode scalar values are errors.
+fn unescape(inner: &str) -> Result<String, String> {
+    let mut result = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('0') => result.push('\0'),
+            Some('\\') => result.push('\\'),
+            Some('"') => result.push('"'),
+            Some('u') => {
+                let rest = chars.as_str();
+                let digits = rest
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.split_once('}'))
+                    .map(|(digits, _)| digits)
+                    .ok_or("Expected '{' and '}' around unicode escape")?;
+                let ch = Some(digits)
+                    .filter(|d| {
+                        (1..=6).contains(&d.len()) && d.chars().all(|c| c.is_ascii_hexdigit())
+                    })
+                    .and_then(|d| u32::from_str_radix(d, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("Invalid unicode escape '\\u{{{}}}'", digits))?;
+                result.push(ch);
+                chars = rest[digits.len() + 2..].chars();
+            }
+            Some(other) => return Err(format!("Unknown escape sequence '\\{}'", other)),
+            None => return Err("Unterminated escape sequence".to_string()),
+        }
+    }
+    Ok(result)
+}
+
 /// Tokenize source code into a vector of tokens with positions
 pub fn tokenize(source: &str) -> Result<Vec<(Token, std::ops::Range<usize>)>, LexerError> {
     let mut tokens = Vec::new();
@@ -416,8 +545,24 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, std::ops::Range<usize>)>, Le
                 tokens.push((token, lexer.span()));
             }
             Err(_) => {
+                let slice = lexer.slice();
+                let message = if slice.starts_with(|c: char| c.is_ascii_digit()) {
+                    format!(
+                        "Invalid number literal '{}' at position {}",
+                        slice,
+                        lexer.span().start
+                    )
+                } else if let Some(Err(e)) = slice
+                    .strip_prefix('"')
+                    .and_then(|rest| rest.strip_suffix('"'))
+                    .map(unescape)
+                {
+                    format!("{} in string literal at position {}", e, lexer.span().start)
+                } else {
+                    format!("Unexpected token at position {}", lexer.span().start)
+                };
                 return Err(LexerError {
-                    message: format!("Unexpected token at position {}", lexer.span().start),
+                    message,
                     span: lexer.span(),
                 });
             }
@@ -490,6 +635,93 @@ mod tests {
         assert_eq!(tokens[3].0, Token::String("Hello, A-lang!".to_string()));
     }
 
+    #[test]
+    fn test_string_escapes() {
+        let tokens = tokenize(r#""smile \u{1F600}\t\"q\"\0 \u{e9}""#).unwrap();
+        assert_eq!(
+            tokens[0].0,
+            Token::String("smile \u{1F600}\t\"q\"\0 \u{e9}".to_string())
+        );
+
+        let err = tokenize(r#"let s = "\u{D800}";"#).unwrap_err();
+        assert!(
+            err.message.contains("Invalid unicode escape '\\u{D800}'"),
+            "{}",
+            err
+        );
+        assert_eq!(err.span.start, 8);
+        let err = tokenize(r#""\u{110000}""#).unwrap_err();
+        assert!(err.message.contains("Invalid unicode escape"), "{}", err);
+        let err = tokenize(r#""C:\path""#).unwrap_err();
+        assert!(
+            err.message.contains("Unknown escape sequence '\\p'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_radix_integers() {
+        let tokens = tokenize("0xff 0xFF 0b1010 0o17 0x7fffffffffffffff").unwrap();
+        let values: Vec<Token> = tokens.into_iter().map(|(t, _)| t).collect();
+        assert_eq!(
+            values,
+            vec![
+                Token::Integer(255),
+                Token::Integer(255),
+                Token::Integer(10),
+                Token::Integer(15),
+                Token::Integer(i64::MAX),
+            ]
+        );
+
+        assert!(tokenize("0x8000000000000000").is_err());
+        // Digits outside the base end the literal
+        assert_eq!(tokenize("0b102").unwrap()[1].0, Token::Integer(2));
+    }
+
+    #[test]
+    fn test_minus_is_never_part_of_a_number() {
+        let tokens = tokenize("10 - 4").unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[1].0, Token::Minus);
+
+        let tokens = tokenize("a -3").unwrap();
+        let kinds: Vec<Token> = tokens.into_iter().map(|(t, _)| t).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Minus,
+                Token::Integer(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_digit_separators() {
+        let tokens = tokenize("1_000 1.234_567 0xFF_FF 0b1010_1010").unwrap();
+        let values: Vec<Token> = tokens.into_iter().map(|(t, _)| t).collect();
+        assert_eq!(
+            values,
+            vec![
+                Token::Integer(1000),
+                Token::Float(1.234567),
+                Token::Integer(0xFFFF),
+                Token::Integer(0b1010_1010),
+            ]
+        );
+
+        assert_eq!(
+            tokenize("_1").unwrap()[0].0,
+            Token::Identifier("_1".to_string())
+        );
+        for bad in ["1_", "1__000", "1_.5", "1._5", "2.5_", "0x_ff"] {
+            let err = tokenize(bad).unwrap_err();
+            assert!(err.message.contains("Invalid number literal"), "{}", bad);
+        }
+    }
+
     #[test]
     fn test_operators() {
         let source = "a + b * c -> d |> e";
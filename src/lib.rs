@@ -33,7 +33,7 @@
 //! ```alang
 //! syntax "unless" {
 //!     pattern: "unless COND then BODY",
-//!     transform: |cond, body| if !cond then body
+//!     transform: (cond, body) => if (!cond) { body }
 //! }
 //! ```
 //!
@@ -118,14 +118,15 @@ pub mod types;
 
 // Re-export commonly used types
 pub use ast::{Expression, Literal, Program, Span, Statement};
-pub use interpreter::value::Value;
-pub use interpreter::{Interpreter, InterpreterError};
+pub use interpreter::value::{RenderStyle, Value};
+pub use interpreter::{Capability, Interpreter, InterpreterError, ProgramTransform};
 pub use lexer::{tokenize, Token};
 pub use reactive::{ReactiveContext, ReactiveError};
 pub use stdlib::{
     ByteBuffer, ByteOrder, FFIContext, GpioController, HardwareManager, HttpClient, SystemUtils,
 };
 pub use time_travel::{TimeTravelDebugger, TimeTravelError};
+pub use types::{TypeError, TypeErrors};
 
 /// A-lang version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -155,12 +156,25 @@ pub const FILE_EXTENSION: &str = ".al";
 /// ```
 pub fn run(source: &str) -> Result<Value, Box<dyn std::error::Error>> {
     let tokens = tokenize(source)?;
-    let program = parser::parse(tokens)?;
+    let program = parser::parse_with_source(tokens, source)?;
     let mut interpreter = Interpreter::new();
     let result = interpreter.execute(&program)?;
     Ok(result)
 }
 
+/// Run A-lang code after a static type-checking pass
+///
+/// Nothing executes if the check fails; the error is a [`TypeErrors`] with
+/// every problem found.
+pub fn run_checked(source: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let tokens = tokenize(source)?;
+    let program = parser::parse_with_source(tokens, source)?;
+    let mut interpreter = Interpreter::new();
+    interpreter.type_check(&program).map_err(TypeErrors)?;
+    let result = interpreter.execute(&program)?;
+    Ok(result)
+}
+
 /// Run A-lang code with a custom interpreter
 ///
 /// This allows you to reuse an interpreter instance to maintain state
@@ -170,11 +184,23 @@ pub fn run_with_interpreter(
     interpreter: &mut Interpreter,
 ) -> Result<Value, Box<dyn std::error::Error>> {
     let tokens = tokenize(source)?;
-    let program = parser::parse(tokens)?;
+    let program = parser::parse_with_source(tokens, source)?;
     let result = interpreter.execute(&program)?;
     Ok(result)
 }
 
+/// Run an A-lang source file
+///
+/// The interpreter's current file is set to `path`, so relative imports
+/// resolve against the file's directory and snapshots record its name.
+pub fn run_file<P: AsRef<std::path::Path>>(path: P) -> Result<Value, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)?;
+    let mut interpreter = Interpreter::new();
+    interpreter.set_current_file(path.to_string_lossy());
+    run_with_interpreter(&source, &mut interpreter)
+}
+
 /// Evaluate a single expression
 pub fn eval(source: &str) -> Result<Value, Box<dyn std::error::Error>> {
     run(source)
@@ -189,6 +215,23 @@ pub fn new_interpreter() -> Interpreter {
 mod tests {
     use super::*;
 
+    fn ints(items: &[i64]) -> Value {
+        Value::Array(items.iter().map(|&n| Value::Integer(n)).collect())
+    }
+
+    fn strings(items: &[&str]) -> Value {
+        Value::Array(items.iter().map(|s| Value::String(s.to_string())).collect())
+    }
+
+    fn object(fields: &[(&str, i64)]) -> Value {
+        Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), Value::Integer(*v)))
+                .collect(),
+        )
+    }
+
     #[test]
     fn test_basic_arithmetic() {
         let result = run("5 + 3").unwrap();
@@ -259,6 +302,3483 @@ mod tests {
         assert_eq!(result, Value::String("integer".to_string()));
     }
 
+    #[test]
+    fn test_replay_steps_through_snapshots() {
+        let source = "x = 1
+checkpoint \"start\"
+x = 2
+snapshot
+y = 3
+snapshot
+x = 4
+snapshot
+replay from \"start\"";
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(source, &mut interpreter).unwrap();
+        let mut debugger = interpreter.time_travel_debugger().write().unwrap();
+
+        let (snapshot, diff) = debugger.step_replay().unwrap();
+        assert_eq!(snapshot.id, 1);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(
+            diff.modified,
+            vec![("x".to_string(), Value::Integer(1), Value::Integer(2))]
+        );
+
+        let (snapshot, diff) = debugger.step_replay().unwrap();
+        assert_eq!(snapshot.id, 2);
+        assert_eq!(diff.added, vec!["y".to_string()]);
+        assert!(diff.modified.is_empty());
+
+        let (snapshot, diff) = debugger.step_replay().unwrap();
+        assert_eq!(snapshot.id, 3);
+        assert_eq!(diff.modified[0].2, Value::Integer(4));
+
+        assert!(matches!(
+            debugger.step_replay(),
+            Err(TimeTravelError::ReplayComplete)
+        ));
+    }
+
+    #[test]
+    fn test_auto_snapshots_follow_config() {
+        use crate::time_travel::TimeTravelConfig;
+
+        let source = "a = 1
+b = 2
+c = 3
+d = 4
+e = 5
+f = 6
+g = 7";
+        let config = TimeTravelConfig {
+            auto_snapshot_interval: Some(3),
+            min_snapshot_interval_ms: 0,
+            ..TimeTravelConfig::default()
+        };
+        let mut interpreter = Interpreter::with_config(config);
+        run_with_interpreter(source, &mut interpreter).unwrap();
+        let debugger = interpreter.time_travel_debugger().read().unwrap();
+        let snapshots = debugger.all_snapshots();
+        // Taken before the 3rd and 6th statements run
+        let lines: Vec<_> = snapshots.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![3, 6]);
+        drop(debugger);
+
+        // A minimum gap longer than the run keeps all but the first
+        let config = TimeTravelConfig {
+            auto_snapshot_interval: Some(3),
+            min_snapshot_interval_ms: 60_000,
+            ..TimeTravelConfig::default()
+        };
+        let mut interpreter = Interpreter::with_config(config);
+        run_with_interpreter(source, &mut interpreter).unwrap();
+        let debugger = interpreter.time_travel_debugger().read().unwrap();
+        assert_eq!(debugger.all_snapshots().len(), 1);
+    }
+
+    #[test]
+    fn test_snapshots_by_tag() {
+        let source = "snapshot \"a\"
+snapshot \"b\" #hot #loop;
+snapshot \"c\" #cold
+snapshot #hot
+snapshot \"e\"
+snapshotsByTag(\"hot\")";
+        let mut interpreter = Interpreter::new();
+        let result = run_with_interpreter(source, &mut interpreter).unwrap();
+        assert_eq!(
+            result,
+            Value::Array(vec![Value::String("b".to_string()), Value::Nil])
+        );
+
+        let debugger = interpreter.time_travel_debugger().read().unwrap();
+        let ids: Vec<_> = debugger
+            .snapshots_with_tag("hot")
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(debugger.all_snapshots()[1].metadata.tags, ["hot", "loop"]);
+    }
+
+    #[test]
+    fn test_run_checked_flags_bad_operands() {
+        assert_eq!(run_checked("1 + 2").unwrap(), Value::Integer(3));
+
+        let err = run_checked("let x = 1\nprint(\"a\" - x)").unwrap_err();
+        let errors = &err.downcast_ref::<TypeErrors>().unwrap().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "Invalid operation '-' for types: string, integer"
+        );
+
+        // Gradual: unknown operands, reassigned names and function
+        // parameters are never errors
+        let source = "fn f(a) { return a - 1 }
+let s = \"x\"
+s = 2
+let n = s - 1
+f(n) + \"!\"";
+        assert_eq!(
+            run_checked(source).unwrap(),
+            Value::String("0!".to_string())
+        );
+
+        // `if` bodies share the enclosing scope, so the inner let rebinds x
+        let source = "let x = \"a\"\nif (true) { let x = 1 }\nx - 1";
+        assert_eq!(run_checked(source).unwrap(), Value::Integer(0));
+    }
+
+    #[test]
+    fn test_snapshots_record_call_stack() {
+        let source = "fn inner(x) {
+    let y = x * 2
+    snapshot \"deep\";
+    return x
+}
+fn outer() {
+    return inner(5)
+}
+outer()";
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(source, &mut interpreter).unwrap();
+        let debugger = interpreter.time_travel_debugger().read().unwrap();
+        let snapshots = debugger.all_snapshots();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].line, 3);
+
+        // Each frame records the line that called it
+        let frames: Vec<_> = snapshots[0]
+            .call_stack
+            .iter()
+            .map(|frame| (frame.function_name.as_str(), frame.line))
+            .collect();
+        assert_eq!(frames, vec![("outer", 9), ("inner", 7)]);
+        // Locals are read when the snapshot is taken, so later lets show up
+        let locals = &snapshots[0].call_stack[1].locals;
+        assert_eq!(locals.get("x"), Some(&Value::Integer(5)));
+        assert_eq!(locals.get("y"), Some(&Value::Integer(10)));
+        assert!(snapshots[0].call_stack[0].locals.is_empty());
+    }
+
+    #[test]
+    fn test_runtime_errors_carry_stack_trace() {
+        let source = "fn parse(text) {
+    return int(text)
+}
+fn load() {
+    return parse(\"abc\")
+}
+load()";
+        let err = run(source).unwrap_err().to_string();
+        assert!(
+            err.ends_with("\n    at parse (line 2)\n    at load (line 5)"),
+            "{}",
+            err
+        );
+
+        // Caught errors keep their plain message, and leave no trace behind
+        let source = "fn parse(text) {
+    return int(text)
+}
+message = nil
+try { parse(\"abc\") } catch (e) { message = e.message }
+message";
+        let Value::String(message) = run(source).unwrap() else {
+            panic!("expected the error message");
+        };
+        assert!(!message.contains("at parse"), "{}", message);
+        let err = run("fn ok() { return 1 }\ntry { int(\"x\") } catch (e) { }\nok()\nint(\"y\")")
+            .unwrap_err()
+            .to_string();
+        assert!(!err.contains("\n    at"), "{}", err);
+    }
+
+    #[test]
+    fn test_run_file_resolves_relative_imports() {
+        let dir = std::env::temp_dir().join(format!("alang_run_file_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("util.al"), "fn double(x) { return x * 2; }").unwrap();
+        std::fs::write(
+            dir.join("main.al"),
+            "import \"util.al\";\nsnapshot \"end\";\ndouble(21)",
+        )
+        .unwrap();
+
+        let main_path = dir.join("main.al");
+        let result = run_file(&main_path).unwrap();
+        assert_eq!(result, Value::Integer(42));
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_current_file(main_path.to_string_lossy());
+        let source = std::fs::read_to_string(&main_path).unwrap();
+        run_with_interpreter(&source, &mut interpreter).unwrap();
+        let debugger = interpreter.time_travel_debugger().read().unwrap();
+        let snapshots = debugger.all_snapshots();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].file, main_path.to_string_lossy());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fs_root_confines_file_builtins() {
+        let base = std::env::temp_dir().join(format!("alang_fs_root_{}", std::process::id()));
+        let root = base.join("root");
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("data/notes.txt"), "inside").unwrap();
+        std::fs::write(base.join("secret"), "outside").unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_fs_root(&root).unwrap();
+        let root_path = root.canonicalize().unwrap();
+
+        assert_eq!(
+            run_with_interpreter(r#"readFile("data/notes.txt")"#, &mut interpreter).unwrap(),
+            Value::String("inside".to_string())
+        );
+        assert_eq!(
+            run_with_interpreter(
+                r#"writeFile("./data/../out.txt", "ok"); listDir(".")"#,
+                &mut interpreter
+            )
+            .unwrap(),
+            Value::Array(vec![
+                Value::String("data".to_string()),
+                Value::String("out.txt".to_string()),
+            ])
+        );
+        let absolute_inside = root_path.join("out.txt");
+        assert_eq!(
+            run_with_interpreter(
+                &format!("readFile({:?})", absolute_inside.to_string_lossy()),
+                &mut interpreter
+            )
+            .unwrap(),
+            Value::String("ok".to_string())
+        );
+
+        let absolute_outside = base.canonicalize().unwrap().join("secret");
+        for source in [
+            r#"readFile("../secret")"#.to_string(),
+            r#"readFile("data/../../secret")"#.to_string(),
+            format!("readFile({:?})", absolute_outside.to_string_lossy()),
+            r#"import "../secret""#.to_string(),
+        ] {
+            let err = run_with_interpreter(&source, &mut interpreter)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("outside the filesystem root"), "{}", err);
+        }
+
+        interpreter.clear_fs_root();
+        assert_eq!(
+            run_with_interpreter(
+                &format!("readFile({:?})", absolute_outside.to_string_lossy()),
+                &mut interpreter
+            )
+            .unwrap(),
+            Value::String("outside".to_string())
+        );
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_fs_root_rejects_symlink_escapes() {
+        use std::os::unix::fs::symlink;
+
+        let base = std::env::temp_dir().join(format!("alang_fs_link_{}", std::process::id()));
+        let root = base.join("root");
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::create_dir_all(base.join("outside")).unwrap();
+        std::fs::write(base.join("outside/secret"), "outside").unwrap();
+        symlink(base.join("outside"), root.join("escape")).unwrap();
+        symlink(base.join("outside/new"), root.join("dangling")).unwrap();
+        symlink(root.join("data"), root.join("alias")).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_fs_root(&root).unwrap();
+        for source in [
+            r#"readFile("escape/secret")"#,
+            r#"writeFile("escape/planted", "x")"#,
+            r#"writeFile("dangling", "x")"#,
+        ] {
+            let err = run_with_interpreter(source, &mut interpreter)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("outside the filesystem root"), "{}", err);
+        }
+        assert!(!base.join("outside/planted").exists());
+        assert!(!base.join("outside/new").exists());
+
+        // Links that stay inside the root are fine
+        assert_eq!(
+            run_with_interpreter(
+                r#"writeFile("alias/a.txt", "in"); readFile("data/a.txt")"#,
+                &mut interpreter
+            )
+            .unwrap(),
+            Value::String("in".to_string())
+        );
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_http_get_and_post_against_local_server() {
+        use std::io::{Read, Write};
+
+        // Answers each connection once: /hello with 200, /echo with the
+        // request's content type and body, anything else with 404
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            for stream in listener.incoming().take(4) {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                let head_end = loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break i + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&request[..head_end]).to_string();
+                // Header names are case-insensitive
+                let header = |name: &str| {
+                    head.lines()
+                        .find_map(|line| {
+                            let (key, value) = line.split_once(':')?;
+                            key.eq_ignore_ascii_case(name).then(|| value.trim().to_string())
+                        })
+                        .unwrap_or_default()
+                };
+                let length: usize = header("Content-Length").parse().unwrap_or(0);
+                while request.len() < head_end + length {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let body = String::from_utf8_lossy(&request[head_end..]);
+
+                let path = head.split_whitespace().nth(1).unwrap_or("");
+                let (status, reply) = match path {
+                    "/hello" => ("200 OK", "hello, wörld".to_string()),
+                    "/echo" => (
+                        "201 Created",
+                        format!("{}|{}", header("Content-Type"), body),
+                    ),
+                    _ => ("404 Not Found", "no such page".to_string()),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n{}",
+                    status,
+                    reply.len(),
+                    reply
+                )
+                .unwrap();
+            }
+        });
+
+        assert_eq!(
+            run(&format!(r#"httpGet("{}/hello")"#, base)).unwrap(),
+            Value::String("hello, wörld".to_string())
+        );
+        assert_eq!(
+            run(&format!(
+                r#"[httpPost("{0}/echo", "{{}}"), httpPost("{0}/echo", "a=1", {{"Content-Type": "text/plain"}})]"#,
+                base
+            ))
+            .unwrap(),
+            Value::Array(vec![
+                Value::String("application/json|{}".to_string()),
+                Value::String("text/plain|a=1".to_string()),
+            ])
+        );
+
+        // A 404 is a catchable error that names the status
+        let source = format!(
+            r#"try {{ httpGet("{}/missing") }} catch (e) {{ e.message }}"#,
+            base
+        );
+        let Value::String(message) = run(&source).unwrap() else {
+            panic!("expected the error message");
+        };
+        assert!(message.contains("HTTP 404 Not Found"), "{}", message);
+        server.join().unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.deny(Capability::Network);
+        assert!(
+            run_with_interpreter(r#"httpPost("http://127.0.0.1:9", "")"#, &mut interpreter)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_read_file_chunks_streams_file() {
+        let dir = std::env::temp_dir().join(format!("alang_chunks_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let content: String = (0..250).map(|i| format!("line {:04}\n", i)).collect();
+        std::fs::write(dir.join("log.txt"), &content).unwrap();
+        std::fs::write(dir.join("accents.txt"), "héllo").unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_fs_root(&dir).unwrap();
+        let source = r#"
+            calls = 0
+            seen = ""
+            fn collect(chunk) {
+                calls = calls + 1
+                seen = seen + chunk
+            }
+            [readFileChunks("log.txt", 1000, collect), calls, seen]
+        "#;
+        assert_eq!(
+            run_with_interpreter(source, &mut interpreter).unwrap(),
+            Value::Array(vec![
+                Value::Integer(3),
+                Value::Integer(3),
+                Value::String(content.clone()),
+            ])
+        );
+
+        // Returning false stops the stream
+        let source = r#"
+            seen = ""
+            fn firstTwo(chunk) {
+                seen = seen + chunk
+                return len(seen) < 2000
+            }
+            [readFileChunks("log.txt", 1000, firstTwo), seen]
+        "#;
+        assert_eq!(
+            run_with_interpreter(source, &mut interpreter).unwrap(),
+            Value::Array(vec![
+                Value::Integer(2),
+                Value::String(content[..2000].to_string()),
+            ])
+        );
+
+        // A character is never split across chunks
+        let source = r#"
+            parts = []
+            fn keep(chunk) { parts = push(parts, chunk) }
+            readFileChunks("accents.txt", 2, keep)
+            parts
+        "#;
+        assert_eq!(
+            run_with_interpreter(source, &mut interpreter).unwrap(),
+            Value::Array(
+                ["h", "é", "ll", "o"]
+                    .iter()
+                    .map(|s| Value::String(s.to_string()))
+                    .collect()
+            )
+        );
+
+        assert!(
+            run_with_interpreter(r#"readFileChunks("log.txt", 0, print)"#, &mut interpreter)
+                .is_err()
+        );
+        interpreter.deny(Capability::Filesystem);
+        assert!(
+            run_with_interpreter(r#"readFileChunks("log.txt", 10, print)"#, &mut interpreter)
+                .is_err()
+        );
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn readFileChunks(...args) { return "mine" }
+            readFileChunks("log.txt", 1000, print)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_json_data_module() {
+        let dir = std::env::temp_dir().join(format!("alang_import_json_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("settings.json"),
+            r#"{"name": "demo", "retries": 3, "tags": ["a", "b"]}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("main.al"),
+            "import config from \"settings.json\";\n`${config.name}:${config.retries}:${len(config.tags)}`",
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.json"), "{\n  \"name\": ,\n}").unwrap();
+        std::fs::write(dir.join("bad.al"), "import cfg from \"broken.json\";").unwrap();
+
+        let result = run_file(dir.join("main.al")).unwrap();
+        assert_eq!(result, Value::String("demo:3:2".to_string()));
+
+        let err = run_file(dir.join("bad.al")).unwrap_err().to_string();
+        assert!(err.contains("broken.json"), "{}", err);
+        assert!(err.contains("line 2"), "{}", err);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_toml_data_module() {
+        let dir = std::env::temp_dir().join(format!("alang_import_toml_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("config.toml"),
+            "title = \"app\"\n\n[server]\nport = 8080\n\n[server.tls]\nenabled = true\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("main.al"),
+            "import config from \"config.toml\";\n[config.title, config.server.port, config.server.tls.enabled]",
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.toml"), "a = 1\nb = = 2\n").unwrap();
+        std::fs::write(dir.join("bad.al"), "import cfg from \"broken.toml\";").unwrap();
+
+        let result = run_file(dir.join("main.al")).unwrap();
+        assert_eq!(
+            result,
+            Value::Array(vec![
+                Value::String("app".to_string()),
+                Value::Integer(8080),
+                Value::Boolean(true),
+            ])
+        );
+
+        let err = run_file(dir.join("bad.al")).unwrap_err().to_string();
+        assert!(err.contains("broken.toml"), "{}", err);
+        assert!(err.contains("line 2"), "{}", err);
+
+        std::fs::write(
+            dir.join("deploy.yaml"),
+            "name: web\nreplicas: 3\nports:\n  - 80\n  - 443\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("deploy.al"),
+            "import deploy from \"deploy.yaml\";\n[deploy.name, deploy.replicas, deploy.ports[1]]",
+        )
+        .unwrap();
+        assert_eq!(
+            run_file(dir.join("deploy.al")).unwrap(),
+            Value::Array(vec![
+                Value::String("web".to_string()),
+                Value::Integer(3),
+                Value::Integer(443),
+            ])
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_exported_names_from_module() {
+        let dir = std::env::temp_dir().join(format!("alang_import_items_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("lib.al"),
+            "let factor = 3\nfn scale(x) { return x * factor }\nexport fn triple(x) { return scale(x) }\nexport const label = \"lib\"",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("main.al"),
+            "import { triple, label } from \"./lib.al\"\nlet factor = 100\nlabel + \":\" + str(triple(7))",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("shadow.al"),
+            "import { triple } from \"./lib.al\"\nfn scale(x) { return -1 }\ntriple(7)",
+        )
+        .unwrap();
+        std::fs::write(dir.join("private.al"), "import { scale } from \"./lib.al\"").unwrap();
+        std::fs::write(
+            dir.join("counter.al"),
+            "let count = 0\nexport fn bump() { count = count + 1\nreturn count }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("bumps.al"),
+            "import { bump } from \"./counter.al\"\n[bump(), bump()]",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("a.al"),
+            "import { b } from \"./b.al\"\nexport fn a() { return 1 }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("b.al"),
+            "import { a } from \"./a.al\"\nexport fn b() { return 2 }",
+        )
+        .unwrap();
+
+        // Only exported names are bound, and they keep seeing the module's own globals
+        assert_eq!(
+            run_file(dir.join("main.al")).unwrap(),
+            Value::String("lib:21".to_string())
+        );
+        // The importer's own `scale` doesn't replace the module's helper
+        assert_eq!(run_file(dir.join("shadow.al")).unwrap(), Value::Integer(21));
+        // Module globals are copied at import, so assignments don't persist
+        assert_eq!(run_file(dir.join("bumps.al")).unwrap(), ints(&[1, 1]));
+
+        let err = run_file(dir.join("private.al")).unwrap_err().to_string();
+        assert!(err.contains("does not export 'scale'"), "{}", err);
+
+        let err = run_file(dir.join("a.al")).unwrap_err().to_string();
+        assert!(err.contains("Import cycle"), "{}", err);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(feature = "msgpack", feature = "cbor"))]
+    fn test_serialize_round_trip_formats() {
+        let source = r#"
+            let data = {
+                name: "sensor-7",
+                readings: [1, -2, 300, 70000, 2.5],
+                meta: {ok: true, missing: nil, tags: ["a", "b"]}
+            };
+            [
+                data,
+                deserialize(serialize(data, "json"), "json"),
+                deserialize(serialize(data, "msgpack"), "msgpack"),
+                deserialize(serialize(data, "cbor"), "cbor")
+            ]
+        "#;
+        let Value::Array(results) = run(source).unwrap() else {
+            panic!("expected an array");
+        };
+        for decoded in &results[1..] {
+            assert_eq!(decoded, &results[0]);
+        }
+
+        let sizes = run(r#"
+            let data = {id: 12345, values: [10, 20, 30, 40], active: true, label: "abc"};
+            [len(serialize(data)), len(serialize(data, "msgpack")), len(serialize(data, "cbor"))]
+        "#)
+        .unwrap();
+        let Value::Array(sizes) = sizes else {
+            panic!("expected an array");
+        };
+        let json_len = sizes[0].as_integer().unwrap();
+        assert!(sizes[1].as_integer().unwrap() < json_len, "{:?}", sizes);
+        assert!(sizes[2].as_integer().unwrap() < json_len, "{:?}", sizes);
+
+        let err = run(r#"serialize(1, "xml")"#).unwrap_err().to_string();
+        assert!(
+            err.contains("Unknown serialization format 'xml'"),
+            "{}",
+            err
+        );
+        let err = run(r#"deserialize([193], "msgpack")"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Failed to parse MessagePack"), "{}", err);
+    }
+
+    #[test]
+    fn test_builtin_sort() {
+        let result = run("sort([3, 1.5, 2, -4])").unwrap();
+        assert_eq!(
+            result,
+            Value::Array(vec![
+                Value::Integer(-4),
+                Value::Float(1.5),
+                Value::Integer(2),
+                Value::Integer(3),
+            ])
+        );
+
+        let result = run(r#"sort(["pear", "apple", "fig"])"#).unwrap();
+        assert_eq!(result, run(r#"["apple", "fig", "pear"]"#).unwrap());
+
+        assert!(run(r#"sort([1, "a", 2])"#).is_err());
+    }
+
+    #[test]
+    fn test_builtin_sort_by_is_stable() {
+        let code = r#"
+            let people = [
+                {name: "Ada", age: 36},
+                {name: "Bob", age: 25},
+                {name: "Cy", age: 36},
+                {name: "Di", age: 25}
+            ];
+            map(sortBy(people, p => p.age), p => p.name)
+        "#;
+        let result = run(code).unwrap();
+        assert_eq!(result, run(r#"["Bob", "Di", "Ada", "Cy"]"#).unwrap());
+
+        assert!(run(r#"sortBy([1, 2], x => x == 1 ? "a" : 2)"#).is_err());
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn sortBy(...args) { return "mine" }
+            sortBy([1], x => x)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_builtin_render() {
+        let code = r#"
+            let tpl = "Hello, ${user.name}! You have ${ count } new ${user.missing}messages.";
+            render(tpl, {user: {name: "Ada"}, count: 3})
+        "#;
+        let result = run(code).unwrap();
+        assert_eq!(
+            result,
+            Value::String("Hello, Ada! You have 3 new messages.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_builtin_render_strict() {
+        let result = run(r#"render("${a.b}", {a: {b: 1}}, true)"#).unwrap();
+        assert_eq!(result, Value::String("1".to_string()));
+
+        let err = run(r#"render("${a.c}", {a: {b: 1}}, true)"#).unwrap_err();
+        assert!(err.to_string().contains("a.c"));
+    }
+
+    #[test]
+    fn test_array_index_assignment() {
+        let code = r#"
+            let arr = [1, 2, 3];
+            arr[0] = 99;
+            let grid = [[0, 0], [0, 0]];
+            grid[1][0] = 7;
+            [arr, grid]
+        "#;
+        let result = run(code).unwrap();
+        assert_eq!(result, run("[[99, 2, 3], [[0, 0], [7, 0]]]").unwrap());
+
+        let err = run("let arr = [1, 2]; arr[2] = 3;").unwrap_err();
+        assert_eq!(err.to_string(), "Index out of bounds");
+    }
+
+    #[test]
+    fn test_string_index_assignment_is_rejected() {
+        let err = run(r#"let s = "abc"; s[0] = "z";"#).unwrap_err();
+        assert!(err.to_string().starts_with("Type error"));
+    }
+
+    #[test]
+    fn test_property_assignment() {
+        let code = r#"
+            let point = {x: 1, y: 2};
+            point.x = 5;
+            point.z = 9;
+            let config = {server: {http: {port: 80}}, items: [{name: "a"}]};
+            config.server.http.port = 8080;
+            config.items[0].name = "b";
+            point["w"] = 4;
+            [point.x, point.y, point.z, config.server.http.port, config.items[0].name, point["w"]]
+        "#;
+        let result = run(code).unwrap();
+        assert_eq!(result, run(r#"[5, 2, 9, 8080, "b", 4]"#).unwrap());
+    }
+
+    #[test]
+    fn test_property_assignment_on_non_object() {
+        let err = run("let n = 3; n.x = 1;").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type error: Cannot set property 'x' on integer"
+        );
+    }
+
+    #[test]
+    fn test_compound_assignment_updates_outer_variable() {
+        let code = r#"
+            let count = 0;
+            let i = 0;
+            while (i < 5) {
+                count += 2;
+                i++;
+            }
+            fn bump() {
+                count *= 10;
+            }
+            bump();
+            count
+        "#;
+        let result = run(code).unwrap();
+        assert_eq!(result, Value::Integer(100));
+    }
+
+    #[test]
+    fn test_let_in_function_does_not_clobber_outer() {
+        let code = r#"
+            let count = 5;
+            fn shadow() {
+                let count = 1;
+                count += 1;
+                return count;
+            }
+            [shadow(), count]
+        "#;
+        let result = run(code).unwrap();
+        assert_eq!(result, run("[2, 5]").unwrap());
+    }
+
+    #[test]
+    fn test_power_operator() {
+        assert_eq!(run("2 ** 10").unwrap(), Value::Integer(1024));
+        // Right-associative: 2 ** (3 ** 2)
+        assert_eq!(run("2 ** 3 ** 2").unwrap(), Value::Integer(512));
+        // Binds tighter than unary minus: -(x ** 2)
+        assert_eq!(run("let x = 2; -x ** 2").unwrap(), Value::Integer(-4));
+        // ...and tighter than multiplication
+        assert_eq!(run("3 * 2 ** 2").unwrap(), Value::Integer(12));
+    }
+
+    #[test]
+    fn test_reactive_nodes_from_ended_scopes_are_collected() {
+        let code = r#"
+            reactive keep = 1;
+            fn make(i) {
+                reactive temp = i;
+                return temp * 2;
+            }
+            let total = 0;
+            let i = 0;
+            while (i < 500) {
+                total += make(i);
+                i++;
+            }
+            total + keep
+        "#;
+        let mut interpreter = Interpreter::new();
+        let result = run_with_interpreter(code, &mut interpreter).unwrap();
+        assert_eq!(result, Value::Integer(249_501));
+
+        let stats = interpreter.reactive_context().stats();
+        assert!(
+            stats.total_nodes < 100,
+            "leaked {} nodes",
+            stats.total_nodes
+        );
+        assert!(interpreter.reactive_context().contains("keep"));
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        assert_eq!(run("12 & 10").unwrap(), Value::Integer(8));
+        assert_eq!(run("12 | 10").unwrap(), Value::Integer(14));
+        assert_eq!(run("12 ^ 10").unwrap(), Value::Integer(6));
+        assert_eq!(run("1 << 4").unwrap(), Value::Integer(16));
+        assert_eq!(run("256 >> 2").unwrap(), Value::Integer(64));
+        // & binds tighter than |, shifts tighter than &, all tighter than ==
+        assert_eq!(run("1 | 2 & 3").unwrap(), Value::Integer(3));
+        assert_eq!(run("1 << 2 & 4").unwrap(), Value::Integer(4));
+        assert_eq!(run("6 & 1 == 0").unwrap(), Value::Boolean(true));
+
+        let err = run("1.5 & 1").unwrap_err();
+        assert!(err.to_string().starts_with("Type error"));
+    }
+
+    #[test]
+    fn test_deferred_reactive_builtins() {
+        let mut interpreter = Interpreter::new();
+        let result = run_with_interpreter(
+            "reactive x = 1; deferEffects(true); x = 2; x = 3; tick(); flush(); x",
+            &mut interpreter,
+        )
+        .unwrap();
+        assert_eq!(result, Value::Integer(3));
+        assert!(interpreter.reactive_context().is_deferred());
+        assert!(run("flush(1)").is_err());
+    }
+
+    #[test]
+    fn test_match_literal_arms() {
+        let describe = |n: &str| {
+            run(&format!(
+                r#"match ({}) {{
+                    0 => {{ "zero" }},
+                    1 => "one",
+                    -1 => "minus one",
+                    _ => "many"
+                }}"#,
+                n
+            ))
+            .unwrap()
+        };
+        assert_eq!(describe("0"), Value::String("zero".to_string()));
+        assert_eq!(describe("1"), Value::String("one".to_string()));
+        assert_eq!(describe("-1"), Value::String("minus one".to_string()));
+        assert_eq!(describe("7"), Value::String("many".to_string()));
+        assert_eq!(run("match (3) { 1 => 10 }").unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn test_match_bindings_guards_and_arrays() {
+        let classify = |v: &str| {
+            run(&format!(
+                r#"match ({}) {{
+                    [x, 0] => `x-axis ${{x}}`,
+                    [a, b] if a == b => "diagonal",
+                    [_, _] => "point",
+                    n if n > 100 => "big",
+                    other => other
+                }}"#,
+                v
+            ))
+            .unwrap()
+        };
+        assert_eq!(classify("[3, 0]"), Value::String("x-axis 3".to_string()));
+        assert_eq!(classify("[2, 2]"), Value::String("diagonal".to_string()));
+        assert_eq!(classify("[1, 2]"), Value::String("point".to_string()));
+        assert_eq!(classify("500"), Value::String("big".to_string()));
+        assert_eq!(classify("5"), Value::Integer(5));
+
+        // Bindings don't leak out of the arm
+        assert!(run("match (1) { x => x }; x").is_err());
+    }
+
+    #[test]
+    fn test_effect_reruns_on_signal_change() {
+        let mut interpreter = Interpreter::new();
+        let source = r#"
+            reactive count = 1
+            reactive seen = 0
+            reactive runs = 0
+            effect () => {
+                seen = count * 10
+                runs = runs + 1
+            }
+        "#;
+        run_with_interpreter(source, &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("[seen, runs]", &mut interpreter).unwrap(),
+            Value::Array(vec![Value::Integer(10), Value::Integer(1)])
+        );
+
+        run_with_interpreter("count = 5", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("[seen, runs]", &mut interpreter).unwrap(),
+            Value::Array(vec![Value::Integer(50), Value::Integer(2)])
+        );
+    }
+
+    #[test]
+    fn test_defer_runs_on_return() {
+        let source = r#"
+            log = ""
+            fn note(s) { log = log + s }
+            fn work() {
+                defer note("closed")
+                note("open;")
+                return 42
+            }
+            result = work();
+            [result, log]
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::Array(vec![
+                Value::Integer(42),
+                Value::String("open;closed".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_defer_runs_when_exception_escapes() {
+        let source = r#"
+            log = ""
+            fn note(s) { log = log + s }
+            fn fail() {
+                defer note("cleanup;")
+                throw "boom"
+            }
+            try {
+                fail()
+            } catch (e) {
+                note("caught " + e)
+            }
+            log
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::String("cleanup;caught boom".to_string())
+        );
+    }
+
+    #[test]
+    fn test_catch_native_errors() {
+        let source = r#"
+            caught = nil
+            try {
+                x = 10 / 0
+            } catch (e) {
+                caught = e
+            }
+            caught
+        "#;
+        let Value::Object(error) = run(source).unwrap() else {
+            panic!("catch should bind an object for a runtime error");
+        };
+        assert_eq!(
+            error.get("message"),
+            Some(&Value::String("Division by zero".to_string()))
+        );
+        assert_eq!(
+            error.get("kind"),
+            Some(&Value::String("DivisionByZero".to_string()))
+        );
+
+        let source = r#"
+            kinds = []
+            try { int("abc") } catch (e) { kinds = push(kinds, e.kind) }
+            try { [1, 2][5] } catch (e) { kinds = push(kinds, e.kind) }
+            try { throw "plain" } catch (e) { kinds = push(kinds, e) }
+            kinds
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::Array(vec![
+                Value::String("RuntimeError".to_string()),
+                Value::String("IndexOutOfBounds".to_string()),
+                Value::String("plain".to_string()),
+            ])
+        );
+
+        // A return inside try still leaves the function
+        let source = r#"
+            fn early() {
+                try { return 1 } catch (e) { return 2 }
+                return 3
+            }
+            early()
+        "#;
+        assert_eq!(run(source).unwrap(), Value::Integer(1));
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        for source in ["10 / 0", "10 % 0"] {
+            let err = run(source).unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref::<InterpreterError>(),
+                    Some(InterpreterError::DivisionByZero)
+                ),
+                "{}",
+                err
+            );
+        }
+        assert_eq!(
+            run("try { 10 % 0 } catch (e) { e.kind }").unwrap(),
+            Value::String("DivisionByZero".to_string())
+        );
+
+        // Floats follow IEEE 754 instead of failing
+        assert_eq!(run("1.0 / 0.0").unwrap(), Value::Float(f64::INFINITY));
+        assert_eq!(run("-1 / 0.0").unwrap(), Value::Float(f64::NEG_INFINITY));
+        assert!(matches!(run("0.0 / 0"), Ok(Value::Float(f)) if f.is_nan()));
+        assert!(matches!(run("5.5 % 0.0"), Ok(Value::Float(f)) if f.is_nan()));
+    }
+
+    #[test]
+    fn test_finally_errors_override_result() {
+        let source = r#"
+            fn work() {
+                try {
+                    return "from try"
+                } finally {
+                    throw "from finally"
+                }
+            }
+            caught = nil
+            try { work() } catch (e) { caught = e }
+            caught
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::String("from finally".to_string())
+        );
+
+        let source = r#"
+            log = []
+            try {
+                throw "boom"
+            } catch (e) {
+                log = push(log, "caught " + e)
+            } finally {
+                log = push(log, "finally")
+            }
+            log
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::Array(vec![
+                Value::String("caught boom".to_string()),
+                Value::String("finally".to_string()),
+            ])
+        );
+
+        // A finally that completes normally keeps the original outcome
+        let source = r#"
+            fn keep() {
+                try { return 1 } finally { x = 2 }
+            }
+            keep()
+        "#;
+        assert_eq!(run(source).unwrap(), Value::Integer(1));
+    }
+
+    #[test]
+    fn test_multiple_defers_run_in_reverse_order() {
+        let source = r#"
+            log = ""
+            fn note(s) { log = log + s }
+            fn work() {
+                defer note("1")
+                defer note("2")
+                defer note("3")
+            }
+            work()
+            log
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("321".to_string()));
+    }
+
+    #[test]
+    fn test_computed_tracks_upstream_signal() {
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(
+            "reactive count = 0; computed double = count * 2; computed plusOne = () => double + 1",
+            &mut interpreter,
+        )
+        .unwrap();
+        assert_eq!(
+            run_with_interpreter("[double, plusOne]", &mut interpreter).unwrap(),
+            Value::Array(vec![Value::Integer(0), Value::Integer(1)])
+        );
+
+        run_with_interpreter("count = 21", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("[double, plusOne]", &mut interpreter).unwrap(),
+            Value::Array(vec![Value::Integer(42), Value::Integer(43)])
+        );
+
+        assert!(run("computed broken = missing + 1").is_err());
+
+        // Declaring a computed again replaces it
+        run_with_interpreter("computed double = count * 3", &mut interpreter).unwrap();
+        run_with_interpreter("count = 2", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("double", &mut interpreter).unwrap(),
+            Value::Integer(6)
+        );
+    }
+
+    #[test]
+    fn test_syntax_extension_unless() {
+        let source = r#"
+            syntax "unless" {
+                pattern: "unless COND then BODY",
+                transform: (cond, body) => if (!cond) { body }
+            }
+
+            let x = 3
+            let out = ""
+            unless x > 10 then out = out + "small"
+            unless x > 1 then { out = out + "!" }
+            unless x == 3 then out = out + "never"
+            out
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("small".to_string()));
+
+        let err = run(r#"
+            syntax "unless" { pattern: "unless COND then BODY", transform: (c, b) => if (!c) { b } }
+            unless true else 1
+        "#)
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("Expected 'then' in 'unless' syntax"),
+            "{}",
+            err
+        );
+
+        let err = run(
+            r#"syntax "loop" { pattern: "forever X", transform: (x) => { forever x } }
+            forever 1"#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("Maximum expansion depth"), "{}", err);
+    }
+
+    #[test]
+    fn test_deep_equal_and_clone() {
+        let code = r#"
+            a = {items: [ref(1), 2], name: "x"}
+            b = {items: [ref(1), 2], name: "x"}
+            c = clone(a)
+            setRef(c.items[0], 5);
+            [a == b, deepEqual(a, b), deepEqual(a, c), deref(a.items[0]), deref(c.items[0])]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Boolean(false),
+                Value::Boolean(true),
+                Value::Boolean(false),
+                Value::Integer(1),
+                Value::Integer(5),
+            ])
+        );
+
+        // Refs that contain themselves
+        let code = r#"
+            r = ref(nil);
+            setRef(r, [r, 1]);
+            r2 = ref(nil);
+            setRef(r2, [r2, 1]);
+            copy = clone(r);
+            setRef(copy, [copy, 2]);
+            [deepEqual(r, r2), deepEqual(r, copy), deref(r)[1]]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Boolean(true),
+                Value::Boolean(false),
+                Value::Integer(1),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_default_parameters() {
+        let code = r#"
+            fn greet(name, greeting = "Hello") {
+                return greeting + ", " + name
+            }
+            fn area(w, h = w * 2) { return w * h }
+            [greet("Ada"), greet("Ada", "Hi"), area(3), area(3, 1)]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::String("Hello, Ada".to_string()),
+                Value::String("Hi, Ada".to_string()),
+                Value::Integer(18),
+                Value::Integer(3),
+            ])
+        );
+
+        let err = run("fn f(a, b = 1) { return a }\nf()")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Expected 1 to 2 arguments, got 0"), "{}", err);
+
+        let err = run("fn f(a = 1, b) { return b }").unwrap_err().to_string();
+        assert!(
+            err.contains("Parameter 'b' needs a default value"),
+            "{}",
+            err
+        );
+        assert_eq!(
+            run("fn f(a = 1, ...rest) { return [a, rest] }\nf()").unwrap(),
+            Value::Array(vec![Value::Integer(1), Value::Array(vec![])])
+        );
+    }
+
+    #[test]
+    fn test_rest_parameters() {
+        let code = r#"
+            fn sum(...nums) {
+                total = 0
+                for (n in nums) {
+                    total = total + n
+                }
+                return total
+            }
+            fn tag(label, sep = ":", ...items) { return label + sep + str(len(items)) }
+            [sum(1, 2, 3), sum(), tag("a"), tag("a", "-", 1, 2), arity(sum)]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Integer(6),
+                Value::Integer(0),
+                Value::String("a:0".to_string()),
+                Value::String("a-2".to_string()),
+                Value::Integer(-1),
+            ])
+        );
+
+        let err = run("fn f(...xs, y) { return y }").unwrap_err().to_string();
+        assert!(
+            err.contains("A rest parameter must be the last parameter"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_let_destructuring() {
+        let code = r#"
+            let [a, [b, c]] = [1, [2, 3]]
+            let [first, _, missing] = [10, 20]
+            let point = {x: 4, y: 5}
+            let { x, y: height, z } = point
+            let { inner: [p, q] } = {inner: [6, 7]};
+            [a, b, c, first, missing, x, height, z, p, q]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3),
+                Value::Integer(10),
+                Value::Nil,
+                Value::Integer(4),
+                Value::Integer(5),
+                Value::Nil,
+                Value::Integer(6),
+                Value::Integer(7),
+            ])
+        );
+
+        let err = run("let [a, b] = 5").unwrap_err().to_string();
+        assert!(
+            err.contains("Cannot destructure integer as an array"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_spread() {
+        let code = r#"
+            let middle = [2, 3, 4]
+            fn add3(a, b, c) { return a + b + c }
+            let args = [10, 20, 30];
+            [[1, ...middle, 5], [...[], ...middle], add3(...args), add3(1, ...[2, 3]), max(...middle)]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                ints(&[1, 2, 3, 4, 5]),
+                ints(&[2, 3, 4]),
+                Value::Integer(60),
+                Value::Integer(6),
+                Value::Integer(4),
+            ])
+        );
+
+        // Spread arguments reach the builtins that take callbacks too
+        let code = r#"
+            let a = [[1, 4, 6], x => x > 2];
+            [find(...a), map(...[[1, 2], x => x * 2]), reduce([1, 2, 3], ...[(s, x) => s + x, 0])]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![Value::Integer(4), ints(&[2, 4]), Value::Integer(6)])
+        );
+        assert_eq!(run("assertEq(...[1, 1])").unwrap(), Value::Nil);
+        let err = run("assertEq(...[1, 2])").unwrap_err().to_string();
+        assert!(err.contains("assertEq failed"), "{}", err);
+
+        let err = run("[1, ...5]").unwrap_err().to_string();
+        assert!(err.contains("Cannot spread integer"), "{}", err);
+    }
+
+    #[test]
+    fn test_optional_chaining_and_coalescing() {
+        let code = r#"
+            let maybeNil = nil
+            let config = {server: {port: 8080}, retries: 0, verbose: false};
+            [
+                maybeNil?.field,
+                maybeNil?.a?.b,
+                config?.server?.port,
+                maybeNil ?? "default",
+                config.retries ?? 3,
+                config.verbose ?? true,
+                maybeNil?.port ?? 80,
+                nil ?? nil ?? 1
+            ]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Nil,
+                Value::Nil,
+                Value::Integer(8080),
+                Value::String("default".to_string()),
+                Value::Integer(0),
+                Value::Boolean(false),
+                Value::Integer(80),
+                Value::Integer(1),
+            ])
+        );
+
+        // The right side of ?? is not evaluated when the left is present
+        assert_eq!(run("1 ?? undefinedName").unwrap(), Value::Integer(1));
+        assert!(run("let x = nil; x.field").is_err());
+
+        // ?. skips the rest of the chain, including indexes and method calls
+        let code = r#"
+            let a = nil
+            let obj = {m: () => "called", items: [1, 2]};
+            [a?.b.c, a?.len(), a?.m(), a?.items[0], obj?.m(), obj?.items[1]]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Nil,
+                Value::Nil,
+                Value::Nil,
+                Value::Nil,
+                Value::String("called".to_string()),
+                Value::Integer(2),
+            ])
+        );
+        assert!(run("let o = {b: nil}; o?.b.c").is_err());
+    }
+
+    #[test]
+    fn test_switch() {
+        let code = r#"
+            fn describe(n) {
+                let out = []
+                switch (n) {
+                    case 1:
+                        out = push(out, "one")
+                        break
+                    case 2:
+                        out = push(out, "two")
+                    case 3:
+                        out = push(out, "three")
+                        break
+                    default:
+                        out = push(out, "other")
+                }
+                return out
+            }
+            [describe(1), describe(2), describe(3), describe(9)]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                strings(&["one"]),
+                strings(&["two", "three"]),
+                strings(&["three"]),
+                strings(&["other"]),
+            ])
+        );
+
+        // No matching case and no default runs nothing; `break` stops at the switch
+        let code = r#"
+            hits = 0
+            for (i in [1, 2]) {
+                switch (i) {
+                    case 5: hits = hits + 100
+                    case 1: hits = hits + 1; break
+                }
+                hits = hits + 10
+            }
+            hits
+        "#;
+        assert_eq!(run(code).unwrap(), Value::Integer(21));
+    }
+
+    #[test]
+    fn test_keywords_as_property_names() {
+        let code = r#"
+            let cfg = {default: 1, case: 2, "switch": 3}
+            cfg.default = cfg.default + cfg.case
+            ;[cfg.default, cfg.switch, {type: "t"}.type]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Integer(3),
+                Value::Integer(3),
+                Value::String("t".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_labeled_break_and_continue() {
+        let code = r#"
+            let pairs = []
+            let i = 0
+            outer: while (i < 3) {
+                i = i + 1
+                for (j in range(3)) {
+                    if (j == 1) { continue outer }
+                    pairs = push(pairs, [i, j])
+                }
+            }
+            found = nil
+            search: for (a in range(5)) {
+                for (let b = 0; b < 5; b++) {
+                    if (a * b == 6) {
+                        found = [a, b]
+                        break search
+                    }
+                }
+            }
+            [pairs, found, i]
+        "#;
+        let pair = |a, b| Value::Array(vec![Value::Integer(a), Value::Integer(b)]);
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Array(vec![pair(1, 0), pair(2, 0), pair(3, 0)]),
+                pair(2, 3),
+                Value::Integer(3),
+            ])
+        );
+
+        let err = run("while (true) { break nowhere }")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("No enclosing loop labeled 'nowhere'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_enumerate_and_zip() {
+        let code = r#"
+            [enumerate(["a", "b"]), enumerate([]), zip([1, 2, 3], ["x", "y"]), zip([], [1])]
+        "#;
+        let pair = |a: Value, b: Value| Value::Array(vec![a, b]);
+        let s = |text: &str| Value::String(text.to_string());
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Array(vec![
+                    pair(Value::Integer(0), s("a")),
+                    pair(Value::Integer(1), s("b")),
+                ]),
+                Value::Array(vec![]),
+                Value::Array(vec![
+                    pair(Value::Integer(1), s("x")),
+                    pair(Value::Integer(2), s("y")),
+                ]),
+                Value::Array(vec![]),
+            ])
+        );
+
+        assert!(run("enumerate(5)").is_err());
+        assert!(run("zip([1], \"ab\")").is_err());
+    }
+
+    #[test]
+    fn test_flatten_unique_sum_product() {
+        assert_eq!(run("flatten([[1], [2, 3]])").unwrap(), ints(&[1, 2, 3]));
+        assert_eq!(
+            run("flatten([[1, [2]], 3, []])").unwrap(),
+            Value::Array(vec![Value::Integer(1), ints(&[2]), Value::Integer(3)])
+        );
+        assert_eq!(run("unique([3, 1, 3, 2, 1])").unwrap(), ints(&[3, 1, 2]));
+        assert_eq!(
+            run("unique([[1], [1], 1])").unwrap(),
+            Value::Array(vec![ints(&[1]), Value::Integer(1)])
+        );
+
+        assert_eq!(run("sum([1, 2, 3])").unwrap(), Value::Integer(6));
+        assert_eq!(run("sum([1, 2.5])").unwrap(), Value::Float(3.5));
+        assert_eq!(run("sum([])").unwrap(), Value::Integer(0));
+        assert_eq!(run("product([2, 3, 4])").unwrap(), Value::Integer(24));
+        assert_eq!(run("product([2, 0.5])").unwrap(), Value::Float(1.0));
+        assert_eq!(run("product([])").unwrap(), Value::Integer(1));
+        assert_eq!(
+            run("fn sum(xs) { return \"mine\" }\nsum([1])").unwrap(),
+            Value::String("mine".to_string())
+        );
+
+        // They are ordinary values, so they can be passed to other functions
+        assert_eq!(
+            run("map([[1, 2], [3]], sum)").unwrap(),
+            Value::Array(vec![Value::Integer(3), Value::Integer(3)])
+        );
+
+        let err = run("sum([1, \"2\"])").unwrap_err().to_string();
+        assert!(err.contains("Type error: sum expects numbers"), "{}", err);
+        let err = run("product(5)").unwrap_err().to_string();
+        assert!(
+            err.contains("Type error: product expects an array"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_for_in_objects_and_strings() {
+        let code = r#"
+            let keys = []
+            let total = 0
+            let scores = {carol: 3, alice: 1, bob: 2}
+            for (name in scores) {
+                keys = push(keys, name)
+                total = total + scores[name]
+            }
+            let chars = []
+            for (c in "héy") {
+                chars = push(chars, c)
+            }
+            [keys, total, chars]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                strings(&["alice", "bob", "carol"]),
+                Value::Integer(6),
+                strings(&["h", "é", "y"]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_range_steps_and_direction() {
+        assert_eq!(run("range(10, 0, -2)").unwrap(), ints(&[10, 8, 6, 4, 2]));
+        assert_eq!(run("range(0, 10, 3)").unwrap(), ints(&[0, 3, 6, 9]));
+        assert_eq!(run("range(0, 5, -1)").unwrap(), ints(&[]));
+        assert_eq!(run("range(3)").unwrap(), ints(&[0, 1, 2]));
+        assert!(run("range(0, 5, 0)").is_err());
+
+        let code = r#"
+            let down = []
+            for (i in 5..0) { down = push(down, i) }
+            let negative = []
+            for (i in 2..-1) { negative = push(negative, i) }
+            let empty = []
+            for (i in 3..3) { empty = push(empty, i) }
+            [down, negative, empty]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                ints(&[5, 4, 3, 2, 1]),
+                ints(&[2, 1, 0]),
+                ints(&[]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_char_and_ord() {
+        assert_eq!(run("ord(\"A\")").unwrap(), Value::Integer(65));
+        assert_eq!(run("ord(\"é\")").unwrap(), Value::Integer(0xE9));
+        assert_eq!(
+            run("char(0x1F600)").unwrap(),
+            Value::String("\u{1F600}".to_string())
+        );
+        assert_eq!(
+            run("char(ord(\"a\") + 1)").unwrap(),
+            Value::String("b".to_string())
+        );
+
+        assert!(run("ord(\"\")").is_err());
+        assert!(run("ord(\"ab\")").is_err());
+        assert!(run("char(0xD800)").is_err());
+        assert!(run("char(0x110000)").is_err());
+        assert!(run("char(-1)").is_err());
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        assert_eq!(
+            run("base64Encode(\"Hello, World!\")").unwrap(),
+            Value::String("SGVsbG8sIFdvcmxkIQ==".to_string())
+        );
+        assert_eq!(
+            run("fromBytes(base64Decode(base64Encode(\"héllo\")))").unwrap(),
+            Value::String("héllo".to_string())
+        );
+
+        let payload = vec![0, 255, 128, 7, 200, 1];
+        let code = r#"
+            let payload = [0, 255, 128, 7, 200, 1]
+            let encoded = base64Encode(payload);
+            [encoded, base64Decode(encoded)]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::String("AP+AB8gB".to_string()),
+                Value::Bytes(payload),
+            ])
+        );
+
+        let err = run("base64Decode(\"not base64!\")")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Runtime error"), "{}", err);
+        assert!(run("base64Encode([256])").is_err());
+    }
+
+    #[test]
+    fn test_bytes_value() {
+        assert_eq!(run("bytes(\"hi\")").unwrap(), Value::Bytes(b"hi".to_vec()));
+        assert_eq!(run("bytes(\"hi\")[0]").unwrap(), Value::Integer(104));
+        assert_eq!(
+            run("type_of(bytes(\"hi\"))").unwrap(),
+            Value::String("bytes".to_string())
+        );
+
+        let code = r#"
+            let b = bytes([1, 2, 3]) + packInt(258);
+            b[0] = 255;
+            let total = 0;
+            for (x in slice(b, 0, 3)) {
+                total = total + x
+            }
+            [len(b), total, byteArray(slice(b, 3, 5)), hexEncode(slice(b, 4, 6))]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Integer(11),
+                Value::Integer(260),
+                Value::Array(vec![Value::Integer(2), Value::Integer(1)]),
+                Value::String("0100".to_string()),
+            ])
+        );
+
+        assert_eq!(
+            run("unpackInt(hexDecode(hexEncode(packInt(-7))))").unwrap(),
+            Value::Integer(-7)
+        );
+        assert_eq!(
+            run("fromBytes(bytes([104, 105]))").unwrap(),
+            Value::String("hi".to_string())
+        );
+        assert_eq!(
+            run("let b = bytes(\"abc\"); [byteArray(b) == [97, 98, 99], stringifyJSON(b)]")
+                .unwrap(),
+            Value::Array(vec![
+                Value::Boolean(true),
+                Value::String("[97,98,99]".to_string()),
+            ])
+        );
+
+        let err = run("let b = bytes(\"hi\"); b[0] = 256")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Type error: Bytes hold integers 0-255"),
+            "{}",
+            err
+        );
+        assert!(run("bytes(\"hi\")[2]").is_err());
+        assert!(run("bytes([300])").is_err());
+
+        // Array functions take arrays only; byteArray converts
+        let err = run("map(bytes(\"hi\"), b => b + 1)")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("map expects an array"), "{}", err);
+        assert_eq!(
+            run("[bytes(map(byteArray(bytes(\"hi\")), b => b + 1)), sum(byteArray(packInt(258)))]")
+                .unwrap(),
+            Value::Array(vec![Value::Bytes(b"ij".to_vec()), Value::Integer(3)])
+        );
+    }
+
+    #[test]
+    fn test_parse_int_and_float() {
+        let cases = [
+            ("parseInt(\"ff\", 16)", Value::Integer(255)),
+            ("parseInt(\"0xFF\")", Value::Integer(255)),
+            ("parseInt(\"  -42px\")", Value::Integer(-42)),
+            ("parseInt(\"101\", 2)", Value::Integer(5)),
+            ("parseInt(\"12\", 2)", Value::Integer(1)),
+            ("parseInt(\"abc\")", Value::Nil),
+            ("parseInt(\"\")", Value::Nil),
+            ("parseInt(\"99999999999999999999\")", Value::Nil),
+            ("parseFloat(\"3.14abc\")", Value::Float(3.14)),
+            ("parseFloat(\" -.5\")", Value::Float(-0.5)),
+            ("parseFloat(\"1e3x\")", Value::Float(1000.0)),
+            ("parseFloat(\"2e\")", Value::Float(2.0)),
+            ("parseFloat(\"7\")", Value::Float(7.0)),
+            ("parseFloat(\"x1\")", Value::Nil),
+            ("parseFloat(\".\")", Value::Nil),
+        ];
+        for (code, expected) in cases {
+            assert_eq!(run(code).unwrap(), expected, "{}", code);
+        }
+
+        assert!(run("parseInt(\"10\", 1)").is_err());
+        assert!(run("parseInt(10)").is_err());
+    }
+
+    #[test]
+    fn test_now_millis_and_monotonic() {
+        let code = r#"
+            let t0 = monotonic()
+            let now = nowMillis()
+            let t1 = monotonic();
+            [now > timestamp() * 1000 - 2000, now <= (timestamp() + 1) * 1000, t1 >= t0, type_of(t0)]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Boolean(true),
+                Value::Boolean(true),
+                Value::Boolean(true),
+                Value::String("float".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_assert_eq() {
+        assert_eq!(run("assertEq(1 + 1, 2)").unwrap(), Value::Nil);
+        assert_eq!(run("assert(true, \"fine\")\n5").unwrap(), Value::Integer(5));
+
+        let code = r#"
+            caught = nil
+            try {
+                assertEq(len("abc"), "3", "length")
+            } catch (e) {
+                caught = e
+            }
+            caught
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::String("assertEq failed: 3 (integer) != 3 (string) (length)".to_string())
+        );
+
+        let err = run("assertEq([1], [2])").unwrap_err().to_string();
+        assert!(
+            err.contains("assertEq failed: [1] (array) != [2] (array)"),
+            "{}",
+            err
+        );
+
+        // Off like the assert statement, without evaluating its arguments
+        let code = r#"
+            setAssertions(false)
+            assertEq(1, 2)
+            assertEq(missing(), 2)
+            "ok"
+        "#;
+        assert_eq!(run(code).unwrap(), Value::String("ok".to_string()));
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn assertEq(...args) { return "mine" }
+            assertEq(1, 2)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_assert_statement() {
+        assert_eq!(run("x = 2\nassert x > 1;\nx").unwrap(), Value::Integer(2));
+
+        let err = run("items = []\nassert len(items) > 0, \"need items\";")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Assertion failed: len(items) > 0"), "{}", err);
+        assert!(err.contains("need items"), "{}", err);
+
+        // The call-like form and try/catch both work
+        let code = r#"
+            caught = nil
+            try {
+                assert(1 == 2, "math")
+            } catch (e) {
+                caught = e
+            }
+            caught
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::String("Assertion failed: 1 == 2 (math)".to_string())
+        );
+
+        // Disabled asserts don't evaluate their condition
+        let code = r#"
+            calls = 0
+            fn check() {
+                calls = calls + 1
+                return false
+            }
+            setAssertions(false)
+            assert check();
+            calls
+        "#;
+        assert_eq!(run(code).unwrap(), Value::Integer(0));
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_assertions(false);
+        assert!(run_with_interpreter("assert false;", &mut interpreter).is_ok());
+
+        // Inside an expression it's an ordinary call
+        let code = r#"
+            check = (c) => assert(c, "bad")
+            x = assert(true, "m")
+            caught = nil
+            try {
+                check(false)
+            } catch (e) {
+                caught = e
+            }
+            [x, caught]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Nil,
+                Value::String("Assertion failed (bad)".to_string())
+            ])
+        );
+        let err = run("map([1], (n) => assert(n > 1))")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Assertion failed"), "{}", err);
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn setAssertions(...args) { return "mine" }
+            setAssertions(false)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_function_introspection_and_bind() {
+        let code = r#"
+            fn add(a, b) { return a + b }
+            square = x => x * x;
+            [arity(add), arity(square), arity(print), functionName(add), functionName(square)]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Integer(2),
+                Value::Integer(1),
+                Value::Integer(-1),
+                Value::String("add".to_string()),
+                Value::Nil,
+            ])
+        );
+
+        let code = r#"
+            counter = {count: 41, next: (step) => this.count + step}
+            next = bind(counter.next, counter)
+            next(1)
+        "#;
+        assert_eq!(run(code).unwrap(), Value::Integer(42));
+
+        assert!(run("fn f() { return this }\nf()").is_err());
+        assert!(run("bind(print, {})").is_err());
+
+        // Builtins compare by identity
+        let source = r#"
+            let p = print;
+            [print == print, p == print, print == len]
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::Array(vec![
+                Value::Boolean(true),
+                Value::Boolean(true),
+                Value::Boolean(false),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_subtraction_and_negative_literals() {
+        assert_eq!(run("10 - 4").unwrap(), Value::Integer(6));
+        assert_eq!(run("a = 5\na -3").unwrap(), Value::Integer(2));
+        assert_eq!(run("let x = -5;\nx").unwrap(), Value::Integer(-5));
+        assert_eq!(run("-2.5 * 2").unwrap(), Value::Float(-5.0));
+    }
+
+    #[test]
+    fn test_radix_integer_literals() {
+        assert_eq!(run("0xff == 255").unwrap(), Value::Boolean(true));
+        assert_eq!(run("1_000 == 1000").unwrap(), Value::Boolean(true));
+        assert_eq!(
+            run("[0b1010 | 0b0101, 0o17 + 1]").unwrap(),
+            Value::Array(vec![Value::Integer(15), Value::Integer(16)])
+        );
+    }
+
+    #[test]
+    fn test_if_match_and_block_expressions() {
+        assert_eq!(
+            run("let y = if (true) { 10 } else { 20 };\ny").unwrap(),
+            Value::Integer(10)
+        );
+
+        let code = r#"
+            fn sign(n) {
+                return if (n < 0) { "negative" } elif (n == 0) { "zero" } else { "positive" }
+            }
+            [sign(-3), sign(0), sign(7)]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::String("negative".to_string()),
+                Value::String("zero".to_string()),
+                Value::String("positive".to_string()),
+            ])
+        );
+
+        let code = r#"
+            let label = match (2) { 1 => "one", 2 => "two", _ => "many" };
+            let area = {
+                let w = 3
+                let h = 4
+                w * h
+            };
+            [label, area]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![Value::String("two".to_string()), Value::Integer(12)])
+        );
+
+        // `{}` in expression position is still an empty object
+        assert_eq!(
+            run("let o = {};\no").unwrap(),
+            Value::Object(Default::default())
+        );
+    }
+
+    #[test]
+    fn test_parallel_block_runs_independent_lets_concurrently() {
+        // Each task waits, up to two seconds, for the other to have started;
+        // run one after the other, the first would give up
+        let code = r#"
+            fn meet(mine, theirs) {
+                setRef(mine, true)
+                tries = 0
+                while (tries < 2000 && !deref(theirs)) {
+                    sleep(1)
+                    tries = tries + 1
+                }
+                return deref(theirs)
+            }
+            readyA = ref(false)
+            readyB = ref(false)
+            parallel {
+                let a = meet(readyA, readyB)
+                let b = meet(readyB, readyA)
+                let both = a && b
+            }
+            [a, b, both]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Boolean(true),
+                Value::Boolean(true),
+                Value::Boolean(true)
+            ])
+        );
+
+        let err = run("parallel { let x = 1\n let y = missing() }")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("missing"), "{}", err);
+    }
+
+    #[test]
+    fn test_block_statement_scopes_lets() {
+        let code = r#"
+            x = 1
+            total = 0
+            {
+                let x = 2
+                let inner = x * 10
+                total = inner
+            }
+            [x, total]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![Value::Integer(1), Value::Integer(20)])
+        );
+        assert!(run("{ let hidden = 1 }\nhidden").is_err());
+
+        // A brace followed by `key:` is still an object literal
+        assert_eq!(
+            run("{ a: 1 }").unwrap(),
+            Value::Object([("a".to_string(), Value::Integer(1))].into_iter().collect())
+        );
+    }
+
+    #[test]
+    fn test_pick_and_omit() {
+        let code = r#"
+            user = {id: 1, name: "ann", password: "secret"}
+            picked = pick(user, ["id", "name", "missing"])
+            omitted = omit(user, ["password", "missing"]);
+            [picked, omitted, user]
+        "#;
+        let user: std::collections::HashMap<String, Value> = [
+            ("id".to_string(), Value::Integer(1)),
+            ("name".to_string(), Value::String("ann".to_string())),
+            ("password".to_string(), Value::String("secret".to_string())),
+        ]
+        .into_iter()
+        .collect();
+        let mut public = user.clone();
+        public.remove("password");
+
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                Value::Object(public.clone()),
+                Value::Object(public),
+                Value::Object(user),
+            ])
+        );
+        assert!(run("pick({a: 1}, [1])").is_err());
+        assert!(run(r#"omit([1], ["a"])"#).is_err());
+    }
+
+    #[test]
+    fn test_object_entries_and_mapping() {
+        let pair =
+            |k: &str, v: i64| Value::Array(vec![Value::String(k.to_string()), Value::Integer(v)]);
+        // Insertion order varies, entries don't
+        for source in ["entries({c: 3, a: 1, b: 2})", "entries({b: 2, c: 3, a: 1})"] {
+            assert_eq!(
+                run(source).unwrap(),
+                Value::Array(vec![pair("a", 1), pair("b", 2), pair("c", 3)])
+            );
+        }
+        assert_eq!(run("entries({})").unwrap(), Value::Array(vec![]));
+
+        assert_eq!(
+            run("let scores = {ann: 1, bob: 2}; [mapValues(scores, s => s * 10), scores]").unwrap(),
+            Value::Array(vec![
+                object(&[("ann", 10), ("bob", 20)]),
+                object(&[("ann", 1), ("bob", 2)]),
+            ])
+        );
+        assert_eq!(
+            run(r#"mapKeys({a: 1, b: 2}, k => k + "_id")"#).unwrap(),
+            object(&[("a_id", 1), ("b_id", 2)])
+        );
+        // Colliding keys: the original key sorting last wins
+        assert_eq!(
+            run(r#"mapKeys({b: 2, a: 1, c: 3}, k => k == "c" ? "c" : "x")"#).unwrap(),
+            object(&[("x", 2), ("c", 3)])
+        );
+
+        let err = run("mapKeys({a: 1}, k => 1)").unwrap_err().to_string();
+        assert!(
+            err.contains("Type error: mapKeys expects the function to return a string"),
+            "{}",
+            err
+        );
+        assert!(run("mapValues([1], v => v)").is_err());
+        assert!(run("entries([1])").is_err());
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn mapKeys(...args) { return "mine" }
+            mapKeys({a: 1}, k => k)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_delete_key_and_merge() {
+        let code = r#"
+            let point = {x: 1, y: 2};
+            [deleteKey(point, "x"), deleteKey(point, "z"), point]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                object(&[("y", 2)]),
+                object(&[("x", 1), ("y", 2)]),
+                object(&[("x", 1), ("y", 2)]),
+            ])
+        );
+
+        let code = r#"
+            let defaults = {color: 1, size: 1, weight: 1};
+            let user = {size: 2, weight: 2};
+            [merge(defaults, user, {weight: 3, extra: 4}), defaults, merge()]
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::Array(vec![
+                object(&[("color", 1), ("size", 2), ("weight", 3), ("extra", 4)]),
+                object(&[("color", 1), ("size", 1), ("weight", 1)]),
+                object(&[]),
+            ])
+        );
+
+        for source in [
+            "merge({a: 1}, [1])",
+            "merge(nil)",
+            "deleteKey([1], \"a\")",
+            "deleteKey({a: 1}, 1)",
+        ] {
+            let err = run(source).unwrap_err().to_string();
+            assert!(err.starts_with("Type error"), "{}: {}", source, err);
+        }
+        // They are ordinary values, so they can be passed to other functions
+        assert_eq!(
+            run("reduce([{a: 1}, {b: 2}, {a: 3}], merge, {})").unwrap(),
+            object(&[("a", 3), ("b", 2)])
+        );
+        // A user function of the same name wins over the builtin
+        assert_eq!(
+            run("fn merge(a, b) { return a + b }\nmerge(1, 2)").unwrap(),
+            Value::Integer(3)
+        );
+    }
+
+    #[test]
+    fn test_classic_for_loop() {
+        let code = r#"
+            sum = 0
+            for (i = 0; i < 5; i++) {
+                sum += i
+            }
+            sum
+        "#;
+        assert_eq!(run(code).unwrap(), Value::Integer(10));
+
+        // `continue` still runs the update
+        let code = r#"
+            odd = 0
+            for (let i = 0; i < 6; i += 1) {
+                if (i % 2 == 0) { continue }
+                odd += i
+            }
+            odd
+        "#;
+        assert_eq!(run(code).unwrap(), Value::Integer(9));
+        // Variables declared by the init are local to the loop
+        assert!(run("for (let j = 0; j < 2; j++) { }\nj").is_err());
+
+        let code = r#"
+            n = 0
+            for (; ; ) {
+                n++
+                if (n == 3) { break }
+            }
+            n
+        "#;
+        assert_eq!(run(code).unwrap(), Value::Integer(3));
+    }
+
+    #[test]
+    fn test_flat_map() {
+        assert_eq!(
+            run("flatMap([1, 2, 3], x => [x, x])").unwrap(),
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(2),
+                Value::Integer(3),
+                Value::Integer(3),
+            ])
+        );
+        // Empty results drop the element, scalars are kept as-is
+        assert_eq!(
+            run("flatMap([1, 2, 3, 4], x => x % 2 == 0 ? [] : x * 10)").unwrap(),
+            Value::Array(vec![Value::Integer(10), Value::Integer(30)])
+        );
+        assert!(run("flatMap(5, x => x)").is_err());
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn flatMap(...args) { return "mine" }
+            flatMap([1], x => [x])
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_take_drop_rotate() {
+        assert_eq!(run("take([1, 2, 3, 4], 2)").unwrap(), ints(&[1, 2]));
+        assert_eq!(run("take([1, 2], 5)").unwrap(), ints(&[1, 2]));
+        assert_eq!(run("drop([1, 2, 3, 4], 2)").unwrap(), ints(&[3, 4]));
+        assert_eq!(run("drop([1, 2], -1)").unwrap(), ints(&[1, 2]));
+        assert_eq!(run("rotate([1, 2, 3], 1)").unwrap(), ints(&[3, 1, 2]));
+        assert_eq!(run("rotate([1, 2, 3], -1)").unwrap(), ints(&[2, 3, 1]));
+        assert_eq!(run("rotate([1, 2, 3], 4)").unwrap(), ints(&[3, 1, 2]));
+        assert_eq!(run("rotate([], 2)").unwrap(), ints(&[]));
+        assert_eq!(
+            run("takeWhile([1, 2, 5, 1], x => x < 3)").unwrap(),
+            ints(&[1, 2])
+        );
+        assert_eq!(
+            run("dropWhile([1, 2, 5, 1], x => x < 3)").unwrap(),
+            ints(&[5, 1])
+        );
+        assert!(run("takeWhile(5, x => x)").is_err());
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn dropWhile(xs, f) { return "mine" }
+            dropWhile([1], x => true)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_find_every_some_for_each() {
+        assert_eq!(
+            run("find([1, 4, 6, 9], x => x % 2 == 0)").unwrap(),
+            Value::Integer(4)
+        );
+        assert_eq!(run("find([1, 3], x => x > 5)").unwrap(), Value::Nil);
+        assert_eq!(
+            run("[every([2, 4], x => x % 2 == 0), every([2, 3], x => x % 2 == 0), every([], x => false)]")
+                .unwrap(),
+            Value::Array(vec![
+                Value::Boolean(true),
+                Value::Boolean(false),
+                Value::Boolean(true),
+            ])
+        );
+
+        // some stops at the first match
+        let source = r#"
+            calls = 0
+            fn big(x) {
+                calls = calls + 1
+                return x > 2
+            }
+            [some([1, 3, 5, 7], big), calls, some([], big)]
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::Array(vec![
+                Value::Boolean(true),
+                Value::Integer(2),
+                Value::Boolean(false),
+            ])
+        );
+
+        let source = r#"
+            total = 0
+            fn add(x) { total = total + x }
+            [forEach([1, 2, 3], add), total]
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::Array(vec![Value::Nil, Value::Integer(6)])
+        );
+
+        for source in [
+            "find(5, x => x)",
+            "every(\"ab\", x => x)",
+            "some({a: 1}, x => x)",
+            "forEach(nil, x => x)",
+        ] {
+            let err = run(source).unwrap_err().to_string();
+            assert!(err.contains("expects an array"), "{}", err);
+        }
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn find(xs, f) { return "mine" }
+            find([1], x => true)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_pmap() {
+        assert_eq!(
+            run("pmap([1, 2, 3], x => x * x)").unwrap(),
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(4),
+                Value::Integer(9)
+            ])
+        );
+        // Order is kept and functions see the surrounding variables
+        assert_eq!(
+            run("let offset = 1000; pmap(range(500), x => x + offset)").unwrap(),
+            Value::Array((1000..1500).map(Value::Integer).collect())
+        );
+        let err = run(r#"pmap([1, 2, 3], x => x == 2 ? missing() : x)"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("missing"), "{}", err);
+        assert!(run("pmap(5, x => x)").is_err());
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn pmap(...args) { return "mine" }
+            pmap([1], x => x)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_spawn_all_and_race_all() {
+        let source = r#"
+            fn slow() { sleep(50); return "slow" }
+            results = spawnAll([slow, () => 1 + 1, () => 10 / 0, () => { throw "boom" }]);
+            [results[0], results[1], results[2].kind, results[2].message, results[3]]
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::Array(vec![
+                Value::String("slow".to_string()),
+                Value::Integer(2),
+                Value::String("DivisionByZero".to_string()),
+                Value::String("Division by zero".to_string()),
+                Value::String("boom".to_string()),
+            ])
+        );
+        assert_eq!(run("spawnAll([])").unwrap(), Value::Array(vec![]));
+
+        // A thrown value comes back as catch would bind it
+        let source = r#"
+            results = spawnAll([() => { throw {code: 7} }]);
+            caught = nil
+            try { throw {code: 7} } catch (e) { caught = e }
+            results[0] == caught
+        "#;
+        assert_eq!(run(source).unwrap(), Value::Boolean(true));
+
+        let source = r#"
+            fn slow() { sleep(300); return "slow" }
+            fn fast() { return "fast" }
+            raceAll([slow, fast, slow])
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("fast".to_string()));
+
+        assert!(run("raceAll([])").is_err());
+        assert!(run("spawnAll([1, 2])").is_err());
+        let err = run(r#"spawnAll([() => print("hi")])"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("needs a pure function"), "{}", err);
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn raceAll(...args) { return "mine" }
+            raceAll([() => 1])
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_shuffle_and_sample() {
+        let shuffled = "seed(42); shuffle(range(20))";
+        let first = run(shuffled).unwrap();
+        assert_eq!(run(shuffled).unwrap(), first);
+        assert_ne!(first, run("seed(43); shuffle(range(20))").unwrap());
+
+        // Same elements, and the input array is left alone
+        assert_eq!(
+            run("let xs = range(20); let ys = shuffle(xs); [sort(ys) == xs, xs == range(20)]")
+                .unwrap(),
+            Value::Array(vec![Value::Boolean(true), Value::Boolean(true)])
+        );
+
+        for _ in 0..20 {
+            let Value::Array(picked) = run("sample([1, 2, 3, 4, 5], 2)").unwrap() else {
+                panic!("sample should return an array");
+            };
+            assert_eq!(picked.len(), 2);
+            assert_ne!(picked[0], picked[1]);
+            assert!(picked.iter().all(|v| matches!(v, Value::Integer(1..=5))));
+        }
+        assert_eq!(run("sample([], 0)").unwrap(), Value::Array(vec![]));
+        assert!(run("sample([1, 2], 3)").is_err());
+        assert!(run("shuffle(5)").is_err());
+    }
+
+    #[test]
+    fn test_seed_makes_random_reproducible() {
+        let source = r#"
+            seed(42);
+            [random(), random(), randomInt(1, 100), randomInt(-5, 5), gpioRead(4)]
+        "#;
+        let first = run(source).unwrap();
+        assert_eq!(run(source).unwrap(), first);
+        let Value::Array(values) = &first else {
+            panic!("expected an array, got {}", first);
+        };
+        assert_ne!(values[0], values[1]);
+        assert!(matches!(values[0], Value::Float(f) if (0.0..1.0).contains(&f)));
+        assert!(matches!(values[2], Value::Integer(1..=100)));
+
+        assert_eq!(run("randomInt(7, 7)").unwrap(), Value::Integer(7));
+        let err = run("randomInt(5, 1)").unwrap_err().to_string();
+        assert!(err.contains("min 5 is above max 1"), "{}", err);
+    }
+
+    #[test]
+    fn test_parallel_reduce() {
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(
+            r#"
+            fn add(a, b) { return a + b }
+            "#,
+            &mut interpreter,
+        )
+        .unwrap();
+        assert_eq!(
+            run_with_interpreter("parallelReduce(range(20000), add, 0)", &mut interpreter).unwrap(),
+            Value::Integer((0..20000).sum())
+        );
+        assert_eq!(
+            run_with_interpreter(
+                "parallelReduce(range(20000), add, 0) == reduce(range(20000), add, 0)",
+                &mut interpreter
+            )
+            .unwrap(),
+            Value::Boolean(true)
+        );
+        // Associative but not commutative: chunks are combined in order
+        let sequential = run_with_interpreter(
+            "reduce(map(range(3000), x => str(x % 10)), add, \"\")",
+            &mut interpreter,
+        )
+        .unwrap();
+        for _ in 0..3 {
+            assert_eq!(
+                run_with_interpreter(
+                    "parallelReduce(map(range(3000), x => str(x % 10)), add, \"\")",
+                    &mut interpreter
+                )
+                .unwrap(),
+                sequential
+            );
+        }
+        assert_eq!(
+            run_with_interpreter("parallelReduce([], add, 0)", &mut interpreter).unwrap(),
+            Value::Integer(0)
+        );
+
+        for source in [
+            "parallelReduce([1, 2], (a, b) => print(a), 0)",
+            "fn noisy(a, b) { print(b); return a + b }\nparallelReduce([1, 2], noisy, 0)",
+            "fn outer(a, b) { return noisy(a, b) }\nparallelReduce([1, 2], outer, 0)",
+            "reactive total = 0\nparallelReduce([1, 2], (a, b) => a + b + total, 0)",
+        ] {
+            let err = run_with_interpreter(source, &mut interpreter)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("needs a pure function"), "{}: {}", source, err);
+        }
+        assert!(run("parallelReduce(5, (a, b) => a + b, 0)").is_err());
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn parallelReduce(...args) { return "mine" }
+            parallelReduce([1], (a, b) => a + b, 0)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_pipeline_operator() {
+        let source = r#"
+            fn double(x) { return x * 2 }
+            3 |> double |> double |> str
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("12".to_string()));
+
+        // Binds looser than arithmetic and accepts any callable expression
+        assert_eq!(run("1 + 2 |> (x => x * 10)").unwrap(), Value::Integer(30));
+        assert_eq!(
+            run("[3, 1, 2] |> sort |> reverse").unwrap(),
+            Value::Array(vec![
+                Value::Integer(3),
+                Value::Integer(2),
+                Value::Integer(1)
+            ])
+        );
+        assert!(run("1 |> 2").is_err());
+    }
+
+    #[test]
+    fn test_stream_pipe() {
+        assert_eq!(run("[1, 2, 3] ~> print").unwrap(), ints(&[1, 2, 3]));
+        let source = r#"
+            total = 0
+            doubled = [1, 2, 3] ~> (x => { total = total + x }) |> (xs => map(xs, x => x * 2));
+            [total, doubled]
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::Array(vec![Value::Integer(6), ints(&[2, 4, 6])])
+        );
+        assert!(run("5 ~> print").is_err());
+
+        // A reactive source runs the handler now and on every change
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(
+            "reactive count = 1; reactive last = 0; count ~> (v => { last = v * 10 })",
+            &mut interpreter,
+        )
+        .unwrap();
+        assert_eq!(
+            run_with_interpreter("last", &mut interpreter).unwrap(),
+            Value::Integer(10)
+        );
+        run_with_interpreter("count = 4", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("last", &mut interpreter).unwrap(),
+            Value::Integer(40)
+        );
+    }
+
+    #[test]
+    fn test_ref_cells_share_mutations() {
+        let source = r#"
+            fn appendTo(cell, item) {
+                setRef(cell, push(deref(cell), item))
+            }
+            fn appendPlain(items, item) {
+                items = push(items, item)
+            }
+            shared = ref([1])
+            plain = [1]
+            appendTo(shared, 2)
+            appendPlain(plain, 2)
+            alias = shared
+            setRef(alias, push(deref(alias), 3));
+            [deref(shared), plain, alias == shared, ref(1) == ref(1)]
+        "#;
+        assert_eq!(
+            run(source).unwrap(),
+            Value::Array(vec![
+                Value::Array(vec![
+                    Value::Integer(1),
+                    Value::Integer(2),
+                    Value::Integer(3)
+                ]),
+                Value::Array(vec![Value::Integer(1)]),
+                Value::Boolean(true),
+                Value::Boolean(false),
+            ])
+        );
+        assert_eq!(
+            run("type_of(ref(1)) + \" \" + str(ref(1))").unwrap(),
+            Value::String("ref ref(1)".to_string())
+        );
+        assert!(run("deref([1])").is_err());
+        assert!(run("setRef(1, 2)").is_err());
+
+        // A ref holding itself prints a marker, and reactive collection
+        // passes over it
+        let code = r#"
+            r = ref(nil);
+            setRef(r, [r]);
+            fn make(i) {
+                reactive temp = i;
+                return temp;
+            }
+            let i = 0;
+            while (i < 200) {
+                make(i);
+                i++;
+            }
+            str(r)
+        "#;
+        assert_eq!(
+            run(code).unwrap(),
+            Value::String("ref([ref(<cycle>)])".to_string())
+        );
+    }
+
+    #[test]
+    fn test_struct_definition_and_new() {
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(
+            r#"
+            struct Point {
+                x: int
+                y: int = 0
+                label = "p"
+            }
+            p = new Point { x: 3 }
+            q = new Point(1, 2, "q")
+            "#,
+            &mut interpreter,
+        )
+        .unwrap();
+        assert_eq!(
+            run_with_interpreter("[p.x, p.y, p.label, q.y, q.label]", &mut interpreter).unwrap(),
+            Value::Array(vec![
+                Value::Integer(3),
+                Value::Integer(0),
+                Value::String("p".to_string()),
+                Value::Integer(2),
+                Value::String("q".to_string()),
+            ])
+        );
+        assert_eq!(
+            run_with_interpreter("type_of(p)", &mut interpreter).unwrap(),
+            Value::String("object".to_string())
+        );
+
+        for (source, expected) in [
+            ("new Point { x: 1, z: 2 }", "Unknown field 'z'"),
+            ("new Point { y: 1 }", "Missing field 'x'"),
+            ("new Point(1, 2, 3, 4)", "4 arguments"),
+            ("new Shape {}", "Unknown struct 'Shape'"),
+        ] {
+            let err = run_with_interpreter(source, &mut interpreter)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(expected), "{}: {}", source, err);
+        }
+        assert!(run("struct P { a, a }").is_err());
+    }
+
+    #[test]
+    fn test_match_on_runtime_type() {
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(
+            r#"
+            fn describe(x) {
+                return match x is {
+                    Integer n => "int " + str(n + 1),
+                    String s => "string of " + str(len(s)),
+                    Array a if len(a) == 0 => "empty array",
+                    Array a => "array starting " + str(a[0]),
+                    Object o => "object with " + str(len(keys(o))) + " keys",
+                    Number => "other number",
+                    _ => "something else"
+                }
+            }
+            "#,
+            &mut interpreter,
+        )
+        .unwrap();
+        assert_eq!(
+            run_with_interpreter(
+                r#"[describe(41), describe("abc"), describe([]), describe([7, 8]),
+                    describe({a: 1, b: 2}), describe(1.5), describe(nil)]"#,
+                &mut interpreter
+            )
+            .unwrap(),
+            Value::Array(
+                [
+                    "int 42",
+                    "string of 3",
+                    "empty array",
+                    "array starting 7",
+                    "object with 2 keys",
+                    "other number",
+                    "something else",
+                ]
+                .into_iter()
+                .map(|s| Value::String(s.to_string()))
+                .collect()
+            )
+        );
+
+        // Parenthesized subjects work too, and enum names are types
+        assert_eq!(
+            run("enum Shape { Dot }\nmatch (Shape.Dot) is { Integer => 1, Shape s => str(s) }")
+                .unwrap(),
+            Value::String("Shape::Dot".to_string())
+        );
+    }
+
+    #[test]
+    fn test_enum_variants_construct_and_match() {
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(
+            r#"
+            enum Color { Red, Green, Rgb(int, int, int) }
+            fn describe(c) {
+                return match (c) {
+                    Color.Red => "red",
+                    Color.Rgb(r, g, b) => r + g + b,
+                    _ => "other"
+                }
+            }
+            "#,
+            &mut interpreter,
+        )
+        .unwrap();
+        assert_eq!(
+            run_with_interpreter(
+                "[describe(Color.Red), describe(Color.Rgb(1, 2, 3)), describe(Color.Green)]",
+                &mut interpreter
+            )
+            .unwrap(),
+            Value::Array(vec![
+                Value::String("red".to_string()),
+                Value::Integer(6),
+                Value::String("other".to_string()),
+            ])
+        );
+        assert_eq!(
+            run_with_interpreter(
+                "[str(Color.Rgb(1, 2, 3)), Color.Red == Color.Red, type_of(Color.Green)]",
+                &mut interpreter
+            )
+            .unwrap(),
+            Value::Array(vec![
+                Value::String("Color::Rgb(1, 2, 3)".to_string()),
+                Value::Boolean(true),
+                Value::String("enum".to_string()),
+            ])
+        );
+
+        let err = run_with_interpreter("Color.Rgb(1, 2)", &mut interpreter)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Color.Rgb expects 3 values"), "{}", err);
+        assert!(run("enum E { A, A }").is_err());
+    }
+
+    #[test]
+    fn test_redefined_function_reaches_captured_copies() {
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(
+            r#"
+            fn greet(name) { return "hi " + name }
+            fn outer() {
+                fn greet(name) { return "local " + name }
+                return greet
+            }
+            fn tag() { return "v1 " + this }
+            handlers = [greet]
+            saved = greet
+            local = outer()
+            tagged = bind(tag, "x")
+            reactive who = "ann"
+            reactive shown = ""
+            effect () => { shown = handlers[0](who) }
+            "#,
+            &mut interpreter,
+        )
+        .unwrap();
+        let check = r#"[map(["bo"], handlers[0])[0], saved("cy"), local("di"), tagged(), shown]"#;
+        assert_eq!(
+            run_with_interpreter(check, &mut interpreter).unwrap(),
+            Value::Array(
+                ["hi bo", "hi cy", "local di", "v1 x", "hi ann"]
+                    .iter()
+                    .map(|s| Value::String(s.to_string()))
+                    .collect()
+            )
+        );
+
+        // Every copy of a top-level function picks up the new body; the
+        // function local to `outer` is a different function and keeps its own
+        run_with_interpreter(
+            r#"
+            fn greet(name) { return "hello " + name }
+            fn tag() { return "v2 " + this }
+            who = "eve"
+            "#,
+            &mut interpreter,
+        )
+        .unwrap();
+        assert_eq!(
+            run_with_interpreter(check, &mut interpreter).unwrap(),
+            Value::Array(
+                ["hello bo", "hello cy", "local di", "v2 x", "hello eve"]
+                    .iter()
+                    .map(|s| Value::String(s.to_string()))
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn test_join_defaults_and_stringifies() {
+        assert_eq!(
+            run("join([1, 2, 3])").unwrap(),
+            Value::String("1,2,3".to_string())
+        );
+        assert_eq!(
+            run(r#"join([1, 2], "-")"#).unwrap(),
+            Value::String("1-2".to_string())
+        );
+        assert_eq!(
+            run("join([1, 2], 0)").unwrap(),
+            Value::String("102".to_string())
+        );
+        // Elements go through the same path as str()
+        assert_eq!(
+            run(r#"join([{a: 1}, "x", nil], "; ") == str({a: 1}) + "; x; nil""#).unwrap(),
+            Value::Boolean(true)
+        );
+        assert!(run("join([1, 2], [])").is_err());
+    }
+
+    #[test]
+    fn test_untrack_reads_without_subscribing() {
+        let mut interpreter = Interpreter::new();
+        let source = r#"
+            reactive base = 10
+            reactive delta = 1
+            computed total = untrack(() => base) + delta
+        "#;
+        run_with_interpreter(source, &mut interpreter).unwrap();
+        assert_eq!(
+            interpreter.reactive_context().stats().total_dependencies,
+            1 // only `delta`
+        );
+
+        run_with_interpreter("base = 20", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("total", &mut interpreter).unwrap(),
+            Value::Integer(11)
+        );
+
+        run_with_interpreter("delta = 2", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("total", &mut interpreter).unwrap(),
+            Value::Integer(22)
+        );
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn untrack(...args) { return "mine" }
+            untrack(() => 1)
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_reactive_graph_dot() {
+        let source = r#"
+            reactive count = 0
+            computed double = count * 2
+            let graph = reactiveGraph
+            graph()
+        "#;
+        let Value::String(dot) = run(source).unwrap() else {
+            panic!("expected a string");
+        };
+        assert!(dot.starts_with("digraph ReactiveGraph {"), "{}", dot);
+        let node_id = |name: &str| {
+            let label = format!(" [label=\"{}\"", name);
+            dot.lines()
+                .find_map(|line| line.trim().split_once(&label).map(|(id, _)| id.to_string()))
+                .unwrap_or_else(|| panic!("no node for {} in {}", name, dot))
+        };
+        let edge = format!("{} -> {};", node_id("count"), node_id("double"));
+        assert!(dot.contains(&edge), "{}", dot);
+    }
+
+    #[test]
+    fn test_reactive_template_string() {
+        let mut interpreter = Interpreter::new();
+        let source = r#"
+            reactive name = "Ada"
+            reactive visits = 1
+            reactive greeting = `Hi ${name}, visit #${visits}`;
+            reactive count_label = `${visits}`
+        "#;
+        run_with_interpreter(source, &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("greeting", &mut interpreter).unwrap(),
+            Value::String("Hi Ada, visit #1".to_string())
+        );
+
+        run_with_interpreter("name = \"Grace\"; visits = 2", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("[greeting, count_label]", &mut interpreter).unwrap(),
+            Value::Array(vec![
+                Value::String("Hi Grace, visit #2".to_string()),
+                Value::String("2".to_string()),
+            ])
+        );
+
+        // A template reading no reactive is a plain reactive string
+        let source = "reactive label = `hello`; label = \"bye\"; label";
+        assert_eq!(
+            run_with_interpreter(source, &mut interpreter).unwrap(),
+            Value::String("bye".to_string())
+        );
+    }
+
+    #[test]
+    fn test_observable_effect_subscribes_per_field() {
+        let mut interpreter = Interpreter::new();
+        let source = r#"
+            o = observable({a: 1, b: 2})
+            reactive seen = 0
+            reactive runs = 0
+            effect () => {
+                seen = o.a
+                runs = runs + 1
+            }
+        "#;
+        run_with_interpreter(source, &mut interpreter).unwrap();
+
+        // Writing a field the effect never read doesn't re-run it
+        run_with_interpreter("o.b = 20", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("[seen, runs, o.b]", &mut interpreter).unwrap(),
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(1),
+                Value::Integer(20)
+            ])
+        );
+
+        run_with_interpreter("o.a = 5", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("[seen, runs]", &mut interpreter).unwrap(),
+            Value::Array(vec![Value::Integer(5), Value::Integer(2)])
+        );
+
+        // Computed values track fields the same way, including index reads
+        run_with_interpreter(
+            r#"computed label = "a=" + o["a"]; o["b"] = 3; o.a = 7"#,
+            &mut interpreter,
+        )
+        .unwrap();
+        assert_eq!(
+            run_with_interpreter("[label, runs]", &mut interpreter).unwrap(),
+            Value::Array(vec![Value::String("a=7".to_string()), Value::Integer(3)])
+        );
+        assert!(run_with_interpreter("observable(1)", &mut interpreter).is_err());
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn observable(...args) { return "mine" }
+            observable({a: 1})
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_sandboxed_interpreter_withholds_host_builtins() {
+        let mut interpreter = Interpreter::new_sandboxed();
+        for source in [
+            r#"exec("echo hi")"#,
+            r#"readFile("Cargo.toml")"#,
+            r#"ffiCall("libc", "abs", [1])"#,
+            r#"setEnv("A_LANG_SANDBOX", "1")"#,
+            r#"import "lib.al""#,
+        ] {
+            assert!(
+                run_with_interpreter(source, &mut interpreter).is_err(),
+                "{} should fail in a sandbox",
+                source
+            );
+        }
+
+        // Pure computation is unaffected
+        assert_eq!(
+            run_with_interpreter(
+                "[map([1, 2], x => x * 2), len(\"abc\"), sqrt(16)]",
+                &mut interpreter
+            )
+            .unwrap(),
+            Value::Array(vec![
+                Value::Array(vec![Value::Integer(2), Value::Integer(4)]),
+                Value::Integer(3),
+                Value::Float(4.0),
+            ])
+        );
+
+        interpreter.allow(Capability::Filesystem);
+        assert_eq!(
+            run_with_interpreter(r#"fileExists("Cargo.toml")"#, &mut interpreter).unwrap(),
+            Value::Boolean(true)
+        );
+        assert!(run_with_interpreter(r#"exec("echo hi")"#, &mut interpreter).is_err());
+
+        interpreter.deny(Capability::Filesystem);
+        assert!(run_with_interpreter(r#"fileExists("Cargo.toml")"#, &mut interpreter).is_err());
+    }
+
+    #[test]
+    fn test_reactive_dependencies_ignore_shadowed_names() {
+        let mut interpreter = Interpreter::new();
+        let source = r#"
+            reactive count = 1
+            reactive runs = 0
+            computed bumped = map([1, 2], count => count + 1)
+            effect () => {
+                let count = 100
+                runs = runs + count
+            }
+        "#;
+        run_with_interpreter(source, &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("runs", &mut interpreter).unwrap(),
+            Value::Integer(100)
+        );
+
+        // Neither node read the outer `count`, so writing it triggers nothing
+        run_with_interpreter("count = 2", &mut interpreter).unwrap();
+        assert_eq!(
+            run_with_interpreter("[runs, bumped]", &mut interpreter).unwrap(),
+            Value::Array(vec![
+                Value::Integer(100),
+                Value::Array(vec![Value::Integer(2), Value::Integer(3)]),
+            ])
+        );
+        assert_eq!(
+            interpreter.reactive_context().stats().total_dependencies,
+            1 // the effect on `runs`
+        );
+    }
+
+    #[test]
+    fn test_undo_redo_restore_user_checkpoints() {
+        let mut interpreter = Interpreter::new();
+        run_with_interpreter(
+            r#"x = 1; pushUndo("first"); x = 2; y = "new"; pushUndo("second"); x = 3"#,
+            &mut interpreter,
+        )
+        .unwrap();
+
+        assert_eq!(interpreter.undo().unwrap(), "second");
+        assert_eq!(
+            run_with_interpreter("[x, y]", &mut interpreter).unwrap(),
+            Value::Array(vec![Value::Integer(2), Value::String("new".to_string())])
+        );
+
+        assert_eq!(
+            run_with_interpreter("undo()", &mut interpreter).unwrap(),
+            Value::String("first".to_string())
+        );
+        assert_eq!(
+            run_with_interpreter("x", &mut interpreter).unwrap(),
+            Value::Integer(1)
+        );
+        assert!(run_with_interpreter("y", &mut interpreter).is_err());
+        assert!(interpreter.undo().is_err());
+
+        assert_eq!(interpreter.redo().unwrap(), "second");
+        assert_eq!(
+            run_with_interpreter("redo(); [x, y]", &mut interpreter).unwrap(),
+            Value::Array(vec![Value::Integer(3), Value::String("new".to_string())])
+        );
+        assert!(interpreter.redo().is_err());
+
+        // A user function of the same name wins over the builtin
+        let source = r#"
+            fn undo(...args) { return "mine" }
+            undo()
+        "#;
+        assert_eq!(run(source).unwrap(), Value::String("mine".to_string()));
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        assert_eq!(run("0.1 + 0.2 == 0.3").unwrap(), Value::Boolean(false));
+        assert_eq!(
+            run("approxEq(0.1 + 0.2, 0.3)").unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(run("approxEq(1.0, 1.001)").unwrap(), Value::Boolean(false));
+        assert_eq!(
+            run("approxEq(1.0, 1.001, 0.01)").unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(run("approxEq(2, 2.0)").unwrap(), Value::Boolean(true));
+        assert_eq!(
+            run(r#"approxEq(float("nan"), float("nan"), 1.0)"#).unwrap(),
+            Value::Boolean(false)
+        );
+        assert!(run("approxEq(1, 1, -1)").is_err());
+        assert!(run(r#"approxEq("a", 1)"#).is_err());
+    }
+
+    #[test]
+    fn test_string_helpers() {
+        let strings = |source: &str| match run(source).unwrap() {
+            Value::Array(items) => items
+                .into_iter()
+                .map(|item| item.as_string())
+                .collect::<Vec<_>>(),
+            other => panic!("expected an array, got {}", other),
+        };
+
+        assert_eq!(
+            run(r#"[startsWith("héllo", "hé"), endsWith("héllo", "lo"), startsWith("abc", "b")]"#)
+                .unwrap(),
+            Value::Array(vec![
+                Value::Boolean(true),
+                Value::Boolean(true),
+                Value::Boolean(false),
+            ])
+        );
+        assert_eq!(
+            strings(r#"[repeat("ab", 3), repeat("é", 0)]"#),
+            vec!["ababab", ""]
+        );
+        assert!(run(r#"repeat("a", -1)"#).is_err());
+        assert!(run(r#"repeat("ab", 9223372036854775807)"#).is_err());
+        assert!(run(r#"repeat("abcd", 1000000000)"#).is_err());
+
+        // Widths count characters, not bytes
+        assert_eq!(
+            strings(
+                r#"[padStart("7", 3, "0"), padEnd("añ", 4, "·"), padStart("x", 4, "ab"), padEnd("long", 2), padStart("é", 3)]"#
+            ),
+            vec!["007", "añ··", "abax", "long", "  é"]
+        );
+
+        assert_eq!(
+            strings(
+                r#"[substring("naïve café", 2, 5), substring("naïve café", 6), substring("日本語", 1, 2), substring("abc", 2, 1), substring("abc", -3, 99)]"#
+            ),
+            vec!["ïve", "café", "本", "", "abc"]
+        );
+        assert!(run(r#"substring("abc", "1")"#).is_err());
+    }
+
+    #[test]
+    fn test_string_len_and_index_count_chars() {
+        assert_eq!(run(r#"len("héllo")"#).unwrap(), Value::Integer(5));
+        assert_eq!(
+            run(r#"let s = "café"; s[len(s) - 1]"#).unwrap(),
+            Value::String("é".to_string())
+        );
+        assert_eq!(
+            run(r#""日本語"[1]"#).unwrap(),
+            Value::String("本".to_string())
+        );
+        assert!(run(r#""héllo"[5]"#).is_err());
+        assert!(run(r#""héllo"[-1]"#).is_err());
+    }
+
+    #[test]
+    fn test_stringify_json_pretty_and_skip_functions() {
+        assert_eq!(
+            run(r#"stringifyJSON({b: [1, 2], a: "x"}, 2)"#).unwrap(),
+            Value::String("{\n  \"a\": \"x\",\n  \"b\": [\n    1,\n    2\n  ]\n}".to_string())
+        );
+        assert_eq!(
+            run("stringifyJSON([1], 4)").unwrap(),
+            Value::String("[\n    1\n]".to_string())
+        );
+        assert_eq!(
+            run("stringifyJSON([1, 2], 0)").unwrap(),
+            Value::String("[1,2]".to_string())
+        );
+
+        // Functions make serialization fail unless they are skipped
+        let source = r#"
+            fn greet() { return "hi" }
+            let user = {name: "Ada", greet: greet, tags: [print, 1]};
+        "#;
+        let err = run(&format!("{} stringifyJSON(user)", source))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Cannot convert"), "{}", err);
+        assert_eq!(
+            run(&format!(
+                "{} stringifyJSON(user, {{skipFunctions: true}})",
+                source
+            ))
+            .unwrap(),
+            Value::String(r#"{"name":"Ada","tags":[null,1]}"#.to_string())
+        );
+        assert_eq!(
+            run(&format!(
+                "{} stringifyJSON(user, {{indent: 1, skipFunctions: true}})",
+                source
+            ))
+            .unwrap(),
+            Value::String("{\n \"name\": \"Ada\",\n \"tags\": [\n  null,\n  1\n ]\n}".to_string())
+        );
+
+        assert!(run("stringifyJSON(1, -2)").is_err());
+        assert!(run(r#"stringifyJSON(1, "  ")"#).is_err());
+    }
+
+    #[test]
+    fn test_format_placeholders() {
+        assert_eq!(
+            run(r#"format("{} + {} = {}", 1, 2.5, "3.5")"#).unwrap(),
+            Value::String("1 + 2.5 = 3.5".to_string())
+        );
+        assert_eq!(
+            run(r#"format("{1}, {0}! {1}?", "world", "hello")"#).unwrap(),
+            Value::String("hello, world! hello?".to_string())
+        );
+        assert_eq!(
+            run(r#"format("{{{}}} {}", [1, 2], true)"#).unwrap(),
+            Value::String("{[1, 2]} true".to_string())
+        );
+        assert_eq!(
+            run(r#"format("no placeholders")"#).unwrap(),
+            Value::String("no placeholders".to_string())
+        );
+
+        let err = run(r#"format("{} and {}", 1)"#).unwrap_err().to_string();
+        assert!(err.contains("needs argument 1 but 1 given"), "{}", err);
+        let err = run(r#"format("{}", 1, 2)"#).unwrap_err().to_string();
+        assert!(err.contains("argument 1 is not used"), "{}", err);
+        assert!(run(r#"format("{2}", 1)"#).is_err());
+        assert!(run(r#"format("{x}", 1)"#).is_err());
+        assert!(run(r#"format("open {", 1)"#).is_err());
+        assert!(run(r#"format("close }")"#).is_err());
+    }
+
+    #[test]
+    fn test_extended_math() {
+        let float = |source: &str| match run(source).unwrap() {
+            Value::Float(f) => f,
+            other => panic!("expected a float, got {}", other),
+        };
+        assert_eq!(float("exp(0)"), 1.0);
+        assert!((float("atan2(1, 1)") - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+        assert!((float("log(exp(2))") - 2.0).abs() < 1e-12);
+        assert!((float("log(8, 2)") - 3.0).abs() < 1e-12);
+        assert_eq!(float("log10(1000)"), 3.0);
+        assert_eq!(float("log2(1024)"), 10.0);
+        assert!((float("asin(1)") - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert_eq!(float("acos(1)"), 0.0);
+        assert!((float("atan(1)") - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+
+        for source in [
+            r#"exp("e")"#,
+            "log([1])",
+            r#"log(8, "two")"#,
+            "atan2(1)",
+            "asin(1, 2)",
+        ] {
+            assert!(run(source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_clamp_lerp_map_range() {
+        assert_eq!(run("clamp(5, 0, 3)").unwrap(), Value::Integer(3));
+        assert_eq!(run("clamp(-2, 0, 3)").unwrap(), Value::Integer(0));
+        assert_eq!(run("clamp(0.5, 0, 1)").unwrap(), Value::Float(0.5));
+        assert_eq!(run("clamp(7, 0, 2.5)").unwrap(), Value::Float(2.5));
+        let err = run("clamp(1, 3, 0)").unwrap_err().to_string();
+        assert!(
+            err.contains("lower bound 3 is above upper bound 0"),
+            "{}",
+            err
+        );
+        assert!(run(r#"clamp("a", 0, 1)"#).is_err());
+
+        assert_eq!(run("lerp(0, 10, 0.5)").unwrap(), Value::Float(5.0));
+        assert_eq!(run("lerp(10, 20, 0)").unwrap(), Value::Float(10.0));
+        assert_eq!(run("lerp(0, 10, 1.5)").unwrap(), Value::Float(15.0));
+
+        assert_eq!(
+            run("mapRange(5, 0, 10, 0, 100)").unwrap(),
+            Value::Float(50.0)
+        );
+        // Inverted output range: 0..1023 sensor reading to 100..0 percent
+        assert_eq!(
+            run("mapRange(256, 0, 1024, 100, 0)").unwrap(),
+            Value::Float(75.0)
+        );
+        assert!(run("mapRange(1, 2, 2, 0, 1)").is_err());
+        assert!(run("mapRange(1, 0, 2, 0)").is_err());
+    }
+
+    #[test]
+    fn test_add_transform_rewrites_program() {
+        fn bump(expr: Expression) -> Expression {
+            match expr {
+                Expression::Literal {
+                    value: Literal::Integer(n),
+                    span,
+                } => Expression::Binary {
+                    left: Box::new(Expression::Literal {
+                        value: Literal::Integer(n),
+                        span: span.clone(),
+                    }),
+                    operator: ast::BinaryOp::Add,
+                    right: Box::new(Expression::Literal {
+                        value: Literal::Integer(1),
+                        span: span.clone(),
+                    }),
+                    span,
+                },
+                Expression::Binary {
+                    left,
+                    operator,
+                    right,
+                    span,
+                } => Expression::Binary {
+                    left: Box::new(bump(*left)),
+                    operator,
+                    right: Box::new(bump(*right)),
+                    span,
+                },
+                other => other,
+            }
+        }
+
+        let mut interpreter = Interpreter::new();
+        interpreter.add_transform(Box::new(|mut program: Program| {
+            program.statements = program
+                .statements
+                .into_iter()
+                .map(|stmt| match stmt {
+                    Statement::Expression { expr, span } => Statement::Expression {
+                        expr: bump(expr),
+                        span,
+                    },
+                    other => other,
+                })
+                .collect();
+            program
+        }));
+
+        // (10 + 1) * (2 + 1)
+        let result = run_with_interpreter("10 * 2", &mut interpreter).unwrap();
+        assert_eq!(result, Value::Integer(33));
+    }
+
+    #[test]
+    fn test_builtin_type_name_deep() {
+        let result = run("typeName([1, 2, 3], true)").unwrap();
+        assert_eq!(result, Value::String("array<integer>".to_string()));
+
+        let result = run(r#"typeName([1, "two"], true)"#).unwrap();
+        assert_eq!(result, Value::String("array<any>".to_string()));
+
+        let result = run(r#"typeName({name: "Ada", age: 36}, true)"#).unwrap();
+        assert_eq!(
+            result,
+            Value::String("object{age: integer, name: string}".to_string())
+        );
+
+        let result = run("typeName([1, 2, 3])").unwrap();
+        assert_eq!(result, Value::String("array".to_string()));
+    }
+
     #[test]
     fn test_version() {
         assert!(!VERSION.is_empty());
@@ -10,19 +10,21 @@ use std::fs;
 use std::path::Path;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+    // --no-assertions skips `assert` statements without evaluating them
+    let assertions = !args.iter().any(|arg| arg == "--no-assertions");
+    let file = args.iter().find(|arg| !arg.starts_with("--"));
 
-    if args.len() > 1 {
+    if let Some(filename) = file {
         // Execute file
-        let filename = &args[1];
-        execute_file(filename);
+        execute_file(filename, assertions);
     } else {
         // Start REPL
-        start_repl();
+        start_repl(assertions);
     }
 }
 
-fn execute_file(filename: &str) {
+fn execute_file(filename: &str, assertions: bool) {
     let path = Path::new(filename);
 
     if !path.exists() {
@@ -39,6 +41,8 @@ fn execute_file(filename: &str) {
     };
 
     let mut interpreter = Interpreter::new();
+    interpreter.set_current_file(filename);
+    interpreter.set_assertions(assertions);
 
     match run_with_interpreter(&source, &mut interpreter) {
         Ok(result) => {
@@ -54,7 +58,7 @@ fn execute_file(filename: &str) {
     }
 }
 
-fn start_repl() {
+fn start_repl(assertions: bool) {
     println!("╔══════════════════════════════════════╗");
     println!("║              A - L A N G             ║");
     println!("║     ___       _                      ║");
@@ -78,6 +82,7 @@ fn start_repl() {
     };
 
     let mut interpreter = Interpreter::new();
+    interpreter.set_assertions(assertions);
     let mut line_number = 1;
 
     loop {
@@ -154,9 +159,15 @@ fn handle_command(command: &str, interpreter: &Interpreter) -> bool {
         ".reactive" => {
             print_reactive_stats(interpreter);
         }
+        ".graph" => {
+            print!("{}", interpreter.reactive_context().to_dot());
+        }
         ".time-travel" => {
             print_time_travel_stats(interpreter);
         }
+        ".step" => {
+            step_replay(interpreter);
+        }
         ".clear" => {
             print!("\x1B[2J\x1B[1;1H");
         }
@@ -176,7 +187,9 @@ fn print_help() {
     println!("  .features      List features");
     println!("  .examples      Show examples");
     println!("  .reactive      Reactive stats");
+    println!("  .graph         Reactive graph in Graphviz DOT format");
     println!("  .time-travel   Time-travel stats");
+    println!("  .step          Step the active replay forward");
     println!("  .clear         Clear screen");
 }
 
@@ -231,3 +244,32 @@ fn print_time_travel_stats(interpreter: &Interpreter) {
     println!("  Checkpoints:      {}", stats.checkpoints_count);
     println!("  Memory Usage:     {} bytes", stats.memory_usage);
 }
+
+fn step_replay(interpreter: &Interpreter) {
+    let debugger = interpreter.time_travel_debugger();
+    let mut debugger = debugger.write().unwrap();
+    let (snapshot, diff) = match debugger.step_replay() {
+        Ok(step) => step,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    match &snapshot.label {
+        Some(label) => println!("Snapshot #{} ({})", snapshot.id, label),
+        None => println!("Snapshot #{}", snapshot.id),
+    }
+    if diff.is_empty() {
+        println!("  (no changes)");
+    }
+    for name in &diff.added {
+        println!("  + {} = {}", name, snapshot.state[name]);
+    }
+    for (name, old, new) in &diff.modified {
+        println!("  ~ {}: {} -> {}", name, old, new);
+    }
+    for name in &diff.removed {
+        println!("  - {}", name);
+    }
+}
@@ -129,6 +129,76 @@ impl Default for ParallelContext {
     }
 }
 
+/// Run independent tasks at the same time and return their results in order.
+///
+/// Tasks from a `parallel { ... }` block may block (sleeping, waiting on I/O),
+/// so each gets its own thread in a pool built for the call instead of
+/// queueing on the CPU-sized global pool.
+pub fn run_concurrently<T, F>(tasks: Vec<F>) -> Result<Vec<T>, String>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    let workers = tasks.len();
+    run_pooled(tasks, workers)
+}
+
+/// Like `run_concurrently`, but with at most `workers` tasks running at once
+pub fn run_pooled<T, F>(tasks: Vec<F>, workers: usize) -> Result<Vec<T>, String>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    let pool = task_pool(workers.min(tasks.len()))?;
+
+    let mut results: Vec<Option<T>> = tasks.iter().map(|_| None).collect();
+    pool.scope(|scope| {
+        for (slot, task) in results.iter_mut().zip(tasks) {
+            scope.spawn(move |_| *slot = Some(task()));
+        }
+    });
+    Ok(results
+        .into_iter()
+        .map(|result| result.expect("scope waits for every task"))
+        .collect())
+}
+
+/// Start every task, at most `workers` at once, and return the first result
+/// to arrive. The call doesn't wait for the others: they finish in the
+/// background and their results are dropped.
+pub fn race<T, F>(tasks: Vec<F>, workers: usize) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    if tasks.is_empty() {
+        return Err("No tasks to race".to_string());
+    }
+    let pool = task_pool(workers.min(tasks.len()))?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    for task in tasks {
+        let sender = sender.clone();
+        pool.spawn(move || {
+            // The receiver is gone once a winner has been taken
+            let _ = sender.send(task());
+        });
+    }
+    drop(sender);
+    receiver
+        .recv()
+        .map_err(|_| "Every raced task failed to finish".to_string())
+}
+
+fn task_pool(threads: usize) -> Result<rayon::ThreadPool, String> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads.max(1))
+        // A panicking spawned task would otherwise abort the process
+        .panic_handler(|_| {})
+        .build()
+        .map_err(|e| format!("Failed to start parallel tasks: {}", e))
+}
+
 /// Statistics about parallel execution
 #[derive(Debug, Clone)]
 pub struct ParallelStats {
@@ -163,6 +233,21 @@ mod tests {
         assert_eq!(result[2], Value::Integer(6));
     }
 
+    #[test]
+    fn test_run_concurrently_overlaps_blocking_tasks() {
+        let start = std::time::Instant::now();
+        let tasks: Vec<_> = (0..3u64)
+            .map(|i| {
+                move || {
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                    i * 10
+                }
+            })
+            .collect();
+        assert_eq!(run_concurrently(tasks).unwrap(), vec![0, 10, 20]);
+        assert!(start.elapsed() < std::time::Duration::from_millis(250));
+    }
+
     #[test]
     fn test_parallel_filter() {
         let ctx = ParallelContext::default();
@@ -5,6 +5,8 @@
 
 use crate::ast::*;
 use crate::lexer::Token;
+use crate::syntax_ext::{PatternPart, SyntaxPattern, SyntaxRule};
+use std::collections::HashMap;
 use std::fmt;
 
 /// Parser error type
@@ -26,11 +28,57 @@ impl std::error::Error for ParseError {}
 pub struct Parser {
     tokens: Vec<(Token, std::ops::Range<usize>)>,
     current: usize,
+    /// Text the tokens were lexed from, used to quote source in `assert`
+    source: Option<String>,
+    /// Byte offset where each line of `source` starts, for span positions
+    line_starts: Vec<usize>,
+    /// `syntax` rules declared so far, keyed by their leading keyword
+    syntax_rules: HashMap<String, SyntaxRule>,
+    /// How many syntax expansions enclose the tokens being parsed
+    expansion_depth: usize,
+    /// Labels of the loops enclosing the tokens being parsed
+    loop_labels: Vec<String>,
 }
 
+/// Nesting limit for syntax expansions, so a rule that expands to itself fails
+const MAX_EXPANSION_DEPTH: usize = 100;
+
 impl Parser {
     pub fn new(tokens: Vec<(Token, std::ops::Range<usize>)>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            source: None,
+            line_starts: Vec::new(),
+            syntax_rules: HashMap::new(),
+            expansion_depth: 0,
+            loop_labels: Vec::new(),
+        }
+    }
+
+    /// Keep the source text the tokens came from
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self.line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        self
+    }
+
+    /// Source text of the tokens in `from..to`, or the tokens themselves
+    /// joined by spaces when the source isn't known
+    fn source_text(&self, from: usize, to: usize) -> String {
+        let tokens = &self.tokens[from..to];
+        match (&self.source, tokens.first(), tokens.last()) {
+            (Some(source), Some((_, first)), Some((_, last))) => {
+                source[first.start..last.end].to_string()
+            }
+            _ => tokens
+                .iter()
+                .map(|(token, _)| token.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
     }
 
     fn is_at_end(&self) -> bool {
@@ -75,7 +123,15 @@ impl Parser {
 
     fn current_span(&self) -> Span {
         if let Some((_, range)) = self.tokens.get(self.current) {
-            Span::new(range.start, range.end, 0, 0)
+            // Lines and columns count from 1 and stay 0 when the source is unknown
+            let (line, column) = match self
+                .line_starts
+                .partition_point(|&start| start <= range.start)
+            {
+                0 => (0, 0),
+                line => (line, range.start - self.line_starts[line - 1] + 1),
+            };
+            Span::new(range.start, range.end, line, column)
         } else {
             Span::dummy()
         }
@@ -112,21 +168,52 @@ impl Parser {
             Some(Token::Elif) => self.parse_if_statement(), // elif is just another if
             Some(Token::While) => self.parse_while_statement(),
             Some(Token::For) => self.parse_for_statement(),
+            Some(Token::Match) => self.parse_match_statement(),
+            Some(Token::Switch) => self.parse_switch_statement(),
+            Some(Token::LeftBrace) if !self.at_object_literal() => self.parse_block_statement(),
+            Some(Token::Parallel) => self.parse_parallel_statement(),
+            Some(Token::Struct) => self.parse_struct_statement(),
+            Some(Token::Enum) => self.parse_enum_statement(),
+            Some(Token::Identifier(_))
+                if matches!(
+                    (
+                        self.tokens.get(self.current + 1),
+                        self.tokens.get(self.current + 2)
+                    ),
+                    (
+                        Some((Token::Colon, _)),
+                        Some((Token::While | Token::For, _))
+                    )
+                ) =>
+            {
+                self.parse_labeled_loop()
+            }
             Some(Token::Break) => {
                 self.advance();
+                let label = self.parse_loop_label("break", &span)?;
                 self.match_token(&Token::Semicolon);
-                Ok(Statement::Break { span })
+                Ok(Statement::Break { label, span })
             }
             Some(Token::Continue) => {
                 self.advance();
+                let label = self.parse_loop_label("continue", &span)?;
                 self.match_token(&Token::Semicolon);
-                Ok(Statement::Continue { span })
+                Ok(Statement::Continue { label, span })
             }
             Some(Token::Try) => self.parse_try_statement(),
             Some(Token::Throw) => self.parse_throw_statement(),
+            Some(Token::Defer) => self.parse_defer_statement(),
             Some(Token::Snapshot) => self.parse_snapshot_statement(),
             Some(Token::Rewind) => self.parse_rewind_statement(),
             Some(Token::Checkpoint) => self.parse_checkpoint_statement(),
+            Some(Token::Replay) => self.parse_replay_statement(),
+            Some(Token::Import) => self.parse_import_statement(),
+            Some(Token::Export) => self.parse_export_statement(),
+            Some(Token::Identifier(name)) if name == "assert" => self.parse_assert_statement(),
+            Some(Token::Syntax) => self.parse_syntax_statement(),
+            Some(Token::Identifier(name)) if self.syntax_rules.contains_key(name) => {
+                self.parse_syntax_use()
+            }
             _ => self.parse_expression_statement(),
         }
     }
@@ -135,6 +222,23 @@ impl Parser {
         let span = self.current_span();
         self.advance(); // consume 'let'
 
+        if self.check(&Token::LeftBracket) || self.check(&Token::LeftBrace) {
+            let pattern = self.parse_binding_pattern()?;
+            if !self.match_token(&Token::Assign) {
+                return Err(ParseError {
+                    message: "Expected '=' after destructuring pattern".to_string(),
+                    span: span.start..span.end,
+                });
+            }
+            let value = self.parse_expression()?;
+            self.match_token(&Token::Semicolon);
+            return Ok(Statement::LetPattern {
+                pattern,
+                value,
+                span,
+            });
+        }
+
         let name = match self.advance() {
             Some(Token::Identifier(n)) => n.clone(),
             _ => {
@@ -163,6 +267,67 @@ impl Parser {
         })
     }
 
+    /// Parse the left side of a destructuring `let`: a name, `_`, an array
+    /// pattern `[a, b]` or an object pattern `{ x, y: alias }`, nested freely
+    fn parse_binding_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let span = self.current_span();
+
+        match self.advance().cloned() {
+            Some(Token::Identifier(name)) if name == "_" => Ok(Pattern::Wildcard),
+            Some(Token::Identifier(name)) => Ok(Pattern::Identifier(name)),
+            Some(Token::LeftBracket) => {
+                let mut elements = Vec::new();
+                while !self.check(&Token::RightBracket) && !self.is_at_end() {
+                    elements.push(self.parse_binding_pattern()?);
+                    if !self.match_token(&Token::Comma) {
+                        break;
+                    }
+                }
+                if !self.match_token(&Token::RightBracket) {
+                    return Err(ParseError {
+                        message: "Expected ']' after array pattern".to_string(),
+                        span: span.start..span.end,
+                    });
+                }
+                Ok(Pattern::Array(elements))
+            }
+            Some(Token::LeftBrace) => {
+                let mut fields = Vec::new();
+                while !self.check(&Token::RightBrace) && !self.is_at_end() {
+                    let key = match self.advance() {
+                        Some(Token::Identifier(key)) => key.clone(),
+                        _ => {
+                            return Err(ParseError {
+                                message: "Expected a key name in object pattern".to_string(),
+                                span: span.start..span.end,
+                            })
+                        }
+                    };
+                    let pattern = if self.match_token(&Token::Colon) {
+                        self.parse_binding_pattern()?
+                    } else {
+                        Pattern::Identifier(key.clone())
+                    };
+                    fields.push((key, pattern));
+                    if !self.match_token(&Token::Comma) {
+                        break;
+                    }
+                }
+                if !self.match_token(&Token::RightBrace) {
+                    return Err(ParseError {
+                        message: "Expected '}' after object pattern".to_string(),
+                        span: span.start..span.end,
+                    });
+                }
+                Ok(Pattern::Object(fields))
+            }
+            _ => Err(ParseError {
+                message: "Expected a name or pattern after 'let'".to_string(),
+                span: span.start..span.end,
+            }),
+        }
+    }
+
     fn parse_const_statement(&mut self) -> Result<Statement, ParseError> {
         let span = self.current_span();
         self.advance(); // consume 'const'
@@ -356,6 +521,7 @@ impl Parser {
         let mut parameters = Vec::new();
         if !self.check(&Token::RightParen) {
             loop {
+                let is_rest = self.match_token(&Token::DotDotDot);
                 let param_name = match self.advance() {
                     Some(Token::Identifier(n)) => n.clone(),
                     _ => {
@@ -366,16 +532,45 @@ impl Parser {
                     }
                 };
 
+                // `name = expr` gives a default for callers that omit it
+                let default_value = if !is_rest && self.match_token(&Token::Assign) {
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
+                // Arguments fill parameters left to right, so defaults must trail
+                if default_value.is_none()
+                    && !is_rest
+                    && parameters
+                        .iter()
+                        .any(|p: &Parameter| p.default_value.is_some())
+                {
+                    return Err(ParseError {
+                        message: format!(
+                            "Parameter '{}' needs a default value because an earlier parameter has one",
+                            param_name
+                        ),
+                        span: span.start..span.end,
+                    });
+                }
+
                 parameters.push(Parameter {
                     name: param_name,
                     type_annotation: None,
-                    default_value: None,
+                    default_value,
+                    is_rest,
                     span: self.current_span(),
                 });
 
                 if !self.match_token(&Token::Comma) {
                     break;
                 }
+                if is_rest {
+                    return Err(ParseError {
+                        message: "A rest parameter must be the last parameter".to_string(),
+                        span: span.start..span.end,
+                    });
+                }
             }
         }
 
@@ -475,6 +670,63 @@ impl Parser {
         })
     }
 
+    /// `label: while (...) { }` or `label: for (...) { }`
+    fn parse_labeled_loop(&mut self) -> Result<Statement, ParseError> {
+        let label = match self.advance() {
+            Some(Token::Identifier(label)) => label.clone(),
+            _ => unreachable!("parse_statement checked for a label"),
+        };
+        self.advance(); // consume ':'
+
+        self.loop_labels.push(label.clone());
+        let statement = match self.peek() {
+            Some(Token::While) => self.parse_while_statement(),
+            _ => self.parse_for_statement(),
+        };
+        self.loop_labels.pop();
+
+        let mut statement = statement?;
+        // A classic for with an init comes wrapped in a block, loop last
+        let target = match &mut statement {
+            Statement::Block { body, .. } => body.last_mut(),
+            other => Some(other),
+        };
+        if let Some(
+            Statement::While { label: slot, .. }
+            | Statement::For { label: slot, .. }
+            | Statement::ForClassic { label: slot, .. },
+        ) = target
+        {
+            *slot = Some(label);
+        }
+        Ok(statement)
+    }
+
+    /// The optional label after `break` or `continue`. Only labels of
+    /// enclosing loops are taken, so a name on the next line stays a statement.
+    fn parse_loop_label(
+        &mut self,
+        keyword: &str,
+        keyword_span: &Span,
+    ) -> Result<Option<String>, ParseError> {
+        let name = match self.peek() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Ok(None),
+        };
+        if self.loop_labels.contains(&name) {
+            self.advance();
+            return Ok(Some(name));
+        }
+        let span = self.current_span();
+        if keyword_span.line != 0 && span.line == keyword_span.line {
+            return Err(ParseError {
+                message: format!("No enclosing loop labeled '{}' to {}", name, keyword),
+                span: span.start..span.end,
+            });
+        }
+        Ok(None)
+    }
+
     fn parse_while_statement(&mut self) -> Result<Statement, ParseError> {
         let span = self.current_span();
         self.advance(); // consume 'while'
@@ -509,6 +761,7 @@ impl Parser {
         Ok(Statement::While {
             condition,
             body,
+            label: None,
             span,
         })
     }
@@ -559,6 +812,7 @@ impl Parser {
                     variable: var_name,
                     iterable,
                     body,
+                    label: None,
                     span,
                 });
             } else {
@@ -567,48 +821,15 @@ impl Parser {
             }
         }
 
-        // Classic C-style for loop: for (init; cond; increment) { }
-        // Parse init - simple assignment check
+        // Classic C-style for loop: for (init; cond; update) { }
         let init = if self.check(&Token::Semicolon) {
-            self.advance(); // consume semicolon
             None
         } else {
-            // Try to parse as assignment first
-            if let Some(Token::Identifier(name)) = self.peek().cloned() {
-                let save_pos = self.current;
-                self.advance();
-
-                if self.check(&Token::Assign) {
-                    self.advance(); // consume =
-                    let value = self.parse_expression()?;
-                    self.match_token(&Token::Semicolon);
-                    Some(Box::new(Statement::Let {
-                        name,
-                        value,
-                        type_annotation: None,
-                        span: span.clone(),
-                    }))
-                } else {
-                    // Backtrack and parse normally
-                    self.current = save_pos;
-                    let expr = self.parse_expression()?;
-                    self.match_token(&Token::Semicolon);
-                    Some(Box::new(Statement::Expression {
-                        expr,
-                        span: span.clone(),
-                    }))
-                }
-            } else {
-                let expr = self.parse_expression()?;
-                self.match_token(&Token::Semicolon);
-                Some(Box::new(Statement::Expression {
-                    expr,
-                    span: span.clone(),
-                }))
-            }
+            Some(Box::new(self.parse_statement()?))
         };
+        // Statement parsers usually consume the ';' themselves
+        self.match_token(&Token::Semicolon);
 
-        // Parse condition
         let condition = if self.check(&Token::Semicolon) {
             None
         } else {
@@ -622,45 +843,15 @@ impl Parser {
             });
         }
 
-        // Parse increment - handle assignment
-        let increment_stmt = if self.check(&Token::RightParen) {
+        let update = if self.check(&Token::RightParen) {
             None
         } else {
-            // Try to parse as assignment
-            if let Some(Token::Identifier(name)) = self.peek().cloned() {
-                let save_pos = self.current;
-                self.advance();
-
-                if self.check(&Token::Assign) {
-                    self.advance(); // consume =
-                    let value = self.parse_expression()?;
-                    Some(Statement::Let {
-                        name,
-                        value,
-                        type_annotation: None,
-                        span: span.clone(),
-                    })
-                } else {
-                    // Backtrack
-                    self.current = save_pos;
-                    let expr = self.parse_expression()?;
-                    Some(Statement::Expression {
-                        expr,
-                        span: span.clone(),
-                    })
-                }
-            } else {
-                let expr = self.parse_expression()?;
-                Some(Statement::Expression {
-                    expr,
-                    span: span.clone(),
-                })
-            }
+            Some(Box::new(self.parse_expression_statement()?))
         };
 
         if !self.match_token(&Token::RightParen) {
             return Err(ParseError {
-                message: "Expected ')' after for loop increment".to_string(),
+                message: "Expected ')' after for loop update".to_string(),
                 span: span.start..span.end,
             });
         }
@@ -672,105 +863,973 @@ impl Parser {
             });
         }
 
-        let mut body = self.parse_block()?;
-
-        // Desugar classic for loop to while loop
-        // for (init; cond; incr) { body } => init; while (cond) { body; incr; }
-
-        if let Some(incr) = increment_stmt {
-            body.push(incr);
-        }
+        let body = self.parse_block()?;
 
-        let while_stmt = Statement::While {
-            condition: condition.unwrap_or(Expression::Literal {
-                value: crate::ast::Literal::Boolean(true),
-                span: span.clone(),
-            }),
+        let for_loop = Statement::ForClassic {
+            condition,
+            update,
             body,
+            label: None,
             span: span.clone(),
         };
+        // for (init; ...) { } => { init; for (; ...) { } }
+        Ok(match init {
+            Some(init) => Statement::Block {
+                body: vec![*init, for_loop],
+                span,
+            },
+            None => for_loop,
+        })
+    }
 
-        if let Some(init_stmt) = init {
-            // Return a block containing init and while
-            Ok(Statement::Expression {
-                expr: Expression::Literal {
-                    value: crate::ast::Literal::Nil,
-                    span: span.clone(),
-                },
+    /// True when the `{` at the cursor opens an object literal (`{ key: ...`)
+    /// rather than a block
+    fn at_object_literal(&self) -> bool {
+        let token = |offset: usize| self.tokens.get(self.current + offset).map(|(t, _)| t);
+        let key =
+            matches!(token(1), Some(Token::String(_))) || token(1).and_then(Token::name).is_some();
+        key && matches!(token(2), Some(Token::Colon))
+    }
+
+    /// Parse `if`, `match` or a block in expression position by reusing the
+    /// statement parser
+    fn parse_statement_expression(&mut self) -> Result<Expression, ParseError> {
+        match self.parse_statement()? {
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
                 span,
-            })
-        } else {
-            Ok(while_stmt)
+            } => Ok(Expression::If {
+                condition: Box::new(condition),
+                then_branch,
+                else_branch,
+                span,
+            }),
+            Statement::Match { value, arms, span } => Ok(Expression::Match {
+                value: Box::new(value),
+                arms,
+                span,
+            }),
+            Statement::Block { body, span } => Ok(Expression::Block { body, span }),
+            other => {
+                let span = other.span();
+                Err(ParseError {
+                    message: "Expected an expression".to_string(),
+                    span: span.start..span.end,
+                })
+            }
         }
     }
 
-    fn parse_snapshot_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_block_statement(&mut self) -> Result<Statement, ParseError> {
         let span = self.current_span();
-        self.advance(); // consume 'snapshot'
-
-        // Only take a label if it's a string literal (not bare identifier which could be next statement)
-        let label = if let Some(Token::String(name)) = self.peek() {
-            let label = Some(name.clone());
-            self.advance();
-            label
-        } else {
-            None
-        };
+        self.advance(); // consume '{'
+        let body = self.parse_block()?;
+        Ok(Statement::Block { body, span })
+    }
 
-        self.match_token(&Token::Semicolon);
+    fn parse_parallel_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'parallel'
 
-        Ok(Statement::Snapshot { label, span })
+        if !self.match_token(&Token::LeftBrace) {
+            return Err(ParseError {
+                message: "Expected '{' after 'parallel'".to_string(),
+                span: span.start..span.end,
+            });
+        }
+        let body = self.parse_block()?;
+        Ok(Statement::Parallel { body, span })
     }
 
-    fn parse_rewind_statement(&mut self) -> Result<Statement, ParseError> {
+    /// `struct Name { field, field: type, field = default }`; commas between
+    /// fields are optional
+    fn parse_struct_statement(&mut self) -> Result<Statement, ParseError> {
         let span = self.current_span();
-        self.advance(); // consume 'rewind'
+        self.advance(); // consume 'struct'
 
-        // Check for "rewind to <label>"
-        let (steps, to_label) = if let Some(Token::Identifier(name)) = self.peek() {
-            if name == "to" {
-                self.advance(); // consume 'to'
-                let label = match self.advance() {
-                    Some(Token::Identifier(n)) | Some(Token::String(n)) => Some(n.clone()),
-                    _ => None,
-                };
-                (None, label)
-            } else {
-                // It's an expression (like a number)
-                (Some(self.parse_expression()?), None)
+        let name = match self.advance() {
+            Some(Token::Identifier(n)) => n.clone(),
+            _ => {
+                return Err(ParseError {
+                    message: "Expected struct name after 'struct'".to_string(),
+                    span: span.start..span.end,
+                })
             }
-        } else if !self.check(&Token::Semicolon) && !self.is_at_end() {
-            (Some(self.parse_expression()?), None)
-        } else {
-            (None, None)
         };
+        if !self.match_token(&Token::LeftBrace) {
+            return Err(ParseError {
+                message: format!("Expected '{{' after struct name '{}'", name),
+                span: span.start..span.end,
+            });
+        }
 
-        self.match_token(&Token::Semicolon);
+        let mut fields: Vec<StructField> = Vec::new();
+        while !self.check(&Token::RightBrace) {
+            let field_span = self.current_span();
+            let field_name = match self.advance() {
+                Some(Token::Identifier(n)) => n.clone(),
+                _ => {
+                    return Err(ParseError {
+                        message: format!("Expected field name in struct '{}'", name),
+                        span: field_span.start..field_span.end,
+                    })
+                }
+            };
+            if fields.iter().any(|field| field.name == field_name) {
+                return Err(ParseError {
+                    message: format!("Duplicate field '{}' in struct '{}'", field_name, name),
+                    span: field_span.start..field_span.end,
+                });
+            }
 
-        Ok(Statement::Rewind {
-            steps,
-            to_label,
-            span,
-        })
+            let type_annotation = if self.match_token(&Token::Colon) {
+                match self.advance() {
+                    Some(Token::Identifier(t)) => TypeAnnotation::Named(t.clone()),
+                    _ => {
+                        return Err(ParseError {
+                            message: format!("Expected type name for field '{}'", field_name),
+                            span: field_span.start..field_span.end,
+                        })
+                    }
+                }
+            } else {
+                TypeAnnotation::Inferred
+            };
+            let default_value = if self.match_token(&Token::Assign) {
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+
+            fields.push(StructField {
+                name: field_name,
+                type_annotation,
+                default_value,
+                span: field_span,
+            });
+            self.match_token(&Token::Comma);
+        }
+        self.advance(); // consume '}'
+
+        Ok(Statement::Struct { name, fields, span })
     }
 
-    fn parse_checkpoint_statement(&mut self) -> Result<Statement, ParseError> {
+    /// `enum Name { Unit, Payload(type, type) }`; commas between variants
+    /// are optional
+    fn parse_enum_statement(&mut self) -> Result<Statement, ParseError> {
         let span = self.current_span();
-        self.advance(); // consume 'checkpoint'
+        self.advance(); // consume 'enum'
 
-        let label = match self.advance() {
-            Some(Token::Identifier(n)) | Some(Token::String(n)) => n.clone(),
+        let name = match self.advance() {
+            Some(Token::Identifier(n)) => n.clone(),
             _ => {
                 return Err(ParseError {
-                    message: "Expected checkpoint label".to_string(),
+                    message: "Expected enum name after 'enum'".to_string(),
                     span: span.start..span.end,
                 })
             }
         };
+        if !self.match_token(&Token::LeftBrace) {
+            return Err(ParseError {
+                message: format!("Expected '{{' after enum name '{}'", name),
+                span: span.start..span.end,
+            });
+        }
 
-        self.match_token(&Token::Semicolon);
+        let mut variants: Vec<EnumVariant> = Vec::new();
+        while !self.check(&Token::RightBrace) {
+            let variant_span = self.current_span();
+            let variant_name = match self.advance() {
+                Some(Token::Identifier(n)) => n.clone(),
+                _ => {
+                    return Err(ParseError {
+                        message: format!("Expected variant name in enum '{}'", name),
+                        span: variant_span.start..variant_span.end,
+                    })
+                }
+            };
+            if variants.iter().any(|variant| variant.name == variant_name) {
+                return Err(ParseError {
+                    message: format!("Duplicate variant '{}' in enum '{}'", variant_name, name),
+                    span: variant_span.start..variant_span.end,
+                });
+            }
+
+            let mut fields = Vec::new();
+            if self.match_token(&Token::LeftParen) {
+                while !self.check(&Token::RightParen) {
+                    match self.advance() {
+                        Some(Token::Identifier(t)) => fields.push(TypeAnnotation::Named(t.clone())),
+                        _ => {
+                            return Err(ParseError {
+                                message: format!(
+                                    "Expected type name in variant '{}.{}'",
+                                    name, variant_name
+                                ),
+                                span: variant_span.start..variant_span.end,
+                            })
+                        }
+                    }
+                    if !self.match_token(&Token::Comma) {
+                        break;
+                    }
+                }
+                if !self.match_token(&Token::RightParen) {
+                    return Err(ParseError {
+                        message: format!("Expected ')' after fields of variant '{}'", variant_name),
+                        span: variant_span.start..variant_span.end,
+                    });
+                }
+            }
+
+            variants.push(EnumVariant {
+                name: variant_name,
+                fields,
+                span: variant_span,
+            });
+            self.match_token(&Token::Comma);
+        }
+        self.advance(); // consume '}'
+
+        Ok(Statement::Enum {
+            name,
+            variants,
+            span,
+        })
+    }
+
+    fn parse_snapshot_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'snapshot'
+
+        // Only take a label if it's a string literal (not bare identifier which could be next statement)
+        let label = if let Some(Token::String(name)) = self.peek() {
+            let label = Some(name.clone());
+            self.advance();
+            label
+        } else {
+            None
+        };
+
+        // `#name` tags, e.g. `snapshot "label" #hot #loop`
+        let mut tags = Vec::new();
+        while self.match_token(&Token::Hash) {
+            match self.advance() {
+                Some(Token::Identifier(tag)) => tags.push(tag.clone()),
+                _ => {
+                    return Err(ParseError {
+                        message: "Expected a tag name after '#'".to_string(),
+                        span: span.start..span.end,
+                    })
+                }
+            }
+        }
+
+        self.match_token(&Token::Semicolon);
+
+        Ok(Statement::Snapshot { label, tags, span })
+    }
+
+    fn parse_rewind_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'rewind'
+
+        // Check for "rewind to <label>"
+        let (steps, to_label) = if let Some(Token::Identifier(name)) = self.peek() {
+            if name == "to" {
+                self.advance(); // consume 'to'
+                let label = match self.advance() {
+                    Some(Token::Identifier(n)) | Some(Token::String(n)) => Some(n.clone()),
+                    _ => None,
+                };
+                (None, label)
+            } else {
+                // It's an expression (like a number)
+                (Some(self.parse_expression()?), None)
+            }
+        } else if !self.check(&Token::Semicolon) && !self.is_at_end() {
+            (Some(self.parse_expression()?), None)
+        } else {
+            (None, None)
+        };
+
+        self.match_token(&Token::Semicolon);
+
+        Ok(Statement::Rewind {
+            steps,
+            to_label,
+            span,
+        })
+    }
+
+    fn parse_checkpoint_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'checkpoint'
+
+        let label = match self.advance() {
+            Some(Token::Identifier(n)) | Some(Token::String(n)) => n.clone(),
+            _ => {
+                return Err(ParseError {
+                    message: "Expected checkpoint label".to_string(),
+                    span: span.start..span.end,
+                })
+            }
+        };
+
+        self.match_token(&Token::Semicolon);
+
+        Ok(Statement::Checkpoint { label, span })
+    }
+
+    /// `replay from "label"`
+    fn parse_replay_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'replay'
+        self.expect_from("replay")?;
+
+        let label = match self.advance() {
+            Some(Token::Identifier(n)) | Some(Token::String(n)) => n.clone(),
+            _ => {
+                return Err(ParseError {
+                    message: "Expected checkpoint label after 'replay from'".to_string(),
+                    span: span.start..span.end,
+                })
+            }
+        };
+
+        self.match_token(&Token::Semicolon);
+
+        Ok(Statement::Replay { label, span })
+    }
+
+    fn parse_import_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'import'
+
+        // import name from "path" / import { a, b } from "path"
+        let mut items = Vec::new();
+        let alias = match self.peek() {
+            Some(Token::Identifier(name)) => {
+                let name = name.clone();
+                self.advance();
+                self.expect_from(&format!("import {}", name))?;
+                Some(name)
+            }
+            Some(Token::LeftBrace) => {
+                self.advance();
+                while !self.check(&Token::RightBrace) {
+                    match self.advance() {
+                        Some(Token::Identifier(name)) => items.push(name.clone()),
+                        _ => {
+                            return Err(ParseError {
+                                message: "Expected a name to import inside '{ }'".to_string(),
+                                span: span.start..span.end,
+                            })
+                        }
+                    }
+                    if !self.match_token(&Token::Comma) {
+                        break;
+                    }
+                }
+                if !self.match_token(&Token::RightBrace) || items.is_empty() {
+                    return Err(ParseError {
+                        message: "Expected '{ name, ... }' after 'import'".to_string(),
+                        span: span.start..span.end,
+                    });
+                }
+                self.expect_from("import { ... }")?;
+                None
+            }
+            _ => None,
+        };
+
+        let path = match self.advance() {
+            Some(Token::String(p)) => p.clone(),
+            _ => {
+                return Err(ParseError {
+                    message: "Expected module path string after 'import'".to_string(),
+                    span: span.start..span.end,
+                })
+            }
+        };
+
+        self.match_token(&Token::Semicolon);
+
+        Ok(Statement::Import {
+            path: vec![path],
+            items,
+            alias,
+            span,
+        })
+    }
+
+    /// Consume the `from` keyword that follows `what` in an import or replay
+    fn expect_from(&mut self, what: &str) -> Result<(), ParseError> {
+        let span = self.current_span();
+        match self.advance() {
+            Some(Token::Identifier(kw)) if kw == "from" => Ok(()),
+            _ => Err(ParseError {
+                message: format!("Expected 'from' after '{}'", what),
+                span: span.start..span.end,
+            }),
+        }
+    }
+
+    /// `export name`, or `export` before a `fn`, `let`, `const`, `var`,
+    /// `struct` or `enum` declaration
+    fn parse_export_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'export'
+
+        if let Some(Token::Identifier(name)) = self.peek() {
+            let item = name.clone();
+            self.advance();
+            self.match_token(&Token::Semicolon);
+            return Ok(Statement::Export {
+                item,
+                declaration: None,
+                span,
+            });
+        }
+
+        let declaration = match self.peek() {
+            Some(
+                Token::Fn | Token::Let | Token::Const | Token::Var | Token::Struct | Token::Enum,
+            ) => self.parse_statement()?,
+            _ => {
+                return Err(ParseError {
+                    message: "Expected a name or declaration after 'export'".to_string(),
+                    span: span.start..span.end,
+                })
+            }
+        };
+        let item = match &declaration {
+            Statement::Function { name, .. }
+            | Statement::Let { name, .. }
+            | Statement::Const { name, .. }
+            | Statement::Struct { name, .. }
+            | Statement::Enum { name, .. } => name.clone(),
+            _ => {
+                return Err(ParseError {
+                    message: "Only declarations can be exported".to_string(),
+                    span: span.start..span.end,
+                })
+            }
+        };
+
+        Ok(Statement::Export {
+            item,
+            declaration: Some(Box::new(declaration)),
+            span,
+        })
+    }
+
+    /// `assert cond;`, `assert cond, message;` or `assert(cond, message);`
+    fn parse_assert_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'assert'
+        let start = self.current;
+
+        let (condition, message, source) = match self.parse_assert_parts() {
+            Ok(parts) => parts,
+            // `assert(cond, message)` isn't a valid parenthesized expression
+            Err(err) if self.tokens.get(start).map(|(t, _)| t) == Some(&Token::LeftParen) => {
+                self.current = start + 1;
+                let parts = self.parse_assert_parts().map_err(|_| err)?;
+                if !self.match_token(&Token::RightParen) {
+                    return Err(ParseError {
+                        message: "Expected ')' after assert arguments".to_string(),
+                        span: span.start..span.end,
+                    });
+                }
+                parts
+            }
+            Err(err) => return Err(err),
+        };
+
+        self.match_token(&Token::Semicolon);
+
+        Ok(Statement::Assert {
+            condition,
+            message,
+            source,
+            span,
+        })
+    }
+
+    fn parse_assert_parts(
+        &mut self,
+    ) -> Result<(Expression, Option<Expression>, String), ParseError> {
+        let start = self.current;
+        let condition = self.parse_expression()?;
+        let source = self.source_text(start, self.current);
+        let message = if self.match_token(&Token::Comma) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        Ok((condition, message, source))
+    }
+
+    /// Parse `syntax "name" { pattern: "...", transform: (a, b) => ... }` and
+    /// register the rule for the rest of the program
+    fn parse_syntax_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'syntax'
+
+        let error = |message: &str| ParseError {
+            message: message.to_string(),
+            span: span.start..span.end,
+        };
+
+        let name = match self.advance() {
+            Some(Token::String(name)) => name.clone(),
+            _ => return Err(error("Expected syntax name string after 'syntax'")),
+        };
+        if !self.match_token(&Token::LeftBrace) {
+            return Err(error("Expected '{' after syntax name"));
+        }
+
+        let mut pattern = None;
+        let mut transform = None;
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            let key = match self.advance() {
+                Some(Token::Identifier(key)) => key.clone(),
+                _ => {
+                    return Err(error(
+                        "Expected 'pattern' or 'transform' in syntax definition",
+                    ))
+                }
+            };
+            if !self.match_token(&Token::Colon) {
+                return Err(error(&format!("Expected ':' after '{}'", key)));
+            }
+            match key.as_str() {
+                "pattern" => match self.advance() {
+                    Some(Token::String(p)) => pattern = Some(p.clone()),
+                    _ => return Err(error("Syntax pattern must be a string")),
+                },
+                "transform" => transform = Some(self.parse_syntax_transform()?),
+                other => return Err(error(&format!("Unknown syntax field '{}'", other))),
+            }
+            self.match_token(&Token::Comma);
+        }
+        if !self.match_token(&Token::RightBrace) {
+            return Err(error("Expected '}' after syntax definition"));
+        }
+        self.match_token(&Token::Semicolon);
+
+        let (Some(pattern), Some((transformer, body))) = (pattern, transform) else {
+            return Err(error("Syntax definition needs a pattern and a transform"));
+        };
+        let parameters = match &transformer {
+            Expression::Lambda { parameters, .. } => {
+                parameters.iter().map(|p| p.name.clone()).collect()
+            }
+            _ => Vec::new(),
+        };
+        let pattern = SyntaxPattern::new(pattern);
+        let body = self.tokens[body].to_vec();
+        let rule = SyntaxRule::new(name.clone(), &pattern, parameters, body)
+            .map_err(|message| error(&message))?;
+        self.syntax_rules.insert(rule.keyword().to_string(), rule);
+
+        Ok(Statement::SyntaxExtension {
+            name,
+            pattern: pattern.pattern_string,
+            transformer,
+            span,
+        })
+    }
+
+    /// Parse a syntax transform, which must be an arrow function. Returns it
+    /// with the token indices of its body, which uses of the rule splice.
+    fn parse_syntax_transform(
+        &mut self,
+    ) -> Result<(Expression, std::ops::Range<usize>), ParseError> {
+        let span = self.current_span();
+        let start = self.current;
+        let transformer = self.parse_expression()?;
+        let arrow = self.tokens[start..self.current]
+            .iter()
+            .position(|(token, _)| *token == Token::FatArrow);
+        match (&transformer, arrow) {
+            (Expression::Lambda { .. }, Some(arrow)) => {
+                Ok((transformer, start + arrow + 1..self.current))
+            }
+            _ => Err(ParseError {
+                message: "Syntax transform must be an arrow function".to_string(),
+                span: span.start..span.end,
+            }),
+        }
+    }
+
+    /// Match a use of a `syntax` rule and parse its expansion in place
+    fn parse_syntax_use(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        let rule = match self.peek() {
+            Some(Token::Identifier(keyword)) => self.syntax_rules[keyword].clone(),
+            _ => unreachable!("checked by parse_statement"),
+        };
+        let error = |message: String| ParseError {
+            message,
+            span: span.start..span.end,
+        };
+        if self.expansion_depth >= MAX_EXPANSION_DEPTH {
+            return Err(error(format!(
+                "Maximum expansion depth exceeded in '{}' syntax",
+                rule.name
+            )));
+        }
+
+        let mut fragments = Vec::new();
+        for part in &rule.parts {
+            match part {
+                PatternPart::Literal(token) => {
+                    if self.peek() != Some(token) {
+                        return Err(error(format!(
+                            "Expected '{}' in '{}' syntax, found {:?}",
+                            token,
+                            rule.name,
+                            self.peek()
+                        )));
+                    }
+                    self.advance();
+                }
+                // A trailing placeholder takes a whole statement, spliced as a block
+                PatternPart::Placeholder(_) if Some(part) == rule.parts.last() => {
+                    let start = self.current;
+                    self.parse_statement()?;
+                    fragments.push(self.wrap_fragment(start, Token::LeftBrace, Token::RightBrace));
+                }
+                PatternPart::Placeholder(_) => {
+                    let start = self.current;
+                    self.parse_expression()?;
+                    fragments.push(self.wrap_fragment(start, Token::LeftParen, Token::RightParen));
+                }
+            }
+        }
+        self.match_token(&Token::Semicolon);
+
+        let mut expansion = Parser {
+            tokens: rule.expand(&fragments),
+            current: 0,
+            source: None,
+            line_starts: Vec::new(),
+            syntax_rules: self.syntax_rules.clone(),
+            expansion_depth: self.expansion_depth + 1,
+            loop_labels: self.loop_labels.clone(),
+        };
+        let mut body = expansion.parse_program()?.statements;
+        if body.len() == 1 {
+            Ok(body.remove(0))
+        } else {
+            Ok(Statement::Block { body, span })
+        }
+    }
+
+    /// The tokens from `start` to the current position, between `open` and `close`
+    fn wrap_fragment(
+        &self,
+        start: usize,
+        open: Token,
+        close: Token,
+    ) -> Vec<(Token, std::ops::Range<usize>)> {
+        let tokens = &self.tokens[start..self.current];
+        let first = tokens.first().map_or(0..0, |(_, range)| range.clone());
+        let last = tokens.last().map_or(0..0, |(_, range)| range.clone());
+        std::iter::once((open, first))
+            .chain(tokens.iter().cloned())
+            .chain(std::iter::once((close, last)))
+            .collect()
+    }
+
+    /// `switch (x) { case 1: ... default: ... }`; each case runs until the
+    /// next `case`, `default` or the closing brace
+    fn parse_switch_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'switch'
+
+        if !self.match_token(&Token::LeftParen) {
+            return Err(ParseError {
+                message: "Expected '(' after 'switch'".to_string(),
+                span: span.start..span.end,
+            });
+        }
+        let value = self.parse_expression()?;
+        if !self.match_token(&Token::RightParen) {
+            return Err(ParseError {
+                message: "Expected ')' after switch value".to_string(),
+                span: span.start..span.end,
+            });
+        }
+        if !self.match_token(&Token::LeftBrace) {
+            return Err(ParseError {
+                message: "Expected '{' after switch value".to_string(),
+                span: span.start..span.end,
+            });
+        }
+
+        let mut cases = Vec::new();
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            let case_span = self.current_span();
+            let value = match self.advance() {
+                Some(Token::Case) => Some(self.parse_expression()?),
+                Some(Token::Default) if cases.iter().any(|c: &SwitchCase| c.value.is_none()) => {
+                    return Err(ParseError {
+                        message: "A switch can only have one 'default'".to_string(),
+                        span: case_span.start..case_span.end,
+                    })
+                }
+                Some(Token::Default) => None,
+                _ => {
+                    return Err(ParseError {
+                        message: "Expected 'case' or 'default' in switch".to_string(),
+                        span: case_span.start..case_span.end,
+                    })
+                }
+            };
+            if !self.match_token(&Token::Colon) {
+                return Err(ParseError {
+                    message: "Expected ':' after switch case".to_string(),
+                    span: case_span.start..case_span.end,
+                });
+            }
+
+            let mut body = Vec::new();
+            while !matches!(
+                self.peek(),
+                Some(Token::Case) | Some(Token::Default) | Some(Token::RightBrace) | None
+            ) {
+                body.push(self.parse_statement()?);
+            }
+            cases.push(SwitchCase {
+                value,
+                body,
+                span: case_span,
+            });
+        }
+
+        if !self.match_token(&Token::RightBrace) {
+            return Err(ParseError {
+                message: "Expected '}' after switch cases".to_string(),
+                span: span.start..span.end,
+            });
+        }
+
+        Ok(Statement::Switch { value, cases, span })
+    }
+
+    fn parse_match_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'match'
+
+        // `match (x) { ... }` matches patterns; `match x is { ... }` matches types
+        let value = if self.match_token(&Token::LeftParen) {
+            let value = self.parse_expression()?;
+            if !self.match_token(&Token::RightParen) {
+                return Err(ParseError {
+                    message: "Expected ')' after match value".to_string(),
+                    span: span.start..span.end,
+                });
+            }
+            value
+        } else {
+            let value = self.parse_expression()?;
+            if !matches!(self.peek(), Some(Token::Identifier(kw)) if kw == "is") {
+                return Err(ParseError {
+                    message: "Expected '(' after 'match'".to_string(),
+                    span: span.start..span.end,
+                });
+            }
+            value
+        };
+        let by_type = matches!(self.peek(), Some(Token::Identifier(kw)) if kw == "is");
+        if by_type {
+            self.advance();
+        }
+
+        if !self.match_token(&Token::LeftBrace) {
+            return Err(ParseError {
+                message: "Expected '{' after match value".to_string(),
+                span: span.start..span.end,
+            });
+        }
+
+        let mut arms = Vec::new();
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            let arm_span = self.current_span();
+            let pattern = if by_type {
+                self.parse_type_pattern()?
+            } else {
+                self.parse_pattern()?
+            };
+
+            // Guards stop below arrow functions so `if x => ...` isn't read as a lambda
+            let guard = if self.match_token(&Token::If) {
+                Some(self.parse_ternary()?)
+            } else {
+                None
+            };
+
+            if !self.match_token(&Token::FatArrow) {
+                return Err(ParseError {
+                    message: "Expected '=>' after match pattern".to_string(),
+                    span: arm_span.start..arm_span.end,
+                });
+            }
+
+            let body = if self.match_token(&Token::LeftBrace) {
+                self.parse_block()?
+            } else {
+                let expr_span = self.current_span();
+                let expr = self.parse_expression()?;
+                vec![Statement::Expression {
+                    expr,
+                    span: expr_span,
+                }]
+            };
+
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+                span: arm_span,
+            });
+
+            if !self.match_token(&Token::Comma) {
+                self.match_token(&Token::Semicolon);
+            }
+        }
+
+        if !self.match_token(&Token::RightBrace) {
+            return Err(ParseError {
+                message: "Expected '}' after match arms".to_string(),
+                span: span.start..span.end,
+            });
+        }
+
+        Ok(Statement::Match { value, arms, span })
+    }
+
+    /// Parse a match pattern: `_`, a literal, a binding name, `[p, ...]`, or
+    /// an enum variant such as `Color.Red` or `Rgb(r, g, b)`
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let span = self.current_span();
+
+        match self.advance().cloned() {
+            Some(Token::Identifier(name)) if name == "_" => Ok(Pattern::Wildcard),
+            Some(Token::Identifier(name)) if self.check(&Token::Dot) => {
+                self.advance(); // consume '.'
+                let variant = match self.advance() {
+                    Some(Token::Identifier(v)) => v.clone(),
+                    _ => {
+                        return Err(ParseError {
+                            message: format!("Expected variant name after '{}.'", name),
+                            span: span.start..span.end,
+                        })
+                    }
+                };
+                self.parse_variant_pattern(Some(name), variant)
+            }
+            Some(Token::Identifier(name)) if self.check(&Token::LeftParen) => {
+                self.parse_variant_pattern(None, name)
+            }
+            Some(Token::Identifier(name)) => Ok(Pattern::Identifier(name)),
+            Some(Token::Integer(n)) => Ok(Pattern::Literal(Literal::Integer(n))),
+            Some(Token::Float(f)) => Ok(Pattern::Literal(Literal::Float(f))),
+            Some(Token::String(s)) => Ok(Pattern::Literal(Literal::String(s))),
+            Some(Token::True) => Ok(Pattern::Literal(Literal::Boolean(true))),
+            Some(Token::False) => Ok(Pattern::Literal(Literal::Boolean(false))),
+            Some(Token::Nil) => Ok(Pattern::Literal(Literal::Nil)),
+            Some(Token::Minus) => match self.advance().cloned() {
+                Some(Token::Integer(n)) => Ok(Pattern::Literal(Literal::Integer(-n))),
+                Some(Token::Float(f)) => Ok(Pattern::Literal(Literal::Float(-f))),
+                _ => Err(ParseError {
+                    message: "Expected number after '-' in pattern".to_string(),
+                    span: span.start..span.end,
+                }),
+            },
+            Some(Token::LeftBracket) => {
+                let mut elements = Vec::new();
+                while !self.check(&Token::RightBracket) && !self.is_at_end() {
+                    elements.push(self.parse_pattern()?);
+                    if !self.match_token(&Token::Comma) {
+                        break;
+                    }
+                }
+
+                if !self.match_token(&Token::RightBracket) {
+                    return Err(ParseError {
+                        message: "Expected ']' after array pattern".to_string(),
+                        span: span.start..span.end,
+                    });
+                }
+
+                Ok(Pattern::Array(elements))
+            }
+            _ => Err(ParseError {
+                message: "Expected pattern in match arm".to_string(),
+                span: span.start..span.end,
+            }),
+        }
+    }
+
+    /// Parse an arm of `match x is { ... }`: `_`, or a type name optionally
+    /// followed by a name to bind the value to
+    fn parse_type_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let span = self.current_span();
+        let type_name = match self.advance() {
+            Some(Token::Identifier(name)) if name == "_" => return Ok(Pattern::Wildcard),
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => {
+                return Err(ParseError {
+                    message: "Expected a type name in 'match ... is' arm".to_string(),
+                    span: span.start..span.end,
+                })
+            }
+        };
+        let binding = match self.peek() {
+            Some(Token::Identifier(name)) => {
+                let name = name.clone();
+                self.advance();
+                Some(name)
+            }
+            _ => None,
+        };
+        Ok(Pattern::Type { type_name, binding })
+    }
+
+    /// Parse the optional `(p, ...)` payload of a variant pattern
+    fn parse_variant_pattern(
+        &mut self,
+        enum_name: Option<String>,
+        name: String,
+    ) -> Result<Pattern, ParseError> {
+        let span = self.current_span();
+        let mut fields = Vec::new();
+        if self.match_token(&Token::LeftParen) {
+            while !self.check(&Token::RightParen) && !self.is_at_end() {
+                fields.push(self.parse_pattern()?);
+                if !self.match_token(&Token::Comma) {
+                    break;
+                }
+            }
+            if !self.match_token(&Token::RightParen) {
+                return Err(ParseError {
+                    message: format!("Expected ')' after fields of variant pattern '{}'", name),
+                    span: span.start..span.end,
+                });
+            }
+        }
 
-        Ok(Statement::Checkpoint { label, span })
+        Ok(Pattern::Variant {
+            enum_name,
+            name,
+            fields,
+        })
     }
 
     fn parse_try_statement(&mut self) -> Result<Statement, ParseError> {
@@ -856,6 +1915,16 @@ impl Parser {
         Ok(Statement::Throw { value, span })
     }
 
+    fn parse_defer_statement(&mut self) -> Result<Statement, ParseError> {
+        let span = self.current_span();
+        self.advance(); // consume 'defer'
+
+        let expression = self.parse_expression()?;
+        self.match_token(&Token::Semicolon);
+
+        Ok(Statement::Defer { expression, span })
+    }
+
     fn parse_expression_statement(&mut self) -> Result<Statement, ParseError> {
         let span = self.current_span();
 
@@ -872,12 +1941,7 @@ impl Parser {
                         self.advance();
                         let value = self.parse_expression()?;
                         self.match_token(&Token::Semicolon);
-                        return Ok(Statement::Let {
-                            name,
-                            value,
-                            type_annotation: None,
-                            span,
-                        });
+                        return Ok(Self::assignment(name, value, span));
                     }
                     Token::PlusAssign => {
                         self.advance();
@@ -892,12 +1956,7 @@ impl Parser {
                             span: span.clone(),
                         };
                         self.match_token(&Token::Semicolon);
-                        return Ok(Statement::Let {
-                            name,
-                            value,
-                            type_annotation: None,
-                            span,
-                        });
+                        return Ok(Self::assignment(name, value, span));
                     }
                     Token::MinusAssign => {
                         self.advance();
@@ -912,12 +1971,7 @@ impl Parser {
                             span: span.clone(),
                         };
                         self.match_token(&Token::Semicolon);
-                        return Ok(Statement::Let {
-                            name,
-                            value,
-                            type_annotation: None,
-                            span,
-                        });
+                        return Ok(Self::assignment(name, value, span));
                     }
                     Token::StarAssign => {
                         self.advance();
@@ -932,12 +1986,7 @@ impl Parser {
                             span: span.clone(),
                         };
                         self.match_token(&Token::Semicolon);
-                        return Ok(Statement::Let {
-                            name,
-                            value,
-                            type_annotation: None,
-                            span,
-                        });
+                        return Ok(Self::assignment(name, value, span));
                     }
                     Token::SlashAssign => {
                         self.advance();
@@ -952,12 +2001,7 @@ impl Parser {
                             span: span.clone(),
                         };
                         self.match_token(&Token::Semicolon);
-                        return Ok(Statement::Let {
-                            name,
-                            value,
-                            type_annotation: None,
-                            span,
-                        });
+                        return Ok(Self::assignment(name, value, span));
                     }
                     Token::PercentAssign => {
                         self.advance();
@@ -972,12 +2016,7 @@ impl Parser {
                             span: span.clone(),
                         };
                         self.match_token(&Token::Semicolon);
-                        return Ok(Statement::Let {
-                            name,
-                            value,
-                            type_annotation: None,
-                            span,
-                        });
+                        return Ok(Self::assignment(name, value, span));
                     }
                     Token::Increment => {
                         // x++
@@ -995,12 +2034,7 @@ impl Parser {
                             span: span.clone(),
                         };
                         self.match_token(&Token::Semicolon);
-                        return Ok(Statement::Let {
-                            name,
-                            value,
-                            type_annotation: None,
-                            span,
-                        });
+                        return Ok(Self::assignment(name, value, span));
                     }
                     Token::Decrement => {
                         // x--
@@ -1018,12 +2052,7 @@ impl Parser {
                             span: span.clone(),
                         };
                         self.match_token(&Token::Semicolon);
-                        return Ok(Statement::Let {
-                            name,
-                            value,
-                            type_annotation: None,
-                            span,
-                        });
+                        return Ok(Self::assignment(name, value, span));
                     }
                     _ => {
                         // Not an assignment, backtrack and parse as expression
@@ -1053,12 +2082,7 @@ impl Parser {
                     span: span.clone(),
                 };
                 self.match_token(&Token::Semicolon);
-                return Ok(Statement::Let {
-                    name,
-                    value,
-                    type_annotation: None,
-                    span,
-                });
+                return Ok(Self::assignment(name, value, span));
             }
         }
 
@@ -1078,12 +2102,7 @@ impl Parser {
                     span: span.clone(),
                 };
                 self.match_token(&Token::Semicolon);
-                return Ok(Statement::Let {
-                    name,
-                    value,
-                    type_annotation: None,
-                    span,
-                });
+                return Ok(Self::assignment(name, value, span));
             }
         }
 
@@ -1091,20 +2110,30 @@ impl Parser {
 
         // Check for assignment (variable reassignment) for complex expressions
         if self.match_token(&Token::Assign) {
-            if let Expression::Identifier { name, .. } = expr {
-                let value = self.parse_expression()?;
-                self.match_token(&Token::Semicolon);
-                return Ok(Statement::Let {
-                    name,
-                    value,
-                    type_annotation: None,
-                    span,
-                });
-            } else {
-                return Err(ParseError {
-                    message: "Invalid assignment target".to_string(),
-                    span: span.start..span.end,
-                });
+            match expr {
+                Expression::Identifier { name, .. } => {
+                    let value = self.parse_expression()?;
+                    self.match_token(&Token::Semicolon);
+                    return Ok(Self::assignment(name, value, span));
+                }
+                Expression::IndexAccess { .. }
+                | Expression::PropertyAccess {
+                    optional: false, ..
+                } => {
+                    let value = self.parse_expression()?;
+                    self.match_token(&Token::Semicolon);
+                    return Ok(Statement::Assign {
+                        target: expr,
+                        value,
+                        span,
+                    });
+                }
+                _ => {
+                    return Err(ParseError {
+                        message: "Invalid assignment target".to_string(),
+                        span: span.start..span.end,
+                    });
+                }
             }
         }
 
@@ -1113,6 +2142,18 @@ impl Parser {
         Ok(Statement::Expression { expr, span })
     }
 
+    /// Build an assignment to a plain variable, as produced by `x = v`, `x += v` and `x++`
+    fn assignment(name: String, value: Expression, span: Span) -> Statement {
+        Statement::Assign {
+            target: Expression::Identifier {
+                name,
+                span: span.clone(),
+            },
+            value,
+            span,
+        }
+    }
+
     fn parse_block(&mut self) -> Result<Vec<Statement>, ParseError> {
         let mut statements = Vec::new();
 
@@ -1160,6 +2201,7 @@ impl Parser {
                         name: param,
                         type_annotation: None,
                         default_value: None,
+                        is_rest: false,
                         span: Span::dummy(),
                     }],
                     body,
@@ -1187,6 +2229,7 @@ impl Parser {
                             name: name.clone(),
                             type_annotation: None,
                             default_value: None,
+                            is_rest: false,
                             span: Span::dummy(),
                         });
 
@@ -1233,11 +2276,11 @@ impl Parser {
     }
 
     fn parse_ternary(&mut self) -> Result<Expression, ParseError> {
-        let mut expr = self.parse_or()?;
+        let mut expr = self.parse_pipeline()?;
 
         if self.match_token(&Token::Question) {
             let span = self.current_span();
-            let then_expr = self.parse_or()?;
+            let then_expr = self.parse_coalesce()?;
 
             if !self.match_token(&Token::Colon) {
                 return Err(ParseError {
@@ -1259,6 +2302,54 @@ impl Parser {
         Ok(expr)
     }
 
+    /// `value |> function` and `source ~> handler`, left-associative and
+    /// binding looser than `||`
+    fn parse_pipeline(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.parse_coalesce()?;
+
+        loop {
+            if self.match_token(&Token::Pipe) {
+                let span = self.current_span();
+                let function = self.parse_coalesce()?;
+                expr = Expression::Pipeline {
+                    value: Box::new(expr),
+                    function: Box::new(function),
+                    span,
+                };
+            } else if self.match_token(&Token::StreamPipe) {
+                let span = self.current_span();
+                let handler = self.parse_coalesce()?;
+                expr = Expression::StreamPipe {
+                    source: Box::new(expr),
+                    handler: Box::new(handler),
+                    span,
+                };
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    /// `a ?? b`, left-associative and binding looser than `||`
+    fn parse_coalesce(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.parse_or()?;
+
+        while self.match_token(&Token::NullCoalesce) {
+            let span = self.current_span();
+            let right = self.parse_or()?;
+            expr = Expression::Binary {
+                left: Box::new(expr),
+                operator: BinaryOp::NullCoalesce,
+                right: Box::new(right),
+                span,
+            };
+        }
+
+        Ok(expr)
+    }
+
     fn parse_or(&mut self) -> Result<Expression, ParseError> {
         let mut expr = self.parse_and()?;
 
@@ -1317,7 +2408,7 @@ impl Parser {
     }
 
     fn parse_comparison(&mut self) -> Result<Expression, ParseError> {
-        let mut expr = self.parse_term()?;
+        let mut expr = self.parse_bitwise_or()?;
 
         while let Some(token) = self.peek() {
             let op = match token {
@@ -1329,6 +2420,83 @@ impl Parser {
             };
             self.advance();
             let span = self.current_span();
+            let right = self.parse_bitwise_or()?;
+            expr = Expression::Binary {
+                left: Box::new(expr),
+                operator: op,
+                right: Box::new(right),
+                span,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // Bitwise operators bind tighter than comparisons (as in Rust and Python),
+    // so `x & 1 == 0` means `(x & 1) == 0`. From loosest to tightest: | ^ & << >>
+
+    fn parse_bitwise_or(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.parse_bitwise_xor()?;
+
+        while self.match_token(&Token::BitwiseOr) {
+            let span = self.current_span();
+            let right = self.parse_bitwise_xor()?;
+            expr = Expression::Binary {
+                left: Box::new(expr),
+                operator: BinaryOp::BitwiseOr,
+                right: Box::new(right),
+                span,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_bitwise_xor(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.parse_bitwise_and()?;
+
+        while self.match_token(&Token::BitwiseXor) {
+            let span = self.current_span();
+            let right = self.parse_bitwise_and()?;
+            expr = Expression::Binary {
+                left: Box::new(expr),
+                operator: BinaryOp::BitwiseXor,
+                right: Box::new(right),
+                span,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_bitwise_and(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.parse_shift()?;
+
+        while self.match_token(&Token::BitwiseAnd) {
+            let span = self.current_span();
+            let right = self.parse_shift()?;
+            expr = Expression::Binary {
+                left: Box::new(expr),
+                operator: BinaryOp::BitwiseAnd,
+                right: Box::new(right),
+                span,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_shift(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.parse_term()?;
+
+        while let Some(token) = self.peek() {
+            let op = match token {
+                Token::LeftShift => BinaryOp::LeftShift,
+                Token::RightShift => BinaryOp::RightShift,
+                _ => break,
+            };
+            self.advance();
+            let span = self.current_span();
             let right = self.parse_term()?;
             expr = Expression::Binary {
                 left: Box::new(expr),
@@ -1393,7 +2561,7 @@ impl Parser {
             let op = match token {
                 Token::Not => UnaryOp::Not,
                 Token::Minus => UnaryOp::Negate,
-                _ => return self.parse_postfix(),
+                _ => return self.parse_power(),
             };
             self.advance();
             let span = self.current_span();
@@ -1405,7 +2573,41 @@ impl Parser {
             });
         }
 
-        self.parse_postfix()
+        self.parse_power()
+    }
+
+    /// Exponentiation binds tighter than unary operators and is right-associative,
+    /// so `-x ** 2` is `-(x ** 2)` and `2 ** 3 ** 2` is `2 ** (3 ** 2)`.
+    fn parse_power(&mut self) -> Result<Expression, ParseError> {
+        let base = self.parse_postfix()?;
+
+        if self.match_token(&Token::Power) {
+            let span = self.current_span();
+            // Recurse through unary so the exponent may be negated: 2 ** -1
+            let exponent = self.parse_unary()?;
+            return Ok(Expression::Binary {
+                left: Box::new(base),
+                operator: BinaryOp::Power,
+                right: Box::new(exponent),
+                span,
+            });
+        }
+
+        Ok(base)
+    }
+
+    /// Parse an array element or call argument, either of which may be a
+    /// `...expr` spread
+    fn parse_element(&mut self) -> Result<Expression, ParseError> {
+        let span = self.current_span();
+        if self.match_token(&Token::DotDotDot) {
+            let expr = self.parse_expression()?;
+            return Ok(Expression::Spread {
+                expr: Box::new(expr),
+                span,
+            });
+        }
+        self.parse_expression()
     }
 
     fn parse_postfix(&mut self) -> Result<Expression, ParseError> {
@@ -1419,7 +2621,7 @@ impl Parser {
 
                     if !self.check(&Token::RightParen) {
                         loop {
-                            arguments.push(self.parse_expression()?);
+                            arguments.push(self.parse_element()?);
                             if !self.match_token(&Token::Comma) {
                                 break;
                             }
@@ -1458,11 +2660,12 @@ impl Parser {
                         span,
                     };
                 }
-                Some(Token::Dot) => {
+                Some(Token::Dot) | Some(Token::QuestionDot) => {
+                    let optional = self.check(&Token::QuestionDot);
                     self.advance();
-                    let property = match self.advance() {
-                        Some(Token::Identifier(name)) => name.clone(),
-                        _ => {
+                    let property = match self.advance().and_then(Token::name) {
+                        Some(name) => name.to_string(),
+                        None => {
                             return Err(ParseError {
                                 message: "Expected property name after '.'".to_string(),
                                 span: self.current_span().start..self.current_span().end,
@@ -1474,6 +2677,7 @@ impl Parser {
                     expr = Expression::PropertyAccess {
                         object: Box::new(expr),
                         property,
+                        optional,
                         span,
                     };
                 }
@@ -1590,7 +2794,7 @@ impl Parser {
 
                 if !self.check(&Token::RightBracket) {
                     loop {
-                        elements.push(self.parse_expression()?);
+                        elements.push(self.parse_element()?);
                         if !self.match_token(&Token::Comma) {
                             break;
                         }
@@ -1606,6 +2810,79 @@ impl Parser {
 
                 Ok(Expression::Array { elements, span })
             }
+            Some(Token::This) => {
+                self.advance();
+                Ok(Expression::This { span })
+            }
+            Some(Token::New) => {
+                self.advance();
+                let class_name = match self.advance() {
+                    Some(Token::Identifier(name)) => name.clone(),
+                    _ => {
+                        return Err(ParseError {
+                            message: "Expected struct name after 'new'".to_string(),
+                            span: span.start..span.end,
+                        })
+                    }
+                };
+
+                let mut arguments = Vec::new();
+                let mut fields = Vec::new();
+                if self.match_token(&Token::LeftParen) {
+                    if !self.check(&Token::RightParen) {
+                        loop {
+                            arguments.push(self.parse_expression()?);
+                            if !self.match_token(&Token::Comma) {
+                                break;
+                            }
+                        }
+                    }
+                    if !self.match_token(&Token::RightParen) {
+                        return Err(ParseError {
+                            message: "Expected ')' after arguments".to_string(),
+                            span: span.start..span.end,
+                        });
+                    }
+                } else if self.check(&Token::LeftBrace) {
+                    match self.parse_primary()? {
+                        Expression::Object {
+                            fields: initializers,
+                            ..
+                        } => fields = initializers,
+                        _ => {
+                            return Err(ParseError {
+                                message: format!(
+                                    "Expected field initializers after 'new {}'",
+                                    class_name
+                                ),
+                                span: span.start..span.end,
+                            })
+                        }
+                    }
+                } else {
+                    return Err(ParseError {
+                        message: format!("Expected '(' or '{{' after 'new {}'", class_name),
+                        span: span.start..span.end,
+                    });
+                }
+
+                Ok(Expression::New {
+                    class_name,
+                    arguments,
+                    fields,
+                    span,
+                })
+            }
+            Some(Token::If) | Some(Token::Match) => self.parse_statement_expression(),
+            Some(Token::LeftBrace)
+                if !self.at_object_literal()
+                    && !matches!(
+                        self.tokens.get(self.current + 1),
+                        Some((Token::RightBrace, _))
+                    ) =>
+            {
+                self.parse_statement_expression()
+            }
             Some(Token::LeftBrace) => {
                 self.advance();
                 let mut fields = Vec::new();
@@ -1613,15 +2890,14 @@ impl Parser {
                 if !self.check(&Token::RightBrace) {
                     loop {
                         let key = match self.advance() {
-                            Some(Token::Identifier(name)) | Some(Token::String(name)) => {
-                                name.clone()
-                            }
-                            _ => {
-                                return Err(ParseError {
-                                    message: "Expected object key".to_string(),
-                                    span: span.start..span.end,
-                                })
-                            }
+                            Some(Token::String(name)) => Some(name.clone()),
+                            token => token.and_then(Token::name).map(str::to_string),
+                        };
+                        let Some(key) = key else {
+                            return Err(ParseError {
+                                message: "Expected object key".to_string(),
+                                span: span.start..span.end,
+                            });
                         };
 
                         if !self.match_token(&Token::Colon) {
@@ -1663,101 +2939,84 @@ impl Parser {
         span: Span,
     ) -> Result<Expression, ParseError> {
         let mut parts: Vec<Expression> = Vec::new();
-        let mut current = String::new();
-        let mut chars = template.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '$' && chars.peek() == Some(&'{') {
-                chars.next(); // consume '{'
-
-                // Add current string part if not empty
-                if !current.is_empty() {
-                    parts.push(Expression::Literal {
-                        value: Literal::String(current.clone()),
-                        span: span.clone(),
-                    });
-                    current.clear();
-                }
 
-                // Extract expression until '}'
-                let mut expr_str = String::new();
-                let mut depth = 1;
-                while let Some(ch) = chars.next() {
-                    if ch == '{' {
-                        depth += 1;
-                        expr_str.push(ch);
-                    } else if ch == '}' {
-                        depth -= 1;
-                        if depth == 0 {
-                            break;
-                        }
-                        expr_str.push(ch);
-                    } else {
-                        expr_str.push(ch);
+        for segment in split_template(template) {
+            match segment {
+                TemplateSegment::Text(text) => parts.push(Expression::Literal {
+                    value: Literal::String(text),
+                    span: span.clone(),
+                }),
+                TemplateSegment::Placeholder(expr_str) => {
+                    // Tokenize and parse the expression, treating it as text on failure
+                    let parsed = crate::lexer::tokenize(&expr_str)
+                        .ok()
+                        .and_then(|tokens| Parser::new(tokens).parse_expression().ok());
+                    match parsed {
+                        Some(expr) => parts.push(expr),
+                        None => parts.push(Expression::Literal {
+                            value: Literal::String(expr_str),
+                            span: span.clone(),
+                        }),
                     }
                 }
+            }
+        }
 
-                // Parse the expression
-                if !expr_str.is_empty() {
-                    // Tokenize and parse the expression
-                    match crate::lexer::tokenize(&expr_str) {
-                        Ok(tokens) => {
-                            let mut expr_parser = Parser::new(tokens);
-                            match expr_parser.parse_expression() {
-                                Ok(expr) => parts.push(expr),
-                                Err(_) => {
-                                    // If parsing fails, treat as string
-                                    parts.push(Expression::Literal {
-                                        value: Literal::String(expr_str),
-                                        span: span.clone(),
-                                    });
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            // If tokenizing fails, treat as string
-                            parts.push(Expression::Literal {
-                                value: Literal::String(expr_str),
-                                span: span.clone(),
-                            });
-                        }
+        Ok(Expression::Template { parts, span })
+    }
+}
+
+/// A piece of a template string: literal text or the source inside `${...}`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateSegment {
+    Text(String),
+    Placeholder(String),
+}
+
+/// Split a template into text and `${...}` placeholder segments.
+///
+/// Braces nest inside placeholders, and empty placeholders are dropped.
+pub fn split_template(template: &str) -> Vec<TemplateSegment> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch == '$' && chars.peek() == Some(&'{') {
+            chars.next(); // consume '{'
+
+            if !current.is_empty() {
+                segments.push(TemplateSegment::Text(std::mem::take(&mut current)));
+            }
+
+            // Extract expression until the matching '}'
+            let mut expr_str = String::new();
+            let mut depth = 1;
+            for ch in chars.by_ref() {
+                if ch == '{' {
+                    depth += 1;
+                } else if ch == '}' {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
                     }
                 }
-            } else {
-                current.push(ch);
+                expr_str.push(ch);
             }
-        }
-
-        // Add remaining string part
-        if !current.is_empty() {
-            parts.push(Expression::Literal {
-                value: Literal::String(current),
-                span: span.clone(),
-            });
-        }
 
-        // Combine all parts with + operator
-        if parts.is_empty() {
-            Ok(Expression::Literal {
-                value: Literal::String(String::new()),
-                span,
-            })
-        } else if parts.len() == 1 {
-            Ok(parts.into_iter().next().unwrap())
-        } else {
-            // Build chain of Binary expressions with Add operator
-            let mut result = parts[0].clone();
-            for part in parts.into_iter().skip(1) {
-                result = Expression::Binary {
-                    left: Box::new(result),
-                    operator: BinaryOp::Add,
-                    right: Box::new(part),
-                    span: span.clone(),
-                };
+            if !expr_str.is_empty() {
+                segments.push(TemplateSegment::Placeholder(expr_str));
             }
-            Ok(result)
+        } else {
+            current.push(ch);
         }
     }
+
+    if !current.is_empty() {
+        segments.push(TemplateSegment::Text(current));
+    }
+
+    segments
 }
 
 /// Parse tokens into an AST
@@ -1766,6 +3025,15 @@ pub fn parse(tokens: Vec<(Token, std::ops::Range<usize>)>) -> Result<Program, Pa
     parser.parse_program()
 }
 
+/// Parse tokens lexed from `source`, keeping the text for messages that
+/// quote it (such as failed `assert` statements)
+pub fn parse_with_source(
+    tokens: Vec<(Token, std::ops::Range<usize>)>,
+    source: &str,
+) -> Result<Program, ParseError> {
+    Parser::new(tokens).with_source(source).parse_program()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
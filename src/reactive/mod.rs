@@ -9,6 +9,7 @@
 //! - Computed values that automatically update when dependencies change
 //! - Effects that run side effects when dependencies change
 //! - Automatic dependency tracking
+//! - Observable objects whose fields are tracked one by one
 //! - Efficient change propagation with topological sorting
 
 use crate::interpreter::value::Value;
@@ -111,9 +112,16 @@ impl Computed {
         self.value.read().unwrap().clone()
     }
 
+    /// Recompute the value, returning the subscribers to notify.
+    ///
+    /// Returns nothing when the result equals the previous value, so an
+    /// input change that doesn't affect the output stops here.
     pub fn recompute(&self, context: &ReactiveContext) -> Vec<NodeId> {
-        let new_value = (self.compute_fn)(context);
+        let new_value = context.tracking(self.id, || (self.compute_fn)(context));
         let mut value = self.value.write().unwrap();
+        if *value == new_value {
+            return Vec::new();
+        }
         *value = new_value;
 
         // Return list of subscribers to notify
@@ -124,6 +132,10 @@ impl Computed {
         self.subscribers.write().unwrap().insert(subscriber_id);
     }
 
+    pub fn unsubscribe(&self, subscriber_id: NodeId) {
+        self.subscribers.write().unwrap().remove(&subscriber_id);
+    }
+
     pub fn dependencies(&self) -> Vec<NodeId> {
         self.dependencies.read().unwrap().iter().cloned().collect()
     }
@@ -177,7 +189,7 @@ impl Effect {
 
     pub fn run(&self, context: &ReactiveContext) {
         if *self.enabled.read().unwrap() {
-            (self.effect_fn)(context);
+            context.tracking(self.id, || (self.effect_fn)(context));
         }
     }
 
@@ -231,6 +243,68 @@ impl ReactiveNode {
     }
 }
 
+/// An object whose fields are separate signals
+///
+/// Reading a field while a computed value or effect runs subscribes that node
+/// to the field alone, and writing a field only notifies its own subscribers.
+#[derive(Debug)]
+pub struct Observable {
+    label: String,
+    fields: RwLock<HashMap<String, Signal>>,
+}
+
+impl Observable {
+    /// Read a field, subscribing the currently running node to it
+    pub fn get(&self, field: &str, context: &ReactiveContext) -> Option<Value> {
+        let signal = self.fields.read().unwrap().get(field)?.clone();
+        context.track(&signal);
+        Some(signal.get())
+    }
+
+    /// Write a field, notifying only the nodes that read it. New fields get
+    /// a signal of their own.
+    pub fn set(
+        &self,
+        field: &str,
+        value: Value,
+        context: &ReactiveContext,
+    ) -> Result<(), ReactiveError> {
+        let existing = self.fields.read().unwrap().get(field).cloned();
+        match existing {
+            Some(signal) => context.notify(signal.set(value)),
+            None => {
+                let name = format!("{}.{}", self.label, field);
+                let signal = context.insert_signal(name, value)?;
+                self.fields
+                    .write()
+                    .unwrap()
+                    .insert(field.to_string(), signal);
+                Ok(())
+            }
+        }
+    }
+
+    /// Current field values, read without subscribing
+    pub fn snapshot(&self) -> HashMap<String, Value> {
+        self.fields
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(field, signal)| (field.clone(), signal.get()))
+            .collect()
+    }
+
+    /// IDs of the field signals, for garbage collection roots
+    pub fn field_ids(&self) -> Vec<NodeId> {
+        self.fields
+            .read()
+            .unwrap()
+            .values()
+            .map(Signal::id)
+            .collect()
+    }
+}
+
 /// The reactive context manages all reactive nodes and change propagation
 pub struct ReactiveContext {
     nodes: RwLock<HashMap<NodeId, ReactiveNode>>,
@@ -245,6 +319,9 @@ pub struct ReactiveContext {
     // Batching support
     batch_mode: RwLock<bool>,
     pending_updates: RwLock<HashSet<NodeId>>,
+
+    // Deferred scheduling: writes only mark dependents, `flush` runs them
+    deferred_mode: RwLock<bool>,
 }
 
 impl ReactiveContext {
@@ -256,6 +333,7 @@ impl ReactiveContext {
             current_node: RwLock::new(None),
             batch_mode: RwLock::new(false),
             pending_updates: RwLock::new(HashSet::new()),
+            deferred_mode: RwLock::new(false),
         }
     }
 
@@ -265,8 +343,13 @@ impl ReactiveContext {
         name: String,
         initial_value: Value,
     ) -> Result<NodeId, ReactiveError> {
+        self.insert_signal(name, initial_value)
+            .map(|signal| signal.id())
+    }
+
+    /// Register a new signal, returning a handle that shares its state
+    fn insert_signal(&self, name: String, initial_value: Value) -> Result<Signal, ReactiveError> {
         let signal = Signal::new(name.clone(), initial_value);
-        let id = signal.id();
 
         let mut nodes = self.nodes.write().unwrap();
         let mut name_to_id = self.name_to_id.write().unwrap();
@@ -275,10 +358,33 @@ impl ReactiveContext {
             return Err(ReactiveError::DuplicateName(name));
         }
 
-        nodes.insert(id, ReactiveNode::Signal(signal));
-        name_to_id.insert(name, id);
+        nodes.insert(signal.id(), ReactiveNode::Signal(signal.clone()));
+        name_to_id.insert(name, signal.id());
 
-        Ok(id)
+        Ok(signal)
+    }
+
+    /// Create an observable object with one signal per field
+    pub fn observable(
+        &self,
+        fields: HashMap<String, Value>,
+    ) -> Result<Arc<Observable>, ReactiveError> {
+        static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let label = format!(
+            "observable#{}",
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+        );
+
+        let mut signals = HashMap::new();
+        for (field, value) in fields {
+            let signal = self.insert_signal(format!("{}.{}", label, field), value)?;
+            signals.insert(field, signal);
+        }
+
+        Ok(Arc::new(Observable {
+            label,
+            fields: RwLock::new(signals),
+        }))
     }
 
     /// Register a computed value
@@ -394,8 +500,19 @@ impl ReactiveContext {
         Ok(id)
     }
 
-    /// Get a signal value by name
+    /// Get a signal value by name. Named reads never subscribe: a node's
+    /// dependencies are the names it was registered with, so this is the
+    /// same read as `peek`.
     pub fn get(&self, name: &str) -> Result<Value, ReactiveError> {
+        self.peek(name)
+    }
+
+    /// Read a signal or computed value without subscribing to it
+    ///
+    /// This never touches `current_node` or any subscriber set, so a compute
+    /// function can read a value once without depending on it. Only
+    /// `Observable` field reads subscribe the running node.
+    pub fn peek(&self, name: &str) -> Result<Value, ReactiveError> {
         let name_to_id = self.name_to_id.read().unwrap();
         let id = name_to_id
             .get(name)
@@ -425,16 +542,7 @@ impl ReactiveContext {
             Some(ReactiveNode::Signal(signal)) => {
                 let subscribers = signal.set(value);
                 drop(nodes);
-
-                if *self.batch_mode.read().unwrap() {
-                    // In batch mode, just collect updates
-                    let mut pending = self.pending_updates.write().unwrap();
-                    pending.extend(subscribers);
-                } else {
-                    // Propagate changes immediately
-                    self.propagate_changes(subscribers)?;
-                }
-                Ok(())
+                self.notify(subscribers)
             }
             Some(ReactiveNode::Computed(_)) => Err(ReactiveError::InvalidOperation(
                 "Cannot directly set a computed value".to_string(),
@@ -446,44 +554,142 @@ impl ReactiveContext {
         }
     }
 
+    /// Hand the subscribers of a written signal to propagation
+    fn notify(&self, subscribers: Vec<NodeId>) -> Result<(), ReactiveError> {
+        if *self.batch_mode.read().unwrap() || *self.deferred_mode.read().unwrap() {
+            // In batch mode, just collect updates
+            let mut pending = self.pending_updates.write().unwrap();
+            pending.extend(subscribers);
+            Ok(())
+        } else {
+            // Propagate changes immediately
+            self.propagate_changes(subscribers)
+        }
+    }
+
     /// Propagate changes through the dependency graph
-    fn propagate_changes(&self, mut changed_ids: Vec<NodeId>) -> Result<(), ReactiveError> {
-        let mut visited = HashSet::new();
-        let mut to_update = VecDeque::new();
-
-        // Add initial changed nodes
-        for id in changed_ids.drain(..) {
-            if visited.insert(id) {
-                to_update.push_back(id);
+    ///
+    /// Every node downstream of `changed_ids` is collected first, then updated
+    /// in topological order, so a node only recomputes once all of its inputs
+    /// are current and never sees a half-updated graph.
+    fn propagate_changes(&self, changed_ids: Vec<NodeId>) -> Result<(), ReactiveError> {
+        let order = self.topological_order(self.affected_nodes(&changed_ids));
+
+        // A computed whose value didn't change leaves its subscribers clean
+        let mut dirty: HashSet<NodeId> = changed_ids.into_iter().collect();
+
+        for id in order {
+            if !dirty.contains(&id) {
+                continue;
+            }
+
+            // Clone the node out so effects can read and write the context freely
+            let node = self.nodes.read().unwrap().get(&id).cloned();
+            match node {
+                Some(ReactiveNode::Computed(computed)) => {
+                    dirty.extend(computed.recompute(self));
+                }
+                Some(ReactiveNode::Effect(effect)) => effect.run(self),
+                _ => {}
             }
         }
 
-        // Process in topological order
-        while let Some(id) = to_update.pop_front() {
-            let nodes = self.nodes.read().unwrap();
+        Ok(())
+    }
 
-            match nodes.get(&id) {
-                Some(ReactiveNode::Computed(computed)) => {
-                    let subscribers = computed.recompute(self);
-                    drop(nodes);
+    /// `start` plus everything transitively subscribed to it
+    fn affected_nodes(&self, start: &[NodeId]) -> HashSet<NodeId> {
+        let nodes = self.nodes.read().unwrap();
+        let mut affected = HashSet::new();
+        let mut queue: VecDeque<NodeId> = start.iter().copied().collect();
 
-                    for sub_id in subscribers {
-                        if visited.insert(sub_id) {
-                            to_update.push_back(sub_id);
-                        }
-                    }
-                }
-                Some(ReactiveNode::Effect(effect)) => {
-                    effect.run(self);
-                    drop(nodes);
+        while let Some(id) = queue.pop_front() {
+            if !affected.insert(id) {
+                continue;
+            }
+            let subscribers = match nodes.get(&id) {
+                Some(ReactiveNode::Signal(signal)) => signal.subscribers.read().unwrap().clone(),
+                Some(ReactiveNode::Computed(computed)) => {
+                    computed.subscribers.read().unwrap().clone()
                 }
-                _ => {
-                    drop(nodes);
+                _ => continue,
+            };
+            queue.extend(subscribers);
+        }
+
+        affected
+    }
+
+    /// Order `affected` so every node comes after its dependencies within the set
+    fn topological_order(&self, affected: HashSet<NodeId>) -> Vec<NodeId> {
+        let dep_graph = self.dependency_graph.read().unwrap();
+        let mut pending: HashMap<NodeId, usize> = HashMap::new();
+        let mut dependents: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+
+        for &id in &affected {
+            let deps = dep_graph.get(&id);
+            let inside = deps
+                .into_iter()
+                .flatten()
+                .filter(|dep| affected.contains(dep));
+            let mut count = 0;
+            for &dep in inside {
+                dependents.entry(dep).or_default().push(id);
+                count += 1;
+            }
+            pending.insert(id, count);
+        }
+
+        let mut ready: VecDeque<NodeId> = pending
+            .iter()
+            .filter(|(_, &count)| count == 0)
+            .map(|(&id, _)| id)
+            .collect();
+        let mut order = Vec::with_capacity(affected.len());
+
+        while let Some(id) = ready.pop_front() {
+            order.push(id);
+            for dependent in dependents.remove(&id).unwrap_or_default() {
+                let count = pending.get_mut(&dependent).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push_back(dependent);
                 }
             }
         }
 
-        Ok(())
+        order
+    }
+
+    /// Run `f` as node `id`, so signals it reads through `track` become its
+    /// dependencies
+    fn tracking<R>(&self, id: NodeId, f: impl FnOnce() -> R) -> R {
+        let previous = self.current_node.write().unwrap().replace(id);
+        let result = f();
+        *self.current_node.write().unwrap() = previous;
+        result
+    }
+
+    /// Subscribe the currently running node, if any, to `signal`
+    fn track(&self, signal: &Signal) {
+        let Some(id) = *self.current_node.read().unwrap() else {
+            return;
+        };
+        signal.subscribe(id);
+        self.dependency_graph
+            .write()
+            .unwrap()
+            .entry(id)
+            .or_default()
+            .insert(signal.id());
+    }
+
+    /// Run `f` with dependency tracking suspended
+    pub fn untracked<R>(&self, f: impl FnOnce() -> R) -> R {
+        let previous = self.current_node.write().unwrap().take();
+        let result = f();
+        *self.current_node.write().unwrap() = previous;
+        result
     }
 
     /// Start batch mode (accumulate updates)
@@ -492,9 +698,32 @@ impl ReactiveContext {
     }
 
     /// End batch mode and propagate all accumulated changes
+    ///
+    /// In deferred mode the changes stay pending until the next `flush`.
     pub fn end_batch(&self) -> Result<(), ReactiveError> {
         *self.batch_mode.write().unwrap() = false;
 
+        if *self.deferred_mode.read().unwrap() {
+            return Ok(());
+        }
+
+        self.flush()
+    }
+
+    /// Switch between synchronous propagation (the default) and deferred
+    /// scheduling, where signal writes only mark dependents dirty
+    pub fn set_deferred(&self, deferred: bool) {
+        *self.deferred_mode.write().unwrap() = deferred;
+    }
+
+    /// Whether signal writes are deferred until `flush`
+    pub fn is_deferred(&self) -> bool {
+        *self.deferred_mode.read().unwrap()
+    }
+
+    /// Run every pending recomputation and effect once, however many writes
+    /// marked it dirty since the last flush
+    pub fn flush(&self) -> Result<(), ReactiveError> {
         let pending: Vec<NodeId> = {
             let mut pending = self.pending_updates.write().unwrap();
             let result = pending.iter().cloned().collect();
@@ -526,6 +755,89 @@ impl ReactiveContext {
         Ok(())
     }
 
+    /// Remove every node that is no longer reachable from `roots`.
+    ///
+    /// Subscriptions are weak: a signal notifying a computed value does not keep
+    /// that computed alive. A node survives only if it is a root (referenced by a
+    /// live binding), an effect, or a dependency of another surviving node.
+    /// Collected nodes are unsubscribed from their dependencies. Returns the
+    /// number of nodes removed.
+    pub fn collect_unreachable(&self, roots: &HashSet<NodeId>) -> usize {
+        let mut nodes = self.nodes.write().unwrap();
+        let mut name_to_id = self.name_to_id.write().unwrap();
+        let mut dep_graph = self.dependency_graph.write().unwrap();
+
+        // Mark
+        let mut live: HashSet<NodeId> = HashSet::new();
+        let mut stack: Vec<NodeId> = roots.iter().cloned().collect();
+        stack.extend(
+            nodes
+                .values()
+                .filter(|node| matches!(node, ReactiveNode::Effect(_)))
+                .map(|node| node.id()),
+        );
+        while let Some(id) = stack.pop() {
+            if nodes.contains_key(&id) && live.insert(id) {
+                if let Some(deps) = dep_graph.get(&id) {
+                    stack.extend(deps.iter().cloned());
+                }
+            }
+        }
+
+        // Sweep
+        let dead: Vec<NodeId> = nodes
+            .keys()
+            .filter(|id| !live.contains(id))
+            .cloned()
+            .collect();
+        for id in &dead {
+            if let Some(deps) = dep_graph.remove(id) {
+                for dep_id in deps {
+                    match nodes.get(&dep_id) {
+                        Some(ReactiveNode::Signal(signal)) => signal.unsubscribe(*id),
+                        Some(ReactiveNode::Computed(computed)) => computed.unsubscribe(*id),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        for id in &dead {
+            if let Some(node) = nodes.remove(id) {
+                if name_to_id.get(node.name()) == Some(id) {
+                    name_to_id.remove(node.name());
+                }
+            }
+        }
+
+        dead.len()
+    }
+
+    /// Number of live reactive nodes
+    pub fn len(&self) -> usize {
+        self.nodes.read().unwrap().len()
+    }
+
+    /// Whether the context holds no reactive nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.read().unwrap().is_empty()
+    }
+
+    /// Check whether a node name is currently registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.name_to_id.read().unwrap().contains_key(name)
+    }
+
+    /// Check whether `name` is registered as a computed value
+    pub fn is_computed(&self, name: &str) -> bool {
+        let Some(id) = self.name_to_id.read().unwrap().get(name).copied() else {
+            return false;
+        };
+        matches!(
+            self.nodes.read().unwrap().get(&id),
+            Some(ReactiveNode::Computed(_))
+        )
+    }
+
     /// Get statistics about the reactive system
     pub fn stats(&self) -> ReactiveStats {
         let nodes = self.nodes.read().unwrap();
@@ -689,6 +1001,204 @@ mod tests {
         assert_eq!(sum, Value::Integer(35));
     }
 
+    #[test]
+    fn test_computed_skips_unchanged_output() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let ctx = ReactiveContext::new();
+        ctx.register_signal("n".to_string(), Value::Integer(1))
+            .unwrap();
+        ctx.register_computed("parity".to_string(), vec!["n".to_string()], |ctx| match ctx
+            .get("n")
+            .unwrap()
+        {
+            Value::Integer(n) => Value::Integer(n % 2),
+            _ => Value::Nil,
+        })
+        .unwrap();
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let runs_in_effect = Arc::clone(&runs);
+        ctx.register_effect(
+            "watch_parity".to_string(),
+            vec!["parity".to_string()],
+            move |_| {
+                runs_in_effect.fetch_add(1, Ordering::SeqCst);
+            },
+        )
+        .unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        ctx.set("n", Value::Integer(2)).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        // 4 is still even: parity is unchanged, so the effect stays quiet
+        ctx.set("n", Value::Integer(4)).unwrap();
+        assert_eq!(ctx.get("parity").unwrap(), Value::Integer(0));
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_diamond_propagation_is_glitch_free() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let int = |ctx: &ReactiveContext, name: &str| match ctx.get(name).unwrap() {
+            Value::Integer(n) => n,
+            _ => 0,
+        };
+
+        // a feeds b and c, both feed d; the b side is two steps longer
+        let ctx = ReactiveContext::new();
+        ctx.register_signal("a".to_string(), Value::Integer(1))
+            .unwrap();
+        ctx.register_computed("b".to_string(), vec!["a".to_string()], move |ctx| {
+            Value::Integer(int(ctx, "a") + 1)
+        })
+        .unwrap();
+        ctx.register_computed("b2".to_string(), vec!["b".to_string()], move |ctx| {
+            Value::Integer(int(ctx, "b") * 10)
+        })
+        .unwrap();
+        ctx.register_computed("b3".to_string(), vec!["b2".to_string()], move |ctx| {
+            Value::Integer(int(ctx, "b2") + 1)
+        })
+        .unwrap();
+        ctx.register_computed("c".to_string(), vec!["a".to_string()], move |ctx| {
+            Value::Integer(int(ctx, "a") * 2)
+        })
+        .unwrap();
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::new(RwLock::new(Vec::new()));
+        let (runs_in_d, seen_in_d) = (Arc::clone(&runs), Arc::clone(&seen));
+        ctx.register_computed(
+            "d".to_string(),
+            vec!["b3".to_string(), "c".to_string()],
+            move |ctx| {
+                runs_in_d.fetch_add(1, Ordering::SeqCst);
+                let sum = int(ctx, "b3") + int(ctx, "c");
+                seen_in_d.write().unwrap().push(sum);
+                Value::Integer(sum)
+            },
+        )
+        .unwrap();
+        assert_eq!(ctx.get("d").unwrap(), Value::Integer(23));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        ctx.set("a", Value::Integer(5)).unwrap();
+
+        // One recompute, and never with a stale b3 or c
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(*seen.read().unwrap(), vec![23, 71]);
+        assert_eq!(ctx.get("d").unwrap(), Value::Integer(71));
+    }
+
+    #[test]
+    fn test_collect_unreachable_computed() {
+        let ctx = ReactiveContext::new();
+        let source = ctx
+            .register_signal("source".to_string(), Value::Integer(1))
+            .unwrap();
+
+        let mut kept = 0;
+        for i in 0..100 {
+            let id = ctx
+                .register_computed(format!("derived{}", i), vec!["source".to_string()], |ctx| {
+                    ctx.get("source").unwrap()
+                })
+                .unwrap();
+            if i == 0 {
+                kept = id;
+            }
+        }
+        assert_eq!(ctx.stats().total_nodes, 101);
+
+        let roots: HashSet<NodeId> = [kept].into_iter().collect();
+        assert_eq!(ctx.collect_unreachable(&roots), 99);
+
+        // The kept computed holds its dependency alive and is still updated
+        let stats = ctx.stats();
+        assert_eq!(stats.total_nodes, 2);
+        assert_eq!(stats.total_dependencies, 1);
+        assert!(ctx.contains("source"));
+        ctx.set("source", Value::Integer(7)).unwrap();
+        assert_eq!(ctx.get("derived0").unwrap(), Value::Integer(7));
+
+        // Collected nodes are no longer subscribed to the signal
+        let nodes = ctx.nodes.read().unwrap();
+        if let Some(ReactiveNode::Signal(signal)) = nodes.get(&source) {
+            assert_eq!(signal.subscribers.read().unwrap().len(), 1);
+        } else {
+            panic!("source signal was collected");
+        }
+    }
+
+    #[test]
+    fn test_peek_adds_no_subscription() {
+        let ctx = ReactiveContext::new();
+        ctx.register_signal("x".to_string(), Value::Integer(1))
+            .unwrap();
+        ctx.register_signal("offset".to_string(), Value::Integer(100))
+            .unwrap();
+
+        ctx.register_computed("shifted".to_string(), vec!["x".to_string()], |ctx| {
+            let x = ctx.get("x").unwrap().as_float().unwrap();
+            let offset = ctx.untracked(|| ctx.peek("offset").unwrap());
+            Value::Float(x + offset.as_float().unwrap())
+        })
+        .unwrap();
+        assert_eq!(ctx.stats().total_dependencies, 1);
+        assert_eq!(ctx.get("shifted").unwrap(), Value::Float(101.0));
+
+        // Writing the peeked signal doesn't rerun the computed...
+        ctx.set("offset", Value::Integer(200)).unwrap();
+        assert_eq!(ctx.get("shifted").unwrap(), Value::Float(101.0));
+        assert_eq!(ctx.stats().total_dependencies, 1);
+
+        let offset_id = ctx.name_to_id.read().unwrap()["offset"];
+        match ctx.nodes.read().unwrap().get(&offset_id) {
+            Some(ReactiveNode::Signal(signal)) => {
+                assert!(signal.subscribers.read().unwrap().is_empty())
+            }
+            _ => panic!("offset should be a signal"),
+        }
+
+        // ...but the next tracked change sees its current value
+        ctx.set("x", Value::Integer(2)).unwrap();
+        assert_eq!(ctx.get("shifted").unwrap(), Value::Float(202.0));
+    }
+
+    #[test]
+    fn test_observable_tracks_fields_separately() {
+        let ctx = ReactiveContext::new();
+        let fields = HashMap::from([
+            ("a".to_string(), Value::Integer(1)),
+            ("b".to_string(), Value::Integer(2)),
+        ]);
+        let obs = ctx.observable(fields).unwrap();
+        let runs = Arc::new(RwLock::new(0));
+
+        let (obs_in_fn, runs_in_fn) = (Arc::clone(&obs), Arc::clone(&runs));
+        ctx.register_computed("a_plus".to_string(), vec![], move |ctx| {
+            *runs_in_fn.write().unwrap() += 1;
+            let a = obs_in_fn.get("a", ctx).unwrap().as_integer().unwrap();
+            Value::Integer(a + 10)
+        })
+        .unwrap();
+        assert_eq!(ctx.get("a_plus").unwrap(), Value::Integer(11));
+
+        obs.set("b", Value::Integer(20), &ctx).unwrap();
+        assert_eq!(*runs.read().unwrap(), 1);
+
+        obs.set("a", Value::Integer(5), &ctx).unwrap();
+        assert_eq!(ctx.get("a_plus").unwrap(), Value::Integer(15));
+        assert_eq!(*runs.read().unwrap(), 2);
+
+        // Reads outside a running node subscribe nothing
+        assert_eq!(obs.get("b", &ctx), Some(Value::Integer(20)));
+        assert_eq!(ctx.stats().total_dependencies, 1);
+    }
+
     #[test]
     fn test_batch_updates() {
         let ctx = ReactiveContext::new();
@@ -706,6 +1216,49 @@ mod tests {
         assert_eq!(ctx.get("b").unwrap(), Value::Integer(20));
     }
 
+    #[test]
+    fn test_deferred_effects_run_once_per_flush() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let ctx = ReactiveContext::new();
+        ctx.register_signal("a".to_string(), Value::Integer(1))
+            .unwrap();
+        ctx.register_signal("b".to_string(), Value::Integer(2))
+            .unwrap();
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::new(RwLock::new(Value::Nil));
+        let (runs_in_effect, seen_in_effect) = (Arc::clone(&runs), Arc::clone(&seen));
+        ctx.register_effect(
+            "log".to_string(),
+            vec!["a".to_string(), "b".to_string()],
+            move |ctx| {
+                runs_in_effect.fetch_add(1, Ordering::SeqCst);
+                *seen_in_effect.write().unwrap() = ctx.get("a").unwrap();
+            },
+        )
+        .unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1); // initial run
+
+        ctx.set_deferred(true);
+        ctx.set("a", Value::Integer(10)).unwrap();
+        ctx.set("b", Value::Integer(20)).unwrap();
+        ctx.set("a", Value::Integer(30)).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        ctx.flush().unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(*seen.read().unwrap(), Value::Integer(30));
+
+        // Nothing pending: flushing again is a no-op
+        ctx.flush().unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        ctx.set_deferred(false);
+        ctx.set("b", Value::Integer(0)).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
     #[test]
     fn test_stats() {
         let ctx = ReactiveContext::new();
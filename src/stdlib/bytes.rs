@@ -477,7 +477,16 @@ impl BinaryEncoder {
 
         let mut result = Vec::new();
 
-        for chunk in chars.chunks(4) {
+        let last = chars.len() / 4;
+        for (index, chunk) in chars.chunks(4).enumerate() {
+            // '=' may only pad the end: "xx==" or "xxx=" in the final group
+            let misplaced_padding = chunk[..2].contains(&'=')
+                || (chunk[2] == '=' && chunk[3] != '=')
+                || (chunk[3] == '=' && index + 1 != last);
+            if misplaced_padding {
+                return Err("Invalid base64 padding".to_string());
+            }
+
             let b1 = decode_char(chunk[0])?;
             let b2 = decode_char(chunk[1])?;
             let b3 = decode_char(chunk[2])?;
@@ -723,6 +732,11 @@ mod tests {
         let encoded = BinaryEncoder::to_base64(data);
         let decoded = BinaryEncoder::from_base64(&encoded).unwrap();
         assert_eq!(decoded, data);
+
+        assert!(BinaryEncoder::from_base64("=AAA").is_err());
+        assert!(BinaryEncoder::from_base64("AA=A").is_err());
+        assert!(BinaryEncoder::from_base64("AA==AAAA").is_err());
+        assert!(BinaryEncoder::from_base64("AAA").is_err());
     }
 
     #[test]
@@ -348,6 +348,10 @@ pub fn value_to_c_string(value: &Value) -> Result<CString, String> {
         Value::String(s) => {
             CString::new(s.as_str()).map_err(|e| format!("Invalid C string: {}", e))
         }
+        // Passed through untouched, so buffers need not be UTF-8
+        Value::Bytes(bytes) => {
+            CString::new(bytes.as_slice()).map_err(|e| format!("Invalid C string: {}", e))
+        }
         _ => Err("Expected string value".to_string()),
     }
 }
@@ -418,5 +422,9 @@ mod tests {
         let str_val = Value::String("hello".to_string());
         let c_str = value_to_c_string(&str_val).unwrap();
         assert_eq!(c_str.to_str().unwrap(), "hello");
+
+        let raw = value_to_c_string(&Value::Bytes(vec![0xff, 0x01])).unwrap();
+        assert_eq!(raw.as_bytes(), &[0xff, 0x01]);
+        assert!(value_to_c_string(&Value::Bytes(vec![b'a', 0])).is_err());
     }
 }
@@ -69,7 +69,7 @@ fn register_network_functions() -> Vec<(String, Value)> {
         // HTTP GET
         (
             "httpGet".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("httpGet expects 1 argument (url)".to_string());
                 }
@@ -84,12 +84,12 @@ fn register_network_functions() -> Vec<(String, Value)> {
                     }
                     _ => Err("httpGet expects a string URL".to_string()),
                 }
-            })),
+            }),
         ),
         // HTTP POST
         (
             "httpPost".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("httpPost expects 2 arguments (url, body)".to_string());
                 }
@@ -104,12 +104,12 @@ fn register_network_functions() -> Vec<(String, Value)> {
                     }
                     _ => Err("httpPost expects (string, string)".to_string()),
                 }
-            })),
+            }),
         ),
         // Parse URL
         (
             "parseUrl".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("parseUrl expects 1 argument (url)".to_string());
                 }
@@ -127,12 +127,12 @@ fn register_network_functions() -> Vec<(String, Value)> {
                     },
                     _ => Err("parseUrl expects a string URL".to_string()),
                 }
-            })),
+            }),
         ),
         // Get local IP
         (
             "getLocalIp".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if !args.is_empty() {
                     return Err("getLocalIp expects no arguments".to_string());
                 }
@@ -141,12 +141,12 @@ fn register_network_functions() -> Vec<(String, Value)> {
                     Ok(ip) => Ok(Value::String(ip)),
                     Err(e) => Err(format!("Failed to get local IP: {}", e)),
                 }
-            })),
+            }),
         ),
         // Check if port is available
         (
             "isPortAvailable".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("isPortAvailable expects 1 argument (port)".to_string());
                 }
@@ -160,7 +160,7 @@ fn register_network_functions() -> Vec<(String, Value)> {
                     }
                     None => Err("isPortAvailable expects an integer port".to_string()),
                 }
-            })),
+            }),
         ),
     ]
 }
@@ -171,7 +171,7 @@ fn register_system_functions() -> Vec<(String, Value)> {
         // Execute command
         (
             "exec".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("exec expects 1 argument (command)".to_string());
                 }
@@ -183,12 +183,12 @@ fn register_system_functions() -> Vec<(String, Value)> {
                     },
                     _ => Err("exec expects a string command".to_string()),
                 }
-            })),
+            }),
         ),
         // Get environment variable
         (
             "getEnv".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("getEnv expects 1 argument (key)".to_string());
                 }
@@ -199,12 +199,12 @@ fn register_system_functions() -> Vec<(String, Value)> {
                         .unwrap_or(Value::Nil)),
                     _ => Err("getEnv expects a string key".to_string()),
                 }
-            })),
+            }),
         ),
         // Set environment variable
         (
             "setEnv".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("setEnv expects 2 arguments (key, value)".to_string());
                 }
@@ -216,12 +216,12 @@ fn register_system_functions() -> Vec<(String, Value)> {
                     }
                     _ => Err("setEnv expects (string, string)".to_string()),
                 }
-            })),
+            }),
         ),
         // Get system info
         (
             "getSystemInfo".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if !args.is_empty() {
                     return Err("getSystemInfo expects no arguments".to_string());
                 }
@@ -232,12 +232,12 @@ fn register_system_functions() -> Vec<(String, Value)> {
                     obj.insert(k, Value::String(v));
                 }
                 Ok(Value::Object(obj))
-            })),
+            }),
         ),
         // Get current working directory
         (
             "getCwd".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if !args.is_empty() {
                     return Err("getCwd expects no arguments".to_string());
                 }
@@ -246,12 +246,12 @@ fn register_system_functions() -> Vec<(String, Value)> {
                     Ok(cwd) => Ok(Value::String(cwd)),
                     Err(e) => Err(format!("Failed to get CWD: {}", e)),
                 }
-            })),
+            }),
         ),
         // Change working directory
         (
             "setCwd".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("setCwd expects 1 argument (path)".to_string());
                 }
@@ -263,23 +263,23 @@ fn register_system_functions() -> Vec<(String, Value)> {
                     },
                     _ => Err("setCwd expects a string path".to_string()),
                 }
-            })),
+            }),
         ),
         // Get timestamp
         (
             "timestamp".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if !args.is_empty() {
                     return Err("timestamp expects no arguments".to_string());
                 }
 
                 Ok(Value::Integer(SystemUtils::get_timestamp() as i64))
-            })),
+            }),
         ),
         // Sleep
         (
             "sleep".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("sleep expects 1 argument (milliseconds)".to_string());
                 }
@@ -294,12 +294,12 @@ fn register_system_functions() -> Vec<(String, Value)> {
                     }
                     None => Err("sleep expects an integer duration".to_string()),
                 }
-            })),
+            }),
         ),
         // Path utilities
         (
             "pathJoin".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 let components: Vec<String> = args
                     .iter()
                     .map(|v| match v {
@@ -309,11 +309,11 @@ fn register_system_functions() -> Vec<(String, Value)> {
                     .collect::<Result<Vec<_>, String>>()?;
 
                 Ok(Value::String(PathUtils::join(&components)))
-            })),
+            }),
         ),
         (
             "pathBasename".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("pathBasename expects 1 argument".to_string());
                 }
@@ -324,7 +324,7 @@ fn register_system_functions() -> Vec<(String, Value)> {
                         .unwrap_or(Value::Nil)),
                     _ => Err("pathBasename expects a string path".to_string()),
                 }
-            })),
+            }),
         ),
     ]
 }
@@ -335,12 +335,13 @@ fn register_bytes_functions() -> Vec<(String, Value)> {
         // Hex encoding
         (
             "toHex".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("toHex expects 1 argument (bytes array)".to_string());
                 }
 
                 match &args[0] {
+                    Value::Bytes(bytes) => Ok(Value::String(BinaryEncoder::to_hex(bytes))),
                     Value::Array(arr) => {
                         let bytes: Result<Vec<u8>, String> = arr
                             .iter()
@@ -364,39 +365,36 @@ fn register_bytes_functions() -> Vec<(String, Value)> {
                     }
                     _ => Err("toHex expects an array of bytes".to_string()),
                 }
-            })),
+            }),
         ),
         // Hex decoding
         (
             "fromHex".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("fromHex expects 1 argument (hex string)".to_string());
                 }
 
                 match &args[0] {
                     Value::String(hex) => match BinaryEncoder::from_hex(hex) {
-                        Ok(bytes) => {
-                            let array: Vec<Value> =
-                                bytes.iter().map(|&b| Value::Integer(b as i64)).collect();
-                            Ok(Value::Array(array))
-                        }
+                        Ok(bytes) => Ok(Value::Bytes(bytes)),
                         Err(e) => Err(format!("Hex decode failed: {}", e)),
                     },
                     _ => Err("fromHex expects a string".to_string()),
                 }
-            })),
+            }),
         ),
         // Base64 encoding
         (
             "toBase64".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("toBase64 expects 1 argument".to_string());
                 }
 
                 match &args[0] {
                     Value::String(s) => Ok(Value::String(BinaryEncoder::to_base64(s.as_bytes()))),
+                    Value::Bytes(bytes) => Ok(Value::String(BinaryEncoder::to_base64(bytes))),
                     Value::Array(arr) => {
                         let bytes: Result<Vec<u8>, String> = arr
                             .iter()
@@ -420,33 +418,29 @@ fn register_bytes_functions() -> Vec<(String, Value)> {
                     }
                     _ => Err("toBase64 expects a string or byte array".to_string()),
                 }
-            })),
+            }),
         ),
         // Base64 decoding
         (
             "fromBase64".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("fromBase64 expects 1 argument (base64 string)".to_string());
                 }
 
                 match &args[0] {
                     Value::String(b64) => match BinaryEncoder::from_base64(b64) {
-                        Ok(bytes) => {
-                            let array: Vec<Value> =
-                                bytes.iter().map(|&b| Value::Integer(b as i64)).collect();
-                            Ok(Value::Array(array))
-                        }
+                        Ok(bytes) => Ok(Value::Bytes(bytes)),
                         Err(e) => Err(format!("Base64 decode failed: {}", e)),
                     },
                     _ => Err("fromBase64 expects a string".to_string()),
                 }
-            })),
+            }),
         ),
         // Bit operations
         (
             "bitGet".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("bitGet expects 2 arguments (value, bit)".to_string());
                 }
@@ -460,11 +454,11 @@ fn register_bytes_functions() -> Vec<(String, Value)> {
                     }
                     _ => Err("bitGet expects (integer, integer)".to_string()),
                 }
-            })),
+            }),
         ),
         (
             "bitSet".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("bitSet expects 2 arguments (value, bit)".to_string());
                 }
@@ -480,7 +474,7 @@ fn register_bytes_functions() -> Vec<(String, Value)> {
                     }
                     _ => Err("bitSet expects (integer, integer)".to_string()),
                 }
-            })),
+            }),
         ),
     ]
 }
@@ -491,36 +485,36 @@ fn register_hardware_functions() -> Vec<(String, Value)> {
         // GPIO functions
         (
             "gpioInit".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("gpioInit expects 2 arguments (pin, mode)".to_string());
                 }
 
                 // This is a placeholder - in real implementation, we'd need access to HardwareManager
                 Ok(Value::Nil)
-            })),
+            }),
         ),
         (
             "gpioWrite".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("gpioWrite expects 2 arguments (pin, state)".to_string());
                 }
 
                 // Placeholder
                 Ok(Value::Nil)
-            })),
+            }),
         ),
         (
             "gpioRead".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 1 {
                     return Err("gpioRead expects 1 argument (pin)".to_string());
                 }
 
                 // Placeholder - returns LOW
                 Ok(Value::Integer(0))
-            })),
+            }),
         ),
     ]
 }
@@ -531,7 +525,7 @@ fn register_ffi_functions() -> Vec<(String, Value)> {
         // Load library
         (
             "ffiLoadLibrary".to_string(),
-            Value::Native(Arc::new(|args| {
+            Value::native(|args| {
                 if args.len() != 2 {
                     return Err("ffiLoadLibrary expects 2 arguments (name, path)".to_string());
                 }
@@ -543,7 +537,7 @@ fn register_ffi_functions() -> Vec<(String, Value)> {
                     }
                     _ => Err("ffiLoadLibrary expects (string, string)".to_string()),
                 }
-            })),
+            }),
         ),
     ]
 }
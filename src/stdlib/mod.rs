@@ -9,7 +9,9 @@ pub mod hardware;
 pub mod http_server;
 pub mod integration;
 pub mod network;
+pub mod random;
 pub mod system;
+pub mod toml;
 pub mod websocket;
 
 // Re-export commonly used types for convenience
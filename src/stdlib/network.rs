@@ -328,125 +328,50 @@ impl HttpClient {
         self.execute(request)
     }
 
-    /// Execute HTTP request (simplified implementation)
+    /// Execute an HTTP or HTTPS request, following up to 10 redirects. A URL
+    /// without a scheme is taken to be `http://`. Any status is returned as a
+    /// response; only transport failures are errors.
     pub fn execute(&self, request: HttpRequest) -> Result<HttpResponse, String> {
-        // Parse URL to extract host, port, and path
-        let url = &request.url;
-
-        // Simple URL parsing (not production-ready, just for demo)
-        let (protocol, rest) = if url.starts_with("http://") {
-            ("http", &url[7..])
-        } else if url.starts_with("https://") {
-            return Err("HTTPS not yet supported in basic implementation".to_string());
+        let url = if request.url.contains("://") {
+            request.url
         } else {
-            ("http", url.as_str())
-        };
-
-        let (host_port, path) = match rest.find('/') {
-            Some(idx) => (&rest[..idx], &rest[idx..]),
-            None => (rest, "/"),
-        };
-
-        let (host, port) = match host_port.find(':') {
-            Some(idx) => (
-                &host_port[..idx],
-                host_port[idx + 1..].parse::<u16>().unwrap_or(80),
-            ),
-            None => (host_port, 80),
+            format!("http://{}", request.url)
         };
-
-        // Build HTTP request string
-        let mut http_request = format!(
-            "{} {} HTTP/1.1\r\nHost: {}\r\n",
-            request.method.as_str(),
-            path,
-            host
-        );
-
-        // Add headers
+        let method = reqwest::Method::from_bytes(request.method.as_str().as_bytes())
+            .map_err(|e| format!("Invalid HTTP method: {}", e))?;
+
+        let client = reqwest::blocking::Client::builder()
+            .redirect(reqwest::redirect::Policy::limited(10))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let mut builder = client
+            .request(method, &url)
+            .timeout(request.timeout.unwrap_or(self.default_timeout));
         for (key, value) in &request.headers {
-            http_request.push_str(&format!("{}: {}\r\n", key, value));
-        }
-
-        // Add body if present
-        if let Some(ref body) = request.body {
-            http_request.push_str(&format!("Content-Length: {}\r\n", body.len()));
-            http_request.push_str("\r\n");
-            http_request.push_str(&String::from_utf8_lossy(body));
-        } else {
-            http_request.push_str("\r\n");
+            builder = builder.header(key.as_str(), value.as_str());
         }
-
-        // Connect and send request
-        let addr = format!("{}:{}", host, port);
-        let mut stream = TcpStream::connect(&addr)
-            .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
-
-        if let Some(timeout) = request.timeout {
-            stream
-                .set_read_timeout(Some(timeout))
-                .map_err(|e| format!("Failed to set timeout: {}", e))?;
+        if let Some(body) = request.body {
+            builder = builder.body(body);
         }
 
-        stream
-            .write_all(http_request.as_bytes())
-            .map_err(|e| format!("Failed to send request: {}", e))?;
-
-        // Read response
-        let mut response_data = Vec::new();
-        stream
-            .read_to_end(&mut response_data)
-            .map_err(|e| format!("Failed to read response: {}", e))?;
-
-        // Parse response (simplified)
-        self.parse_response(&response_data)
-    }
-
-    fn parse_response(&self, data: &[u8]) -> Result<HttpResponse, String> {
-        let response_str = String::from_utf8_lossy(data);
-        let mut lines = response_str.lines();
-
-        // Parse status line
-        let status_line = lines.next().ok_or("Empty response")?;
-        let parts: Vec<&str> = status_line.split_whitespace().collect();
-
-        if parts.len() < 3 {
-            return Err("Invalid status line".to_string());
-        }
-
-        let status_code = parts[1]
-            .parse::<u16>()
-            .map_err(|e| format!("Invalid status code: {}", e))?;
-        let status_text = parts[2..].join(" ");
-
-        // Parse headers
-        let mut headers = HashMap::new();
-        let mut body_start = 0;
-
-        for (i, line) in lines.enumerate() {
-            if line.is_empty() {
-                body_start = i + 2; // +2 to skip status line and this empty line
-                break;
-            }
-
-            if let Some(colon_pos) = line.find(':') {
-                let key = line[..colon_pos].trim().to_string();
-                let value = line[colon_pos + 1..].trim().to_string();
-                headers.insert(key, value);
-            }
-        }
-
-        // Extract body
-        let response_lines: Vec<&str> = response_str.lines().collect();
-        let body = if body_start < response_lines.len() {
-            response_lines[body_start..].join("\n").into_bytes()
-        } else {
-            Vec::new()
-        };
+        let response = builder.send().map_err(|e| e.to_string())?;
+        let status = response.status();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(key, value)| {
+                let value = value.to_str().ok()?;
+                Some((key.as_str().to_string(), value.to_string()))
+            })
+            .collect();
+        let body = response
+            .bytes()
+            .map_err(|e| format!("Failed to read response: {}", e))?
+            .to_vec();
 
         Ok(HttpResponse {
-            status_code,
-            status_text,
+            status_code: status.as_u16(),
+            status_text: status.canonical_reason().unwrap_or_default().to_string(),
             headers,
             body,
         })
@@ -556,6 +481,59 @@ mod tests {
         assert_eq!(request.headers.get("User-Agent").unwrap(), "A-lang/1.0");
     }
 
+    #[test]
+    fn test_execute_follows_redirects_and_decodes_chunks() {
+        use std::io::{Read, Write};
+
+        // /old redirects to /gone, which answers 404 with a chunked body
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let reply: &[u8] = if request.starts_with(b"GET /old ") {
+                    b"HTTP/1.1 302 Found\r\nLocation: /gone\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                } else {
+                    b"HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n4\r\nnot \r\n5;x=1\r\nfound\r\n0\r\n\r\n"
+                };
+                stream.write_all(reply).unwrap();
+            }
+        });
+
+        let response = HttpClient::new().get(&format!("{}/old", addr)).unwrap();
+        server.join().unwrap();
+        assert_eq!(response.status_code, 404);
+        assert_eq!(response.status_text, "Not Found");
+        assert_eq!(response.headers.get("content-type").unwrap(), "text/plain");
+        assert_eq!(response.body_string().unwrap(), "not found");
+    }
+
+    #[test]
+    fn test_https_urls_are_attempted() {
+        // Nothing listens on port 9, so this fails to connect rather than
+        // being turned away for its scheme
+        let err = HttpClient::new()
+            .with_timeout(Duration::from_secs(5))
+            .get("https://127.0.0.1:9/")
+            .unwrap_err();
+        assert!(!err.contains("not yet supported"), "{}", err);
+    }
+
+    /// Needs network access: `cargo test --features network-tests`
+    #[cfg(feature = "network-tests")]
+    #[test]
+    fn test_https_get() {
+        let response = HttpClient::new().get("https://example.com/").unwrap();
+        assert_eq!(response.status_code, 200);
+        assert!(response.body_string().unwrap().contains("Example Domain"));
+    }
+
     #[test]
     fn test_url_parsing() {
         let result = NetUtils::parse_url("http://example.com:8080/path/to/resource").unwrap();
@@ -0,0 +1,137 @@
+//! Seedable pseudo-random number generator for A-lang
+//!
+//! xorshift64* with its state derived from the seed by splitmix64, so nearby
+//! seeds still give unrelated sequences. Fast and good enough for shuffling,
+//! sampling and simulations; not for cryptography.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+/// A small seedable generator; the same seed always yields the same sequence
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// A generator with a fixed seed, for reproducible runs
+    pub fn seeded(seed: u64) -> Self {
+        // splitmix64 never maps to the all-zero state xorshift gets stuck in
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Self {
+            state: (z ^ (z >> 31)) | 1,
+        }
+    }
+
+    /// A generator seeded from the process's hash keys and the clock
+    pub fn from_entropy() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        std::time::SystemTime::now().hash(&mut hasher);
+        std::process::id().hash(&mut hasher);
+        Self::seeded(hasher.finish())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform integer in `0..bound`; `bound` must be non-zero
+    pub fn below(&mut self, bound: u64) -> u64 {
+        // Reject the top sliver of the range that would bias the modulo
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let n = self.next_u64();
+            if n < zone {
+                return n % bound;
+            }
+        }
+    }
+
+    /// Uniform float in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill an f64 mantissa exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform integer in `lo..=hi`; `lo` must not be above `hi`
+    pub fn between(&mut self, lo: i64, hi: i64) -> i64 {
+        let span = hi.abs_diff(lo);
+        let offset = match span.checked_add(1) {
+            Some(count) => self.below(count),
+            None => self.next_u64(),
+        };
+        lo.wrapping_add_unsigned(offset)
+    }
+
+    /// Fisher–Yates over the first `count` positions: afterwards
+    /// `items[..count]` is a uniform random selection in random order
+    pub fn partial_shuffle<T>(&mut self, items: &mut [T], count: usize) {
+        let len = items.len();
+        for i in 0..count.min(len) {
+            let j = i + self.below((len - i) as u64) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Put `items` in a uniformly random order
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        let len = items.len();
+        self.partial_shuffle(items, len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Rng::seeded(7);
+        let mut b = Rng::seeded(7);
+        let mut c = Rng::seeded(8);
+        let first: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..5).map(|_| c.next_u64()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_below_stays_in_range() {
+        let mut rng = Rng::seeded(1);
+        let mut seen = [false; 6];
+        for _ in 0..600 {
+            seen[rng.below(6) as usize] = true;
+        }
+        assert!(seen.iter().all(|&hit| hit));
+    }
+
+    #[test]
+    fn test_between_covers_both_ends() {
+        let mut rng = Rng::seeded(5);
+        let mut seen = [false; 5];
+        for _ in 0..500 {
+            let n = rng.between(-2, 2);
+            seen[(n + 2) as usize] = true;
+        }
+        assert!(seen.iter().all(|&hit| hit));
+        assert_eq!(rng.between(9, 9), 9);
+        rng.between(i64::MIN, i64::MAX);
+
+        let f = rng.next_f64();
+        assert!((0.0..1.0).contains(&f));
+    }
+
+    #[test]
+    fn test_shuffle_is_a_permutation() {
+        let mut rng = Rng::seeded(3);
+        let mut items: Vec<u32> = (0..50).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..50).collect::<Vec<_>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+    }
+}
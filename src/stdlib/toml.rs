@@ -0,0 +1,110 @@
+//! TOML reader for A-lang data imports
+//!
+//! Parsing is done by the `toml` crate; this module maps its values onto
+//! A-lang values. Tables become objects and date-times are kept as strings.
+
+use crate::interpreter::value::Value;
+
+/// Parse a TOML document into an object value
+pub fn parse(source: &str) -> Result<Value, toml::de::Error> {
+    source.parse::<toml::Table>().map(table_to_value)
+}
+
+fn table_to_value(table: toml::Table) -> Value {
+    Value::Object(
+        table
+            .into_iter()
+            .map(|(key, value)| (key, to_value(value)))
+            .collect(),
+    )
+}
+
+fn to_value(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(n) => Value::Integer(n),
+        toml::Value::Float(x) => Value::Float(x),
+        toml::Value::Boolean(b) => Value::Boolean(b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(to_value).collect()),
+        toml::Value::Table(table) => table_to_value(table),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get<'a>(value: &'a Value, path: &[&str]) -> &'a Value {
+        path.iter().fold(value, |value, key| match value {
+            Value::Object(map) => &map[*key],
+            _ => panic!("not an object at {}", key),
+        })
+    }
+
+    #[test]
+    fn test_tables_and_scalars() {
+        let doc = parse(
+            r#"
+# top-level keys
+title = "Demo"   # trailing comment
+port = 8_080
+ratio = 0.5
+debug = true
+born = 1979-05-27T07:32:00Z
+
+[server.tls]
+cert = 'C:\certs\a.pem'
+versions = [1, 2,
+  3, # wrapped
+]
+
+[[users]]
+name = "ann"
+
+[[users]]
+name = "bob"
+meta = { admin = true, tags = ["x"] }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(get(&doc, &["title"]), &Value::String("Demo".to_string()));
+        assert_eq!(get(&doc, &["port"]), &Value::Integer(8080));
+        assert_eq!(get(&doc, &["ratio"]), &Value::Float(0.5));
+        assert_eq!(get(&doc, &["debug"]), &Value::Boolean(true));
+        assert_eq!(
+            get(&doc, &["born"]),
+            &Value::String("1979-05-27T07:32:00Z".to_string())
+        );
+        assert_eq!(
+            get(&doc, &["server", "tls", "cert"]),
+            &Value::String("C:\\certs\\a.pem".to_string())
+        );
+        assert_eq!(
+            get(&doc, &["server", "tls", "versions"]),
+            &Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3)
+            ])
+        );
+        match get(&doc, &["users"]) {
+            Value::Array(users) => {
+                assert_eq!(users.len(), 2);
+                assert_eq!(get(&users[1], &["meta", "admin"]), &Value::Boolean(true));
+            }
+            other => panic!("expected array of tables, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_errors_report_position() {
+        let err = parse("a = 1\nb = [1, 2\nc = 3").unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{}", err);
+
+        let err = parse("a = 1\na = 2").unwrap_err();
+        assert!(err.to_string().contains("duplicate key"), "{}", err);
+        assert!(err.to_string().contains("line 2"), "{}", err);
+    }
+}
@@ -16,9 +16,7 @@
 //! ```alang
 //! syntax "unless" {
 //!     pattern: "unless CONDITION then BODY",
-//!     transform: |cond, body| {
-//!         if !cond then body
-//!     }
+//!     transform: (cond, body) => if (!cond) { body }
 //! }
 //!
 //! // Now you can use the new syntax:
@@ -26,9 +24,16 @@
 //!     print("x is not greater than 10");
 //! }
 //! ```
+//!
+//! The parser expands uses of a [`SyntaxRule`] by splicing tokens: each
+//! placeholder is parsed as a parenthesized expression (or, when it ends the
+//! pattern, a statement in braces), and the transform body is re-parsed with
+//! every parameter replaced by its fragment.
 
 use crate::ast::{Expression, Statement};
+use crate::lexer::{tokenize, Token};
 use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::Arc;
 
 /// A syntax extension definition
@@ -87,6 +92,102 @@ impl SyntaxPattern {
     }
 }
 
+/// One word of a syntax rule's pattern
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternPart {
+    /// A word that must appear as this exact token
+    Literal(Token),
+    /// An uppercase word, matched by an expression (a statement when last)
+    Placeholder(String),
+}
+
+/// A `syntax` declaration, expanded by the parser at each use
+#[derive(Debug, Clone)]
+pub struct SyntaxRule {
+    pub name: String,
+    pub parts: Vec<PatternPart>,
+    pub parameters: Vec<String>,
+    pub body: Vec<(Token, Range<usize>)>,
+}
+
+impl SyntaxRule {
+    /// Build a rule from its pattern and a transform with one parameter per
+    /// placeholder. The pattern must start with an identifier, which becomes
+    /// the keyword that triggers the rule.
+    pub fn new(
+        name: String,
+        pattern: &SyntaxPattern,
+        parameters: Vec<String>,
+        body: Vec<(Token, Range<usize>)>,
+    ) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        for word in pattern.pattern_string.split_whitespace() {
+            if pattern.placeholders.iter().any(|p| p == word) {
+                parts.push(PatternPart::Placeholder(word.to_string()));
+                continue;
+            }
+            match tokenize(word).map_err(|e| e.message)?.as_slice() {
+                [(token, _)] => parts.push(PatternPart::Literal(token.clone())),
+                _ => return Err(format!("Invalid word '{}' in syntax pattern", word)),
+            }
+        }
+
+        if !matches!(
+            parts.first(),
+            Some(PatternPart::Literal(Token::Identifier(_)))
+        ) {
+            return Err(format!(
+                "Syntax pattern for '{}' must start with an identifier keyword",
+                name
+            ));
+        }
+        if parameters.len() != pattern.placeholders.len() {
+            return Err(format!(
+                "Syntax transform for '{}' takes {} parameters but the pattern has {} placeholders",
+                name,
+                parameters.len(),
+                pattern.placeholders.len()
+            ));
+        }
+
+        Ok(Self {
+            name,
+            parts,
+            parameters,
+            body,
+        })
+    }
+
+    /// The identifier that starts every use of this rule
+    pub fn keyword(&self) -> &str {
+        match self.parts.first() {
+            Some(PatternPart::Literal(Token::Identifier(keyword))) => keyword,
+            _ => unreachable!("checked in SyntaxRule::new"),
+        }
+    }
+
+    /// Splice the matched fragments (one per placeholder, in order) into the
+    /// transform body in place of the corresponding parameters
+    pub fn expand(&self, fragments: &[Vec<(Token, Range<usize>)>]) -> Vec<(Token, Range<usize>)> {
+        let mut tokens = Vec::new();
+        for (token, range) in &self.body {
+            let fragment = match token {
+                Token::Identifier(name) => self
+                    .parameters
+                    .iter()
+                    .position(|p| p == name)
+                    .and_then(|i| fragments.get(i)),
+                _ => None,
+            };
+            match fragment {
+                Some(fragment) => tokens.extend(fragment.iter().cloned()),
+                None => tokens.push((token.clone(), range.clone())),
+            }
+        }
+        tokens
+    }
+}
+
 /// Registry for syntax extensions
 pub struct SyntaxRegistry {
     extensions: HashMap<String, SyntaxExtension>,
@@ -178,6 +279,49 @@ mod tests {
         assert_eq!(pattern.placeholders[1], "BODY");
     }
 
+    #[test]
+    fn test_syntax_rule_expansion() {
+        let pattern = SyntaxPattern::new("unless COND then BODY".to_string());
+        let body = tokenize("if (!c) { b }").unwrap();
+        let rule = SyntaxRule::new(
+            "unless".to_string(),
+            &pattern,
+            vec!["c".to_string(), "b".to_string()],
+            body,
+        )
+        .unwrap();
+        assert_eq!(rule.keyword(), "unless");
+        assert_eq!(
+            rule.parts[2],
+            PatternPart::Literal(Token::Identifier("then".to_string()))
+        );
+
+        let fragments = [tokenize("(x > 1)").unwrap(), tokenize("{ f() }").unwrap()];
+        let expanded: Vec<Token> = rule
+            .expand(&fragments)
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        let expected: Vec<Token> = tokenize("if (!(x > 1)) { { f() } }")
+            .unwrap()
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        assert_eq!(expanded, expected);
+
+        let err = SyntaxRule::new("bad".to_string(), &pattern, vec![], Vec::new()).unwrap_err();
+        assert!(err.contains("takes 0 parameters"), "{}", err);
+        let leading = SyntaxPattern::new("COND unless".to_string());
+        let err = SyntaxRule::new(
+            "bad".to_string(),
+            &leading,
+            vec!["c".to_string()],
+            Vec::new(),
+        )
+        .unwrap_err();
+        assert!(err.contains("must start with an identifier"), "{}", err);
+    }
+
     #[test]
     fn test_registry() {
         let mut registry = SyntaxRegistry::new();
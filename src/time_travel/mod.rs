@@ -12,6 +12,8 @@
 use crate::interpreter::value::Value;
 use chrono::{DateTime, Utc};
 use im::HashMap as PersistentHashMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::fmt;
 
@@ -19,7 +21,7 @@ use std::fmt;
 const MAX_SNAPSHOTS: usize = 1000;
 
 /// A snapshot of the program state at a specific point in time
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snapshot {
     /// Unique identifier for this snapshot
     pub id: usize,
@@ -31,6 +33,7 @@ pub struct Snapshot {
     pub timestamp: DateTime<Utc>,
 
     /// The program state (variables and their values)
+    #[serde(serialize_with = "serialize_bindings")]
     pub state: PersistentHashMap<String, Value>,
 
     /// Call stack at the time of snapshot
@@ -47,17 +50,31 @@ pub struct Snapshot {
 }
 
 /// Stack frame information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StackFrame {
     pub function_name: String,
+    /// Line of the call that entered this function
     pub line: usize,
+    /// The function's local bindings, including its arguments, when the
+    /// snapshot was taken
+    #[serde(serialize_with = "serialize_bindings")]
     pub locals: PersistentHashMap<String, Value>,
 }
 
+/// Write the bindings serde can read back, leaving out functions, reactive
+/// handles and other values that only mean something in a live run
+fn serialize_bindings<S: Serializer>(
+    bindings: &PersistentHashMap<String, Value>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(bindings.iter().filter(|(_, value)| value.is_serializable()))
+}
+
 /// Metadata associated with a snapshot
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SnapshotMetadata {
-    /// Memory usage at snapshot time (in bytes)
+    /// Approximate bytes of the captured state and call stack, counting
+    /// values the snapshot shares with others in full
     pub memory_usage: usize,
 
     /// Number of operations since last snapshot
@@ -86,6 +103,18 @@ pub struct TimeTravelDebugger {
 
     /// Counter for generating snapshot IDs
     next_id: usize,
+
+    /// Snapshot ID the active replay has reached, if one is running
+    replay: Option<usize>,
+}
+
+/// A debugging session as `export_to_json` writes it
+#[derive(Serialize, Deserialize)]
+struct Session<'a> {
+    snapshots: Cow<'a, VecDeque<Snapshot>>,
+    checkpoints: Cow<'a, std::collections::HashMap<String, usize>>,
+    current_index: usize,
+    next_id: usize,
 }
 
 /// Configuration for time-travel debugging
@@ -125,6 +154,7 @@ impl TimeTravelDebugger {
             enabled: true,
             config,
             next_id: 0,
+            replay: None,
         }
     }
 
@@ -138,6 +168,23 @@ impl TimeTravelDebugger {
         self.enabled = enabled;
     }
 
+    /// Whether an automatic snapshot is due after `operations` statements:
+    /// the count must land on `auto_snapshot_interval` and at least
+    /// `min_snapshot_interval_ms` must have passed since the latest snapshot
+    pub fn auto_snapshot_due(&self, operations: usize) -> bool {
+        let interval = match self.config.auto_snapshot_interval {
+            Some(interval) if interval > 0 => interval,
+            _ => return false,
+        };
+        if !self.enabled || !operations.is_multiple_of(interval) {
+            return false;
+        }
+        self.snapshots.back().is_none_or(|last| {
+            let elapsed = Utc::now().signed_duration_since(last.timestamp);
+            elapsed.num_milliseconds() >= self.config.min_snapshot_interval_ms as i64
+        })
+    }
+
     /// Take a snapshot of the current state
     pub fn snapshot(
         &mut self,
@@ -164,6 +211,15 @@ impl TimeTravelDebugger {
             }
         }
 
+        let memory_usage = Self::state_size(&state)
+            + call_stack
+                .iter()
+                .map(|frame| {
+                    std::mem::size_of::<StackFrame>()
+                        + frame.function_name.capacity()
+                        + Self::state_size(&frame.locals)
+                })
+                .sum::<usize>();
         let snapshot = Snapshot {
             id: self.next_id,
             label: label.clone(),
@@ -173,7 +229,7 @@ impl TimeTravelDebugger {
             line,
             file,
             metadata: SnapshotMetadata {
-                memory_usage: 0, // TODO: Calculate actual memory usage
+                memory_usage,
                 operations_count: 0,
                 tags: Vec::new(),
             },
@@ -260,6 +316,33 @@ impl TimeTravelDebugger {
             .ok_or(TimeTravelError::SnapshotNotFound)
     }
 
+    /// Add tags to the snapshot with `id`; tags it already has are skipped
+    pub fn tag_snapshot(
+        &mut self,
+        id: usize,
+        tags: impl IntoIterator<Item = String>,
+    ) -> Result<(), TimeTravelError> {
+        let snapshot = self
+            .snapshots
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or(TimeTravelError::SnapshotNotFound)?;
+        for tag in tags {
+            if !snapshot.metadata.tags.contains(&tag) {
+                snapshot.metadata.tags.push(tag);
+            }
+        }
+        Ok(())
+    }
+
+    /// Snapshots carrying `tag`, oldest first
+    pub fn snapshots_with_tag(&self, tag: &str) -> Vec<&Snapshot> {
+        self.snapshots
+            .iter()
+            .filter(|s| s.metadata.tags.iter().any(|t| t == tag))
+            .collect()
+    }
+
     /// Get the current snapshot
     pub fn current_snapshot(&self) -> Result<&Snapshot, TimeTravelError> {
         if self.snapshots.is_empty() {
@@ -306,6 +389,7 @@ impl TimeTravelDebugger {
         self.snapshots.clear();
         self.checkpoints.clear();
         self.current_index = 0;
+        self.replay = None;
     }
 
     /// Get statistics about the debugger
@@ -320,22 +404,75 @@ impl TimeTravelDebugger {
 
     /// Estimate memory usage of all snapshots
     fn estimate_memory_usage(&self) -> usize {
-        // Simplified estimation
-        self.snapshots.len() * std::mem::size_of::<Snapshot>()
+        self.snapshots
+            .iter()
+            .map(|snapshot| std::mem::size_of::<Snapshot>() + snapshot.metadata.memory_usage)
+            .sum()
+    }
+
+    /// Bytes held by the bindings in `state`, counted as if no other snapshot
+    /// shared them
+    fn state_size(state: &PersistentHashMap<String, Value>) -> usize {
+        state
+            .iter()
+            .map(|(name, value)| {
+                std::mem::size_of::<String>() + name.capacity() + value.deep_size()
+            })
+            .sum()
     }
 
-    /// Export snapshots to JSON for analysis (placeholder)
+    /// Export the snapshots, checkpoints and current position as JSON, for
+    /// saving a debugging session. Functions and other live-only values are
+    /// left out of the recorded state.
     pub fn export_to_json(&self) -> Result<String, TimeTravelError> {
-        // TODO: Implement proper serialization
-        Ok(format!("{{\"snapshots\": {}}}", self.snapshots.len()))
+        let session = Session {
+            snapshots: Cow::Borrowed(&self.snapshots),
+            checkpoints: Cow::Borrowed(&self.checkpoints),
+            current_index: self.current_index,
+            next_id: self.next_id,
+        };
+        serde_json::to_string(&session)
+            .map_err(|e| TimeTravelError::SerializationError(e.to_string()))
     }
 
-    /// Import snapshots from JSON (placeholder)
-    pub fn import_from_json(&mut self, _json: &str) -> Result<(), TimeTravelError> {
-        // TODO: Implement proper deserialization
-        Err(TimeTravelError::SerializationError(
-            "Not yet implemented".to_string(),
-        ))
+    /// Replace the snapshots, checkpoints and position with a session saved by
+    /// `export_to_json`. The configuration and enabled flag are kept.
+    pub fn import_from_json(&mut self, json: &str) -> Result<(), TimeTravelError> {
+        let session: Session = serde_json::from_str(json)
+            .map_err(|e| TimeTravelError::SerializationError(e.to_string()))?;
+        let snapshots = session.snapshots.into_owned();
+        if session.current_index > snapshots.len() {
+            return Err(TimeTravelError::SerializationError(format!(
+                "current index {} is past the {} saved snapshots",
+                session.current_index,
+                snapshots.len()
+            )));
+        }
+        if let Some((name, index)) = session
+            .checkpoints
+            .iter()
+            .find(|(_, index)| **index >= snapshots.len())
+        {
+            return Err(TimeTravelError::SerializationError(format!(
+                "checkpoint '{}' points past the {} saved snapshots (index {})",
+                name,
+                snapshots.len(),
+                index
+            )));
+        }
+
+        self.checkpoints = session.checkpoints.into_owned();
+        self.current_index = session.current_index;
+        self.next_id = session.next_id.max(
+            snapshots
+                .iter()
+                .map(|snapshot| snapshot.id + 1)
+                .max()
+                .unwrap_or(0),
+        );
+        self.snapshots = snapshots;
+        self.replay = None;
+        Ok(())
     }
 
     /// Replay from a snapshot, executing operations forward
@@ -352,6 +489,40 @@ impl TimeTravelDebugger {
             debugger: self,
         })
     }
+
+    /// Start a replay at the checkpoint `label`; `step_replay` then walks
+    /// forward from it, so the replay survives between REPL commands
+    pub fn start_replay(&mut self, label: &str) -> Result<&Snapshot, TimeTravelError> {
+        let index = *self
+            .checkpoints
+            .get(label)
+            .ok_or_else(|| TimeTravelError::CheckpointNotFound(label.to_string()))?;
+        let snapshot = self
+            .snapshots
+            .get(index)
+            .ok_or(TimeTravelError::SnapshotNotFound)?;
+        self.replay = Some(snapshot.id);
+        Ok(snapshot)
+    }
+
+    /// Advance the active replay by one snapshot, returning it together with
+    /// what changed since the snapshot before it
+    pub fn step_replay(&mut self) -> Result<(&Snapshot, StateDiff), TimeTravelError> {
+        let from_id = self.replay.ok_or(TimeTravelError::NoReplay)?;
+        let mut session = self.replay_from(from_id)?;
+        let previous = session.current_snapshot()?.state.clone();
+        let next = session.step_forward()?;
+        let diff = StateDiff::compute(&previous, &next.state);
+        let next_id = next.id;
+
+        self.replay = Some(next_id);
+        let snapshot = self
+            .snapshots
+            .iter()
+            .find(|s| s.id == next_id)
+            .ok_or(TimeTravelError::SnapshotNotFound)?;
+        Ok((snapshot, diff))
+    }
 }
 
 /// Difference between two states
@@ -401,6 +572,100 @@ impl StateDiff {
     }
 }
 
+/// User-controlled undo/redo history, kept apart from the automatic
+/// snapshot timeline so only explicit restore points are undoable
+#[derive(Debug, Clone, Default)]
+pub struct UndoHistory {
+    /// Restore points in the order they were recorded
+    points: Vec<(String, PersistentHashMap<String, Value>)>,
+
+    /// Index of the point the live state was last restored to or recorded at
+    cursor: usize,
+}
+
+/// The outcome of an undo or redo step
+#[derive(Debug, Clone)]
+pub struct UndoStep {
+    /// Label of the restore point now in effect
+    pub label: String,
+
+    /// Changes that take the live state to the restored one
+    pub diff: StateDiff,
+
+    /// The restored state, for looking up values of `diff.added`
+    pub state: PersistentHashMap<String, Value>,
+}
+
+impl UndoHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `state` as a restore point, discarding anything that could be redone
+    pub fn push(&mut self, label: String, state: PersistentHashMap<String, Value>) {
+        self.points.truncate(self.cursor + 1);
+        self.points.push((label, state));
+        self.cursor = self.points.len() - 1;
+    }
+
+    /// Step back from `live`. Changes made since the current restore point
+    /// are undone first (and kept so `redo` can bring them back); otherwise
+    /// the previous restore point is restored.
+    pub fn undo(
+        &mut self,
+        live: PersistentHashMap<String, Value>,
+    ) -> Result<UndoStep, TimeTravelError> {
+        let (_, current) = self
+            .points
+            .get(self.cursor)
+            .ok_or(TimeTravelError::NothingToUndo)?;
+
+        if !StateDiff::compute(current, &live).is_empty() {
+            self.points.truncate(self.cursor + 1);
+            self.points.push(("(unsaved)".to_string(), live.clone()));
+        } else if self.cursor == 0 {
+            return Err(TimeTravelError::NothingToUndo);
+        } else {
+            self.cursor -= 1;
+        }
+
+        Ok(self.step_to(&live))
+    }
+
+    /// Step forward to the restore point most recently undone
+    pub fn redo(
+        &mut self,
+        live: PersistentHashMap<String, Value>,
+    ) -> Result<UndoStep, TimeTravelError> {
+        if self.cursor + 1 >= self.points.len() {
+            return Err(TimeTravelError::NothingToRedo);
+        }
+        self.cursor += 1;
+        Ok(self.step_to(&live))
+    }
+
+    /// Whether `undo(live)` would succeed: `live` has changes since the
+    /// current restore point, or there is an earlier point to step back to
+    pub fn can_undo(&self, live: &PersistentHashMap<String, Value>) -> bool {
+        self.points.get(self.cursor).is_some_and(|(_, current)| {
+            self.cursor > 0 || !StateDiff::compute(current, live).is_empty()
+        })
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cursor + 1 < self.points.len()
+    }
+
+    fn step_to(&self, live: &PersistentHashMap<String, Value>) -> UndoStep {
+        let (label, state) = &self.points[self.cursor];
+        UndoStep {
+            label: label.clone(),
+            diff: StateDiff::compute(live, state),
+            state: state.clone(),
+        }
+    }
+}
+
 /// Replay session for stepping through historical execution
 pub struct ReplaySession<'a> {
     start_index: usize,
@@ -452,6 +717,9 @@ pub enum TimeTravelError {
     CheckpointNotFound(String),
     SerializationError(String),
     ReplayComplete,
+    NoReplay,
+    NothingToUndo,
+    NothingToRedo,
 }
 
 impl fmt::Display for TimeTravelError {
@@ -477,6 +745,14 @@ impl fmt::Display for TimeTravelError {
                 write!(f, "Serialization error: {}", msg)
             }
             TimeTravelError::ReplayComplete => write!(f, "Replay session is complete"),
+            TimeTravelError::NoReplay => {
+                write!(
+                    f,
+                    "No replay in progress; start one with replay from \"label\""
+                )
+            }
+            TimeTravelError::NothingToUndo => write!(f, "Nothing to undo"),
+            TimeTravelError::NothingToRedo => write!(f, "Nothing to redo"),
         }
     }
 }
@@ -486,6 +762,7 @@ impl std::error::Error for TimeTravelError {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
 
     #[test]
     fn test_snapshot_creation() {
@@ -505,6 +782,110 @@ mod tests {
         assert_eq!(debugger.snapshots.len(), 1);
     }
 
+    #[test]
+    fn test_snapshot_memory_usage_reflects_state() {
+        let mut debugger = TimeTravelDebugger::default();
+        debugger
+            .snapshot(
+                PersistentHashMap::new(),
+                vec![],
+                1,
+                "test.al".to_string(),
+                None,
+            )
+            .unwrap();
+        let after_empty = debugger.stats().memory_usage;
+
+        let mut state = PersistentHashMap::new();
+        state.insert(
+            "big".to_string(),
+            Value::Array((0..10_000).map(Value::Integer).collect()),
+        );
+        debugger
+            .snapshot(state, vec![], 2, "test.al".to_string(), None)
+            .unwrap();
+
+        let snapshots = debugger.all_snapshots();
+        let (empty, big) = (
+            snapshots[0].metadata.memory_usage,
+            snapshots[1].metadata.memory_usage,
+        );
+        assert!(big > empty + 10_000 * std::mem::size_of::<Value>());
+        assert_eq!(
+            debugger.stats().memory_usage,
+            after_empty + std::mem::size_of::<Snapshot>() + big
+        );
+    }
+
+    #[test]
+    fn test_json_export_round_trip() {
+        let mut debugger = TimeTravelDebugger::default();
+        for (i, label) in [None, Some("start"), None, Some("end")]
+            .into_iter()
+            .enumerate()
+        {
+            let mut state = PersistentHashMap::new();
+            state.insert("i".to_string(), Value::Integer(i as i64));
+            state.insert(
+                "tags".to_string(),
+                Value::Array(vec![Value::String("a".to_string()), Value::Float(0.5)]),
+            );
+            // Live-only values are left out of the export
+            state.insert("print".to_string(), Value::native(|_| Ok(Value::Nil)));
+            let call_stack = vec![StackFrame {
+                function_name: "step".to_string(),
+                line: 3,
+                locals: PersistentHashMap::unit("n".to_string(), Value::Integer(1)),
+            }];
+            debugger
+                .snapshot(
+                    state,
+                    call_stack,
+                    i + 1,
+                    "test.al".to_string(),
+                    label.map(String::from),
+                )
+                .unwrap();
+        }
+        debugger.rewind(2).unwrap();
+
+        let json = debugger.export_to_json().unwrap();
+        let mut restored = TimeTravelDebugger::default();
+        restored.import_from_json(&json).unwrap();
+
+        assert_eq!(restored.stats().total_snapshots, 4);
+        assert_eq!(restored.stats().checkpoints_count, 2);
+        assert_eq!(restored.current_index, 2);
+        assert_eq!(restored.jump_to_checkpoint("start").unwrap().line, 2);
+        let end = restored.jump_to_checkpoint("end").unwrap();
+        assert_eq!(end.state.get("i"), Some(&Value::Integer(3)));
+        assert_eq!(
+            end.state.get("tags"),
+            Some(&Value::Array(vec![
+                Value::String("a".to_string()),
+                Value::Float(0.5)
+            ]))
+        );
+        assert!(end.state.get("print").is_none());
+        assert_eq!(end.call_stack[0].function_name, "step");
+        assert_eq!(end.call_stack[0].locals.get("n"), Some(&Value::Integer(1)));
+        assert_eq!(end.timestamp, debugger.all_snapshots()[3].timestamp);
+
+        // New snapshots continue the id sequence
+        let id = restored
+            .snapshot(
+                PersistentHashMap::new(),
+                vec![],
+                9,
+                "test.al".to_string(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(id, debugger.all_snapshots()[3].id + 1);
+
+        assert!(restored.import_from_json("{\"snapshots\": 4}").is_err());
+    }
+
     #[test]
     fn test_rewind() {
         let mut debugger = TimeTravelDebugger::default();
@@ -556,4 +937,46 @@ mod tests {
         assert_eq!(diff.added.len(), 1);
         assert_eq!(diff.modified.len(), 1);
     }
+
+    #[test]
+    fn test_undo_history_round_trip() {
+        let state = |x: i64| {
+            let mut state = PersistentHashMap::new();
+            state.insert("x".to_string(), Value::Integer(x));
+            state
+        };
+        let mut history = UndoHistory::new();
+        history.push("a".to_string(), state(1));
+        history.push("b".to_string(), state(2));
+
+        // Unsaved changes are undone first, back to "b"
+        let step = history.undo(state(3)).unwrap();
+        assert_eq!(step.label, "b");
+        assert_eq!(
+            step.diff.modified,
+            vec![("x".to_string(), Value::Integer(3), Value::Integer(2))]
+        );
+
+        assert_eq!(history.undo(state(2)).unwrap().label, "a");
+        assert!(!history.can_undo(&state(1)));
+        assert!(matches!(
+            history.undo(state(1)),
+            Err(TimeTravelError::NothingToUndo)
+        ));
+        // Changes made since the first point can still be undone
+        assert!(history.can_undo(&state(9)));
+
+        assert_eq!(history.redo(state(1)).unwrap().label, "b");
+        let step = history.redo(state(2)).unwrap();
+        assert_eq!(step.state.get("x"), Some(&Value::Integer(3)));
+        assert!(!history.can_redo());
+
+        // Recording a new point drops the redo branch
+        history.undo(state(3)).unwrap();
+        history.push("c".to_string(), state(5));
+        assert!(matches!(
+            history.redo(state(5)),
+            Err(TimeTravelError::NothingToRedo)
+        ));
+    }
 }
@@ -6,8 +6,9 @@
 //! - Context-dependent type behavior
 //! - Type refinement based on control flow
 
+use crate::interpreter::value::Value;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 /// Type representation
@@ -89,6 +90,7 @@ pub enum ConstraintKind {
 }
 
 /// Type inference context
+#[derive(Clone)]
 pub struct TypeContext {
     /// Variable types
     bindings: HashMap<String, Type>,
@@ -119,6 +121,15 @@ impl TypeContext {
         self.bindings.get(name)
     }
 
+    /// Build a context whose bindings are the types of existing runtime values
+    pub fn from_values<'a>(values: impl IntoIterator<Item = (&'a String, &'a Value)>) -> Self {
+        let mut context = Self::new();
+        for (name, value) in values {
+            context.bind(name.clone(), Type::of_value(value));
+        }
+        context
+    }
+
     /// Create a type alias
     pub fn alias(&mut self, name: String, ty: Type) {
         self.aliases.insert(name, ty);
@@ -147,6 +158,53 @@ impl Default for TypeContext {
 }
 
 impl Type {
+    /// Map a runtime value to its type, recursing into containers.
+    ///
+    /// Arrays take the unified type of their elements (`array<any>` when they
+    /// disagree, `array<unknown>` when empty). Functions get `unknown`
+    /// parameters since runtime values carry no annotations.
+    pub fn of_value(value: &Value) -> Type {
+        match value {
+            Value::Nil => Type::Nil,
+            Value::Boolean(_) => Type::Boolean,
+            Value::Integer(_) => Type::Integer,
+            Value::Float(_) => Type::Float,
+            Value::String(_) => Type::String,
+            Value::Array(items) => {
+                let element = items
+                    .iter()
+                    .map(Type::of_value)
+                    .try_fold(Type::Unknown, |acc, ty| acc.unify(&ty))
+                    .unwrap_or(Type::Any);
+                Type::Array(Box::new(element))
+            }
+            Value::Object(fields) => Type::Object(
+                fields
+                    .iter()
+                    .map(|(key, v)| (key.clone(), Type::of_value(v)))
+                    .collect(),
+            ),
+            Value::Observable(obs) => Type::of_value(&Value::Object(obs.snapshot())),
+            Value::Tuple(items) => Type::Tuple(items.iter().map(Type::of_value).collect()),
+            Value::Function(func) => Type::Function {
+                parameters: vec![Type::Unknown; func.parameters.len()],
+                return_type: Box::new(Type::Unknown),
+            },
+            Value::Native(_) => Type::Function {
+                parameters: Vec::new(),
+                return_type: Box::new(Type::Unknown),
+            },
+            Value::Struct { name, .. } => Type::Named(name.clone()),
+            Value::Enum { type_name, .. } => Type::Named(type_name.clone()),
+            Value::ReactiveRef(_) => Type::Reactive(Box::new(Type::Unknown)),
+            Value::Range { .. }
+            | Value::Bytes(_)
+            | Value::Quote(_)
+            | Value::Future(_)
+            | Value::Ref(_) => Type::Named(value.type_name().to_string()),
+        }
+    }
+
     /// Check if this type is compatible with another type
     pub fn is_compatible_with(&self, other: &Type) -> bool {
         match (self, other) {
@@ -173,6 +231,23 @@ impl Type {
         matches!(self, Type::String)
     }
 
+    /// Whether this type is fully known, with no gradual part a checker
+    /// would have to guess at
+    pub fn is_concrete(&self) -> bool {
+        matches!(
+            self,
+            Type::Nil
+                | Type::Boolean
+                | Type::Integer
+                | Type::Float
+                | Type::String
+                | Type::Array(_)
+                | Type::Tuple(_)
+                | Type::Object(_)
+                | Type::Function { .. }
+        )
+    }
+
     /// Check if this type is callable
     pub fn is_callable(&self) -> bool {
         matches!(self, Type::Function { .. })
@@ -194,7 +269,15 @@ impl Type {
                 let names: Vec<_> = types.iter().map(|t| t.name()).collect();
                 format!("({})", names.join(", "))
             }
-            Type::Object(_) => "object".to_string(),
+            Type::Object(fields) if fields.is_empty() => "object".to_string(),
+            Type::Object(fields) => {
+                let mut entries: Vec<_> = fields
+                    .iter()
+                    .map(|(key, ty)| format!("{}: {}", key, ty.name()))
+                    .collect();
+                entries.sort();
+                format!("object{{{}}}", entries.join(", "))
+            }
             Type::Function { .. } => "function".to_string(),
             Type::Union(types) => {
                 let names: Vec<_> = types.iter().map(|t| t.name()).collect();
@@ -263,6 +346,9 @@ impl TypeInference {
                 ..
             } => self.infer_binary(left, operator, right),
             Expression::Array { elements, .. } => self.infer_array(elements),
+            Expression::Lambda {
+                parameters, body, ..
+            } => self.infer_function(parameters, body),
             _ => Ok(Type::Unknown),
         }
     }
@@ -287,32 +373,68 @@ impl TypeInference {
     ) -> Result<Type, TypeError> {
         let left_type = self.infer(left)?;
         let right_type = self.infer(right)?;
+        Self::binary_result(operator, &left_type, &right_type)
+    }
 
+    /// The type `left operator right` produces. Gradual: an operand whose
+    /// type isn't fully known passes, and only combinations the runtime
+    /// always rejects are errors.
+    fn binary_result(
+        operator: &crate::ast::BinaryOp,
+        left: &Type,
+        right: &Type,
+    ) -> Result<Type, TypeError> {
         use crate::ast::BinaryOp;
 
+        let invalid = || TypeError::InvalidOperation {
+            operation: operator.to_string(),
+            types: vec![left.clone(), right.clone()],
+        };
+        let numeric = || left.unify(right).unwrap_or(Type::Float);
+
         match operator {
-            BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide => {
-                if left_type.is_numeric() && right_type.is_numeric() {
-                    Ok(left_type.unify(&right_type).unwrap_or(Type::Float))
-                } else if matches!(operator, BinaryOp::Add)
-                    && left_type.is_string()
-                    && right_type.is_string()
-                {
-                    Ok(Type::String)
-                } else {
-                    Err(TypeError::TypeMismatch {
-                        expected: "numeric or string".to_string(),
-                        found: format!("{} and {}", left_type.name(), right_type.name()),
-                    })
-                }
-            }
             BinaryOp::Equal
             | BinaryOp::NotEqual
             | BinaryOp::Less
             | BinaryOp::LessEqual
             | BinaryOp::Greater
-            | BinaryOp::GreaterEqual => Ok(Type::Boolean),
-            BinaryOp::And | BinaryOp::Or => Ok(Type::Boolean),
+            | BinaryOp::GreaterEqual => return Ok(Type::Boolean),
+            BinaryOp::And | BinaryOp::Or => return Ok(Type::Boolean),
+            BinaryOp::NullCoalesce => {
+                return Ok(match left {
+                    Type::Nil => right.clone(),
+                    _ if left.is_concrete() => left.clone(),
+                    _ => Type::Unknown,
+                })
+            }
+            _ => {}
+        }
+        if !left.is_concrete() || !right.is_concrete() {
+            return Ok(Type::Unknown);
+        }
+
+        match operator {
+            BinaryOp::Add => match (left, right) {
+                _ if left.is_numeric() && right.is_numeric() => Ok(numeric()),
+                // A string on either side concatenates the other's text
+                _ if left.is_string() || right.is_string() => Ok(Type::String),
+                (Type::Array(a), Type::Array(b)) => {
+                    Ok(Type::Array(Box::new(a.unify(b).unwrap_or(Type::Unknown))))
+                }
+                _ => Err(invalid()),
+            },
+            BinaryOp::Multiply => match (left, right) {
+                _ if left.is_numeric() && right.is_numeric() => Ok(numeric()),
+                (Type::String, Type::Integer) | (Type::Integer, Type::String) => Ok(Type::String),
+                _ => Err(invalid()),
+            },
+            BinaryOp::Subtract | BinaryOp::Divide | BinaryOp::Modulo | BinaryOp::Power => {
+                if left.is_numeric() && right.is_numeric() {
+                    Ok(numeric())
+                } else {
+                    Err(invalid())
+                }
+            }
             _ => Ok(Type::Unknown),
         }
     }
@@ -326,6 +448,255 @@ impl TypeInference {
         Ok(Type::Array(Box::new(first_type)))
     }
 
+    /// Infer a function's signature from how its body uses the parameters.
+    /// An arithmetic operator whose other operand is a number (or an array,
+    /// for `+`) pins the parameter to that type, and pins from several uses
+    /// are unified. The return type joins every `return` value and a trailing
+    /// expression, so `fn process(x) { x + 1 }` is `(integer) -> integer`.
+    /// Parameters nothing pins stay `Unknown`.
+    pub fn infer_function(
+        &mut self,
+        parameters: &[crate::ast::Parameter],
+        body: &[crate::ast::Statement],
+    ) -> Result<Type, TypeError> {
+        let outer = self.context.clone();
+        let signature = self.solve_function(parameters, body);
+        self.context = outer;
+        signature
+    }
+
+    fn solve_function(
+        &mut self,
+        parameters: &[crate::ast::Parameter],
+        body: &[crate::ast::Statement],
+    ) -> Result<Type, TypeError> {
+        use crate::ast::Statement;
+
+        for param in parameters {
+            self.context
+                .bind(param.name.clone(), Type::TypeVar(param.name.clone()));
+        }
+        let mut constraints = HashMap::new();
+        let mut results = Vec::new();
+        self.collect_constraints(body, &mut constraints, &mut results)?;
+        if let Some(Statement::Expression { expr, .. }) = body.last() {
+            results.push(expr);
+        }
+
+        // Re-read the body's results with each parameter at its solved type
+        let parameter_types: Vec<_> = parameters
+            .iter()
+            .map(|p| constraints.get(&p.name).cloned().unwrap_or(Type::Unknown))
+            .collect();
+        for (param, ty) in parameters.iter().zip(&parameter_types) {
+            self.context.bind(param.name.clone(), ty.clone());
+        }
+        let mut result_types: Vec<Type> = Vec::new();
+        for expr in results {
+            let ty = self.infer_gradual(expr)?;
+            if !result_types.contains(&ty) {
+                result_types.push(ty);
+            }
+        }
+        let return_type = match result_types.len() {
+            0 => Type::Nil,
+            1 => result_types.remove(0),
+            _ => result_types
+                .iter()
+                .skip(1)
+                .try_fold(result_types[0].clone(), |joined, ty| joined.unify(ty))
+                .unwrap_or(Type::Union(result_types)),
+        };
+
+        Ok(Type::Function {
+            parameters: parameter_types,
+            return_type: Box::new(return_type),
+        })
+    }
+
+    /// `infer`, with names this context doesn't know read as `Unknown`
+    fn infer_gradual(&mut self, expr: &crate::ast::Expression) -> Result<Type, TypeError> {
+        match self.infer(expr) {
+            Err(TypeError::UndefinedVariable(_)) => Ok(Type::Unknown),
+            result => result,
+        }
+    }
+
+    /// Walk a function body, pinning parameter type variables from their
+    /// uses and gathering the expressions it returns
+    fn collect_constraints<'a>(
+        &mut self,
+        statements: &'a [crate::ast::Statement],
+        constraints: &mut HashMap<String, Type>,
+        results: &mut Vec<&'a crate::ast::Expression>,
+    ) -> Result<(), TypeError> {
+        use crate::ast::{Expression, Statement};
+
+        for statement in statements {
+            match statement {
+                Statement::Let { name, value, .. } | Statement::Const { name, value, .. } => {
+                    self.collect_expression(value, constraints)?;
+                    let ty = self.infer_gradual(value)?;
+                    self.context.bind(name.clone(), ty);
+                }
+                Statement::LetPattern { pattern, value, .. } => {
+                    self.collect_expression(value, constraints)?;
+                    let mut names = Vec::new();
+                    pattern.bind_names(&mut names);
+                    for name in names {
+                        self.context.bind(name, Type::Unknown);
+                    }
+                }
+                Statement::Assign { target, value, .. } => {
+                    self.collect_expression(value, constraints)?;
+                    if let Expression::Identifier { name, .. } = target {
+                        // Later uses see the new value, not the parameter
+                        self.context.bind(name.clone(), Type::Unknown);
+                    }
+                }
+                Statement::Return {
+                    value: Some(value), ..
+                } => {
+                    self.collect_expression(value, constraints)?;
+                    results.push(value);
+                }
+                Statement::Expression { expr, .. } => {
+                    self.collect_expression(expr, constraints)?;
+                }
+                Statement::If {
+                    condition,
+                    then_branch,
+                    else_branch,
+                    ..
+                } => {
+                    self.collect_expression(condition, constraints)?;
+                    self.collect_constraints(then_branch, constraints, results)?;
+                    if let Some(else_branch) = else_branch {
+                        self.collect_constraints(else_branch, constraints, results)?;
+                    }
+                }
+                Statement::While {
+                    condition, body, ..
+                } => {
+                    self.collect_expression(condition, constraints)?;
+                    self.collect_constraints(body, constraints, results)?;
+                }
+                Statement::For {
+                    variable,
+                    iterable,
+                    body,
+                    ..
+                } => {
+                    self.collect_expression(iterable, constraints)?;
+                    self.context.bind(variable.clone(), Type::Unknown);
+                    self.collect_constraints(body, constraints, results)?;
+                }
+                Statement::Block { body, .. } => {
+                    self.collect_constraints(body, constraints, results)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_expression(
+        &mut self,
+        expr: &crate::ast::Expression,
+        constraints: &mut HashMap<String, Type>,
+    ) -> Result<(), TypeError> {
+        use crate::ast::Expression;
+
+        match expr {
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.collect_expression(left, constraints)?;
+                self.collect_expression(right, constraints)?;
+                for (side, other) in [(left, right), (right, left)] {
+                    let var = match side.as_ref() {
+                        Expression::Identifier { name, .. } => match self.context.get(name) {
+                            Some(Type::TypeVar(var)) => var.clone(),
+                            _ => continue,
+                        },
+                        _ => continue,
+                    };
+                    let other_type = self.infer_gradual(other)?;
+                    if let Some(pinned) = Self::operand_constraint(operator, &other_type) {
+                        let joined =
+                            match constraints.get(&var) {
+                                Some(existing) => existing.unify(&pinned).ok_or_else(|| {
+                                    TypeError::TypeMismatch {
+                                        expected: existing.name(),
+                                        found: format!("{} for parameter {}", pinned.name(), var),
+                                    }
+                                })?,
+                                None => pinned,
+                            };
+                        constraints.insert(var, joined);
+                    }
+                }
+            }
+            Expression::Unary { operand, .. } => self.collect_expression(operand, constraints)?,
+            Expression::Call {
+                callee, arguments, ..
+            } => {
+                self.collect_expression(callee, constraints)?;
+                for argument in arguments {
+                    self.collect_expression(argument, constraints)?;
+                }
+            }
+            Expression::Array { elements, .. } => {
+                for element in elements {
+                    self.collect_expression(element, constraints)?;
+                }
+            }
+            Expression::IndexAccess { object, index, .. } => {
+                self.collect_expression(object, constraints)?;
+                self.collect_expression(index, constraints)?;
+            }
+            Expression::PropertyAccess { object, .. } | Expression::Spread { expr: object, .. } => {
+                self.collect_expression(object, constraints)?;
+            }
+            Expression::Ternary {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                self.collect_expression(condition, constraints)?;
+                self.collect_expression(then_expr, constraints)?;
+                self.collect_expression(else_expr, constraints)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The type an arithmetic `operator` pins its other operand to, given
+    /// this operand's type
+    fn operand_constraint(operator: &crate::ast::BinaryOp, other: &Type) -> Option<Type> {
+        use crate::ast::BinaryOp;
+
+        match operator {
+            BinaryOp::Add
+            | BinaryOp::Subtract
+            | BinaryOp::Multiply
+            | BinaryOp::Divide
+            | BinaryOp::Modulo
+            | BinaryOp::Power
+                if other.is_numeric() =>
+            {
+                Some(other.clone())
+            }
+            BinaryOp::Add if matches!(other, Type::Array(_)) => Some(other.clone()),
+            _ => None,
+        }
+    }
+
     /// Get the type context
     pub fn context(&self) -> &TypeContext {
         &self.context
@@ -343,6 +714,491 @@ impl Default for TypeInference {
     }
 }
 
+/// Gradual checker run over a whole program before it executes. `let` and
+/// `const` bindings take the type of their initializer, and operators are
+/// checked with the same rules as `TypeInference`; whatever can't be pinned
+/// down is `Unknown` and never reported.
+pub struct TypeChecker {
+    inference: TypeInference,
+
+    /// Bindings a function body starts from. Functions are dynamically
+    /// scoped, so the locals around their definition may not be the ones
+    /// they see when called.
+    globals: TypeContext,
+
+    /// Names a plain assignment rebinds somewhere, whose `let` type is stale
+    reassigned: HashSet<String>,
+
+    errors: Vec<TypeError>,
+}
+
+impl TypeChecker {
+    /// A checker whose program starts with the bindings in `globals`
+    pub fn new(globals: TypeContext) -> Self {
+        Self {
+            inference: TypeInference {
+                context: globals.clone(),
+            },
+            globals,
+            reassigned: HashSet::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Check `program`, returning every error found
+    pub fn check(mut self, program: &crate::ast::Program) -> Vec<TypeError> {
+        // A first pass collects the reassigned names, so a `let` is never
+        // typed from an initializer that a later assignment replaces
+        self.check_block(&program.statements);
+        self.errors.clear();
+        self.inference.context = self.globals.clone();
+        self.check_block(&program.statements);
+        self.errors
+    }
+
+    fn check_block(&mut self, statements: &[crate::ast::Statement]) {
+        for statement in statements {
+            self.check_statement(statement);
+        }
+    }
+
+    /// Check `statements` in a nested scope, dropping its bindings afterwards
+    fn check_scoped(&mut self, statements: &[crate::ast::Statement], bound: &[String]) {
+        let outer = self.inference.context.clone();
+        for name in bound {
+            self.inference.context.bind(name.clone(), Type::Unknown);
+        }
+        self.check_block(statements);
+        self.inference.context = outer;
+    }
+
+    /// Check a body the interpreter runs in the enclosing scope, as it does for
+    /// `if`, `while` and `try`. Its bindings outlive it, but only when it runs
+    /// (and runs far enough), so afterwards each name it bound or rebound is
+    /// `Unknown`.
+    fn check_unscoped(&mut self, statements: &[crate::ast::Statement]) {
+        let outer = self.inference.context.clone();
+        self.check_block(statements);
+        let inner = std::mem::replace(&mut self.inference.context, outer);
+        for (name, ty) in &inner.bindings {
+            if self.inference.context.get(name) != Some(ty) {
+                self.bind_unknown(name);
+            }
+        }
+    }
+
+    fn check_function(
+        &mut self,
+        parameters: &[crate::ast::Parameter],
+        body: &[crate::ast::Statement],
+    ) {
+        for default in parameters.iter().filter_map(|p| p.default_value.as_ref()) {
+            self.check_expression(default);
+        }
+        let outer = std::mem::replace(&mut self.inference.context, self.globals.clone());
+        let names: Vec<_> = parameters.iter().map(|p| p.name.clone()).collect();
+        self.check_scoped(body, &names);
+        self.inference.context = outer;
+    }
+
+    fn check_arms(&mut self, arms: &[crate::ast::MatchArm]) {
+        for arm in arms {
+            let mut names = Vec::new();
+            arm.pattern.bind_names(&mut names);
+            let outer = self.inference.context.clone();
+            for name in &names {
+                self.inference.context.bind(name.clone(), Type::Unknown);
+            }
+            if let Some(guard) = &arm.guard {
+                self.check_expression(guard);
+            }
+            self.check_block(&arm.body);
+            self.inference.context = outer;
+        }
+    }
+
+    fn bind_unknown(&mut self, name: &str) {
+        self.inference.context.bind(name.to_string(), Type::Unknown);
+    }
+
+    fn check_statement(&mut self, statement: &crate::ast::Statement) {
+        use crate::ast::{Expression, Statement};
+
+        match statement {
+            Statement::Let { name, value, .. } | Statement::Const { name, value, .. } => {
+                let ty = self.check_expression(value);
+                if self.reassigned.contains(name) {
+                    self.bind_unknown(name);
+                } else {
+                    self.inference.context.bind(name.clone(), ty);
+                }
+            }
+            Statement::LetPattern { pattern, value, .. } => {
+                self.check_expression(value);
+                let mut names = Vec::new();
+                pattern.bind_names(&mut names);
+                for name in &names {
+                    self.bind_unknown(name);
+                }
+            }
+            Statement::Assign { target, value, .. } => {
+                self.check_expression(value);
+                match target {
+                    Expression::Identifier { name, .. } => {
+                        self.reassigned.insert(name.clone());
+                        self.bind_unknown(name);
+                    }
+                    _ => {
+                        self.check_expression(target);
+                    }
+                }
+            }
+            Statement::Reactive {
+                name,
+                initial_value,
+                ..
+            } => {
+                self.check_expression(initial_value);
+                self.bind_unknown(name);
+            }
+            Statement::Function {
+                name,
+                parameters,
+                body,
+                ..
+            } => {
+                self.bind_unknown(name);
+                self.check_function(parameters, body);
+            }
+            Statement::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.check_expression(value);
+                }
+            }
+            Statement::Expression { expr, .. } => {
+                self.check_expression(expr);
+            }
+            Statement::Assert {
+                condition, message, ..
+            } => {
+                self.check_expression(condition);
+                if let Some(message) = message {
+                    self.check_expression(message);
+                }
+            }
+            Statement::Block { body, .. }
+            | Statement::Effect { body, .. }
+            | Statement::Atomic { body, .. } => self.check_scoped(body, &[]),
+            // Runs to completion in the current scope
+            Statement::Parallel { body, .. } => self.check_block(body),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.check_expression(condition);
+                self.check_unscoped(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.check_unscoped(else_branch);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.check_expression(condition);
+                self.check_unscoped(body);
+            }
+            Statement::For {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                self.check_expression(iterable);
+                self.check_scoped(body, std::slice::from_ref(variable));
+            }
+            Statement::ForClassic {
+                condition,
+                update,
+                body,
+                ..
+            } => {
+                if let Some(condition) = condition {
+                    self.check_expression(condition);
+                }
+                self.check_unscoped(body);
+                if let Some(update) = update {
+                    self.check_statement(update);
+                }
+            }
+            Statement::Match { value, arms, .. } => {
+                self.check_expression(value);
+                self.check_arms(arms);
+            }
+            Statement::Switch { value, cases, .. } => {
+                self.check_expression(value);
+                // Any case can be jumped to directly, so each is checked on its own
+                for case in cases {
+                    if let Some(value) = &case.value {
+                        self.check_expression(value);
+                    }
+                    self.check_scoped(&case.body, &[]);
+                }
+            }
+            Statement::Struct { name, .. } | Statement::Enum { name, .. } => {
+                self.bind_unknown(name);
+            }
+            Statement::Class {
+                name,
+                constructor,
+                methods,
+                ..
+            } => {
+                self.bind_unknown(name);
+                if let Some(constructor) = constructor {
+                    self.check_function(&constructor.parameters, &constructor.body);
+                }
+                for method in methods {
+                    self.check_function(&method.parameters, &method.body);
+                }
+            }
+            Statement::Import { items, alias, .. } => {
+                for name in items.iter().chain(alias) {
+                    self.bind_unknown(name);
+                }
+            }
+            Statement::Export {
+                declaration: Some(declaration),
+                ..
+            } => self.check_statement(declaration),
+            Statement::Try {
+                try_block,
+                catch_clause,
+                finally_block,
+                ..
+            } => {
+                self.check_unscoped(try_block);
+                if let Some(catch) = catch_clause {
+                    let bound: Vec<_> = catch.parameter.iter().cloned().collect();
+                    self.check_scoped(&catch.body, &bound);
+                }
+                if let Some(finally_block) = finally_block {
+                    self.check_block(finally_block);
+                }
+            }
+            Statement::Throw { value, .. } => {
+                self.check_expression(value);
+            }
+            Statement::Defer { expression, .. } => {
+                self.check_expression(expression);
+            }
+            Statement::Rewind { steps, .. } => {
+                if let Some(steps) = steps {
+                    self.check_expression(steps);
+                }
+            }
+            Statement::Watch {
+                expression,
+                handler,
+                ..
+            } => {
+                self.check_expression(expression);
+                self.check_scoped(handler, &[]);
+            }
+            Statement::Computed {
+                name, expression, ..
+            } => {
+                self.check_expression(expression);
+                self.bind_unknown(name);
+            }
+            Statement::SyntaxExtension { transformer, .. } => {
+                self.check_expression(transformer);
+            }
+            Statement::Concurrent { tasks, .. } => {
+                for task in tasks {
+                    self.check_expression(task);
+                }
+            }
+            Statement::Export {
+                declaration: None, ..
+            }
+            | Statement::Break { .. }
+            | Statement::Continue { .. }
+            | Statement::Snapshot { .. }
+            | Statement::Checkpoint { .. }
+            | Statement::Replay { .. }
+            | Statement::Context { .. } => {}
+        }
+    }
+
+    /// Check `expr` and everything inside it, returning its type
+    fn check_expression(&mut self, expr: &crate::ast::Expression) -> Type {
+        use crate::ast::{Expression, UnaryOp};
+
+        match expr {
+            Expression::Literal { value, .. } => self.inference.infer_literal(value),
+            Expression::Identifier { name, .. } => self
+                .inference
+                .context
+                .get(name)
+                .cloned()
+                .unwrap_or(Type::Unknown),
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let left_type = self.check_expression(left);
+                let right_type = self.check_expression(right);
+                TypeInference::binary_result(operator, &left_type, &right_type).unwrap_or_else(
+                    |error| {
+                        self.errors.push(error);
+                        Type::Unknown
+                    },
+                )
+            }
+            Expression::Unary {
+                operator, operand, ..
+            } => {
+                let ty = self.check_expression(operand);
+                match operator {
+                    UnaryOp::Not => Type::Boolean,
+                    UnaryOp::Negate if ty.is_numeric() => ty,
+                    _ => Type::Unknown,
+                }
+            }
+            Expression::Template { parts, .. } => {
+                for part in parts {
+                    self.check_expression(part);
+                }
+                Type::String
+            }
+            Expression::Array { elements, .. } => {
+                let types: Vec<_> = elements.iter().map(|e| self.check_expression(e)).collect();
+                let element = types.into_iter().next().unwrap_or(Type::Unknown);
+                Type::Array(Box::new(element))
+            }
+            Expression::Call {
+                callee, arguments, ..
+            } => {
+                self.check_expression(callee);
+                for argument in arguments {
+                    self.check_expression(argument);
+                }
+                Type::Unknown
+            }
+            Expression::Object { fields, .. } => {
+                for (_, value) in fields {
+                    self.check_expression(value);
+                }
+                Type::Unknown
+            }
+            Expression::New {
+                arguments, fields, ..
+            } => {
+                for argument in arguments.iter().chain(fields.iter().map(|(_, v)| v)) {
+                    self.check_expression(argument);
+                }
+                Type::Unknown
+            }
+            Expression::PropertyAccess { object, .. } | Expression::Spread { expr: object, .. } => {
+                self.check_expression(object);
+                Type::Unknown
+            }
+            Expression::IndexAccess { object, index, .. } => {
+                self.check_expression(object);
+                self.check_expression(index);
+                Type::Unknown
+            }
+            Expression::Lambda {
+                parameters, body, ..
+            } => {
+                self.check_function(parameters, body);
+                Type::Unknown
+            }
+            Expression::Await { expression, .. }
+            | Expression::Signal {
+                initial_value: expression,
+                ..
+            }
+            | Expression::Unquote { expression, .. } => {
+                self.check_expression(expression);
+                Type::Unknown
+            }
+            Expression::ParallelMap {
+                collection: first,
+                function: second,
+                ..
+            }
+            | Expression::ParallelFilter {
+                collection: first,
+                predicate: second,
+                ..
+            }
+            | Expression::Pipeline {
+                value: first,
+                function: second,
+                ..
+            }
+            | Expression::StreamPipe {
+                source: first,
+                handler: second,
+                ..
+            }
+            | Expression::Range {
+                start: first,
+                end: second,
+                ..
+            } => {
+                self.check_expression(first);
+                self.check_expression(second);
+                Type::Unknown
+            }
+            Expression::Ternary {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                self.check_expression(condition);
+                let then_type = self.check_expression(then_expr);
+                let else_type = self.check_expression(else_expr);
+                if then_type == else_type {
+                    then_type
+                } else {
+                    Type::Unknown
+                }
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.check_expression(condition);
+                self.check_unscoped(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.check_unscoped(else_branch);
+                }
+                Type::Unknown
+            }
+            Expression::Match { value, arms, .. } => {
+                self.check_expression(value);
+                self.check_arms(arms);
+                Type::Unknown
+            }
+            Expression::Block { body, .. } => {
+                self.check_scoped(body, &[]);
+                Type::Unknown
+            }
+            // Quoted code is data until something evaluates it
+            Expression::Quote { .. } | Expression::This { .. } => Type::Unknown,
+        }
+    }
+}
+
 /// Type error
 #[derive(Debug, Clone)]
 pub enum TypeError {
@@ -377,6 +1233,19 @@ impl fmt::Display for TypeError {
 
 impl std::error::Error for TypeError {}
 
+/// Every error a `TypeChecker` pass found
+#[derive(Debug, Clone)]
+pub struct TypeErrors(pub Vec<TypeError>);
+
+impl fmt::Display for TypeErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let messages: Vec<_> = self.0.iter().map(|e| e.to_string()).collect();
+        write!(f, "{}", messages.join("\n"))
+    }
+}
+
+impl std::error::Error for TypeErrors {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -400,10 +1269,174 @@ mod tests {
         assert_eq!(unified, Some(Type::Float));
     }
 
+    #[test]
+    fn test_of_value_primitives() {
+        assert_eq!(Type::of_value(&Value::Nil), Type::Nil);
+        assert_eq!(Type::of_value(&Value::Boolean(true)), Type::Boolean);
+        assert_eq!(Type::of_value(&Value::Integer(1)), Type::Integer);
+        assert_eq!(Type::of_value(&Value::Float(1.5)), Type::Float);
+        assert_eq!(
+            Type::of_value(&Value::String("s".to_string())),
+            Type::String
+        );
+    }
+
+    #[test]
+    fn test_of_value_containers() {
+        let ints = Value::Array(vec![Value::Integer(1), Value::Integer(2)]);
+        assert_eq!(Type::of_value(&ints), Type::Array(Box::new(Type::Integer)));
+
+        let numbers = Value::Array(vec![Value::Integer(1), Value::Float(2.0)]);
+        assert_eq!(Type::of_value(&numbers), Type::Array(Box::new(Type::Float)));
+
+        let mixed = Value::Array(vec![Value::Integer(1), Value::String("a".to_string())]);
+        assert_eq!(Type::of_value(&mixed), Type::Array(Box::new(Type::Any)));
+
+        assert_eq!(
+            Type::of_value(&Value::Array(vec![])),
+            Type::Array(Box::new(Type::Unknown))
+        );
+
+        let mut fields = HashMap::new();
+        fields.insert("name".to_string(), Value::String("Ada".to_string()));
+        let mut expected = HashMap::new();
+        expected.insert("name".to_string(), Type::String);
+        assert_eq!(
+            Type::of_value(&Value::Object(fields)),
+            Type::Object(expected)
+        );
+
+        let tuple = Value::Tuple(vec![Value::Integer(1), Value::Boolean(false)]);
+        assert_eq!(
+            Type::of_value(&tuple),
+            Type::Tuple(vec![Type::Integer, Type::Boolean])
+        );
+    }
+
+    #[test]
+    fn test_of_value_callables_and_named() {
+        use crate::interpreter::value::FunctionValue;
+        use std::sync::Arc;
+
+        let func = Value::Function(Arc::new(FunctionValue {
+            name: Some("add".to_string()),
+            parameters: vec!["a".to_string(), "b".to_string()],
+            defaults: vec![None, None],
+            variadic: false,
+            body: vec![],
+            closure: HashMap::new(),
+            is_async: false,
+            reloadable: false,
+        }));
+        assert_eq!(
+            Type::of_value(&func),
+            Type::Function {
+                parameters: vec![Type::Unknown, Type::Unknown],
+                return_type: Box::new(Type::Unknown),
+            }
+        );
+
+        let native = Value::native(|_| Ok(Value::Nil));
+        assert!(Type::of_value(&native).is_callable());
+
+        let point = Value::Struct {
+            name: "Point".to_string(),
+            fields: HashMap::new(),
+        };
+        assert_eq!(Type::of_value(&point), Type::Named("Point".to_string()));
+
+        let variant = Value::Enum {
+            type_name: "Color".to_string(),
+            variant: "Red".to_string(),
+            values: vec![],
+        };
+        assert_eq!(Type::of_value(&variant), Type::Named("Color".to_string()));
+
+        assert_eq!(
+            Type::of_value(&Value::ReactiveRef(0)),
+            Type::Reactive(Box::new(Type::Unknown))
+        );
+        let range = Value::Range {
+            start: 0,
+            end: 3,
+            inclusive: false,
+        };
+        assert_eq!(Type::of_value(&range), Type::Named("range".to_string()));
+    }
+
+    #[test]
+    fn test_context_from_values() {
+        let mut values = HashMap::new();
+        values.insert("count".to_string(), Value::Integer(3));
+        let context = TypeContext::from_values(&values);
+        assert_eq!(context.get("count"), Some(&Type::Integer));
+    }
+
     #[test]
     fn test_type_names() {
         assert_eq!(Type::Integer.name(), "integer");
         assert_eq!(Type::Array(Box::new(Type::String)).name(), "array<string>");
         assert_eq!(Type::Optional(Box::new(Type::Boolean)).name(), "boolean?");
     }
+
+    #[test]
+    fn test_binary_result_is_gradual() {
+        use crate::ast::BinaryOp;
+
+        let check = |op, l: Type, r: Type| TypeInference::binary_result(&op, &l, &r);
+        assert_eq!(
+            check(BinaryOp::Add, Type::Integer, Type::Float).unwrap(),
+            Type::Float
+        );
+        assert_eq!(
+            check(BinaryOp::Add, Type::String, Type::Integer).unwrap(),
+            Type::String
+        );
+        assert_eq!(
+            check(BinaryOp::Multiply, Type::String, Type::Integer).unwrap(),
+            Type::String
+        );
+        assert!(check(BinaryOp::Subtract, Type::String, Type::Integer).is_err());
+        assert!(check(BinaryOp::Add, Type::Boolean, Type::Nil).is_err());
+
+        for gradual in [Type::Unknown, Type::Any] {
+            assert_eq!(
+                check(BinaryOp::Subtract, Type::String, gradual).unwrap(),
+                Type::Unknown
+            );
+        }
+    }
+
+    #[test]
+    fn test_infer_function_from_usage() {
+        use crate::ast::Statement;
+
+        let signature = |source: &str| {
+            let tokens = crate::lexer::tokenize(source).unwrap();
+            let program = crate::parser::parse(tokens).unwrap();
+            match &program.statements[0] {
+                Statement::Function {
+                    parameters, body, ..
+                } => TypeInference::new().infer_function(parameters, body),
+                other => panic!("expected a function, got {:?}", other),
+            }
+        };
+
+        // The README example
+        assert_eq!(
+            signature("fn process(x) { x + 1 }").unwrap(),
+            Type::Function {
+                parameters: vec![Type::Integer],
+                return_type: Box::new(Type::Integer),
+            }
+        );
+        assert_eq!(
+            signature("fn scale(a, label) { let y = a * 2; return a / 0.5 }").unwrap(),
+            Type::Function {
+                parameters: vec![Type::Float, Type::Unknown],
+                return_type: Box::new(Type::Float),
+            }
+        );
+        assert!(signature("fn f(x) { return x - 1 + (x + [1]) }").is_err());
+    }
 }